@@ -0,0 +1,58 @@
+// Per-poll cost of the old full refresh against the incremental one used by `sampler`.
+// Run with `cargo bench --bench process_refresh`; the gap grows with the process count.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+// Stand-ins for a typical watch list / blacklist
+const WANTED: [&str; 3] = ["chrome", "node", "code"];
+
+fn details() -> ProcessRefreshKind {
+    ProcessRefreshKind::nothing()
+        .with_cpu()
+        .with_memory()
+        .with_exe(UpdateKind::OnlyIfNotSet)
+        .with_cmd(UpdateKind::OnlyIfNotSet)
+        .with_user(UpdateKind::OnlyIfNotSet)
+}
+
+fn is_wanted(name: &str) -> bool {
+    let name = name.to_lowercase();
+    WANTED.iter().any(|w| name.contains(w))
+}
+
+// Both keep their System between polls, as the pollers did; building one per poll would also
+// time the CPU, memory and disk setup that neither approach pays per poll
+fn full_refresh(c: &mut Criterion) {
+    let mut sys = System::new();
+    c.bench_function("full refresh (every process, every stat)", |b| {
+        b.iter(|| {
+            sys.refresh_processes(ProcessesToUpdate::All, true);
+            sys.processes().len()
+        })
+    });
+}
+
+fn incremental_refresh(c: &mut Criterion) {
+    let mut sys = System::new();
+    c.bench_function("incremental refresh (listing + wanted PIDs)", |b| {
+        b.iter(|| {
+            sys.refresh_processes_specifics(
+                ProcessesToUpdate::All,
+                true,
+                ProcessRefreshKind::nothing(),
+            );
+            let pids: Vec<Pid> = sys
+                .processes()
+                .values()
+                .filter(|p| is_wanted(&p.name().to_string_lossy()))
+                .map(|p| p.pid())
+                .collect();
+            sys.refresh_processes_specifics(ProcessesToUpdate::Some(&pids), false, details());
+            pids.len()
+        })
+    });
+}
+
+criterion_group!(benches, full_refresh, incremental_refresh);
+criterion_main!(benches);
@@ -0,0 +1,194 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+// How often the headless agent enforces the blacklist (the UI polls every second)
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Deserialize)]
+struct BlacklistBody {
+    name: String,
+    auto_kill: bool,
+    cpu_threshold: f32,
+}
+
+// Decode a URL path segment or query value ("%20" and "+")
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 2;
+            }
+            (b'+', _) => out.push(b' '),
+            (b, _) => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn query_param(query: &str, key: &str) -> Option<String> {
+    query.split('&').find_map(|pair| {
+        let (k, v) = pair.split_once('=')?;
+        (k == key).then(|| decode(v))
+    })
+}
+
+/// Compare digests byte by byte without stopping early, so response times don't leak how much
+/// of the token a guess got right (or its length)
+pub(crate) fn same_secret(a: &str, b: &str) -> bool {
+    let (a, b) = (Sha256::digest(a.as_bytes()), Sha256::digest(b.as_bytes()));
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+        == 0
+}
+
+fn is_authorized(request: &Request, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    request
+        .headers()
+        .iter()
+        .filter(|h| h.field.equiv("Authorization"))
+        .fold(false, |ok, h| same_secret(h.value.as_str(), &expected) | ok)
+}
+
+fn to_json<T: serde::Serialize>(value: T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
+
+fn route(request: &mut Request) -> Option<Result<Value, String>> {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let segments: Vec<String> = path.trim_matches('/').split('/').map(decode).collect();
+    let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+
+    let result = match (request.method(), segments.as_slice()) {
+        (Method::Get, ["api", "stats"]) => to_json(crate::system_stats()),
+        (Method::Get, ["api", "processes"]) => {
+            // Without ?names= the agent's stored watch list is used
+            let names =
+                query_param(query, "names").map(|n| n.split(',').map(|s| s.to_string()).collect());
+            // ?columns= limits the fields returned, as in the app
+            let columns = crate::columns::Columns::new(
+                query_param(query, "columns")
+                    .map(|c| c.split(',').map(|s| s.to_string()).collect()),
+            );
+            to_json(crate::process_groups(
+                names,
+                &columns,
+                &crate::GroupQuery::default(),
+            ))
+        }
+        (Method::Get, ["api", "blacklist"]) => to_json(crate::get_blacklist()),
+        (Method::Post, ["api", "blacklist"]) => {
+            let mut body = String::new();
+            if let Err(e) = request.as_reader().read_to_string(&mut body) {
+                return Some(Err(e.to_string()));
+            }
+            serde_json::from_str::<BlacklistBody>(&body)
+                .map_err(|e| format!("Invalid body: {}", e))
+                .and_then(|b| crate::insert_blacklist_entry(&b.name, b.auto_kill, b.cpu_threshold))
+                .and_then(to_json)
+        }
+        (Method::Delete, ["api", "blacklist", name]) => {
+            crate::delete_blacklist_entry(name).and_then(to_json)
+        }
+        (Method::Get, ["api", "logs"]) => to_json(crate::get_activity_logs()),
+        (Method::Post, ["api", "kill", pid]) => match pid.parse::<u32>() {
+            Ok(pid) => crate::kill_process_by_pid(pid).and_then(to_json),
+            Err(_) => Err("Invalid PID".into()),
+        },
+        (Method::Post, ["api", "kill-group", name]) => {
+            crate::kill_processes_by_name(name).and_then(to_json)
+        }
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn respond(request: Request, status: u16, body: Value) {
+    let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    request.respond(response).ok();
+}
+
+// Public API
+/// Mirror activity log entries to journald/syslog for this run, whatever the saved setting says
+pub fn enable_syslog() {
+    crate::syslog::force_enable();
+}
+
+/// Read the override environment variables and take the override flags out of `args`
+/// (program name included), returning the rest. Call before `serve`.
+pub fn init_overrides(args: &[String]) -> Result<Vec<String>, String> {
+    crate::overrides::init(args)
+}
+
+/// Run headless: enforce the blacklist in the background and serve the REST API.
+/// Every request must carry `Authorization: Bearer <token>`.
+pub fn serve(addr: &str, token: &str) -> Result<(), String> {
+    if token.is_empty() {
+        return Err("An API token is required to run the agent".into());
+    }
+
+    crate::logging::init(&crate::overrides::log_level(&crate::read_state(|state| {
+        state.settings.log_level.clone()
+    })));
+    crate::i18n::set_language(&crate::read_state(|state| state.settings.language.clone())).ok();
+    crate::power::set_enabled(crate::read_state(|state| state.settings.low_power_mode));
+    crate::power::start_monitor();
+    crate::refresher::start(&crate::read_state(|state| {
+        state.settings.refresh_intervals.clone()
+    }));
+    crate::metrics::register_providers();
+    crate::scheduler::start_executor();
+    crate::mqtt::start();
+    crate::notifier::start();
+    crate::digest::start();
+    crate::config_file::start();
+    crate::sync::start();
+    let interval = crate::overrides::poll_interval().unwrap_or(WATCH_INTERVAL);
+    thread::spawn(move || loop {
+        crate::run_blacklist_check();
+        thread::sleep(crate::power::interval(interval));
+    });
+
+    // --api-port / AKT_API_PORT keeps the bind address but replaces its port
+    let addr = match crate::overrides::api_port() {
+        Some(port) => {
+            let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+            format!("{}:{}", host, port)
+        }
+        None => addr.to_string(),
+    };
+    let server = Server::http(&addr).map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+    tracing::info!(%addr, "agent listening");
+
+    for mut request in server.incoming_requests() {
+        if !is_authorized(&request, token) {
+            respond(request, 401, json!({ "error": "Unauthorized" }));
+            continue;
+        }
+
+        match route(&mut request) {
+            Some(Ok(value)) => respond(request, 200, value),
+            Some(Err(err)) => respond(request, 400, json!({ "error": err })),
+            None => respond(request, 404, json!({ "error": "Not found" })),
+        }
+    }
+    Ok(())
+}
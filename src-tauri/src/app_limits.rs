@@ -0,0 +1,287 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::System;
+
+use crate::matcher::Matcher;
+use crate::{
+    notes, notify_event, process_ctl, process_log, protection, read_state, record_activity,
+    sampler, sessions, with_state, ActivityLog,
+};
+
+// Runtime is sampled at this interval (stretched in low-power mode); each sample adds the time
+// actually elapsed since the previous one to today's usage
+const TICK: Duration = Duration::from_secs(10);
+// Warn this long before an app runs out of time
+const WARN_BEFORE_SECS: u64 = 5 * 60;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitAction {
+    Kill,
+    Suspend,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AppLimit {
+    pub name: String,
+    pub daily_minutes: u32,
+    pub action: LimitAction,
+    pub created_at: String,
+}
+
+/// Runtime counted so far today, persisted so restarts don't reset the budget
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct AppUsage {
+    pub date: String,
+    pub seconds: HashMap<String, u64>,
+    pub warned: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct AppUsageRow {
+    pub name: String,
+    pub used_minutes: f32,
+    pub limit_minutes: u32,
+    pub remaining_minutes: f32,
+    pub exceeded: bool,
+    pub running: bool,
+}
+
+#[derive(Serialize, Clone)]
+struct LimitWarning {
+    name: String,
+    remaining_minutes: u32,
+}
+
+// (limit key, pid, start time) of each process we suspended, resumed when the day rolls over
+// or its limit is removed
+static SUSPENDED: Mutex<Vec<(String, u32, u64)>> = Mutex::new(Vec::new());
+
+fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+fn matching_pids<'a>(sys: &'a System, name: &str) -> Vec<&'a sysinfo::Process> {
+    let matcher = Matcher::new(name);
+    sys.processes()
+        .values()
+        .filter(|p| matcher.matches(&p.name().to_string_lossy()))
+        .collect()
+}
+
+/// Resume what the limits `key` selects suspended
+fn resume_where(key: impl Fn(&str) -> bool) {
+    let resumed: Vec<(String, u32, u64)> = {
+        let mut suspended = SUSPENDED.lock().unwrap();
+        let (resumed, kept) = suspended.drain(..).partition(|(k, _, _)| key(k));
+        *suspended = kept;
+        resumed
+    };
+    for (_, pid, start_time) in resumed {
+        process_ctl::resume_same(pid, start_time).ok();
+    }
+}
+
+fn resume_suspended() {
+    resume_where(|_| true);
+}
+
+fn tick(sys: &mut System, elapsed_secs: u64) {
+    let limits = read_state(|state| state.app_limits.clone());
+    if limits.is_empty() {
+        return;
+    }
+    let matchers: Vec<Matcher> = limits.iter().map(|l| Matcher::new(&l.name)).collect();
+    sampler::refresh(sys, |p| {
+        let name = p.name().to_string_lossy();
+        matchers.iter().any(|m| m.matches(&name))
+    });
+    let cpu_count = (sys.cpus().len() as f32).max(1.0);
+    let users = sysinfo::Users::new_with_refreshed_list();
+    let allow_protected = protection::override_enabled();
+    let escalation = read_state(|state| state.settings.kill_escalation.clone());
+    let session_scope = read_state(|state| state.settings.session_scope.clone());
+    let own_pids = protection::own_pids(sys);
+
+    let date = today();
+    if read_state(|state| state.app_usage.date != date) {
+        resume_suspended();
+    }
+
+    let mut warnings = vec![];
+    // Out of time; acted on once the state lock is released
+    let mut over: Vec<(&AppLimit, Vec<&sysinfo::Process>)> = vec![];
+    with_state(|state| {
+        // New day: start every budget from zero
+        if state.app_usage.date != date {
+            state.app_usage = AppUsage {
+                date: date.clone(),
+                ..Default::default()
+            };
+        }
+
+        for limit in &limits {
+            let key = limit.name.to_lowercase();
+            let running: Vec<_> = matching_pids(sys, &limit.name)
+                .into_iter()
+                .filter(|p| !own_pids.contains(&p.pid().as_u32()))
+                .filter(|p| session_scope.allows(sessions::session_of(p.pid().as_u32())))
+                .collect();
+            if running.is_empty() {
+                continue;
+            }
+
+            let used = state.app_usage.seconds.entry(key.clone()).or_insert(0);
+            *used += elapsed_secs;
+            let used = *used;
+            let budget = limit.daily_minutes as u64 * 60;
+
+            if used >= budget {
+                over.push((limit, running));
+            } else if budget - used <= WARN_BEFORE_SECS && !state.app_usage.warned.contains(&key) {
+                state.app_usage.warned.push(key);
+                warnings.push(LimitWarning {
+                    name: limit.name.clone(),
+                    remaining_minutes: ((budget - used) / 60) as u32,
+                });
+            }
+        }
+    });
+
+    let mut logs = vec![];
+    for (limit, running) in over {
+        for p in running {
+            let pid = p.pid().as_u32();
+            let (ok, verb) = match limit.action {
+                LimitAction::Kill => (
+                    protection::check(p, allow_protected).is_ok()
+                        && process_ctl::terminate(p, &escalation).is_ok(),
+                    "killed",
+                ),
+                LimitAction::Suspend => {
+                    let mut suspended = SUSPENDED.lock().unwrap();
+                    if suspended
+                        .iter()
+                        .any(|(_, p_pid, start)| (*p_pid, *start) == (pid, p.start_time()))
+                    {
+                        continue;
+                    }
+                    let ok = protection::check(p, allow_protected).is_ok()
+                        && notes::check_process(p).is_ok()
+                        && process_ctl::suspend_process(pid).is_ok();
+                    if ok {
+                        suspended.push((limit.name.to_lowercase(), pid, p.start_time()));
+                    }
+                    (ok, "suspended")
+                }
+            };
+            logs.push(ActivityLog {
+                cpu_usage: p.cpu_usage() / cpu_count,
+                was_killed: ok && limit.action == LimitAction::Kill,
+                reason: if ok {
+                    format!(
+                        "Daily limit of {} min reached, {}",
+                        limit.daily_minutes, verb
+                    )
+                } else {
+                    format!("Daily limit reached but the app could not be {}", verb)
+                },
+                freed_memory_kb: if ok && limit.action == LimitAction::Kill {
+                    p.memory() / 1024
+                } else {
+                    0
+                },
+                ..process_log(sys, &users, p)
+            });
+        }
+    }
+    if !logs.is_empty() {
+        with_state(|state| {
+            for log in logs {
+                record_activity(state, log);
+            }
+        });
+    }
+
+    for warning in warnings {
+        notify_event("app-limit-warning", warning);
+    }
+}
+
+// Public API
+pub fn start_tracker() {
+    thread::spawn(|| {
+        let mut sys = System::new();
+        let mut last = Instant::now();
+        loop {
+            let interval = crate::power::interval(TICK);
+            thread::sleep(interval);
+            // A gap much longer than the sleep means the machine was suspended; nothing ran then
+            let elapsed = last.elapsed().min(interval * 2);
+            last = Instant::now();
+            tick(&mut sys, elapsed.as_secs_f64().round() as u64);
+        }
+    });
+}
+
+pub fn usage_today() -> Vec<AppUsageRow> {
+    let (limits, usage) = read_state(|state| (state.app_limits.clone(), state.app_usage.clone()));
+    let is_today = usage.date == today();
+
+    // Only names are needed, so nothing beyond the process list is refreshed
+    sampler::with_processes(
+        |_| false,
+        |sys, _| {
+            limits
+                .iter()
+                .map(|limit| {
+                    let used_secs = if is_today {
+                        *usage.seconds.get(&limit.name.to_lowercase()).unwrap_or(&0)
+                    } else {
+                        0
+                    };
+                    let used_minutes = used_secs as f32 / 60.0;
+                    AppUsageRow {
+                        name: limit.name.clone(),
+                        used_minutes,
+                        limit_minutes: limit.daily_minutes,
+                        remaining_minutes: (limit.daily_minutes as f32 - used_minutes).max(0.0),
+                        exceeded: used_secs >= limit.daily_minutes as u64 * 60,
+                        running: !matching_pids(sys, &limit.name).is_empty(),
+                    }
+                })
+                .collect()
+        },
+    )
+}
+
+/// (pid, start time) of the processes suspended for running out of time
+pub fn suspended() -> Vec<(u32, u64)> {
+    SUSPENDED
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_, pid, start_time)| (*pid, *start_time))
+        .collect()
+}
+
+/// Let the apps suspended under the limit on `name` run again, once it is removed
+pub fn resume_limit(name: &str) {
+    let key = name.to_lowercase();
+    resume_where(|k| k == key);
+}
+
+/// Clear today's counters and let suspended apps run again
+pub fn reset_usage() {
+    with_state(|state| {
+        state.app_usage = AppUsage {
+            date: today(),
+            ..Default::default()
+        };
+    });
+    resume_suspended();
+}
@@ -0,0 +1,138 @@
+// Which processes have an audio session open (WASAPI on Windows, PulseAudio/PipeWire
+// sink inputs elsewhere), so rules can spare apps that are playing something
+
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::sampler;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AudioSession {
+    pub pid: u32,
+    pub name: String,
+    pub active: bool, // Playing right now, not just open / paused
+}
+
+#[cfg(windows)]
+fn raw_sessions() -> Result<Vec<(u32, bool)>, String> {
+    use windows::core::ComInterface;
+    use windows::Win32::Media::Audio::{
+        eRender, AudioSessionStateActive, IAudioSessionControl2, IAudioSessionManager2,
+        IMMDeviceEnumerator, MMDeviceEnumerator, DEVICE_STATE_ACTIVE,
+    };
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED,
+    };
+
+    let fail = |e: windows::core::Error| format!("Failed to enumerate audio sessions: {}", e);
+    unsafe {
+        // Already initialized on this thread is fine
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).map_err(fail)?;
+        let devices = enumerator
+            .EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)
+            .map_err(fail)?;
+
+        let mut sessions = Vec::new();
+        for i in 0..devices.GetCount().map_err(fail)? {
+            let Ok(device) = devices.Item(i) else {
+                continue;
+            };
+            let Ok(manager) = device.Activate::<IAudioSessionManager2>(CLSCTX_ALL, None) else {
+                continue;
+            };
+            let Ok(list) = manager.GetSessionEnumerator() else {
+                continue;
+            };
+            for j in 0..list.GetCount().unwrap_or(0) {
+                let Some(control) = list
+                    .GetSession(j)
+                    .ok()
+                    .and_then(|s| s.cast::<IAudioSessionControl2>().ok())
+                else {
+                    continue;
+                };
+                let pid = control.GetProcessId().unwrap_or(0);
+                // PID 0 is the system sounds session
+                if pid != 0 {
+                    let active = control.GetState() == Ok(AudioSessionStateActive);
+                    sessions.push((pid, active));
+                }
+            }
+        }
+        Ok(sessions)
+    }
+}
+
+#[cfg(not(windows))]
+fn raw_sessions() -> Result<Vec<(u32, bool)>, String> {
+    pactl_streams("sink-inputs")
+}
+
+// Public API
+pub fn sessions() -> Result<Vec<AudioSession>, String> {
+    let mut raw = raw_sessions()?;
+    raw.sort_unstable();
+    // A process with several streams is active if any of them is
+    raw.dedup_by(|a, b| {
+        if a.0 == b.0 {
+            b.1 |= a.1;
+        }
+        a.0 == b.0
+    });
+    Ok(sampler::with_processes(
+        |_| false,
+        |sys, _| {
+            raw.into_iter()
+                .map(|(pid, active)| AudioSession {
+                    pid,
+                    name: sys
+                        .process(sysinfo::Pid::from_u32(pid))
+                        .map(|p| p.name().to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    active,
+                })
+                .collect()
+        },
+    ))
+}
+
+/// Parse `pactl list <list>` (sink-inputs, source-outputs): one block per stream, with
+/// "Corked: yes|no" and an `application.process.id = "1234"` property. Returns (PID, active).
+#[cfg(not(windows))]
+pub fn pactl_streams(list: &str) -> Result<Vec<(u32, bool)>, String> {
+    let output = crate::containers::hidden_command("pactl")
+        .args(["list", list])
+        .output()
+        .map_err(|e| format!("pactl is not available: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    let mut streams = Vec::new();
+    let mut corked = false;
+    for line in String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+    {
+        if let Some(value) = line.strip_prefix("Corked:") {
+            corked = value.trim() == "yes";
+        } else if let Some(value) = line.strip_prefix("application.process.id = ") {
+            if let Ok(pid) = value.trim_matches('"').parse() {
+                streams.push((pid, !corked));
+            }
+        }
+    }
+    Ok(streams)
+}
+
+/// PIDs playing audio right now; empty if sessions can't be read
+pub fn playing_pids() -> HashSet<u32> {
+    raw_sessions()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(_, active)| *active)
+        .map(|(pid, _)| pid)
+        .collect()
+}
@@ -0,0 +1,175 @@
+// Process enumeration and killing behind a trait, so matching and kill logic can run
+// against an in-memory process table instead of the real system
+
+use serde::Serialize;
+use std::collections::HashSet;
+#[cfg(test)]
+use std::sync::Mutex;
+use sysinfo::{Pid, Process, ProcessesToUpdate, System};
+
+use crate::{process_ctl, protection, read_state, sampler};
+
+/// One process as the kill logic sees it
+#[derive(Clone, Debug)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub cpu: f32, // Normalized across all cores
+    pub memory_kb: u64,
+    pub start_time: u64, // Unix seconds; tells a recycled PID apart from the original
+    pub exe_path: Option<String>,
+    pub own: bool,       // This app, its children or its WebView2 hosts
+    pub protected: bool, // On the built-in protected list
+}
+
+/// Why a process survived a kill
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KillErrorKind {
+    NotFound,     // Exited (or its PID was recycled) before we got to it
+    AccessDenied, // Usually needs admin rights
+    StillRunning, // Signalled but didn't exit, even after escalating
+    Protected,    // On the built-in protected list
+    OwnProcess,   // Part of this app
+    NeverTouch,   // Its executable is rated "never touch" in the process notes
+}
+
+/// Result of killing one instance in a group operation
+#[derive(Serialize, Clone, Debug)]
+pub struct KillOutcome {
+    pub pid: u32,
+    pub name: String,
+    pub success: bool,
+    pub error_kind: Option<KillErrorKind>,
+    pub freed_memory_kb: u64,
+}
+
+pub trait ProcessBackend {
+    /// Processes whose name `wanted` selects
+    fn processes(&self, wanted: &dyn Fn(&str) -> bool) -> Vec<ProcessInfo>;
+    fn process(&self, pid: u32) -> Option<ProcessInfo>;
+    /// Kill `target`, but only if its PID still belongs to the same process
+    fn kill(&self, target: &ProcessInfo) -> Result<(), KillErrorKind>;
+}
+
+// ============= sysinfo =============
+
+/// The real process table, read through the shared sampler
+pub struct SysinfoBackend;
+
+fn info(p: &Process, cpu_count: f32, own_pids: &HashSet<u32>) -> ProcessInfo {
+    ProcessInfo {
+        pid: p.pid().as_u32(),
+        name: p.name().to_string_lossy().to_string(),
+        cpu: p.cpu_usage() / cpu_count,
+        memory_kb: p.memory() / 1024,
+        start_time: p.start_time(),
+        exe_path: p.exe().map(|e| e.to_string_lossy().to_string()),
+        own: own_pids.contains(&p.pid().as_u32()),
+        protected: protection::check(p, false).is_err(),
+    }
+}
+
+impl ProcessBackend for SysinfoBackend {
+    fn processes(&self, wanted: &dyn Fn(&str) -> bool) -> Vec<ProcessInfo> {
+        let is_wanted = |p: &Process| wanted(&p.name().to_string_lossy());
+        sampler::with_processes(is_wanted, |sys, cpu_count| {
+            let own_pids = protection::own_pids(sys);
+            sys.processes()
+                .values()
+                .filter(|p| is_wanted(p))
+                .map(|p| info(p, cpu_count, &own_pids))
+                .collect()
+        })
+    }
+
+    fn process(&self, pid: u32) -> Option<ProcessInfo> {
+        let pid = Pid::from_u32(pid);
+        sampler::with_processes(
+            |p| p.pid() == pid,
+            |sys, cpu_count| {
+                let own_pids = protection::own_pids(sys);
+                sys.process(pid).map(|p| info(p, cpu_count, &own_pids))
+            },
+        )
+    }
+
+    fn kill(&self, target: &ProcessInfo) -> Result<(), KillErrorKind> {
+        let mut sys = System::new();
+        let pid = Pid::from_u32(target.pid);
+        sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+
+        let process = sys
+            .process(pid)
+            .filter(|p| p.start_time() == target.start_time)
+            .ok_or(KillErrorKind::NotFound)?;
+        let policy = read_state(|state| state.settings.kill_escalation.clone());
+        process_ctl::terminate(process, &policy)
+    }
+}
+
+// ============= In-memory =============
+
+/// Fixed process table for exercising rule and kill logic without touching real processes
+#[cfg(test)]
+#[derive(Default)]
+pub struct MockBackend {
+    processes: Mutex<Vec<ProcessInfo>>,
+    failing: Vec<u32>, // Kills of these PIDs fail as if access were denied
+}
+
+#[cfg(test)]
+impl MockBackend {
+    pub fn new(processes: Vec<ProcessInfo>) -> Self {
+        Self {
+            processes: Mutex::new(processes),
+            failing: Vec::new(),
+        }
+    }
+
+    pub fn fail_kill(mut self, pid: u32) -> Self {
+        self.failing.push(pid);
+        self
+    }
+
+    /// What is still "running" after the kills so far
+    pub fn running(&self) -> Vec<ProcessInfo> {
+        self.processes.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl ProcessBackend for MockBackend {
+    fn processes(&self, wanted: &dyn Fn(&str) -> bool) -> Vec<ProcessInfo> {
+        self.processes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|p| wanted(&p.name))
+            .cloned()
+            .collect()
+    }
+
+    fn process(&self, pid: u32) -> Option<ProcessInfo> {
+        self.processes
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|p| p.pid == pid)
+            .cloned()
+    }
+
+    fn kill(&self, target: &ProcessInfo) -> Result<(), KillErrorKind> {
+        if self.failing.contains(&target.pid) {
+            return Err(KillErrorKind::AccessDenied);
+        }
+        let mut processes = self.processes.lock().unwrap();
+        let before = processes.len();
+        processes.retain(|p| p.pid != target.pid || p.start_time != target.start_time);
+        if processes.len() < before {
+            Ok(())
+        } else {
+            Err(KillErrorKind::NotFound)
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// Whole-app backup in one zip: the state (rules, settings, activity log), the stats history
+// and config.toml, plus a manifest. For moving to another machine, or a checkpoint before
+// experimenting with the config. Diagnostic logs and per-session runtime state are left out.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::{data_dir, flush_state, read_state, with_state, AppState};
+
+const MANIFEST: &str = "manifest.json";
+const STATE_FILE: &str = "blacklist_data.json";
+// Restored as-is; anything else in an archive is ignored
+const EXTRA_FILES: [&str; 2] = ["stats_history.json", "config.toml"];
+// Bumped when the archive layout changes; older archives stay restorable
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Manifest {
+    pub format: u32,
+    pub app_version: String,
+    pub created_at: String,
+    pub machine: String,
+    pub files: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct RestoreResult {
+    pub manifest: Manifest,
+    pub safety_backup: String, // What was replaced, in case the restore was a mistake
+}
+
+fn now() -> String {
+    Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn write_entry(zip: &mut ZipWriter<File>, name: &str, data: &[u8]) -> Result<(), String> {
+    zip.start_file(name, SimpleFileOptions::default())
+        .map_err(|e| e.to_string())?;
+    zip.write_all(data).map_err(|e| e.to_string())
+}
+
+fn read_entry(zip: &mut ZipArchive<File>, name: &str) -> Result<Option<Vec<u8>>, String> {
+    let mut entry = match zip.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+    let mut data = Vec::new();
+    entry
+        .read_to_end(&mut data)
+        .map_err(|e| format!("{}: {}", name, e))?;
+    Ok(Some(data))
+}
+
+/// Write everything to `path`; the state comes from memory so it matches what is running
+fn write_archive(path: &Path) -> Result<Manifest, String> {
+    flush_state();
+    let state = read_state(serde_json::to_vec_pretty).map_err(|e| e.to_string())?;
+    let extras: Vec<(&str, Vec<u8>)> = EXTRA_FILES
+        .iter()
+        .filter_map(|name| {
+            fs::read(data_dir().join(name))
+                .ok()
+                .map(|data| (*name, data))
+        })
+        .collect();
+
+    let mut files = vec![STATE_FILE.to_string()];
+    files.extend(extras.iter().map(|(name, _)| name.to_string()));
+    let manifest = Manifest {
+        format: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: now(),
+        machine: sysinfo::System::host_name().unwrap_or_default(),
+        files,
+    };
+
+    let file = File::create(path).map_err(|e| format!("Failed to create backup: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let manifest_data = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    write_entry(&mut zip, MANIFEST, &manifest_data)?;
+    write_entry(&mut zip, STATE_FILE, &state)?;
+    for (name, data) in &extras {
+        write_entry(&mut zip, name, data)?;
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to write backup: {}", e))?;
+    Ok(manifest)
+}
+
+// Public API
+/// Zip the app's data to `path`
+pub fn backup_all(path: &Path) -> Result<Manifest, String> {
+    if path.is_dir() {
+        return Err("Pick a file name for the backup, not a folder".into());
+    }
+    let manifest = write_archive(path)?;
+    tracing::info!(path = %path.display(), "backup written");
+    Ok(manifest)
+}
+
+/// Replace the app's data with the archive at `path`. Everything is read and checked before
+/// anything is touched, and the current data is saved to backups/ first.
+pub fn restore_all(path: &Path) -> Result<RestoreResult, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut zip = ZipArchive::new(file).map_err(|_| "Not a backup archive".to_string())?;
+
+    let manifest: Manifest = read_entry(&mut zip, MANIFEST)?
+        .ok_or("Not a backup archive (no manifest)")
+        .and_then(|data| {
+            serde_json::from_slice(&data).map_err(|_| "The backup manifest is unreadable")
+        })?;
+    if manifest.format > FORMAT_VERSION {
+        return Err(format!(
+            "This backup was made by a newer version ({}); update the app to restore it",
+            manifest.app_version
+        ));
+    }
+    let state: AppState = read_entry(&mut zip, STATE_FILE)?
+        .ok_or("The backup has no state file")
+        .map_err(String::from)
+        .and_then(|data| {
+            serde_json::from_slice(&data).map_err(|e| format!("The backup state is invalid: {}", e))
+        })?;
+    let mut extras = Vec::new();
+    for name in EXTRA_FILES {
+        if let Some(data) = read_entry(&mut zip, name)? {
+            extras.push((name, data));
+        }
+    }
+
+    let backups = data_dir().join("backups");
+    fs::create_dir_all(&backups)
+        .map_err(|e| format!("Failed to create {}: {}", backups.display(), e))?;
+    let safety = backups.join(format!(
+        "before-restore-{}.zip",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    write_archive(&safety)?;
+
+    for (name, data) in &extras {
+        let target = data_dir().join(name);
+        fs::write(&target, data)
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+    }
+    // The restored config.toml belongs with the restored state
+    crate::config_file::mark_seen();
+    crate::digest::reload_history();
+    let settings = state.settings.clone();
+    with_state(|current| *current = state);
+    crate::logging::set_level(&settings.log_level).ok();
+    crate::apply_settings(&settings);
+
+    tracing::info!(
+        path = %path.display(),
+        from = %manifest.machine,
+        created_at = %manifest.created_at,
+        "backup restored"
+    );
+    Ok(RestoreResult {
+        manifest,
+        safety_backup: safety.to_string_lossy().to_string(),
+    })
+}
@@ -0,0 +1,310 @@
+// Per-process baselines: a slowly moving average of the CPU and memory each watched process
+// (anything a rule matches) normally uses, learned once a minute and kept across restarts.
+// A process running well above its usual level raises "anomaly-detected", and rules can use
+// the same comparison as a trigger ("kill at 3x its normal memory").
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::matcher::Matcher;
+use crate::{notify_event, read_state};
+
+const TICK: Duration = Duration::from_secs(60);
+// Readings needed before a baseline is trusted
+const MIN_SAMPLES: u32 = 30;
+// Readings the average spans once learned, about a day of use
+const WINDOW_SAMPLES: u32 = 24 * 60;
+const ALERT_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AnomalyPolicy {
+    pub enabled: bool, // Raise "anomaly-detected"; baselines are learned either way
+    pub factor: f32,   // Multiple of the baseline that counts as anomalous
+    // Below these a process is never anomalous, so idle apps waking up don't alert
+    pub min_cpu: f32,
+    pub min_memory_mb: u64,
+}
+
+impl Default for AnomalyPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            factor: 3.0,
+            min_cpu: 10.0,
+            min_memory_mb: 100,
+        }
+    }
+}
+
+fn validate_factor(factor: f32) -> Result<(), String> {
+    if !(1.5..=100.0).contains(&factor) {
+        return Err("Factor must be between 1.5 and 100".into());
+    }
+    Ok(())
+}
+
+impl AnomalyPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_factor(self.factor)?;
+        if !(0.0..=100.0).contains(&self.min_cpu) {
+            return Err("Minimum CPU must be between 0 and 100%".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Cpu,
+    #[default]
+    Memory,
+    Either,
+}
+
+/// Rule trigger: act when the process uses `factor` times its usual amount
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AnomalyTrigger {
+    pub metric: Metric,
+    pub factor: f32,
+}
+
+impl AnomalyTrigger {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_factor(self.factor)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct Baseline {
+    cpu: f32,
+    memory_mb: f32,
+    samples: u32,
+}
+
+/// A reading well above the baseline
+#[derive(Serialize, Clone)]
+pub struct Anomaly {
+    pub metric: String, // "cpu" or "memory"
+    pub current: f32,   // CPU percent or memory MB
+    pub usual: f32,
+    pub ratio: f32,
+}
+
+/// Sent as "anomaly-detected"
+#[derive(Serialize, Clone)]
+struct AnomalyEvent {
+    pid: u32,
+    name: String,
+    #[serde(flatten)]
+    anomaly: Anomaly,
+}
+
+#[derive(Serialize, Clone)]
+pub struct BaselineInfo {
+    pub name: String,
+    pub cpu: f32,
+    pub memory_mb: f32,
+    pub samples: u32,
+    pub learned: bool,
+}
+
+// Keyed by lower-case process name
+static BASELINES: Mutex<Option<HashMap<String, Baseline>>> = Mutex::new(None);
+
+fn baselines_path() -> PathBuf {
+    crate::data_dir().join("baselines.json")
+}
+
+fn with_baselines<R>(f: impl FnOnce(&mut HashMap<String, Baseline>) -> R) -> R {
+    let mut guard = BASELINES.lock().unwrap();
+    let baselines = guard.get_or_insert_with(|| {
+        fs::read_to_string(baselines_path())
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    });
+    f(baselines)
+}
+
+fn save(baselines: &HashMap<String, Baseline>) {
+    if let Ok(data) = serde_json::to_string(baselines) {
+        fs::write(baselines_path(), data).ok();
+    }
+}
+
+/// The highest reading at or above `factor` times its baseline, if any
+fn compare(
+    baseline: &Baseline,
+    metric: Metric,
+    factor: f32,
+    cpu: f32,
+    memory_mb: f32,
+    policy: &AnomalyPolicy,
+) -> Option<Anomaly> {
+    if baseline.samples < MIN_SAMPLES {
+        return None;
+    }
+    let cpu =
+        (metric != Metric::Memory && cpu >= policy.min_cpu).then(|| ("cpu", cpu, baseline.cpu));
+    let memory = (metric != Metric::Cpu && memory_mb >= policy.min_memory_mb as f32)
+        .then(|| ("memory", memory_mb, baseline.memory_mb));
+    cpu.into_iter()
+        .chain(memory)
+        // A near-zero baseline would make any blip look huge
+        .map(|(name, current, usual)| (name, current, usual.max(0.1)))
+        .filter(|(_, current, usual)| *current >= usual * factor)
+        .map(|(name, current, usual)| Anomaly {
+            metric: name.to_string(),
+            current,
+            usual,
+            ratio: current / usual,
+        })
+        .max_by(|a, b| a.ratio.total_cmp(&b.ratio))
+}
+
+fn tick(alerted: &mut HashMap<(u32, u64), Instant>) {
+    let policy = read_state(|state| state.settings.anomalies.clone());
+    let rules: Vec<Matcher> =
+        read_state(|state| state.blacklist.iter().map(Matcher::for_rule).collect());
+    if rules.is_empty() {
+        return;
+    }
+    let watched = |name: &str| rules.iter().any(|m| m.matches(name));
+    // (pid, start time, name, CPU, memory MB)
+    let readings: Vec<(u32, u64, String, f32, f32)> = crate::sampler::with_processes(
+        |p| watched(&p.name().to_string_lossy()),
+        |sys, cpu_count| {
+            let own_pids = crate::protection::own_pids(sys);
+            sys.processes()
+                .values()
+                .filter(|p| !own_pids.contains(&p.pid().as_u32()))
+                .filter(|p| watched(&p.name().to_string_lossy()))
+                .map(|p| {
+                    (
+                        p.pid().as_u32(),
+                        p.start_time(),
+                        p.name().to_string_lossy().to_string(),
+                        p.cpu_usage() / cpu_count,
+                        p.memory() as f32 / (1024.0 * 1024.0),
+                    )
+                })
+                .collect()
+        },
+    );
+
+    let mut anomalies: Vec<AnomalyEvent> = vec![];
+    with_baselines(|baselines| {
+        for (pid, start_time, name, cpu, memory_mb) in &readings {
+            let baseline = baselines.entry(name.to_lowercase()).or_default();
+            let anomaly = compare(
+                baseline,
+                Metric::Either,
+                policy.factor,
+                *cpu,
+                *memory_mb,
+                &policy,
+            );
+            // Anomalous readings stay out of the average, or a runaway would become normal
+            match anomaly {
+                Some(anomaly) => {
+                    let key = (*pid, *start_time);
+                    if policy.enabled
+                        && alerted
+                            .get(&key)
+                            .is_none_or(|at| at.elapsed() >= ALERT_COOLDOWN)
+                    {
+                        alerted.insert(key, Instant::now());
+                        anomalies.push(AnomalyEvent {
+                            pid: *pid,
+                            name: name.clone(),
+                            anomaly,
+                        });
+                    }
+                }
+                None => {
+                    // Plain mean while learning, then a moving average over the window
+                    baseline.samples = baseline.samples.saturating_add(1);
+                    let weight = 1.0 / baseline.samples.min(WINDOW_SAMPLES) as f32;
+                    baseline.cpu += (cpu - baseline.cpu) * weight;
+                    baseline.memory_mb += (memory_mb - baseline.memory_mb) * weight;
+                }
+            }
+        }
+        save(baselines);
+    });
+    let alive: HashSet<(u32, u64)> = readings.iter().map(|r| (r.0, r.1)).collect();
+    alerted.retain(|key, _| alive.contains(key));
+
+    for event in anomalies {
+        tracing::info!(
+            pid = event.pid,
+            name = %event.name,
+            metric = %event.anomaly.metric,
+            ratio = event.anomaly.ratio,
+            "usage anomaly"
+        );
+        notify_event("anomaly-detected", event);
+    }
+}
+
+// Public API
+pub fn start_recorder() {
+    thread::spawn(|| {
+        let mut alerted: HashMap<(u32, u64), Instant> = HashMap::new();
+        loop {
+            tick(&mut alerted);
+            thread::sleep(crate::power::interval(TICK));
+        }
+    });
+}
+
+/// Whether a process named `name` is over the rule's anomaly trigger right now
+pub fn check(trigger: &AnomalyTrigger, name: &str, cpu: f32, memory_mb: f32) -> Option<Anomaly> {
+    let policy = read_state(|state| state.settings.anomalies.clone());
+    with_baselines(|baselines| {
+        let baseline = baselines.get(&name.to_lowercase())?;
+        compare(
+            baseline,
+            trigger.metric,
+            trigger.factor,
+            cpu,
+            memory_mb,
+            &policy,
+        )
+    })
+}
+
+pub fn list() -> Vec<BaselineInfo> {
+    let mut list: Vec<BaselineInfo> = with_baselines(|baselines| {
+        baselines
+            .iter()
+            .map(|(name, b)| BaselineInfo {
+                name: name.clone(),
+                cpu: b.cpu,
+                memory_mb: b.memory_mb,
+                samples: b.samples,
+                learned: b.samples >= MIN_SAMPLES,
+            })
+            .collect()
+    });
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    list
+}
+
+/// Forget what was learned, e.g. after an update legitimately changed the app's usage
+pub fn reset(name: &str) -> bool {
+    with_baselines(|baselines| {
+        let removed = baselines.remove(&name.to_lowercase()).is_some();
+        if removed {
+            save(baselines);
+        }
+        removed
+    })
+}
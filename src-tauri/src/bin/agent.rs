@@ -0,0 +1,41 @@
+// Headless agent: enforces the blacklist and exposes the REST API for remote consoles.
+//
+// Usage: akt-agent [--bind 127.0.0.1:7878] [--token <secret>] [--syslog]
+//                  [--data-dir <path>] [--poll-interval <ms>] [--dry-run]
+//                  [--api-port <port>] [--log-level <level>]
+// The token can also be supplied through the AKT_AGENT_TOKEN environment variable.
+// The API is plain HTTP and only listens locally by default; to reach it from another machine,
+// put it behind a TLS reverse proxy rather than binding it to a public address.
+// --syslog mirrors activity log entries to journald/syslog.
+// The other flags override saved settings for this run; each has an AKT_* environment
+// variable counterpart (see src/overrides.rs).
+
+fn main() {
+    let mut bind = "127.0.0.1:7878".to_string();
+    let mut token = std::env::var("AKT_AGENT_TOKEN").unwrap_or_default();
+
+    let args = match tauri_app_lib::agent::init_overrides(&std::env::args().collect::<Vec<_>>()) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bind" => bind = args.next().unwrap_or(bind),
+            "--token" => token = args.next().unwrap_or(token),
+            "--syslog" => tauri_app_lib::agent::enable_syslog(),
+            other => {
+                eprintln!("Unknown argument: {}", other);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if let Err(e) = tauri_app_lib::agent::serve(&bind, &token) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
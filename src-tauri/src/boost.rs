@@ -0,0 +1,202 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::thread;
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::matcher::Matcher;
+use crate::{
+    notes, process_ctl, process_log, protection, read_state, record_activity, sampler, with_state,
+    ActivityLog,
+};
+
+// Processes above this working set get trimmed after the blacklist pass
+const HEAVY_MEMORY_BYTES: u64 = 500 * 1024 * 1024;
+
+#[derive(Serialize)]
+pub struct BoostSummary {
+    pub killed: u32,
+    pub suspended: u32,
+    pub trimmed: u32,
+    pub failed: u32,
+    pub memory_freed_mb: f64,
+    pub cpu_before: f32,
+    pub cpu_after: f32,
+    pub details: Vec<String>,
+}
+
+// PIDs suspended by the last boost, resumed by `end_boost`
+static SUSPENDED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// System CPU and memory, measured twice so CPU usage has a baseline to diff against.
+/// Processes are left to the shared sampler.
+fn sample(sys: &mut System) {
+    sys.refresh_cpu_usage();
+    thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_cpu_usage();
+    sys.refresh_memory();
+}
+
+/// A matching process as sampled, acted on once the sampler lock is released
+struct Target {
+    pid: Pid,
+    start_time: u64,
+    guard: Result<(), String>, // Protected or marked never touch
+    log: ActivityLog,
+}
+
+// Public API
+/// Kill (or suspend) every auto-kill blacklist entry now and trim the remaining heavy processes
+pub fn boost_now(suspend: bool) -> BoostSummary {
+    let rules: Vec<Matcher> = read_state(|state| {
+        state
+            .blacklist
+            .iter()
+            .filter(|e| e.auto_kill)
+            .map(Matcher::for_rule)
+            .collect()
+    });
+
+    let mut sys = System::new();
+    sample(&mut sys);
+    let memory_before = sys.used_memory();
+    let cpu_before = sys.global_cpu_usage();
+
+    let mut summary = BoostSummary {
+        killed: 0,
+        suspended: 0,
+        trimmed: 0,
+        failed: 0,
+        memory_freed_mb: 0.0,
+        cpu_before,
+        cpu_after: cpu_before,
+        details: vec![],
+    };
+    let users = sysinfo::Users::new_with_refreshed_list();
+    let allow_protected = protection::override_enabled();
+    let escalation = read_state(|state| state.settings.kill_escalation.clone());
+
+    let is_wanted = |p: &Process| {
+        let name = p.name().to_string_lossy();
+        rules.iter().any(|r| r.matches(&name))
+    };
+    let targets: Vec<Target> = sampler::with_processes(is_wanted, |sys, cpu_count| {
+        let own_pids = protection::own_pids(sys);
+        sys.processes()
+            .values()
+            .filter(|p| is_wanted(p) && !own_pids.contains(&p.pid().as_u32()))
+            .map(|p| Target {
+                pid: p.pid(),
+                start_time: p.start_time(),
+                guard: protection::check(p, allow_protected)
+                    .map_err(|e| e.to_string())
+                    .and_then(|_| notes::check_process(p)),
+                log: ActivityLog {
+                    cpu_usage: p.cpu_usage() / cpu_count,
+                    ..process_log(sys, &users, p)
+                },
+            })
+            .collect()
+    });
+
+    // Fresh handles for the kills, so a PID recycled since the sample is left alone
+    let pids: Vec<Pid> = targets.iter().map(|t| t.pid).collect();
+    let mut fresh = System::new();
+    fresh.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&pids),
+        true,
+        ProcessRefreshKind::everything(),
+    );
+    let mut logs = vec![];
+    for target in targets {
+        if let Err(err) = target.guard {
+            summary.details.push(err);
+            continue;
+        }
+        let pid = target.pid.as_u32();
+        let Some(p) = fresh
+            .process(target.pid)
+            .filter(|p| p.start_time() == target.start_time)
+        else {
+            continue;
+        };
+
+        let ok = if suspend {
+            let ok = process_ctl::suspend_process(pid).is_ok();
+            if ok {
+                SUSPENDED.lock().unwrap().push(pid);
+                summary.suspended += 1;
+            }
+            ok
+        } else {
+            let ok = process_ctl::terminate(p, &escalation).is_ok();
+            if ok {
+                summary.killed += 1;
+            }
+            ok
+        };
+        if !ok {
+            summary.failed += 1;
+            summary
+                .details
+                .push(format!("Could not stop {} (PID {})", target.log.name, pid));
+        }
+
+        logs.push(ActivityLog {
+            was_killed: ok && !suspend,
+            reason: match (ok, suspend) {
+                (true, false) => "Boost mode - killed".to_string(),
+                (true, true) => "Boost mode - suspended".to_string(),
+                (false, _) => "Boost mode - failed (permission denied?)".to_string(),
+            },
+            freed_memory_kb: if ok && !suspend {
+                target.log.memory_kb
+            } else {
+                0
+            },
+            ..target.log
+        });
+    }
+
+    // Trimming needs everyone's working set; the sampler keeps that cached between callers
+    let heavy: Vec<u32> = sampler::with_all_processes(|sys, _| {
+        let own_pids = protection::own_pids(sys);
+        sys.processes()
+            .values()
+            .filter(|p| !is_wanted(p) && !own_pids.contains(&p.pid().as_u32()))
+            .filter(|p| p.memory() >= HEAVY_MEMORY_BYTES)
+            .map(|p| p.pid().as_u32())
+            .collect()
+    });
+    for pid in heavy {
+        if process_ctl::trim_memory(pid).is_ok() {
+            summary.trimmed += 1;
+        }
+    }
+
+    if !logs.is_empty() {
+        with_state(|state| {
+            for log in logs {
+                record_activity(state, log);
+            }
+        });
+    }
+
+    sample(&mut sys);
+    summary.memory_freed_mb =
+        memory_before.saturating_sub(sys.used_memory()) as f64 / 1024.0 / 1024.0;
+    summary.cpu_after = sys.global_cpu_usage();
+    summary
+}
+
+/// PIDs suspended by the last boost and not resumed yet
+pub fn suspended_pids() -> Vec<u32> {
+    SUSPENDED.lock().unwrap().clone()
+}
+
+/// Resume everything the last boost suspended; returns how many processes came back
+pub fn end_boost() -> u32 {
+    let pids: Vec<u32> = SUSPENDED.lock().unwrap().drain(..).collect();
+    pids.into_iter()
+        .filter(|pid| process_ctl::resume_process(*pid).is_ok())
+        .count() as u32
+}
@@ -0,0 +1,116 @@
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+use sysinfo::System;
+
+use crate::{read_state, sampler, with_state};
+
+// Processes are sampled this often while inside the boot window
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+// Length of the window after boot that counts as "startup"
+const BOOT_WINDOW_SECS: u64 = 5 * 60;
+
+/// Startup cost of one app during the boot window
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BootApp {
+    pub name: String,
+    pub first_seen_secs: u64, // Seconds after boot when it was first seen
+    pub peak_memory_kb: u64,
+    pub cpu_seconds: f32, // Approximate CPU time used across all instances
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct BootReport {
+    pub boot_time: u64, // Unix seconds
+    pub booted_at: String,
+    pub window_minutes: u64,
+    pub complete: bool, // False when the app started late or is still sampling
+    pub apps: Vec<BootApp>,
+}
+
+fn sample(apps: &mut HashMap<String, BootApp>, uptime: u64) {
+    // Sum instances first so peak memory is per app, not per process
+    let totals = sampler::with_all_processes(|sys, cpu_count| {
+        let mut totals: HashMap<String, (u64, f32)> = HashMap::new();
+        for p in sys.processes().values() {
+            let total = totals
+                .entry(p.name().to_string_lossy().to_string())
+                .or_default();
+            total.0 += p.memory() / 1024;
+            total.1 += p.cpu_usage() / cpu_count;
+        }
+        totals
+    });
+
+    for (name, (memory_kb, cpu)) in totals {
+        let app = apps.entry(name.clone()).or_insert(BootApp {
+            name,
+            first_seen_secs: uptime,
+            peak_memory_kb: 0,
+            cpu_seconds: 0.0,
+        });
+        app.peak_memory_kb = app.peak_memory_kb.max(memory_kb);
+        app.cpu_seconds += cpu / 100.0 * SAMPLE_INTERVAL.as_secs_f32();
+    }
+}
+
+fn save(boot_time: u64, apps: &HashMap<String, BootApp>, complete: bool) {
+    let mut apps: Vec<BootApp> = apps.values().cloned().collect();
+    // Most expensive first
+    apps.sort_by(|a, b| {
+        b.cpu_seconds
+            .partial_cmp(&a.cpu_seconds)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let booted_at = Local
+        .timestamp_opt(boot_time as i64, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
+    with_state(|state| {
+        state.boot_impact = Some(BootReport {
+            boot_time,
+            booted_at,
+            window_minutes: BOOT_WINDOW_SECS / 60,
+            complete,
+            apps,
+        });
+    });
+}
+
+// Public API
+/// Sample processes until the boot window closes; does nothing when the app starts too late
+pub fn start_recorder() {
+    let boot_time = System::boot_time();
+    let already_recorded = read_state(|state| {
+        state
+            .boot_impact
+            .as_ref()
+            .is_some_and(|r| r.boot_time == boot_time && r.complete)
+    });
+    if already_recorded || System::uptime() >= BOOT_WINDOW_SECS {
+        return;
+    }
+
+    thread::spawn(move || {
+        let mut apps: HashMap<String, BootApp> = HashMap::new();
+        loop {
+            thread::sleep(SAMPLE_INTERVAL);
+            let uptime = System::uptime();
+            if uptime >= BOOT_WINDOW_SECS {
+                save(boot_time, &apps, true);
+                return;
+            }
+            sample(&mut apps, uptime);
+            // Saved as it goes so a crash mid-window still leaves a partial report
+            save(boot_time, &apps, false);
+        }
+    });
+}
+
+pub fn report() -> Option<BootReport> {
+    read_state(|state| state.boot_impact.clone())
+}
@@ -0,0 +1,110 @@
+// What this install is allowed to do, so the UI can explain degraded features up front
+// instead of leaving users to wonder why a kill failed or GPU usage reads 0
+
+use serde::Serialize;
+
+use crate::{gpu, i18n, notifications, process_ctl, protection, sampler};
+
+// Enough to tell "never allowed" from one process that happens to be locked down
+const KILL_PROBES: usize = 5;
+
+#[derive(Serialize, Clone)]
+pub struct Capabilities {
+    pub elevated: bool,
+    pub gpu_counters: bool,
+    pub kill_other_users: Option<bool>, // None when no other user's process was running to test
+    pub notifications: Option<bool>,    // None where the app shows no notifications
+    pub autostart: Option<bool>,        // Can register to start with the system
+    pub notes: Vec<String>,             // One line per degraded feature, in the UI language
+}
+
+/// Try opening other users' (unprotected) processes for termination without killing them
+fn probe_kill_other_users() -> Option<bool> {
+    let candidates: Vec<u32> = sampler::with_all_processes(|sys, _| {
+        let own_user = sys
+            .process(sysinfo::Pid::from_u32(std::process::id()))
+            .and_then(|p| p.user_id().cloned());
+        sys.processes()
+            .values()
+            .filter(|p| p.pid().as_u32() > 4) // Idle and System
+            .filter(|p| p.user_id().is_some() && p.user_id() != own_user.as_ref())
+            .filter(|p| protection::check(p, false).is_ok())
+            .map(|p| p.pid().as_u32())
+            .take(KILL_PROBES)
+            .collect()
+    });
+    if candidates.is_empty() {
+        return None;
+    }
+    Some(candidates.into_iter().any(process_ctl::can_terminate))
+}
+
+fn autostart_allowed() -> Option<bool> {
+    #[cfg(windows)]
+    {
+        use windows::core::HSTRING;
+        use windows::Win32::System::Registry::{
+            RegCloseKey, RegOpenKeyExW, HKEY, HKEY_CURRENT_USER, KEY_SET_VALUE,
+        };
+
+        let mut key = HKEY::default();
+        unsafe {
+            let opened = RegOpenKeyExW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(r"Software\Microsoft\Windows\CurrentVersion\Run"),
+                0,
+                KEY_SET_VALUE,
+                &mut key,
+            )
+            .is_ok();
+            if opened {
+                let _ = RegCloseKey(key);
+            }
+            Some(opened)
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        // XDG autostart entries
+        let dir = dirs::config_dir()?.join("autostart");
+        Some(
+            std::fs::create_dir_all(&dir).is_ok()
+                && std::fs::metadata(&dir).is_ok_and(|m| !m.permissions().readonly()),
+        )
+    }
+}
+
+// Public API
+pub fn check() -> Capabilities {
+    let elevated = crate::is_running_as_admin();
+    let gpu_counters = gpu::is_available();
+    let kill_other_users = probe_kill_other_users();
+    let notifications = notifications::permitted();
+    let autostart = autostart_allowed();
+
+    let mut notes = Vec::new();
+    if !elevated {
+        notes.push(i18n::t("capability.not_elevated", &[]));
+    }
+    if !gpu_counters {
+        notes.push(i18n::t("capability.no_gpu_counters", &[]));
+    }
+    if kill_other_users == Some(false) {
+        notes.push(i18n::t("capability.cannot_kill_others", &[]));
+    }
+    if notifications == Some(false) {
+        notes.push(i18n::t("capability.notifications_off", &[]));
+    }
+    if autostart == Some(false) {
+        notes.push(i18n::t("capability.no_autostart", &[]));
+    }
+
+    Capabilities {
+        elevated,
+        gpu_counters,
+        kill_other_users,
+        notifications,
+        autostart,
+        notes,
+    }
+}
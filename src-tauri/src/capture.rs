@@ -0,0 +1,215 @@
+// Which processes are using the webcam or microphone: the capability access registry on
+// Windows, open /dev/video* and capture PCM handles plus PulseAudio/PipeWire source
+// outputs elsewhere
+
+use serde::Serialize;
+use std::collections::HashMap;
+use sysinfo::{Process, System};
+
+use crate::sampler;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Device {
+    Camera,
+    Microphone,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CaptureUse {
+    pub pid: u32,
+    pub name: String,
+    pub devices: Vec<Device>,
+}
+
+fn add(usage: &mut HashMap<u32, Vec<Device>>, pid: u32, device: Device) {
+    let devices = usage.entry(pid).or_default();
+    if !devices.contains(&device) {
+        devices.push(device);
+        devices.sort();
+    }
+}
+
+#[cfg(windows)]
+mod registry {
+    use windows::core::{HSTRING, PWSTR};
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegEnumKeyExW, RegGetValueW, RegOpenKeyExW, HKEY, HKEY_CURRENT_USER, KEY_READ,
+        RRF_RT_REG_QWORD,
+    };
+
+    /// Names of the subkeys of HKCU\`path`
+    pub fn subkeys(path: &str) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut key = HKEY::default();
+        unsafe {
+            if RegOpenKeyExW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(path),
+                0,
+                KEY_READ,
+                &mut key,
+            )
+            .is_err()
+            {
+                return names;
+            }
+            let mut buf = [0u16; 512];
+            let mut index = 0;
+            loop {
+                let mut len = buf.len() as u32;
+                let result = RegEnumKeyExW(
+                    key,
+                    index,
+                    PWSTR(buf.as_mut_ptr()),
+                    &mut len,
+                    None,
+                    PWSTR::null(),
+                    None,
+                    None,
+                );
+                if result.is_err() {
+                    break;
+                }
+                names.push(String::from_utf16_lossy(&buf[..len as usize]));
+                index += 1;
+            }
+            let _ = RegCloseKey(key);
+        }
+        names
+    }
+
+    pub fn qword(path: &str, value: &str) -> Option<u64> {
+        let mut data = 0u64;
+        let mut size = std::mem::size_of::<u64>() as u32;
+        unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(path),
+                &HSTRING::from(value),
+                RRF_RT_REG_QWORD,
+                None,
+                Some(&mut data as *mut u64 as *mut _),
+                Some(&mut size),
+            )
+        }
+        .ok()
+        .map(|_| data)
+    }
+}
+
+/// Devices each capturing process among those `wanted` selects is using, keyed by PID.
+/// Windows records every app's camera/microphone access; an entry that has started but not
+/// stopped (LastUsedTimeStop = 0) is in use right now.
+/// Takes the sampled `sys` so callers already inside `sampler::with_processes` can use it.
+#[cfg(windows)]
+pub fn usage(sys: &System, wanted: impl Fn(&Process) -> bool) -> HashMap<u32, Vec<Device>> {
+    const CONSENT_STORE: &str =
+        r"Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore";
+
+    let in_use = |path: &str| {
+        registry::qword(path, "LastUsedTimeStart").unwrap_or(0) != 0
+            && registry::qword(path, "LastUsedTimeStop") == Some(0)
+    };
+    // Lower-cased executable paths, and install folder prefixes of packaged apps
+    let mut paths: Vec<(String, Device)> = Vec::new();
+    let mut packages: Vec<(String, Device)> = Vec::new();
+    for (capability, device) in [
+        ("webcam", Device::Camera),
+        ("microphone", Device::Microphone),
+    ] {
+        let base = format!(r"{}\{}", CONSENT_STORE, capability);
+        let desktop = format!(r"{}\NonPackaged", base);
+        for app in registry::subkeys(&desktop) {
+            if in_use(&format!(r"{}\{}", desktop, app)) {
+                // "C:#Program Files#App#app.exe"
+                paths.push((app.replace('#', r"\").to_lowercase(), device));
+            }
+        }
+        for package in registry::subkeys(&base) {
+            if package != "NonPackaged" && in_use(&format!(r"{}\{}", base, package)) {
+                // Family "Name_PublisherId" installs to WindowsApps\Name_Version_Arch__PublisherId
+                let name = package.split('_').next().unwrap_or(&package).to_lowercase();
+                packages.push((format!(r"\windowsapps\{}_", name), device));
+            }
+        }
+    }
+
+    let mut usage = HashMap::new();
+    if paths.is_empty() && packages.is_empty() {
+        return usage;
+    }
+    for (pid, p) in sys.processes().iter().filter(|(_, p)| wanted(p)) {
+        let Some(exe) = p.exe().map(|e| e.to_string_lossy().to_lowercase()) else {
+            continue;
+        };
+        let matched = paths.iter().filter(|(path, _)| *path == exe).chain(
+            packages
+                .iter()
+                .filter(|(folder, _)| exe.contains(folder.as_str())),
+        );
+        for (_, device) in matched {
+            add(&mut usage, pid.as_u32(), *device);
+        }
+    }
+    usage
+}
+
+/// Devices each capturing process among those `wanted` selects is using, keyed by PID.
+/// Only their open files are scanned, so the watcher pays for the processes rules match.
+#[cfg(not(windows))]
+pub fn usage(sys: &System, wanted: impl Fn(&Process) -> bool) -> HashMap<u32, Vec<Device>> {
+    let mut usage = HashMap::new();
+    // Only processes we're allowed to inspect show up here
+    for pid in sys
+        .processes()
+        .iter()
+        .filter(|(_, p)| wanted(p))
+        .map(|(pid, _)| pid.as_u32())
+    {
+        let Ok(fds) = std::fs::read_dir(format!("/proc/{}/fd", pid)) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            let target = target.to_string_lossy();
+            if target.starts_with("/dev/video") {
+                add(&mut usage, pid, Device::Camera);
+            } else if target.starts_with("/dev/snd/pcmC") && target.ends_with('c') {
+                // ALSA capture device, e.g. /dev/snd/pcmC0D0c
+                add(&mut usage, pid, Device::Microphone);
+            }
+        }
+    }
+    let streams = crate::audio::pactl_streams("source-outputs").unwrap_or_default();
+    for (pid, active) in streams {
+        let selected = sys
+            .process(sysinfo::Pid::from_u32(pid))
+            .is_some_and(|p| wanted(p));
+        if active && selected {
+            add(&mut usage, pid, Device::Microphone);
+        }
+    }
+    usage
+}
+
+// Public API
+pub fn in_use() -> Vec<CaptureUse> {
+    let mut rows: Vec<CaptureUse> = sampler::with_all_processes(|sys, _| {
+        usage(sys, |_| true)
+            .into_iter()
+            .map(|(pid, devices)| CaptureUse {
+                pid,
+                name: sys
+                    .process(sysinfo::Pid::from_u32(pid))
+                    .map(|p| p.name().to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                devices,
+            })
+            .collect()
+    });
+    rows.sort_by_key(|r| r.pid);
+    rows
+}
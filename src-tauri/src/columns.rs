@@ -0,0 +1,44 @@
+// Column selection for process-list commands, so the frontend can ask for only the fields
+// it shows and skip both the work and the JSON for the rest
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+// Read per process from the OS, so only filled in when a caller lists them
+const OPT_IN: [&str; 2] = ["total_threads", "total_handles"];
+
+/// Requested columns; the default (no list) means every column except the `OPT_IN` ones.
+/// Shared between rows, so cloning is cheap.
+#[derive(Clone, Default)]
+pub struct Columns(Option<Arc<HashSet<String>>>);
+
+impl Columns {
+    /// Unknown names are ignored
+    pub fn new(columns: Option<Vec<String>>) -> Self {
+        Self(columns.map(|c| Arc::new(c.into_iter().collect())))
+    }
+
+    pub fn has(&self, column: &str) -> bool {
+        match &self.0 {
+            Some(c) => c.contains(column),
+            None => !OPT_IN.contains(&column),
+        }
+    }
+}
+
+/// Serialize a row as a map: `always` fields unconditionally, `optional` ones only when
+/// `$row.columns` selects them. Keys are the field names.
+macro_rules! serialize_columns {
+    ($serializer:expr, $row:expr, always: [$($always:ident),*], optional: [$($optional:ident),*]) => {{
+        use serde::ser::SerializeMap;
+        let mut map = $serializer.serialize_map(None)?;
+        $(map.serialize_entry(stringify!($always), &$row.$always)?;)*
+        $(
+            if $row.columns.has(stringify!($optional)) {
+                map.serialize_entry(stringify!($optional), &$row.$optional)?;
+            }
+        )*
+        map.end()
+    }};
+}
+pub(crate) use serialize_columns;
@@ -0,0 +1,337 @@
+// Optional human-editable config.toml beside the JSON state. Its [settings] and [[rules]] are
+// applied at startup and again whenever the file changes, so power users can keep their setup
+// in a text editor or under version control. The JSON state stays the source of truth for
+// everything the file leaves out.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::{data_dir, emit_event, read_state, with_state, BlacklistEntry, Settings};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+// Bookkeeping the app keeps for itself, plus the sync credentials; ignored in the file, left
+// out of exports and never synced. Secrets the file may hold but sync leaves out are in
+// sync::PRIVATE_SETTINGS.
+const RULE_RUNTIME_FIELDS: [&str; 5] = [
+    "created_at",
+    "kill_count",
+    "confirmed",
+    "last_matched",
+    "snoozed_until",
+];
+const SETTINGS_RUNTIME_FIELDS: [&str; 3] = ["/lock_pin_hash", "/email_digest/last_sent", "/sync"];
+
+#[derive(Serialize, Clone)]
+pub struct ConfigFileStatus {
+    pub path: String,
+    pub exists: bool,
+    pub last_loaded: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// Payload of "config-reloaded"
+#[derive(Serialize, Clone)]
+pub struct ConfigReload {
+    pub settings: bool,       // The file has a [settings] table
+    pub rules: Option<usize>, // Rule count when the file has [[rules]]
+}
+
+// Modification time of the version last applied (or rejected), so each edit is read once
+static SEEN: Mutex<Option<SystemTime>> = Mutex::new(None);
+// (last applied at, last rejection)
+static STATUS: Mutex<(Option<String>, Option<String>)> = Mutex::new((None, None));
+
+fn path() -> PathBuf {
+    data_dir().join("config.toml")
+}
+
+fn modified() -> Option<SystemTime> {
+    fs::metadata(path()).and_then(|m| m.modified()).ok()
+}
+
+fn now() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Overlay `overrides` onto `base`, table by table; arrays and values are replaced whole
+fn merge(base: &mut Value, overrides: &Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+/// TOML has no null; drop unset options before exporting
+fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+fn remove_pointer(value: &mut Value, pointer: &str) {
+    let (parent, key) = pointer.rsplit_once('/').unwrap_or(("", pointer));
+    if let Some(Value::Object(map)) = value.pointer_mut(parent) {
+        map.remove(key);
+    }
+}
+
+fn merged_settings(current: &Settings, overrides: &Value) -> Result<Settings, String> {
+    let mut overrides = overrides.clone();
+    for pointer in SETTINGS_RUNTIME_FIELDS {
+        remove_pointer(&mut overrides, pointer);
+    }
+    let mut settings = serde_json::to_value(current).map_err(|e| e.to_string())?;
+    merge(&mut settings, &overrides);
+    serde_json::from_value(settings).map_err(|e| format!("[settings]: {}", e))
+}
+
+/// The file's rules in its order. A rule that already exists (by name, ignoring case) keeps
+/// its kill count and any field the file doesn't set; new rules start from the defaults.
+fn merged_rules(
+    current: &[BlacklistEntry],
+    tables: &[Value],
+) -> Result<Vec<BlacklistEntry>, String> {
+    let mut rules: Vec<BlacklistEntry> = Vec::new();
+    for (i, table) in tables.iter().enumerate() {
+        let name = table
+            .get("name")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .ok_or_else(|| format!("rules[{}]: a name is required", i))?;
+        if rules
+            .iter()
+            .any(|r| r.name.to_lowercase() == name.to_lowercase())
+        {
+            return Err(format!("rules[{}]: \"{}\" is listed twice", i, name));
+        }
+
+        let base = current
+            .iter()
+            .find(|e| e.name.to_lowercase() == name.to_lowercase())
+            .cloned()
+            .unwrap_or_else(|| BlacklistEntry::new(name.to_string(), false, 0.0));
+        let mut entry = serde_json::to_value(base).map_err(|e| e.to_string())?;
+        let mut table = table.clone();
+        if let Value::Object(map) = &mut table {
+            map.retain(|key, _| !RULE_RUNTIME_FIELDS.contains(&key.as_str()));
+        }
+        merge(&mut entry, &table);
+        let rule: BlacklistEntry =
+            serde_json::from_value(entry).map_err(|e| format!("rules[{}] ({}): {}", i, name, e))?;
+        validate_rule(&rule).map_err(|e| format!("rules[{}] ({}): {}", i, name, e))?;
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+fn validate_rule(rule: &BlacklistEntry) -> Result<(), String> {
+    if !(0.0..=101.0).contains(&rule.cpu_threshold) || !(0.0..=101.0).contains(&rule.gpu_threshold)
+    {
+        return Err("Thresholds must be between 0 and 101".into());
+    }
+    if let Some(source) = &rule.rule_script {
+        crate::scripting::compile(source)?;
+    }
+    Ok(())
+}
+
+/// The same checks the individual settings commands run
+fn validate_settings(settings: &Settings) -> Result<(), String> {
+    settings.quiet_hours.validate()?;
+    settings.units.validate()?;
+    settings.refresh_intervals.validate()?;
+    settings.memory_pressure.validate()?;
+    settings.cpu_pressure.validate()?;
+    settings.mqtt.validate()?;
+    settings.bot.validate()?;
+    settings.email_digest.validate()?;
+    crate::maintenance::validate_all(&settings.maintenance_windows)?;
+    if settings.self_budget.cpu_percent <= 0.0 || settings.self_budget.memory_mb == 0 {
+        return Err("Budget limits must be greater than zero".into());
+    }
+    if settings.kill_escalation.exit_timeout_ms > 10_000 {
+        return Err("Exit timeout must be 10 seconds or less".into());
+    }
+    if !crate::i18n::available().contains(&settings.language) {
+        return Err(format!("Unsupported language: {}", settings.language));
+    }
+    Ok(())
+}
+
+fn apply(text: &str) -> Result<ConfigReload, String> {
+    let table: toml::Table = toml::from_str(text).map_err(|e| e.to_string())?;
+    apply_document(&serde_json::to_value(table).map_err(|e| e.to_string())?)
+}
+
+/// Apply the file if it changed since it was last read. Emits "config-reloaded", or
+/// "config-error" with the reason the edit was rejected.
+fn check() {
+    let modified = modified();
+    {
+        let mut seen = SEEN.lock().unwrap();
+        if modified.is_none() || *seen == modified {
+            return;
+        }
+        *seen = modified;
+    }
+
+    let result = fs::read_to_string(path())
+        .map_err(|e| e.to_string())
+        .and_then(|text| apply(&text));
+    let mut status = STATUS.lock().unwrap();
+    match result {
+        Ok(reload) => {
+            tracing::info!(rules = ?reload.rules, "config.toml applied");
+            *status = (Some(now()), None);
+            emit_event("config-reloaded", reload);
+        }
+        Err(err) => {
+            let err = format!("config.toml not applied: {}", err);
+            tracing::warn!("{}", err);
+            status.1 = Some(err.clone());
+            emit_event("config-error", err);
+        }
+    }
+}
+
+// Public API
+/// Validate a { settings, rules } document (either part optional), then apply it in one go;
+/// nothing changes on error
+pub fn apply_document(file: &Value) -> Result<ConfigReload, String> {
+    let Value::Object(sections) = file else {
+        return Err("Expected a table with settings and rules".into());
+    };
+    if let Some(key) = sections.keys().find(|k| *k != "settings" && *k != "rules") {
+        return Err(format!(
+            "Unknown section \"{}\" (expected settings or rules)",
+            key
+        ));
+    }
+    let (current_settings, current_rules) =
+        read_state(|state| (state.settings.clone(), state.blacklist.clone()));
+
+    let settings = match file.get("settings") {
+        Some(overrides @ Value::Object(_)) => {
+            let settings = merged_settings(&current_settings, overrides)?;
+            validate_settings(&settings)?;
+            Some(settings)
+        }
+        Some(_) => return Err("settings must be a table".into()),
+        None => None,
+    };
+    let rules = match file.get("rules") {
+        Some(Value::Array(tables)) => Some(merged_rules(&current_rules, tables)?),
+        Some(_) => return Err("rules must be an array of [[rules]] tables".into()),
+        None => None,
+    };
+
+    if let Some(settings) = &settings {
+        // Last check that can fail; also applies the level right away
+        crate::logging::set_level(&settings.log_level)?;
+    }
+    let reload = ConfigReload {
+        settings: settings.is_some(),
+        rules: rules.as_ref().map(Vec::len),
+    };
+    with_state(|state| {
+        if let Some(settings) = settings.clone() {
+            state.settings = settings;
+        }
+        if let Some(rules) = rules {
+            state.blacklist = rules;
+        }
+    });
+
+    if let Some(settings) = &settings {
+        crate::apply_settings(settings);
+    }
+    Ok(reload)
+}
+
+/// Apply config.toml now if present, then keep watching it for edits
+pub fn start() {
+    check();
+    thread::spawn(|| loop {
+        thread::sleep(crate::power::interval(CHECK_INTERVAL));
+        check();
+    });
+}
+
+pub fn status() -> ConfigFileStatus {
+    let (last_loaded, last_error) = STATUS.lock().unwrap().clone();
+    ConfigFileStatus {
+        path: path().to_string_lossy().to_string(),
+        exists: path().exists(),
+        last_loaded,
+        last_error,
+    }
+}
+
+/// The current settings and rules without the app's own bookkeeping, as { settings, rules }
+pub fn document() -> Result<Value, String> {
+    let (settings, rules) = read_state(|state| {
+        (
+            serde_json::to_value(&state.settings),
+            serde_json::to_value(&state.blacklist),
+        )
+    });
+    let mut settings = settings.map_err(|e| e.to_string())?;
+    let mut rules = rules.map_err(|e| e.to_string())?;
+    for pointer in SETTINGS_RUNTIME_FIELDS {
+        remove_pointer(&mut settings, pointer);
+    }
+    for rule in rules
+        .as_array_mut()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+    {
+        rule.retain(|key, _| !RULE_RUNTIME_FIELDS.contains(&key.as_str()));
+    }
+
+    let mut file = Map::new();
+    file.insert("settings".into(), settings);
+    file.insert("rules".into(), rules);
+    Ok(Value::Object(file))
+}
+
+/// Write the current settings and rules to config.toml as a starting point for editing.
+/// Later changes made in the app are not written back; export again to pick them up.
+pub fn export() -> Result<String, String> {
+    let mut file = document()?;
+    strip_nulls(&mut file);
+    let text = toml::to_string_pretty(&file).map_err(|e| e.to_string())?;
+
+    let path = path();
+    fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    // Our own write matches the state already loaded
+    mark_seen();
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Take config.toml as already applied, after the app itself wrote a version matching the
+/// state (an export, a restored backup), so the watcher doesn't apply it over that state
+pub fn mark_seen() {
+    *SEEN.lock().unwrap() = modified();
+}
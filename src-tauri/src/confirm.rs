@@ -0,0 +1,67 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// A kill held back until the user approves the rule's first kill
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PendingKill {
+    pub id: u32,
+    pub rule: String,
+    pub name: String,
+    pub pid: u32,
+    pub start_time: u64, // So approval never kills a process that recycled the PID
+    pub cpu_usage: f32,
+    pub gpu_usage: f32,
+    pub requested_at: String,
+}
+
+// (next id, pending requests) - kept in memory only, a restart simply asks again
+static PENDING: Mutex<(u32, Vec<PendingKill>)> = Mutex::new((1, Vec::new()));
+
+// Public API
+/// Queue a confirmation for `rule`; returns None when one is already waiting for that rule
+pub fn request(
+    rule: &str,
+    name: &str,
+    (pid, start_time): (u32, u64),
+    cpu: f32,
+    gpu: f32,
+) -> Option<PendingKill> {
+    let mut pending = PENDING.lock().unwrap();
+    if pending.1.iter().any(|p| p.rule.eq_ignore_ascii_case(rule)) {
+        return None;
+    }
+
+    let request = PendingKill {
+        id: pending.0,
+        rule: rule.to_string(),
+        name: name.to_string(),
+        pid,
+        start_time,
+        cpu_usage: cpu,
+        gpu_usage: gpu,
+        requested_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    };
+    pending.0 += 1;
+    pending.1.push(request.clone());
+    Some(request)
+}
+
+pub fn take(id: u32) -> Option<PendingKill> {
+    let mut pending = PENDING.lock().unwrap();
+    let idx = pending.1.iter().position(|p| p.id == id)?;
+    Some(pending.1.remove(idx))
+}
+
+/// Drop pending requests for a rule (used when the rule is removed or reconfigured)
+pub fn forget_rule(rule: &str) {
+    PENDING
+        .lock()
+        .unwrap()
+        .1
+        .retain(|p| !p.rule.eq_ignore_ascii_case(rule));
+}
+
+pub fn list() -> Vec<PendingKill> {
+    PENDING.lock().unwrap().1.clone()
+}
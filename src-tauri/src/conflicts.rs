@@ -0,0 +1,139 @@
+// Overlaps between blacklist rules and their whitelist (exclude) patterns. Matching is by
+// substring and the watcher stops at the first rule that matches, so rule order matters.
+
+use serde::Serialize;
+
+use crate::matcher::normalize;
+use crate::BlacklistEntry;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    Shadowed,            // An earlier rule catches everything this rule would
+    Overlap,             // An earlier rule catches some of what this rule would
+    WhitelistOverridden, // A process this rule whitelists is still caught by another rule
+    SelfExcluded,        // The rule's own whitelist excludes everything it matches
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RuleConflict {
+    pub kind: ConflictKind,
+    pub rule: String,
+    pub other: Option<String>,   // The rule it conflicts with
+    pub pattern: Option<String>, // The exclude pattern involved
+    pub message: String,
+}
+
+/// Result of a rule add/update, with any conflicts the change is involved in
+#[derive(Serialize)]
+pub struct RuleChange {
+    pub message: String,
+    pub conflicts: Vec<RuleConflict>,
+}
+
+struct Rule<'a> {
+    name: &'a str,
+    pattern: String,
+    excludes: Vec<String>,
+}
+
+impl Rule<'_> {
+    /// Every name containing `text` is excluded by this rule
+    fn excludes_all(&self, text: &str) -> bool {
+        self.excludes.iter().any(|e| text.contains(e.as_str()))
+    }
+}
+
+// Public API
+/// Every conflicting or shadowed rule. Case is folded, so this errs towards reporting.
+pub fn validate(blacklist: &[BlacklistEntry]) -> Vec<RuleConflict> {
+    let rules: Vec<Rule> = blacklist
+        .iter()
+        .map(|e| Rule {
+            name: &e.name,
+            pattern: normalize(&e.name, false),
+            excludes: e
+                .exclude_patterns
+                .iter()
+                .map(|p| normalize(p, false))
+                .collect(),
+        })
+        .collect();
+    let mut conflicts = Vec::new();
+
+    for (i, rule) in rules.iter().enumerate() {
+        if rule.excludes_all(&rule.pattern) {
+            conflicts.push(RuleConflict {
+                kind: ConflictKind::SelfExcluded,
+                rule: rule.name.to_string(),
+                other: None,
+                pattern: rule
+                    .excludes
+                    .iter()
+                    .find(|e| rule.pattern.contains(e.as_str()))
+                    .cloned(),
+                message: format!("\"{}\" excludes every process it matches", rule.name),
+            });
+            continue;
+        }
+
+        for earlier in &rules[..i] {
+            if earlier.excludes_all(&rule.pattern) {
+                continue;
+            }
+            if rule.pattern.contains(&earlier.pattern) {
+                conflicts.push(RuleConflict {
+                    kind: ConflictKind::Shadowed,
+                    rule: rule.name.to_string(),
+                    other: Some(earlier.name.to_string()),
+                    pattern: None,
+                    message: format!(
+                        "\"{}\" never applies: \"{}\" comes first and matches everything it does",
+                        rule.name, earlier.name
+                    ),
+                });
+            } else if earlier.pattern.contains(&rule.pattern) {
+                conflicts.push(RuleConflict {
+                    kind: ConflictKind::Overlap,
+                    rule: rule.name.to_string(),
+                    other: Some(earlier.name.to_string()),
+                    pattern: None,
+                    message: format!(
+                        "Processes matching both \"{}\" and \"{}\" are handled by \"{}\" first",
+                        rule.name, earlier.name, earlier.name
+                    ),
+                });
+            }
+        }
+
+        for exclude in &rule.excludes {
+            for other in rules.iter().filter(|o| o.name != rule.name) {
+                if exclude.contains(&other.pattern) && !other.excludes_all(exclude) {
+                    conflicts.push(RuleConflict {
+                        kind: ConflictKind::WhitelistOverridden,
+                        rule: rule.name.to_string(),
+                        other: Some(other.name.to_string()),
+                        pattern: Some(exclude.clone()),
+                        message: format!(
+                            "\"{}\" is whitelisted in \"{}\" but still matched by \"{}\"",
+                            exclude, rule.name, other.name
+                        ),
+                    });
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+/// Conflicts that involve the rule `name`, on either side
+pub fn involving(blacklist: &[BlacklistEntry], name: &str) -> Vec<RuleConflict> {
+    let key = normalize(name, false);
+    validate(blacklist)
+        .into_iter()
+        .filter(|c| {
+            normalize(&c.rule, false) == key
+                || c.other.as_ref().is_some_and(|o| normalize(o, false) == key)
+        })
+        .collect()
+}
@@ -0,0 +1,234 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::process::{Command, Output};
+use sysinfo::System;
+
+// Host processes that hold the memory of Hyper-V / WSL2 virtual machines
+const VM_PROCESS_NAMES: [&str; 2] = ["vmmem", "vmmemwsl"];
+
+#[derive(Serialize, Clone)]
+pub struct ContainerInfo {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub status: String,
+    pub cpu_percent: f32,
+    pub memory_kb: u64,
+    pub memory_percent: f32,
+}
+
+#[derive(Serialize, Clone)]
+pub struct WslDistro {
+    pub name: String,
+    pub state: String,
+    pub version: u32,
+    pub is_default: bool,
+}
+
+/// Build a command that doesn't flash a console window on Windows
+pub fn hidden_command(program: &str) -> Command {
+    #[allow(unused_mut)]
+    let mut cmd = Command::new(program);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    cmd
+}
+
+fn run(program: &str, args: &[&str]) -> Result<Output, String> {
+    let output = hidden_command(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("{} is not available: {}", program, e))?;
+
+    if output.status.success() {
+        Ok(output)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+// Parse sizes printed by docker, e.g. "512MiB" or "1.2GB"
+fn parse_size_kb(text: &str) -> u64 {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let value: f64 = number.parse().unwrap_or(0.0);
+    let factor = match unit.trim() {
+        "B" => 1.0 / 1024.0,
+        "kB" | "KB" | "KiB" => 1.0,
+        "MB" | "MiB" => 1024.0,
+        "GB" | "GiB" => 1024.0 * 1024.0,
+        "TB" | "TiB" => 1024.0 * 1024.0 * 1024.0,
+        _ => 0.0,
+    };
+    (value * factor) as u64
+}
+
+fn parse_percent(text: &str) -> f32 {
+    text.trim().trim_end_matches('%').parse().unwrap_or(0.0)
+}
+
+fn json_lines(output: &Output) -> Vec<serde_json::Value> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+fn field(value: &serde_json::Value, key: &str) -> String {
+    value[key].as_str().unwrap_or_default().to_string()
+}
+
+// Public API
+/// Running Docker containers with their live CPU/memory usage
+pub fn list_containers() -> Result<Vec<ContainerInfo>, String> {
+    let ps = run("docker", &["ps", "--no-trunc", "--format", "{{json .}}"])?;
+    let stats = run(
+        "docker",
+        &["stats", "--no-stream", "--format", "{{json .}}"],
+    )?;
+
+    // docker stats reports short IDs
+    let usage: HashMap<String, serde_json::Value> = json_lines(&stats)
+        .into_iter()
+        .map(|v| (field(&v, "ID"), v))
+        .collect();
+
+    Ok(json_lines(&ps)
+        .iter()
+        .map(|c| {
+            let id = field(c, "ID");
+            let stat = usage
+                .iter()
+                .find(|(short, _)| !short.is_empty() && id.starts_with(short.as_str()))
+                .map(|(_, v)| v);
+            let mem_used = stat
+                .map(|s| field(s, "MemUsage"))
+                .unwrap_or_default()
+                .split('/')
+                .next()
+                .map(parse_size_kb)
+                .unwrap_or(0);
+
+            ContainerInfo {
+                id: id.chars().take(12).collect(),
+                name: field(c, "Names"),
+                image: field(c, "Image"),
+                status: field(c, "Status"),
+                cpu_percent: stat
+                    .map(|s| parse_percent(&field(s, "CPUPerc")))
+                    .unwrap_or(0.0),
+                memory_kb: mem_used,
+                memory_percent: stat
+                    .map(|s| parse_percent(&field(s, "MemPerc")))
+                    .unwrap_or(0.0),
+            }
+        })
+        .collect())
+}
+
+pub fn stop_container(id: &str) -> Result<String, String> {
+    // An id starting with '-' would be read as an option
+    if id.is_empty() || id.starts_with('-') {
+        return Err(format!("Invalid container id: {}", id));
+    }
+    run("docker", &["stop", "--", id]).map(|_| format!("Container {} stopped", id))
+}
+
+/// Installed WSL distributions (Windows only)
+pub fn list_wsl_distros() -> Result<Vec<WslDistro>, String> {
+    if !cfg!(windows) {
+        return Err("WSL is only available on Windows".into());
+    }
+
+    let output = run("wsl", &["--list", "--verbose"])?;
+    // wsl.exe writes UTF-16LE
+    let utf16: Vec<u16> = output
+        .stdout
+        .chunks_exact(2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    let text = String::from_utf16_lossy(&utf16).replace('\0', "");
+
+    Ok(text
+        .lines()
+        .skip(1) // header: NAME STATE VERSION
+        .filter_map(|line| {
+            let is_default = line.trim_start().starts_with('*');
+            let cols: Vec<&str> = line
+                .trim_start_matches([' ', '*'])
+                .split_whitespace()
+                .collect();
+            if cols.len() < 3 {
+                return None;
+            }
+            Some(WslDistro {
+                name: cols[..cols.len() - 2].join(" "),
+                state: cols[cols.len() - 2].to_string(),
+                version: cols[cols.len() - 1].parse().unwrap_or(0),
+                is_default,
+            })
+        })
+        .collect())
+}
+
+pub fn is_vm_process(name: &str) -> bool {
+    VM_PROCESS_NAMES.contains(&name.to_lowercase().trim_end_matches(".exe"))
+}
+
+/// Memory currently attributed to the VM host processes
+pub fn vm_memory_bytes(sys: &System) -> u64 {
+    sys.processes()
+        .values()
+        .filter(|p| is_vm_process(&p.name().to_string_lossy()))
+        .map(|p| p.memory())
+        .sum()
+}
+
+/// Shut down all WSL distros so vmmem hands its memory back to Windows. Without `confirm`
+/// nothing is stopped and the error names the distros that would be.
+pub fn shrink_wsl_memory(confirm: bool) -> Result<String, String> {
+    if !cfg!(windows) {
+        return Err("WSL is only available on Windows".into());
+    }
+    if !confirm {
+        let running: Vec<String> = list_wsl_distros()?
+            .into_iter()
+            .filter(|d| d.state.eq_ignore_ascii_case("running"))
+            .map(|d| d.name)
+            .collect();
+        return Err(if running.is_empty() {
+            "Confirm to shut down WSL".to_string()
+        } else {
+            format!(
+                "Confirm to shut down WSL; this stops {}",
+                running.join(", ")
+            )
+        });
+    }
+
+    let before = crate::sampler::with_processes(
+        |p| is_vm_process(&p.name().to_string_lossy()),
+        |sys, _| vm_memory_bytes(sys),
+    );
+
+    run("wsl", &["--shutdown"])?;
+    // vmmem's usage just before the shutdown; Windows may not reclaim all of it at once
+    Ok(format!(
+        "WSL shut down, about {:.2} GB released (estimated)",
+        before as f64 / 1024.0 / 1024.0 / 1024.0
+    ))
+}
+
+pub fn terminate_wsl_distro(name: &str) -> Result<String, String> {
+    if !cfg!(windows) {
+        return Err("WSL is only available on Windows".into());
+    }
+    run("wsl", &["--terminate", name]).map(|_| format!("WSL distro {} terminated", name))
+}
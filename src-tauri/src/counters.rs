@@ -0,0 +1,106 @@
+// Thread and handle counts per process, for spotting leaks across an app family.
+// sysinfo doesn't expose either on Windows.
+
+#[cfg(windows)]
+use std::collections::HashMap;
+
+/// Counts taken in one pass; build one per listing, not per process
+pub struct Counters {
+    // From a single Toolhelp snapshot of every process, taken the first time a count is read
+    #[cfg(windows)]
+    threads: std::cell::OnceCell<HashMap<u32, u32>>,
+}
+
+impl Counters {
+    pub fn new() -> Self {
+        #[cfg(windows)]
+        {
+            Self {
+                threads: std::cell::OnceCell::new(),
+            }
+        }
+        #[cfg(not(windows))]
+        {
+            Self {}
+        }
+    }
+
+    pub fn threads(&self, pid: u32) -> Option<u32> {
+        #[cfg(windows)]
+        {
+            self.threads
+                .get_or_init(windows_thread_counts)
+                .get(&pid)
+                .copied()
+        }
+        #[cfg(target_os = "linux")]
+        {
+            let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("Threads:"))
+                .and_then(|n| n.trim().parse().ok())
+        }
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = pid;
+            None
+        }
+    }
+
+    /// Open handles on Windows, open file descriptors on Linux. None when access is denied.
+    pub fn handles(&self, pid: u32) -> Option<u32> {
+        #[cfg(windows)]
+        {
+            use windows::Win32::Foundation::CloseHandle;
+            use windows::Win32::System::Threading::{
+                GetProcessHandleCount, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+            };
+
+            unsafe {
+                let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+                let mut count = 0;
+                let result = GetProcessHandleCount(process, &mut count);
+                CloseHandle(process).ok();
+                result.ok().map(|_| count)
+            }
+        }
+        #[cfg(target_os = "linux")]
+        {
+            let entries = std::fs::read_dir(format!("/proc/{}/fd", pid)).ok()?;
+            Some(entries.count() as u32)
+        }
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = pid;
+            None
+        }
+    }
+}
+
+#[cfg(windows)]
+fn windows_thread_counts() -> HashMap<u32, u32> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+        TH32CS_SNAPPROCESS,
+    };
+
+    let mut counts = HashMap::new();
+    unsafe {
+        let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else {
+            return counts;
+        };
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut more = Process32FirstW(snapshot, &mut entry).is_ok();
+        while more {
+            counts.insert(entry.th32ProcessID, entry.cntThreads);
+            more = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+        CloseHandle(snapshot).ok();
+    }
+    counts
+}
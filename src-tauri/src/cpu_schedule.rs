@@ -0,0 +1,60 @@
+// Time-of-day CPU thresholds for a rule, e.g. 20% during work hours and 80% at night. The
+// first period covering the current time wins; outside every period the rule's own threshold
+// applies.
+
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+/// `end` earlier than `start` spans midnight
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ThresholdPeriod {
+    pub start: String,  // "HH:MM"
+    pub end: String,    // "HH:MM"
+    pub threshold: f32, // Same meaning as the rule's: 0 = always, above 100 = CPU not checked
+}
+
+fn parse_time(text: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(text, "%H:%M").ok()
+}
+
+impl ThresholdPeriod {
+    fn contains(&self, time: NaiveTime) -> bool {
+        let (Some(start), Some(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        if start <= end {
+            time >= start && time < end
+        } else {
+            time >= start || time < end
+        }
+    }
+}
+
+// Public API
+pub fn validate(periods: &[ThresholdPeriod]) -> Result<(), String> {
+    for p in periods {
+        let (Some(start), Some(end)) = (parse_time(&p.start), parse_time(&p.end)) else {
+            return Err("Times must be in HH:MM format".into());
+        };
+        if start == end {
+            return Err(format!("{}-{} is an empty period", p.start, p.end));
+        }
+        if !(0.0..=101.0).contains(&p.threshold) {
+            return Err("Threshold must be between 0 and 101%".into());
+        }
+    }
+    Ok(())
+}
+
+/// Threshold in force at `time`, or `default` when no period covers it
+pub fn threshold_at(periods: &[ThresholdPeriod], default: f32, time: NaiveTime) -> f32 {
+    periods
+        .iter()
+        .find(|p| p.contains(time))
+        .map_or(default, |p| p.threshold)
+}
+
+/// The rule's CPU threshold right now
+pub fn current(rule: &crate::BlacklistEntry) -> f32 {
+    threshold_at(&rule.cpu_schedule, rule.cpu_threshold, Local::now().time())
+}
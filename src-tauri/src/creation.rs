@@ -0,0 +1,427 @@
+// Process creation watcher. Listens for process starts instead of waiting for the next poll, so
+// rules that kill on sight (auto-kill with no CPU threshold) act within milliseconds of a launch.
+// Windows subscribes to WMI Win32_ProcessStartTrace (admin) or __InstanceCreationEvent; Linux
+// uses the netlink process connector (root). Without either it lists processes at the process
+// refresh interval, so instant kill is off by default: there it is no faster than the watcher.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::matcher::Matcher;
+use crate::system_actions::RuleAction;
+use crate::{emit_event, read_state, BlacklistEntry};
+
+struct Started {
+    pid: u32,
+    name: String,
+    at: Instant,
+}
+
+#[derive(Serialize, Clone)]
+pub struct LaunchWatchStatus {
+    pub enabled: bool,
+    pub source: String, // "wmi_trace", "wmi", "netlink" or "polling"; empty until started
+}
+
+/// Sent as "launch-killed" for each process killed right after it started
+#[derive(Serialize, Clone)]
+struct LaunchKilled {
+    pid: u32,
+    name: String,
+    latency_ms: u64, // From the start notification to the kill
+}
+
+static SOURCE: Mutex<&str> = Mutex::new("");
+
+fn set_source(source: &'static str) {
+    *SOURCE.lock().unwrap() = source;
+    tracing::info!(source, "watching process starts");
+}
+
+/// Rules with nothing to measure: a process that just started can be judged right away
+fn is_instant(rule: &BlacklistEntry) -> bool {
+    rule.auto_kill && crate::cpu_schedule::current(rule) <= 0.0 && rule.rule_script.is_none()
+}
+
+fn instant_rules() -> Vec<Matcher> {
+    read_state(|state| {
+        // Blocked programs are caught at launch even with instant kill turned off
+        let instant_kill = state.settings.instant_kill;
+        state
+            .blacklist
+            .iter()
+            .filter(|rule| {
+                rule.action == RuleAction::BlockLaunch || (instant_kill && is_instant(rule))
+            })
+            .map(Matcher::for_rule)
+            .collect()
+    })
+}
+
+fn dispatch(rx: Receiver<Started>) {
+    while let Ok(first) = rx.recv() {
+        let rules = instant_rules();
+        // A launcher can start a burst of processes; one check covers all of them
+        let matched: Vec<Started> = std::iter::once(first)
+            .chain(rx.try_iter())
+            .filter(|s| rules.iter().any(|m| m.matches(&s.name)))
+            .collect();
+        if matched.is_empty() {
+            continue;
+        }
+        // The full check applies every guard (protection, confirmation, snoozes) and logs
+        crate::sampler::invalidate();
+        let logs = crate::run_blacklist_check();
+        for started in &matched {
+            if logs.iter().any(|l| l.was_killed && l.pid == started.pid) {
+                let latency_ms = started.at.elapsed().as_millis() as u64;
+                tracing::info!(
+                    pid = started.pid,
+                    name = started.name,
+                    latency_ms,
+                    "killed at launch"
+                );
+                emit_event(
+                    "launch-killed",
+                    LaunchKilled {
+                        pid: started.pid,
+                        name: started.name.clone(),
+                        latency_ms,
+                    },
+                );
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+unsafe fn read_started(
+    obj: &windows::Win32::System::Wmi::IWbemClassObject,
+    nested: bool,
+) -> Option<Started> {
+    use windows::core::{w, ComInterface};
+    use windows::Win32::System::Variant::{VariantClear, VARIANT};
+    use windows::Win32::System::Wmi::IWbemClassObject;
+
+    // Creation events wrap the new Win32_Process; the start trace carries the fields itself
+    let target = if nested {
+        let mut value = VARIANT::default();
+        obj.Get(w!("TargetInstance"), 0, &mut value, None, None)
+            .ok()?;
+        let target = (*value.Anonymous.Anonymous.Anonymous.punkVal)
+            .as_ref()
+            .and_then(|unknown| unknown.cast::<IWbemClassObject>().ok());
+        VariantClear(&mut value).ok();
+        target
+    } else {
+        None
+    };
+    if nested && target.is_none() {
+        return None;
+    }
+    let obj = target.as_ref().unwrap_or(obj);
+    let (pid_property, name_property) = if nested {
+        (w!("ProcessId"), w!("Name"))
+    } else {
+        (w!("ProcessID"), w!("ProcessName"))
+    };
+
+    let mut pid = VARIANT::default();
+    obj.Get(pid_property, 0, &mut pid, None, None).ok()?;
+    let pid = pid.Anonymous.Anonymous.Anonymous.lVal as u32;
+    let mut name = VARIANT::default();
+    obj.Get(name_property, 0, &mut name, None, None).ok()?;
+    let started = Started {
+        pid,
+        name: (*name.Anonymous.Anonymous.Anonymous.bstrVal).to_string(),
+        at: Instant::now(),
+    };
+    VariantClear(&mut name).ok();
+    Some(started)
+}
+
+#[cfg(windows)]
+fn listen(tx: &Sender<Started>) -> Result<(), String> {
+    use windows::core::{BSTR, PCWSTR};
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoSetProxyBlanket, CLSCTX_INPROC_SERVER,
+        COINIT_MULTITHREADED, EOAC_NONE, RPC_C_AUTHN_LEVEL_CALL, RPC_C_IMP_LEVEL_IMPERSONATE,
+    };
+    use windows::Win32::System::Wmi::{
+        IWbemLocator, WbemLocator, WBEM_FLAG_FORWARD_ONLY, WBEM_FLAG_RETURN_IMMEDIATELY,
+        WBEM_GENERIC_FLAG_TYPE, WBEM_INFINITE,
+    };
+    const RPC_C_AUTHN_WINNT: u32 = 10;
+    const RPC_C_AUTHZ_NONE: u32 = 0;
+
+    // The kernel start trace fires at once but needs admin; instance events poll every second
+    let queries = [
+        ("wmi_trace", "SELECT * FROM Win32_ProcessStartTrace", false),
+        (
+            "wmi",
+            "SELECT * FROM __InstanceCreationEvent WITHIN 1 WHERE TargetInstance ISA 'Win32_Process'",
+            true,
+        ),
+    ];
+    let flags = WBEM_GENERIC_FLAG_TYPE(WBEM_FLAG_RETURN_IMMEDIATELY.0 | WBEM_FLAG_FORWARD_ONLY.0);
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+        let locator: IWbemLocator = CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER)
+            .map_err(|e| format!("WMI unavailable: {}", e))?;
+        let services = locator
+            .ConnectServer(
+                &BSTR::from("ROOT\\CIMV2"),
+                &BSTR::new(),
+                &BSTR::new(),
+                &BSTR::new(),
+                0,
+                &BSTR::new(),
+                None,
+            )
+            .map_err(|e| format!("Failed to connect to WMI: {}", e))?;
+        CoSetProxyBlanket(
+            &services,
+            RPC_C_AUTHN_WINNT,
+            RPC_C_AUTHZ_NONE,
+            PCWSTR::null(),
+            RPC_C_AUTHN_LEVEL_CALL,
+            RPC_C_IMP_LEVEL_IMPERSONATE,
+            None,
+            EOAC_NONE,
+        )
+        .ok();
+
+        let mut last_error = String::new();
+        for (source, query, nested) in queries {
+            let events = match services.ExecNotificationQuery(
+                &BSTR::from("WQL"),
+                &BSTR::from(query),
+                flags,
+                None,
+            ) {
+                Ok(events) => events,
+                Err(e) => {
+                    last_error = format!("{}: {}", source, e);
+                    continue;
+                }
+            };
+            set_source(source);
+            loop {
+                let mut objects = [None];
+                let mut returned = 0;
+                // Access denied for the trace shows up here, on the first wait
+                let result = events.Next(WBEM_INFINITE.0, &mut objects, &mut returned);
+                if let Err(e) = result.ok() {
+                    last_error = format!("{}: {}", source, e);
+                    break;
+                }
+                let Some(started) = objects[0].take().and_then(|obj| read_started(&obj, nested))
+                else {
+                    continue;
+                };
+                if tx.send(started).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        Err(last_error)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn process_name(pid: u32) -> Option<String> {
+    // sysinfo names processes after their executable; comm is cut at 15 characters
+    std::fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .and_then(|exe| exe.file_name().map(|n| n.to_string_lossy().to_string()))
+        .or_else(|| {
+            std::fs::read_to_string(format!("/proc/{}/comm", pid))
+                .ok()
+                .map(|comm| comm.trim_end().to_string())
+        })
+}
+
+#[cfg(target_os = "linux")]
+fn listen(tx: &Sender<Started>) -> Result<(), String> {
+    const NETLINK_CONNECTOR: i32 = 11;
+    const CN_IDX_PROC: u32 = 1;
+    const CN_VAL_PROC: u32 = 1;
+    const PROC_CN_MCAST_LISTEN: u32 = 1;
+    const PROC_EVENT_EXEC: u32 = 2;
+    // nlmsghdr, then cn_msg, then struct proc_event
+    const NLMSG_HDRLEN: usize = 16;
+    const CN_MSG_LEN: usize = 20;
+
+    struct Socket(i32);
+    impl Drop for Socket {
+        fn drop(&mut self) {
+            unsafe { libc::close(self.0) };
+        }
+    }
+    let os_error = |what: &str| format!("{}: {}", what, std::io::Error::last_os_error());
+
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            NETLINK_CONNECTOR,
+        )
+    };
+    if fd < 0 {
+        return Err(os_error("netlink socket"));
+    }
+    let socket = Socket(fd);
+    let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as u16;
+    addr.nl_groups = CN_IDX_PROC;
+    // Joining the process events group needs CAP_NET_ADMIN
+    let bound = unsafe {
+        libc::bind(
+            socket.0,
+            &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_nl>() as u32,
+        )
+    };
+    if bound < 0 {
+        return Err(os_error("netlink bind"));
+    }
+
+    let mut request = Vec::with_capacity(NLMSG_HDRLEN + CN_MSG_LEN + 4);
+    request.extend(((NLMSG_HDRLEN + CN_MSG_LEN + 4) as u32).to_ne_bytes()); // nlmsg_len
+    request.extend((libc::NLMSG_DONE as u16).to_ne_bytes()); // nlmsg_type
+    request.extend(0u16.to_ne_bytes()); // nlmsg_flags
+    request.extend(0u32.to_ne_bytes()); // nlmsg_seq
+    request.extend(std::process::id().to_ne_bytes()); // nlmsg_pid
+    request.extend(CN_IDX_PROC.to_ne_bytes()); // cn_msg id
+    request.extend(CN_VAL_PROC.to_ne_bytes());
+    request.extend(0u32.to_ne_bytes()); // seq
+    request.extend(0u32.to_ne_bytes()); // ack
+    request.extend(4u16.to_ne_bytes()); // len
+    request.extend(0u16.to_ne_bytes()); // flags
+    request.extend(PROC_CN_MCAST_LISTEN.to_ne_bytes());
+    let sent = unsafe {
+        libc::send(
+            socket.0,
+            request.as_ptr() as *const libc::c_void,
+            request.len(),
+            0,
+        )
+    };
+    if sent < 0 {
+        return Err(os_error("netlink subscribe"));
+    }
+    set_source("netlink");
+
+    let mut buf = [0u8; 4096];
+    loop {
+        let len = unsafe {
+            libc::recv(
+                socket.0,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                0,
+            )
+        };
+        if len < 0 {
+            let err = std::io::Error::last_os_error();
+            // ENOBUFS: processes started faster than we read and some events were dropped
+            if matches!(err.raw_os_error(), Some(libc::EINTR | libc::ENOBUFS)) {
+                continue;
+            }
+            return Err(format!("netlink receive: {}", err));
+        }
+        let event = buf
+            .get(NLMSG_HDRLEN + CN_MSG_LEN..len as usize)
+            .unwrap_or_default();
+        let u32_at = |at: usize| {
+            event
+                .get(at..at + 4)
+                .map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        // proc_event: what, cpu, timestamp, then exec's process_pid and process_tgid
+        if u32_at(0) != Some(PROC_EVENT_EXEC) {
+            continue;
+        }
+        let Some(pid) = u32_at(20) else {
+            continue;
+        };
+        let Some(name) = process_name(pid) else {
+            continue; // Already gone
+        };
+        let started = Started {
+            pid,
+            name,
+            at: Instant::now(),
+        };
+        if tx.send(started).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn listen(_tx: &Sender<Started>) -> Result<(), String> {
+    Err("no process start notifications on this platform".into())
+}
+
+/// Fallback: list processes often and report the new PIDs
+fn poll(tx: &Sender<Started>) {
+    set_source("polling");
+    let mut sys = System::new();
+    let mut known: HashSet<u32> = HashSet::new();
+    loop {
+        // Nothing to apply instantly: stay idle and start from a fresh list later
+        if instant_rules().is_empty() {
+            known.clear();
+            thread::sleep(Duration::from_secs(5));
+            continue;
+        }
+        sys.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing(),
+        );
+        let first_pass = known.is_empty();
+        for p in sys.processes().values() {
+            let pid = p.pid().as_u32();
+            if known.insert(pid) && !first_pass {
+                let started = Started {
+                    pid,
+                    name: p.name().to_string_lossy().to_string(),
+                    at: Instant::now(),
+                };
+                if tx.send(started).is_err() {
+                    return;
+                }
+            }
+        }
+        known.retain(|pid| sys.process(sysinfo::Pid::from_u32(*pid)).is_some());
+        thread::sleep(crate::refresher::interval(
+            crate::refresher::Source::Processes,
+        ));
+    }
+}
+
+// Public API
+pub fn start() {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || dispatch(rx));
+    thread::spawn(move || {
+        if let Err(err) = listen(&tx) {
+            tracing::info!(%err, "process start notifications unavailable, polling instead");
+        }
+        poll(&tx);
+    });
+}
+
+pub fn status() -> LaunchWatchStatus {
+    LaunchWatchStatus {
+        enabled: read_state(|state| state.settings.instant_kill),
+        source: SOURCE.lock().unwrap().to_string(),
+    }
+}
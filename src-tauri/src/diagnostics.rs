@@ -0,0 +1,168 @@
+// Support bundle: recent logs, the config with secrets removed, the tail of the activity log
+// and what the machine reports about itself, zipped into one file
+
+use chrono::Local;
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use sysinfo::System;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::containers::hidden_command;
+use crate::{capabilities, gpu, logging, metrics, read_state, runtime};
+
+const ACTIVITY_EXCERPT: usize = 200;
+const MAX_LOG_BYTES: u64 = 4 * 1024 * 1024; // Per file; only the newest part of bigger ones
+const REDACTED: &str = "<redacted>";
+
+/// Config as saved, minus the PIN hash, agent tokens, MQTT/SMTP/sync passwords, bot token, user names and the activity log
+fn sanitized_config() -> Value {
+    let mut config = read_state(|state| serde_json::to_value(state).unwrap_or(Value::Null));
+    if let Some(config) = config.as_object_mut() {
+        config.remove("activity_logs");
+        if let Some(settings) = config.get_mut("settings") {
+            for secret in [
+                "/lock_pin_hash",
+                "/mqtt/password",
+                "/bot/token",
+                "/email_digest/password",
+                "/sync/password",
+            ] {
+                if let Some(value) = settings.pointer_mut(secret).filter(|v| !v.is_null()) {
+                    *value = json!(REDACTED);
+                }
+            }
+        }
+        if let Some(hosts) = config.get_mut("remote_hosts").and_then(Value::as_array_mut) {
+            for host in hosts {
+                host["token"] = json!(REDACTED);
+            }
+        }
+    }
+    config
+}
+
+fn activity_excerpt() -> Value {
+    let mut logs = read_state(|state| {
+        let skip = state.activity_logs.len().saturating_sub(ACTIVITY_EXCERPT);
+        serde_json::to_value(&state.activity_logs[skip..]).unwrap_or(Value::Null)
+    });
+    for log in logs.as_array_mut().into_iter().flatten() {
+        if !log["user"].is_null() {
+            log["user"] = json!(REDACTED);
+        }
+    }
+    logs
+}
+
+/// Display adapters as the OS names them
+fn gpu_adapters() -> Vec<String> {
+    #[cfg(windows)]
+    let output = hidden_command("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "(Get-CimInstance Win32_VideoController).Name",
+        ])
+        .output();
+    #[cfg(not(windows))]
+    let output = hidden_command("lspci").output();
+
+    let text = output
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default();
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter(|line| {
+            cfg!(windows) || line.contains("VGA") || line.contains("3D") || line.contains("Display")
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+fn system_report() -> Value {
+    let mut sys = System::new();
+    sys.refresh_memory();
+    sys.refresh_cpu_list(sysinfo::CpuRefreshKind::nothing());
+
+    // Two samples, since PDH rate counters read 0 on the first one
+    gpu::get_gpu_usages();
+    let gpu_sample = gpu::get_gpu_usages();
+    let busiest = gpu_sample.values().cloned().fold(0.0f32, f32::max);
+
+    json!({
+        "generated_at": Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "os": System::long_os_version(),
+        "kernel": System::kernel_version(),
+        "arch": std::env::consts::ARCH,
+        "cpu_count": sys.cpus().len(),
+        "memory_total_bytes": sys.total_memory(),
+        "memory_used_bytes": sys.used_memory(),
+        "elevated": crate::is_running_as_admin(),
+        "low_power_active": crate::power::is_active(),
+        "gpu": {
+            "adapters": gpu_adapters(),
+            "monitor": gpu::status(),
+            "processes_sampled": gpu_sample.len(),
+            "processes_nonzero": gpu_sample.values().filter(|v| **v > 0.0).count(),
+            "busiest_percent": busiest,
+        },
+        "capabilities": capabilities::check(),
+        "metric_providers": metrics::list_providers(),
+        "last_shutdown": runtime::last_shutdown(),
+    })
+}
+
+/// The newest `MAX_LOG_BYTES` of a log file
+fn read_tail(path: &Path) -> Result<Vec<u8>, String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let start = data.len().saturating_sub(MAX_LOG_BYTES as usize);
+    Ok(data[start..].to_vec())
+}
+
+fn write_entry(zip: &mut ZipWriter<File>, name: &str, data: &[u8]) -> Result<(), String> {
+    zip.start_file(name, SimpleFileOptions::default())
+        .map_err(|e| e.to_string())?;
+    zip.write_all(data).map_err(|e| e.to_string())
+}
+
+fn write_json(zip: &mut ZipWriter<File>, name: &str, value: &Value) -> Result<(), String> {
+    let data = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    write_entry(zip, name, &data)
+}
+
+// Public API
+/// Write a diagnostics zip under the data dir and return its path
+pub fn create_bundle() -> Result<PathBuf, String> {
+    let dir = crate::data_dir().join("diagnostics");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!(
+        "akt-diagnostics-{}.zip",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    let file = File::create(&path).map_err(|e| format!("Failed to create bundle: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+
+    write_json(&mut zip, "system.json", &system_report())?;
+    write_json(&mut zip, "config.json", &sanitized_config())?;
+    write_json(&mut zip, "activity_log.json", &activity_excerpt())?;
+
+    let mut logs: Vec<PathBuf> = fs::read_dir(logging::log_dir())
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    logs.sort();
+    for log in logs.iter().filter(|p| p.is_file()) {
+        if let (Some(name), Ok(data)) = (log.file_name(), read_tail(log)) {
+            write_entry(&mut zip, &format!("logs/{}", name.to_string_lossy()), &data)?;
+        }
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to write bundle: {}", e))?;
+    tracing::info!(path = %path.display(), "diagnostics bundle created");
+    Ok(path)
+}
@@ -0,0 +1,407 @@
+// Daily or weekly email summary of enforcement activity: kill counts, top offenders and
+// memory/disk trends. Memory and disk usage are sampled hourly into a small history file
+// next to the config, so the trend covers the whole period even if the UI was closed.
+
+use chrono::{Duration as ChronoDuration, Local, NaiveDateTime, Timelike};
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{read_state, with_state};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const RETRY_AFTER: Duration = Duration::from_secs(60 * 60); // After a failed send
+const SAMPLE_EVERY_MINUTES: i64 = 60;
+const MAX_SAMPLES: usize = 24 * 31;
+const TOP_OFFENDERS: usize = 5;
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    #[default]
+    Daily,
+    Weekly,
+}
+
+impl Frequency {
+    fn days(self) -> i64 {
+        match self {
+            Frequency::Daily => 1,
+            Frequency::Weekly => 7,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    #[default]
+    StartTls,
+    Tls,
+    None, // Plain SMTP, e.g. a relay on the local network
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct EmailDigest {
+    pub enabled: bool,
+    pub frequency: Frequency,
+    pub send_hour: u32, // Local hour the digest goes out
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    pub last_sent: Option<String>, // Kept by the scheduler
+}
+
+impl Default for EmailDigest {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            frequency: Frequency::Daily,
+            send_hour: 8,
+            smtp_host: String::new(),
+            smtp_port: 587,
+            security: SmtpSecurity::StartTls,
+            username: None,
+            password: None,
+            from: String::new(),
+            to: Vec::new(),
+            last_sent: None,
+        }
+    }
+}
+
+impl EmailDigest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.send_hour > 23 {
+            return Err("Send hour must be between 0 and 23".into());
+        }
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.smtp_host.trim().is_empty() {
+            return Err("SMTP server is required".into());
+        }
+        self.from
+            .parse::<Mailbox>()
+            .map_err(|e| format!("Invalid sender address: {}", e))?;
+        if self.to.is_empty() {
+            return Err("At least one recipient is required".into());
+        }
+        for to in &self.to {
+            to.parse::<Mailbox>()
+                .map_err(|e| format!("Invalid recipient {}: {}", to, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// One hourly reading for the trend section, also used by the weekly report
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Sample {
+    pub at: String,
+    pub memory_percent: f32,
+    pub disks: Vec<(String, f32)>, // Mount point and usage percent
+    #[serde(default)]
+    pub cpu_percent: f32,
+    #[serde(default)]
+    pub apps: Vec<String>, // Process names running in the user's session, for usage time
+}
+
+static SAMPLES: Mutex<Option<Vec<Sample>>> = Mutex::new(None);
+static LAST_FAILURE: Mutex<Option<Instant>> = Mutex::new(None);
+
+fn history_path() -> PathBuf {
+    crate::data_dir().join("stats_history.json")
+}
+
+fn with_samples<R>(f: impl FnOnce(&mut Vec<Sample>) -> R) -> R {
+    let mut guard = SAMPLES.lock().unwrap();
+    let samples = guard.get_or_insert_with(|| {
+        fs::read_to_string(history_path())
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    });
+    f(samples)
+}
+
+fn parse_time(text: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(text, TIME_FORMAT).ok()
+}
+
+/// Distinct names of the processes running in our session, kernel threads left out
+fn running_apps() -> Vec<String> {
+    let session = crate::sessions::current_session();
+    let mut apps: Vec<String> = crate::sampler::with_processes(
+        |_| false,
+        |sys, _| {
+            sys.processes()
+                .values()
+                .filter(|p| p.thread_kind() != Some(sysinfo::ThreadKind::Kernel))
+                .filter(|p| crate::sessions::session_of(p.pid().as_u32()) == session)
+                .map(|p| p.name().to_string_lossy().to_string())
+                .collect()
+        },
+    );
+    apps.sort_unstable();
+    apps.dedup();
+    apps
+}
+
+fn record_sample() {
+    let now = Local::now().naive_local();
+    let due = with_samples(|samples| {
+        samples
+            .last()
+            .and_then(|s| parse_time(&s.at))
+            .is_none_or(|at| now - at >= ChronoDuration::minutes(SAMPLE_EVERY_MINUTES))
+    });
+    if !due {
+        return;
+    }
+    let stats = crate::system_stats();
+    let sample = Sample {
+        at: now.format(TIME_FORMAT).to_string(),
+        memory_percent: stats.memory_percent,
+        disks: stats
+            .disks
+            .iter()
+            .map(|d| (d.mount_point.clone(), d.usage_percent))
+            .collect(),
+        cpu_percent: stats.cpu_usage,
+        apps: running_apps(),
+    };
+    with_samples(|samples| {
+        samples.push(sample);
+        let excess = samples.len().saturating_sub(MAX_SAMPLES);
+        samples.drain(..excess);
+        if let Ok(data) = serde_json::to_string(samples) {
+            fs::write(history_path(), data).ok();
+        }
+    });
+}
+
+/// Subject and plain-text body for the period starting at `since`
+fn compose(since: NaiveDateTime, frequency: Frequency) -> (String, String) {
+    let since_text = since.format(TIME_FORMAT).to_string();
+    let host = sysinfo::System::host_name().unwrap_or_else(|| "this computer".to_string());
+
+    // Activity log timestamps sort as text
+    let (kills, failed, detections, offenders) = read_state(|state| {
+        let recent: Vec<_> = state
+            .activity_logs
+            .iter()
+            .filter(|log| log.detected_at >= since_text)
+            .collect();
+        let mut offenders: HashMap<&str, (u32, u64)> = HashMap::new();
+        for log in recent.iter().filter(|log| log.was_killed) {
+            let entry = offenders.entry(log.name.as_str()).or_default();
+            entry.0 += 1;
+            entry.1 += log.freed_memory_kb;
+        }
+        let mut offenders: Vec<(String, u32, u64)> = offenders
+            .into_iter()
+            .map(|(name, (count, freed))| (name.to_string(), count, freed))
+            .collect();
+        offenders.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        offenders.truncate(TOP_OFFENDERS);
+        (
+            recent.iter().filter(|log| log.was_killed).count(),
+            recent
+                .iter()
+                .filter(|log| !log.was_killed && !log.retry_history.is_empty())
+                .count(),
+            recent.len(),
+            offenders,
+        )
+    });
+
+    let samples: Vec<Sample> = with_samples(|samples| {
+        samples
+            .iter()
+            .filter(|s| s.at >= since_text)
+            .cloned()
+            .collect()
+    });
+
+    let period = match frequency {
+        Frequency::Daily => "daily",
+        Frequency::Weekly => "weekly",
+    };
+    let subject = format!(
+        "AutomateKillTask {} digest for {}: {} kills",
+        period, host, kills
+    );
+
+    let mut body = format!(
+        "Enforcement activity on {} since {}\n\nKills: {}\nActivity log entries: {}\n",
+        host, since_text, kills, detections
+    );
+    if failed > 0 {
+        body += &format!("Kills that needed retries or failed: {}\n", failed);
+    }
+
+    body += "\nTop offenders:\n";
+    if offenders.is_empty() {
+        body += "  (none)\n";
+    }
+    for (name, count, freed_kb) in &offenders {
+        body += &format!(
+            "  {} - {} kills, {:.0} MB freed\n",
+            name,
+            count,
+            *freed_kb as f64 / 1024.0
+        );
+    }
+
+    body += "\nMemory:\n";
+    if samples.is_empty() {
+        body += "  (no samples yet)\n";
+    } else {
+        let values: Vec<f32> = samples.iter().map(|s| s.memory_percent).collect();
+        let average = values.iter().sum::<f32>() / values.len() as f32;
+        let peak = values.iter().cloned().fold(0.0f32, f32::max);
+        body += &format!("  Average {:.0}%, peak {:.0}%\n", average, peak);
+    }
+
+    if let (Some(first), Some(last)) = (samples.first(), samples.last()) {
+        body += "\nDisks:\n";
+        for (mount, now) in &last.disks {
+            match first.disks.iter().find(|(m, _)| m == mount) {
+                Some((_, then)) => {
+                    body += &format!("  {} {:.0}% used ({:+.1} points)\n", mount, now, now - then)
+                }
+                None => body += &format!("  {} {:.0}% used\n", mount, now),
+            }
+        }
+    }
+    (subject, body)
+}
+
+fn send(settings: &EmailDigest, subject: &str, body: &str) -> Result<(), String> {
+    let mut message = Message::builder()
+        .from(
+            settings
+                .from
+                .parse()
+                .map_err(|e| format!("Invalid sender address: {}", e))?,
+        )
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN);
+    for to in &settings.to {
+        message = message.to(to
+            .parse()
+            .map_err(|e| format!("Invalid recipient {}: {}", to, e))?);
+    }
+    let message = message.body(body.to_string()).map_err(|e| e.to_string())?;
+
+    let host = settings.smtp_host.trim();
+    let mut transport = match settings.security {
+        SmtpSecurity::StartTls => SmtpTransport::starttls_relay(host),
+        SmtpSecurity::Tls => SmtpTransport::relay(host),
+        SmtpSecurity::None => Ok(SmtpTransport::builder_dangerous(host)),
+    }
+    .map_err(|e| format!("SMTP setup failed: {}", e))?
+    .port(settings.smtp_port);
+    if let Some(username) = settings.username.as_ref().filter(|u| !u.is_empty()) {
+        transport = transport.credentials(Credentials::new(
+            username.clone(),
+            settings.password.clone().unwrap_or_default(),
+        ));
+    }
+    transport
+        .build()
+        .send(&message)
+        .map(|_| ())
+        .map_err(|e| format!("Sending the digest failed: {}", e))
+}
+
+fn period_start(settings: &EmailDigest) -> NaiveDateTime {
+    let now = Local::now().naive_local();
+    settings
+        .last_sent
+        .as_deref()
+        .and_then(parse_time)
+        .unwrap_or(now - ChronoDuration::days(settings.frequency.days()))
+}
+
+fn is_due(settings: &EmailDigest) -> bool {
+    let now = Local::now().naive_local();
+    if !settings.enabled || now.hour() < settings.send_hour {
+        return false;
+    }
+    let last = settings.last_sent.as_deref().and_then(parse_time);
+    last.is_none_or(|last| (now.date() - last.date()).num_days() >= settings.frequency.days())
+}
+
+fn tick() {
+    record_sample();
+    let settings = read_state(|state| state.settings.email_digest.clone());
+    let backing_off = LAST_FAILURE
+        .lock()
+        .unwrap()
+        .is_some_and(|at| at.elapsed() < RETRY_AFTER);
+    if !is_due(&settings) || backing_off {
+        return;
+    }
+    match send_now(&settings) {
+        Ok(()) => tracing::info!("email digest sent"),
+        Err(err) => {
+            tracing::warn!("{}", err);
+            *LAST_FAILURE.lock().unwrap() = Some(Instant::now());
+        }
+    }
+}
+
+// Public API
+pub fn start() {
+    thread::spawn(|| loop {
+        tick();
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+/// Send the digest for the period since the last one and remember when it went out
+pub fn send_now(settings: &EmailDigest) -> Result<(), String> {
+    let (subject, body) = compose(period_start(settings), settings.frequency);
+    send(settings, &subject, &body)?;
+    let sent_at = Local::now().format(TIME_FORMAT).to_string();
+    with_state(|state| {
+        state.settings.email_digest.last_sent = Some(sent_at);
+    });
+    Ok(())
+}
+
+/// Hourly samples taken at or after `since` ("YYYY-MM-DD HH:MM:SS")
+pub fn samples_since(since: &str) -> Vec<Sample> {
+    with_samples(|samples| {
+        samples
+            .iter()
+            .filter(|s| s.at.as_str() >= since)
+            .cloned()
+            .collect()
+    })
+}
+
+/// Minutes between samples, i.e. the usage time one sample stands for
+pub fn sample_minutes() -> i64 {
+    SAMPLE_EVERY_MINUTES
+}
+
+/// Drop the cached samples so the next use reads stats_history.json again (after a restore)
+pub fn reload_history() {
+    *SAMPLES.lock().unwrap() = None;
+}
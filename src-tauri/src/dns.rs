@@ -0,0 +1,120 @@
+// Reverse DNS for connection rows, resolved in the background with a TTL cache so listing
+// connections never waits on the network
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::emit_event;
+
+const RESOLVED_TTL: Duration = Duration::from_secs(10 * 60);
+// Failures are retried sooner; they're often transient
+const FAILED_TTL: Duration = Duration::from_secs(2 * 60);
+// Lookups can take seconds each, so a few run side by side
+const WORKERS: usize = 4;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    Resolved,
+    Pending, // Queued; a "dns-resolved" event follows
+    Failed,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Resolved {
+    pub address: String,
+    pub hostname: Option<String>,
+}
+
+struct Entry {
+    hostname: Option<String>,
+    expires: Instant,
+}
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<IpAddr, Entry>,
+    pending: HashSet<IpAddr>,
+}
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+static QUEUE: OnceLock<Mutex<Sender<IpAddr>>> = OnceLock::new();
+
+fn resolve(ip: IpAddr) {
+    // lookup_addr echoes the address back when there is no PTR record
+    let hostname = dns_lookup::lookup_addr(&ip)
+        .ok()
+        .filter(|name| name.parse::<IpAddr>().is_err());
+    let ttl = if hostname.is_some() {
+        RESOLVED_TTL
+    } else {
+        FAILED_TTL
+    };
+    {
+        let mut guard = CACHE.lock().unwrap();
+        let cache = guard.get_or_insert_with(Cache::default);
+        cache.pending.remove(&ip);
+        cache.entries.insert(
+            ip,
+            Entry {
+                hostname: hostname.clone(),
+                expires: Instant::now() + ttl,
+            },
+        );
+    }
+    emit_event(
+        "dns-resolved",
+        Resolved {
+            address: ip.to_string(),
+            hostname,
+        },
+    );
+}
+
+fn queue() -> &'static Mutex<Sender<IpAddr>> {
+    QUEUE.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<IpAddr>();
+        let rx: Arc<Mutex<Receiver<IpAddr>>> = Arc::new(Mutex::new(rx));
+        for _ in 0..WORKERS {
+            let rx = rx.clone();
+            thread::spawn(move || loop {
+                let next = rx.lock().unwrap().recv();
+                match next {
+                    Ok(ip) => resolve(ip),
+                    Err(_) => break,
+                }
+            });
+        }
+        Mutex::new(tx)
+    })
+}
+
+// Public API
+/// Cached hostname for `address`, queueing a lookup when it's missing or expired
+pub fn lookup(address: &str) -> (Option<String>, Resolution) {
+    let Ok(ip) = address.parse::<IpAddr>() else {
+        return (None, Resolution::Failed);
+    };
+    let mut guard = CACHE.lock().unwrap();
+    let cache = guard.get_or_insert_with(Cache::default);
+
+    if let Some(entry) = cache
+        .entries
+        .get(&ip)
+        .filter(|e| e.expires > Instant::now())
+    {
+        return match &entry.hostname {
+            Some(name) => (Some(name.clone()), Resolution::Resolved),
+            None => (None, Resolution::Failed),
+        };
+    }
+    if cache.pending.insert(ip) {
+        queue().lock().unwrap().send(ip).ok();
+    }
+    (None, Resolution::Pending)
+}
@@ -0,0 +1,173 @@
+// Memory dumps of a running process for debugging: MiniDumpWriteDump on Windows, gdb's gcore
+// on Linux. Rules can ask for one right before the watcher kills a match, so a hang or runaway
+// can still be looked at after the process is gone. On Linux those are every thread's
+// backtrace as text, since gdb has no smaller core than a full one.
+
+use chrono::Local;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::data_dir;
+
+const DUMP_FOLDER: &str = "dumps";
+// Dumps taken before kills pile up fast; only the newest in dumps/ are kept
+const MAX_KILL_DUMPS: usize = 10;
+
+/// Full dumps hold all process memory; stack dumps only threads, stacks, data segments and
+/// handles, which is enough for a hang and quick enough to take right before a kill
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DumpKind {
+    Full,
+    Stacks,
+}
+
+#[cfg(windows)]
+fn write_dump(pid: u32, path: &Path, kind: DumpKind) -> Result<(), String> {
+    use std::fs::File;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::System::Diagnostics::Debug::{
+        MiniDumpWithDataSegs, MiniDumpWithFullMemory, MiniDumpWithHandleData,
+        MiniDumpWithThreadInfo, MiniDumpWithUnloadedModules, MiniDumpWriteDump, MINIDUMP_TYPE,
+    };
+    use windows::Win32::System::Threading::{
+        OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ,
+    };
+
+    let common =
+        MiniDumpWithHandleData.0 | MiniDumpWithThreadInfo.0 | MiniDumpWithUnloadedModules.0;
+    let kind = match kind {
+        DumpKind::Full => MINIDUMP_TYPE(common | MiniDumpWithFullMemory.0),
+        DumpKind::Stacks => MINIDUMP_TYPE(common | MiniDumpWithDataSegs.0),
+    };
+    let file =
+        File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let result = unsafe {
+        let process = OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid)
+            .map_err(|e| format!("Cannot open process {}: {}", pid, e))?;
+        let written = MiniDumpWriteDump(
+            process,
+            pid,
+            HANDLE(file.as_raw_handle() as isize),
+            kind,
+            None,
+            None,
+            None,
+        );
+        let _ = CloseHandle(process);
+        written
+    };
+    result.map_err(|e| format!("MiniDumpWriteDump failed: {}", e))
+}
+
+#[cfg(target_os = "linux")]
+fn write_dump(pid: u32, path: &Path, kind: DumpKind) -> Result<(), String> {
+    if kind == DumpKind::Stacks {
+        let output = crate::containers::hidden_command("gdb")
+            .args(["-p", &pid.to_string(), "-batch", "-nx"])
+            .args(["-ex", "thread apply all bt"])
+            .output()
+            .map_err(|_| "Stack dumps need gdb on Linux".to_string())?;
+        if !output.status.success() || output.stdout.is_empty() {
+            return Err(format!(
+                "gdb failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        return fs::write(path, &output.stdout).map_err(|e| e.to_string());
+    }
+    // gcore always writes a full core, to "<prefix>.<pid>"
+    let prefix = path.with_extension("");
+    let output = crate::containers::hidden_command("gcore")
+        .arg("-o")
+        .arg(&prefix)
+        .arg(pid.to_string())
+        .output()
+        .map_err(|_| "Dumps need gcore (part of gdb) on Linux".to_string())?;
+    let written = PathBuf::from(format!("{}.{}", prefix.display(), pid));
+    if !output.status.success() || !written.exists() {
+        return Err(format!(
+            "gcore failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    fs::rename(&written, path).map_err(|e| e.to_string())
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn write_dump(_pid: u32, _path: &Path, _kind: DumpKind) -> Result<(), String> {
+    Err("Process dumps are not supported on this platform".into())
+}
+
+fn dump(pid: u32, path: &Path, kind: DumpKind) -> Result<(), String> {
+    let result = write_dump(pid, path, kind);
+    if result.is_err() {
+        // Don't leave a half-written dump behind
+        fs::remove_file(path).ok();
+    }
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    tracing::info!(pid, path = %path.display(), size, ok = result.is_ok(), "process dump");
+    result
+}
+
+/// dumps/<time>-<name>-<pid>.dmp
+fn default_path(pid: u32, name: &str) -> Result<PathBuf, String> {
+    let dir = data_dir().join(DUMP_FOLDER);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Ok(dir.join(format!(
+        "{}-{}-{}.dmp",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        name,
+        pid
+    )))
+}
+
+/// Delete the oldest dumps in `dir` beyond MAX_KILL_DUMPS
+fn prune(dir: &Path) {
+    let mut dumps: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    // Names start with a timestamp, so they sort oldest first
+    dumps.sort();
+    let excess = dumps.len().saturating_sub(MAX_KILL_DUMPS);
+    for path in &dumps[..excess] {
+        fs::remove_file(path).ok();
+    }
+}
+
+// Public API
+/// Write a full memory dump of `pid` to `path`, or under dumps/ when None; returns the path
+pub fn dump_process(pid: u32, name: &str, path: Option<&Path>) -> Result<PathBuf, String> {
+    let path = match path {
+        Some(path) if path.is_dir() => {
+            return Err("Pick a file name for the dump, not a folder".into())
+        }
+        Some(path) => path.to_path_buf(),
+        None => default_path(pid, name)?,
+    };
+    dump(pid, &path, DumpKind::Full)?;
+    Ok(path)
+}
+
+/// Stack dump under dumps/ right before the watcher kills `pid`; returns where it went
+pub fn dump_before_kill(pid: u32, name: &str) -> Result<PathBuf, String> {
+    let path = default_path(pid, name)?;
+    // Backtraces on Linux are plain text
+    #[cfg(target_os = "linux")]
+    let path = path.with_extension("txt");
+    dump(pid, &path, DumpKind::Stacks)?;
+    if let Some(dir) = path.parent() {
+        prune(dir);
+    }
+    Ok(path)
+}
@@ -0,0 +1,158 @@
+// Machine-wide conditions rules can depend on: power source, Wi-Fi network, VPN, metered
+// connection and memory pressure
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use sysinfo::Networks;
+
+use crate::metrics::{self, MetricKind};
+use crate::{power, refresher};
+
+// SSID and metered checks spawn processes on Linux, so a snapshot is reused for a while
+const CACHE_TTL: Duration = Duration::from_secs(10);
+
+// Interface names used by common VPN clients: Unix device prefixes, then fragments of
+// Windows adapter names
+const VPN_PREFIXES: [&str; 6] = ["tun", "tap", "wg", "ppp", "utun", "ipsec"];
+const VPN_NAMES: [&str; 4] = ["vpn", "wireguard", "nordlynx", "tap-windows"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+}
+
+/// Snapshot of the conditions rules can test
+#[derive(Serialize, Clone)]
+pub struct Environment {
+    pub power: PowerSource,
+    pub wifi_ssid: Option<String>,
+    pub vpn_active: bool,
+    pub metered: Option<bool>, // None when the OS doesn't say
+    pub memory_percent: f32,
+}
+
+/// Extra conditions a rule needs before it kills; unset fields are ignored
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(default)]
+pub struct Conditions {
+    pub power: Option<PowerSource>,
+    pub wifi_ssid: Option<String>, // Exact network name, case-insensitive
+    pub vpn_active: Option<bool>,
+    pub metered: Option<bool>,
+    pub memory_above_percent: Option<f32>,
+}
+
+impl Conditions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn met(&self, env: &Environment) -> bool {
+        self.power.is_none_or(|p| p == env.power)
+            && self.wifi_ssid.as_ref().is_none_or(|ssid| {
+                env.wifi_ssid
+                    .as_ref()
+                    .is_some_and(|current| current.eq_ignore_ascii_case(ssid))
+            })
+            && self.vpn_active.is_none_or(|v| v == env.vpn_active)
+            && self.metered.is_none_or(|m| env.metered == Some(m))
+            && self
+                .memory_above_percent
+                .is_none_or(|limit| env.memory_percent > limit)
+    }
+}
+
+static CACHE: Mutex<Option<(Instant, Environment)>> = Mutex::new(None);
+
+fn vpn_active() -> bool {
+    Networks::new_with_refreshed_list().keys().any(|name| {
+        let name = name.to_lowercase();
+        VPN_PREFIXES.iter().any(|p| name.starts_with(p))
+            || VPN_NAMES.iter().any(|n| name.contains(n))
+    })
+}
+
+#[cfg(windows)]
+fn network_profile() -> (Option<String>, Option<bool>) {
+    use windows::Networking::Connectivity::{NetworkCostType, NetworkInformation};
+
+    let Ok(profile) = NetworkInformation::GetInternetConnectionProfile() else {
+        return (None, None);
+    };
+    let ssid = profile
+        .IsWlanConnectionProfile()
+        .unwrap_or(false)
+        .then(|| {
+            profile
+                .WlanConnectionProfileDetails()
+                .and_then(|d| d.GetConnectedSsid())
+        })
+        .and_then(|ssid| ssid.ok())
+        .map(|ssid| ssid.to_string());
+    let metered = profile
+        .GetConnectionCost()
+        .and_then(|cost| cost.NetworkCostType())
+        .ok()
+        .and_then(|kind| match kind {
+            NetworkCostType::Unrestricted => Some(false),
+            NetworkCostType::Fixed | NetworkCostType::Variable => Some(true),
+            _ => None,
+        });
+    (ssid, metered)
+}
+
+#[cfg(not(windows))]
+fn network_profile() -> (Option<String>, Option<bool>) {
+    let nmcli = |args: &[&str]| {
+        std::process::Command::new("nmcli")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+    };
+    let ssid = nmcli(&["-t", "-f", "active,ssid", "dev", "wifi"]).and_then(|out| {
+        out.lines()
+            .find_map(|line| line.strip_prefix("yes:"))
+            .map(|ssid| ssid.to_string())
+    });
+    // One line per device, e.g. "no (guessed)"
+    let metered = nmcli(&["-t", "-g", "GENERAL.METERED", "dev", "show"])
+        .map(|out| out.lines().any(|line| line.trim_start().starts_with("yes")));
+    (ssid, metered)
+}
+
+// Public API
+/// Current environment, at most `CACHE_TTL` old
+pub fn current() -> Environment {
+    let mut cache = CACHE.lock().unwrap();
+    if let Some((at, env)) = cache.as_ref() {
+        if at.elapsed() < CACHE_TTL {
+            return env.clone();
+        }
+    }
+
+    let stats =
+        refresher::cached_stats().unwrap_or_else(|| metrics::collect(&[MetricKind::System]));
+    let (wifi_ssid, metered) = network_profile();
+    let env = Environment {
+        power: if power::status().on_battery {
+            PowerSource::Battery
+        } else {
+            PowerSource::Ac
+        },
+        wifi_ssid,
+        vpn_active: vpn_active(),
+        metered,
+        memory_percent: if stats.memory_total > 0 {
+            stats.memory_used as f32 / stats.memory_total as f32 * 100.0
+        } else {
+            0.0
+        },
+    };
+    *cache = Some((Instant::now(), env.clone()));
+    env
+}
@@ -0,0 +1,127 @@
+// Auto-kills mirrored to the Windows Application Event Log (source "AutomateKillTaskManager"),
+// so managed machines can collect enforcement events with their existing log pipelines
+
+use crate::ActivityLog;
+
+#[cfg(windows)]
+const SOURCE: &str = "AutomateKillTaskManager";
+#[cfg(windows)]
+const EVENT_KILLED: u32 = 1000;
+
+#[cfg(windows)]
+mod win {
+    use std::sync::OnceLock;
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::{HANDLE, PSID};
+    use windows::Win32::System::EventLog::{
+        RegisterEventSourceW, ReportEventW, EVENTLOG_INFORMATION_TYPE,
+    };
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegOpenKeyExW, HKEY, HKEY_LOCAL_MACHINE, KEY_READ,
+    };
+
+    // Opened once and kept for the life of the process
+    static SOURCE_HANDLE: OnceLock<Option<isize>> = OnceLock::new();
+
+    pub fn is_registered() -> bool {
+        let path = format!(
+            r"SYSTEM\CurrentControlSet\Services\EventLog\Application\{}",
+            super::SOURCE
+        );
+        let mut key = HKEY::default();
+        unsafe {
+            let opened = RegOpenKeyExW(
+                HKEY_LOCAL_MACHINE,
+                &HSTRING::from(path),
+                0,
+                KEY_READ,
+                &mut key,
+            )
+            .is_ok();
+            if opened {
+                let _ = RegCloseKey(key);
+            }
+            opened
+        }
+    }
+
+    pub fn report(event_id: u32, message: &str) -> Result<(), String> {
+        let handle = SOURCE_HANDLE
+            .get_or_init(|| unsafe {
+                RegisterEventSourceW(PCWSTR::null(), &HSTRING::from(super::SOURCE))
+                    .ok()
+                    .map(|h| h.0)
+            })
+            .ok_or("Event source could not be opened")?;
+        let message = HSTRING::from(message);
+        unsafe {
+            ReportEventW(
+                HANDLE(handle),
+                EVENTLOG_INFORMATION_TYPE,
+                0,
+                event_id,
+                PSID::default(),
+                0,
+                Some(&[PCWSTR(message.as_ptr())]),
+                None,
+            )
+        }
+        .map_err(|e| e.to_string())
+    }
+}
+
+// Public API
+/// Register the event source so Event Viewer shows our messages without the "description
+/// cannot be found" preamble. Needs admin rights once; events are written either way.
+pub fn register_source() -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        if win::is_registered() {
+            return Ok(());
+        }
+        let output = crate::containers::hidden_command("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                &format!("New-EventLog -LogName Application -Source {}", SOURCE),
+            ])
+            .output()
+            .map_err(|e| format!("PowerShell is not available: {}", e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        Err("The Windows Event Log is only available on Windows".into())
+    }
+}
+
+/// Write one kill to the Application log
+pub fn report_kill(log: &ActivityLog) {
+    #[cfg(windows)]
+    {
+        let mut message = format!(
+            "Process {} (PID {}) was killed at {}.\r\nReason: {}",
+            log.name, log.pid, log.detected_at, log.reason
+        );
+        if let Some(path) = &log.exe_path {
+            message += &format!("\r\nPath: {}", path);
+        }
+        if let Some(user) = &log.user {
+            message += &format!("\r\nUser: {}", user);
+        }
+        if log.freed_memory_kb > 0 {
+            message += &format!("\r\nFreed memory: {} KB", log.freed_memory_kb);
+        }
+        if let Err(err) = win::report(EVENT_KILLED, &message) {
+            tracing::warn!("Event Log write failed: {}", err);
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = log;
+    }
+}
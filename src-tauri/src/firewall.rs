@@ -0,0 +1,303 @@
+// Block an executable's outbound traffic instead of killing it. Uses Windows Firewall rules
+// on Windows and nftables on Linux. nftables can't match on the executable, so on Linux its
+// processes are moved into a cgroup of their own that an nftables rule drops; the watcher
+// moves instances started later into it too.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use sysinfo::System;
+
+/// A firewall rule this app created
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NetworkBlock {
+    pub path: String,
+    pub rule_name: String,
+    pub created_at: String,
+}
+
+// Prefix of every rule we create, so they're recognizable in wf.msc
+const RULE_PREFIX: &str = "AutomateKillTask block";
+
+/// Short hash of the full path, so same-named executables in different folders get their
+/// own rules
+fn path_hash(path: &str) -> String {
+    Sha256::digest(path.as_bytes())
+        .iter()
+        .take(4)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn rule_name(path: &str) -> String {
+    let file = Path::new(path)
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    format!("{}: {} ({})", RULE_PREFIX, file, path_hash(path))
+}
+
+#[cfg(windows)]
+fn netsh(args: &[String]) -> Result<(), String> {
+    let output = crate::containers::hidden_command("netsh")
+        .args(args)
+        .output()
+        .map_err(|e| format!("netsh is not available: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        // netsh reports errors on stdout
+        let message = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Err(format!(
+            "Firewall rule change failed (requires Administrator?): {}",
+            message
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod nft {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use std::sync::Mutex;
+    use sysinfo::System;
+
+    const TABLE: &str = "automatekilltask";
+    const CHAIN: &str = "output";
+    const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+    // Cgroup each moved PID came from, so unblocking can put it back. Lost on restart, in
+    // which case the process goes to the root cgroup instead.
+    static ORIGINS: Mutex<Vec<(u32, String)>> = Mutex::new(Vec::new());
+
+    fn nft(args: &[&str]) -> Result<String, String> {
+        let output = Command::new("nft")
+            .args(args)
+            .output()
+            .map_err(|e| format!("nft is not available: {}", e))?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            Err(format!(
+                "Firewall rule change failed (requires root?): {}",
+                message
+            ))
+        }
+    }
+
+    /// Name of the cgroup holding the blocked executable's processes, relative to the root
+    pub fn cgroup(hash: &str) -> String {
+        format!("automatekilltask-block-{}", hash)
+    }
+
+    fn cgroup_dir(cgroup: &str) -> PathBuf {
+        PathBuf::from(CGROUP_ROOT).join(cgroup)
+    }
+
+    /// The process's cgroup v2 path, e.g. "/user.slice/user-1000.slice/session-2.scope"
+    fn cgroup_of(pid: u32) -> Option<String> {
+        fs::read_to_string(format!("/proc/{}/cgroup", pid))
+            .ok()?
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))
+            .map(str::to_string)
+    }
+
+    fn move_to(pid: u32, cgroup: &str) -> Result<(), String> {
+        let procs = PathBuf::from(CGROUP_ROOT)
+            .join(cgroup.trim_start_matches('/'))
+            .join("cgroup.procs");
+        fs::write(&procs, pid.to_string())
+            .map_err(|e| format!("Failed to move PID {} to {}: {}", pid, cgroup, e))
+    }
+
+    /// Move every running instance of `path` into `cgroup`
+    pub fn capture(sys: &System, path: &str, cgroup: &str) {
+        let path = Path::new(path);
+        let file = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let target = format!("/{}", cgroup);
+        // The process name is the kernel's comm, at most 15 bytes of the file name, so it only
+        // narrows down which executables are worth reading
+        let candidates = sys
+            .processes()
+            .values()
+            .filter(|p| file.starts_with(&*p.name().to_string_lossy()));
+        for pid in candidates.map(|p| p.pid().as_u32()) {
+            let exe = fs::read_link(format!("/proc/{}/exe", pid));
+            if !exe.is_ok_and(|exe| exe == path) {
+                continue;
+            }
+            let Some(origin) = cgroup_of(pid).filter(|origin| *origin != target) else {
+                continue;
+            };
+            if move_to(pid, cgroup).is_ok() {
+                let mut origins = ORIGINS.lock().unwrap();
+                origins.retain(|(p, _)| *p != pid);
+                origins.push((pid, origin));
+            }
+        }
+    }
+
+    pub fn add(sys: &System, path: &str, cgroup: &str, rule_name: &str) -> Result<(), String> {
+        let dir = cgroup_dir(cgroup);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {} (requires root?): {}", dir.display(), e))?;
+        nft(&["add", "table", "inet", TABLE])?;
+        nft(&[
+            "add",
+            "chain",
+            "inet",
+            TABLE,
+            CHAIN,
+            "{ type filter hook output priority 0 ; policy accept ; }",
+        ])?;
+        // The cgroup has to exist before the rule referring to it is loaded
+        nft(&[
+            "add",
+            "rule",
+            "inet",
+            TABLE,
+            CHAIN,
+            "socket",
+            "cgroupv2",
+            "level",
+            "1",
+            &format!("\"{}\"", cgroup),
+            "drop",
+            "comment",
+            &format!("\"{}\"", rule_name),
+        ])?;
+        capture(sys, path, cgroup);
+        Ok(())
+    }
+
+    pub fn remove(cgroup: &str, rule_name: &str) -> Result<(), String> {
+        // Rules are deleted by handle: `... comment "<name>" # handle 7`
+        let listing = nft(&["-a", "list", "chain", "inet", TABLE, CHAIN])?;
+        let comment = format!("comment \"{}\"", rule_name);
+        if let Some(handle) = listing
+            .lines()
+            .find(|line| line.contains(&comment))
+            .and_then(|line| line.rsplit_once("# handle "))
+            .map(|(_, handle)| handle.trim().to_string())
+        {
+            nft(&["delete", "rule", "inet", TABLE, CHAIN, "handle", &handle])?;
+        }
+
+        let dir = cgroup_dir(cgroup);
+        let pids: Vec<u32> = fs::read_to_string(dir.join("cgroup.procs"))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|pid| pid.trim().parse().ok())
+            .collect();
+        let mut origins = ORIGINS.lock().unwrap();
+        for pid in pids {
+            let origin = origins
+                .iter()
+                .position(|(p, _)| *p == pid)
+                .map(|i| origins.remove(i).1)
+                .unwrap_or_else(|| "/".to_string());
+            move_to(pid, &origin).ok();
+        }
+        fs::remove_dir(&dir).ok();
+        Ok(())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+const UNSUPPORTED: &str =
+    "Blocking an app's network access is only supported with Windows Firewall or nftables";
+
+// Public API
+/// Add an outbound block rule for the executable at `path`
+pub fn block(path: &str) -> Result<NetworkBlock, String> {
+    if !Path::new(path).is_file() {
+        return Err(format!("{} is not an executable file", path));
+    }
+    let block = NetworkBlock {
+        path: path.to_string(),
+        rule_name: rule_name(path),
+        created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    };
+    #[cfg(windows)]
+    {
+        netsh(&[
+            "advfirewall".into(),
+            "firewall".into(),
+            "add".into(),
+            "rule".into(),
+            format!("name={}", block.rule_name),
+            "dir=out".into(),
+            "action=block".into(),
+            format!("program={}", block.path),
+            "enable=yes".into(),
+        ])?;
+        Ok(block)
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let cgroup = nft::cgroup(&path_hash(path));
+        crate::sampler::with_processes(
+            |_| false,
+            |sys, _| nft::add(sys, path, &cgroup, &block.rule_name),
+        )?;
+        Ok(block)
+    }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        let _ = block;
+        Err(UNSUPPORTED.into())
+    }
+}
+
+/// Remove the rule `block` created
+pub fn unblock(block: &NetworkBlock) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        netsh(&[
+            "advfirewall".into(),
+            "firewall".into(),
+            "delete".into(),
+            "rule".into(),
+            format!("name={}", block.rule_name),
+            format!("program={}", block.path),
+        ])
+    }
+    #[cfg(target_os = "linux")]
+    {
+        nft::remove(&nft::cgroup(&path_hash(&block.path)), &block.rule_name)
+    }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        let _ = block;
+        Err(UNSUPPORTED.into())
+    }
+}
+
+/// Called by the watcher with the sampled process list: on Linux, moves newly started
+/// instances of blocked executables into their blocked cgroup
+pub fn enforce(sys: &System) {
+    #[cfg(target_os = "linux")]
+    {
+        let paths: Vec<String> = crate::read_state(|state| {
+            state
+                .network_blocks
+                .iter()
+                .map(|b| b.path.clone())
+                .collect()
+        });
+        for path in paths {
+            nft::capture(sys, &path, &nft::cgroup(&path_hash(&path)));
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = sys;
+    }
+}
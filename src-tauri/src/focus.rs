@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use sysinfo::System;
+
+use crate::matcher::Matcher;
+use crate::process_ctl::{self, Priority, SavedPriority};
+use crate::protection;
+use crate::read_state;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HogAction {
+    #[default]
+    Deprioritize,
+    Suspend,
+}
+
+/// Booster mode: keep one app at high priority and hold back the "background hogs" while it runs
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct FocusSettings {
+    pub app: Option<String>,
+    pub hogs: Vec<String>,
+    pub hog_action: HogAction,
+}
+
+#[derive(Serialize)]
+pub struct FocusStatus {
+    pub app: Option<String>,
+    pub running: bool,
+    pub hogs: Vec<String>,
+    pub hog_action: HogAction,
+    pub boosted_pids: Vec<u32>,
+    pub throttled_pids: Vec<u32>,
+}
+
+/// A process we changed, and its priority from before the change (None for a suspend)
+struct Changed {
+    pid: u32,
+    start_time: u64,
+    priority: Option<SavedPriority>,
+}
+
+/// What we changed, so it can be undone when the focus app exits
+#[derive(Default)]
+struct Applied {
+    boosted: Vec<Changed>,
+    throttled: Vec<(Changed, HogAction)>,
+}
+
+impl Applied {
+    fn contains(&self, (pid, start_time): (u32, u64)) -> bool {
+        self.boosted
+            .iter()
+            .chain(self.throttled.iter().map(|(c, _)| c))
+            .any(|c| c.pid == pid && c.start_time == start_time)
+    }
+}
+
+static APPLIED: Mutex<Applied> = Mutex::new(Applied {
+    boosted: Vec::new(),
+    throttled: Vec::new(),
+});
+
+fn matching(sys: &System, name: &str) -> Vec<(u32, u64)> {
+    let matcher = Matcher::new(name);
+    sys.processes()
+        .iter()
+        .filter(|(_, p)| matcher.matches(&p.name().to_string_lossy()))
+        .map(|(pid, p)| (pid.as_u32(), p.start_time()))
+        .collect()
+}
+
+/// Lower or raise the priority of `pid`, remembering what it was
+fn reprioritize((pid, start_time): (u32, u64), priority: Priority) -> Option<Changed> {
+    let saved = process_ctl::save_priority(pid).ok()?;
+    process_ctl::set_priority(pid, priority).ok()?;
+    Some(Changed {
+        pid,
+        start_time,
+        priority: Some(saved),
+    })
+}
+
+/// Put every process back the way it was; a PID recycled since is left alone
+fn undo(applied: &mut Applied) {
+    let throttled = applied.throttled.drain(..).map(|(c, _)| c);
+    for c in applied.boosted.drain(..).chain(throttled) {
+        match c.priority {
+            Some(priority) => process_ctl::restore_priority(c.pid, c.start_time, priority).ok(),
+            None => process_ctl::resume_same(c.pid, c.start_time).ok(),
+        };
+    }
+}
+
+// Public API
+/// Called by the watcher on every poll with a freshly refreshed process list
+pub fn enforce(sys: &System) {
+    let settings = read_state(|state| state.settings.focus.clone());
+    let mut applied = APPLIED.lock().unwrap();
+
+    let focus_pids = settings
+        .app
+        .as_deref()
+        .map(|app| matching(sys, app))
+        .unwrap_or_default();
+    if focus_pids.is_empty() {
+        undo(&mut applied);
+        return;
+    }
+
+    for process in focus_pids {
+        if applied.contains(process) {
+            continue;
+        }
+        if let Some(changed) = reprioritize(process, Priority::High) {
+            applied.boosted.push(changed);
+        }
+    }
+
+    let own_pids = protection::own_pids(sys);
+    for hog in &settings.hogs {
+        for process in matching(sys, hog) {
+            let (pid, start_time) = process;
+            if own_pids.contains(&pid) || applied.contains(process) {
+                continue;
+            }
+            let changed = match settings.hog_action {
+                HogAction::Deprioritize => reprioritize(process, Priority::Idle),
+                HogAction::Suspend => process_ctl::suspend_process(pid).ok().map(|_| Changed {
+                    pid,
+                    start_time,
+                    priority: None,
+                }),
+            };
+            if let Some(changed) = changed {
+                applied.throttled.push((changed, settings.hog_action));
+            }
+        }
+    }
+}
+
+/// Restore everything focus mode changed (used when the settings change)
+pub fn release() {
+    undo(&mut APPLIED.lock().unwrap());
+}
+
+/// Hogs currently suspended (not just deprioritized) by focus mode, with their start times
+pub fn suspended() -> Vec<(u32, u64)> {
+    APPLIED
+        .lock()
+        .unwrap()
+        .throttled
+        .iter()
+        .filter(|(_, action)| *action == HogAction::Suspend)
+        .map(|(c, _)| (c.pid, c.start_time))
+        .collect()
+}
+
+pub fn status() -> FocusStatus {
+    let settings = read_state(|state| state.settings.focus.clone());
+    let applied = APPLIED.lock().unwrap();
+
+    FocusStatus {
+        app: settings.app,
+        running: !applied.boosted.is_empty(),
+        hogs: settings.hogs,
+        hog_action: settings.hog_action,
+        boosted_pids: applied.boosted.iter().map(|c| c.pid).collect(),
+        throttled_pids: applied.throttled.iter().map(|(c, _)| c.pid).collect(),
+    }
+}
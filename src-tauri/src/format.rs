@@ -0,0 +1,107 @@
+// Unit conventions and number formatting shared by the backend and the UI
+
+use serde::{Deserialize, Serialize};
+
+/// User preference for how sizes are shown
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct UnitSettings {
+    pub binary: bool, // true = 1024-based (GiB), false = 1000-based (GB)
+    pub decimal_separator: String,
+    pub decimals: u8,
+}
+
+impl Default for UnitSettings {
+    fn default() -> Self {
+        Self {
+            binary: true,
+            decimal_separator: ".".to_string(),
+            decimals: 1,
+        }
+    }
+}
+
+/// Sent with stats so the UI formats raw values the same way the backend does
+#[derive(Serialize, Clone)]
+pub struct FormatHints {
+    pub binary: bool,
+    pub unit_base: u64,
+    pub units: Vec<&'static str>,
+    pub decimal_separator: String,
+    pub decimals: u8,
+}
+
+const BINARY_UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+const DECIMAL_UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+impl UnitSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.decimal_separator != "." && self.decimal_separator != "," {
+            return Err("Decimal separator must be \".\" or \",\"".into());
+        }
+        if self.decimals > 3 {
+            return Err("Use at most 3 decimals".into());
+        }
+        Ok(())
+    }
+
+    pub fn hints(&self) -> FormatHints {
+        FormatHints {
+            binary: self.binary,
+            unit_base: if self.binary { 1024 } else { 1000 },
+            units: if self.binary {
+                BINARY_UNITS.to_vec()
+            } else {
+                DECIMAL_UNITS.to_vec()
+            },
+            decimal_separator: self.decimal_separator.clone(),
+            decimals: self.decimals,
+        }
+    }
+
+    fn number(&self, value: f64) -> String {
+        let text = format!("{:.*}", self.decimals as usize, value);
+        if self.decimal_separator == "." {
+            text
+        } else {
+            text.replace('.', &self.decimal_separator)
+        }
+    }
+}
+
+// Public API
+pub fn format_bytes(bytes: u64, units: &UnitSettings) -> String {
+    let base = if units.binary { 1024.0 } else { 1000.0 };
+    let names = if units.binary {
+        BINARY_UNITS
+    } else {
+        DECIMAL_UNITS
+    };
+
+    let mut value = bytes as f64;
+    let mut idx = 0;
+    while value >= base && idx < names.len() - 1 {
+        value /= base;
+        idx += 1;
+    }
+    if idx == 0 {
+        format!("{} {}", bytes, names[0])
+    } else {
+        format!("{} {}", units.number(value), names[idx])
+    }
+}
+
+/// "1d 2h", "3h 5m", "4m 10s" or "12s"
+pub fn format_duration(seconds: u64) -> String {
+    let (days, hours) = (seconds / 86_400, (seconds % 86_400) / 3600);
+    let (minutes, secs) = ((seconds % 3600) / 60, seconds % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, secs)
+    } else {
+        format!("{}s", secs)
+    }
+}
@@ -1,174 +1,251 @@
+// Per-process GPU usage. Windows reads the PDH "GPU Engine" counters; other platforms have no
+// per-process source yet and report `GpuSupport::Unsupported` so the UI can hide GPU columns.
+
+use serde::Serialize;
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Whether per-process GPU usage can be measured here
+#[allow(dead_code)] // Each platform only reaches some of these
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuSupport {
+    Available,
+    Unavailable, // Supported platform, but the counters couldn't be opened
+    Unsupported, // No per-process GPU source on this platform
+}
+
+#[derive(Serialize, Clone)]
+pub struct GpuMonitorStatus {
+    pub support: GpuSupport,
+    pub source: Option<&'static str>,
+    pub detail: Option<String>, // Why the counters are unavailable
+}
 
 #[cfg(windows)]
-use windows::core::PCWSTR;
-#[cfg(windows)]
-use windows::Win32::System::Performance::{
-    PdhAddEnglishCounterW, PdhCloseQuery, PdhCollectQueryData, PdhGetFormattedCounterArrayW,
-    PdhOpenQueryW, PDH_FMT_COUNTERVALUE_ITEM_W, PDH_FMT_DOUBLE,
-};
+mod pdh {
+    use std::collections::HashMap;
+    use windows::core::PCWSTR;
+    use windows::Win32::System::Performance::{
+        PdhAddEnglishCounterW, PdhCloseQuery, PdhCollectQueryData, PdhGetFormattedCounterArrayW,
+        PdhOpenQueryW, PDH_FMT_COUNTERVALUE_ITEM_W, PDH_FMT_DOUBLE,
+    };
+
+    /// An open PDH query on every GPU engine instance, closed on drop
+    pub struct Query {
+        query: isize,
+        counter: isize,
+    }
 
-pub struct GpuMonitor {
-    #[cfg(windows)]
-    query: isize,
-    #[cfg(windows)]
-    counter: isize,
-    #[cfg(windows)]
-    initialized: bool,
-}
+    impl Query {
+        pub fn open() -> Result<Self, String> {
+            unsafe {
+                let mut query = 0;
+                let mut counter = 0;
 
-// Global instance to maintain state (PDH requires state for rate counters)
-static GPU_MONITOR: Mutex<Option<GpuMonitor>> = Mutex::new(None);
+                // 0 = ERROR_SUCCESS
+                let status = PdhOpenQueryW(None, 0, &mut query);
+                if status != 0 {
+                    return Err(format!("PDH query could not be opened (0x{:08X})", status));
+                }
 
-impl GpuMonitor {
-    pub fn new() -> Self {
-        #[cfg(windows)]
-        unsafe {
-            let mut query = 0;
-            let mut counter = 0;
-
-            // Open Query (0 = ERROR_SUCCESS)
-            if PdhOpenQueryW(None, 0, &mut query) != 0 {
-                return Self {
-                    query: 0,
-                    counter: 0,
-                    initialized: false,
-                };
-            }
+                // All engines (3D, Video, Copy...) of all processes; the wildcard picks up new
+                // processes by itself. The English name works on localized Windows too.
+                let path: Vec<u16> = "\\GPU Engine(*)\\Utilization Percentage"
+                    .encode_utf16()
+                    .chain(Some(0))
+                    .collect();
+                let status = PdhAddEnglishCounterW(query, PCWSTR(path.as_ptr()), 0, &mut counter);
+                if status != 0 {
+                    let _ = PdhCloseQuery(query);
+                    return Err(format!("GPU Engine counter unavailable (0x{:08X})", status));
+                }
 
-            // Add English Counter for GPU Utilization
-            // This covers all engines (3D, Video, Copy, etc.) for all processes
-            // The wildcard * automatically handles new processes
-            let path = "BUFFER_PLACEHOLDER"; // Will encode utf16 locally
-                                             // We use PdhAddEnglishCounterW to support non-English Windows
-                                             // Path: \GPU Engine(*)\Utilization Percentage
-            let mut path_utf16: Vec<u16> = "\\GPU Engine(*)\\Utilization Percentage"
-                .encode_utf16()
-                .collect();
-            path_utf16.push(0);
-
-            let result = PdhAddEnglishCounterW(query, PCWSTR(path_utf16.as_ptr()), 0, &mut counter);
-
-            if result != 0 {
-                let _ = PdhCloseQuery(query);
-                return Self {
-                    query: 0,
-                    counter: 0,
-                    initialized: false,
-                };
+                // Rate counters need a first sample to compare against
+                let _ = PdhCollectQueryData(query);
+                Ok(Self { query, counter })
             }
+        }
 
-            // Initial collect
-            let _ = PdhCollectQueryData(query);
+        pub fn sample(&self) -> HashMap<u32, f32> {
+            let mut usage_map: HashMap<u32, f32> = HashMap::new();
 
-            Self {
-                query,
-                counter,
-                initialized: true,
+            unsafe {
+                let status = PdhCollectQueryData(self.query);
+                if status != 0 {
+                    tracing::debug!(status, "PDH collection failed");
+                    return usage_map;
+                }
+
+                // The first call only reports the buffer size (PDH_MORE_DATA)
+                let mut buffer_size = 0;
+                let mut item_count = 0;
+                let _ = PdhGetFormattedCounterArrayW(
+                    self.counter,
+                    PDH_FMT_DOUBLE,
+                    &mut buffer_size,
+                    &mut item_count,
+                    None,
+                );
+                if buffer_size == 0 {
+                    return usage_map;
+                }
+
+                let mut buffer = vec![0u8; buffer_size as usize];
+                let items_ptr = buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
+                if PdhGetFormattedCounterArrayW(
+                    self.counter,
+                    PDH_FMT_DOUBLE,
+                    &mut buffer_size,
+                    &mut item_count,
+                    Some(items_ptr),
+                ) == 0
+                {
+                    for i in 0..item_count {
+                        let item = *items_ptr.offset(i as isize);
+                        let name_ptr = item.szName.0;
+                        if !name_ptr.is_null() {
+                            let name_len = (0..).take_while(|&i| *name_ptr.offset(i) != 0).count();
+                            let name_slice = std::slice::from_raw_parts(name_ptr, name_len);
+                            let name = String::from_utf16_lossy(name_slice);
+
+                            // e.g. "pid_1234_luid_..._engtype_3D"
+                            if let Some(pid) = super::parse_pid_from_instance(&name) {
+                                let value = item.FmtValue.Anonymous.doubleValue;
+                                *usage_map.entry(pid).or_insert(0.0) += value as f32;
+                            }
+                        }
+                    }
+                }
             }
-        }
 
-        #[cfg(not(windows))]
-        Self {}
+            tracing::trace!(processes = usage_map.len(), "GPU usage sampled");
+            usage_map
+        }
     }
 
-    #[cfg(windows)]
-    pub fn get_usage(&self) -> HashMap<u32, f32> {
-        if !self.initialized {
-            return HashMap::new();
+    impl Drop for Query {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = PdhCloseQuery(self.query);
+            }
         }
+    }
+}
 
-        let mut usage_map: HashMap<u32, f32> = HashMap::new();
+pub struct GpuMonitor {
+    support: GpuSupport,
+    detail: Option<String>,
+    #[cfg(windows)]
+    query: Option<pdh::Query>,
+}
 
-        unsafe {
-            // Collect new data
-            if PdhCollectQueryData(self.query) != 0 {
-                return usage_map;
-            }
+/// Shared monitor; PDH rate counters need the previous sample, so there is only ever one.
+/// A Mutex, not a RwLock: sampling collects into the query, so two readers would race.
+pub type SharedGpuMonitor = Arc<Mutex<GpuMonitor>>;
 
-            // Prepare to get array
-            let mut buffer_size = 0;
-            let mut item_count = 0;
-
-            // First call to get buffer size
-            // We expect PdhGetFormattedCounterArrayW to return PDH_MORE_DATA (which is non-zero)
-            let _ = PdhGetFormattedCounterArrayW(
-                self.counter,
-                PDH_FMT_DOUBLE,
-                &mut buffer_size,
-                &mut item_count,
-                None,
-            );
-
-            if buffer_size == 0 {
-                return usage_map;
-            }
+// Opened on first use; setup() also registers it as Tauri managed state
+static GPU_MONITOR: OnceLock<SharedGpuMonitor> = OnceLock::new();
 
-            let mut buffer = vec![0u8; buffer_size as usize];
-            let items_ptr = buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
-
-            // Second call to get actual data
-            if PdhGetFormattedCounterArrayW(
-                self.counter,
-                PDH_FMT_DOUBLE,
-                &mut buffer_size,
-                &mut item_count,
-                Some(items_ptr),
-            ) == 0
-            {
-                // Iterate over items
-                for i in 0..item_count {
-                    let item = *items_ptr.offset(i as isize);
-                    let name_ptr = item.szName.0;
-                    if !name_ptr.is_null() {
-                        let name_len = (0..).take_while(|&i| *name_ptr.offset(i) != 0).count();
-                        let name_slice = std::slice::from_raw_parts(name_ptr, name_len);
-                        let name = String::from_utf16_lossy(name_slice);
-
-                        // Parse instance name to get PID
-                        // Format example: "pid_1234_engtype_3D_eng_0"
-                        if let Some(pid) = parse_pid_from_instance(&name) {
-                            let value = item.FmtValue.Anonymous.doubleValue;
-                            *usage_map.entry(pid).or_insert(0.0) += value as f32;
-                        }
+impl GpuMonitor {
+    pub fn new() -> Self {
+        #[cfg(windows)]
+        {
+            match pdh::Query::open() {
+                Ok(query) => Self {
+                    support: GpuSupport::Available,
+                    detail: None,
+                    query: Some(query),
+                },
+                Err(err) => {
+                    tracing::warn!("{}; GPU usage unavailable", err);
+                    Self {
+                        support: GpuSupport::Unavailable,
+                        detail: Some(err),
+                        query: None,
                     }
                 }
             }
         }
+        #[cfg(not(windows))]
+        {
+            Self {
+                support: GpuSupport::Unsupported,
+                detail: None,
+            }
+        }
+    }
 
-        usage_map
+    pub fn status(&self) -> GpuMonitorStatus {
+        GpuMonitorStatus {
+            support: self.support,
+            source: cfg!(windows).then_some("pdh"),
+            detail: self.detail.clone(),
+        }
     }
 
-    #[cfg(not(windows))]
     pub fn get_usage(&self) -> HashMap<u32, f32> {
-        HashMap::new()
+        #[cfg(windows)]
+        {
+            self.query
+                .as_ref()
+                .map(pdh::Query::sample)
+                .unwrap_or_default()
+        }
+        #[cfg(not(windows))]
+        {
+            HashMap::new()
+        }
     }
 }
 
-// Helper to parse PID
+/// PID from a GPU Engine instance name ("pid_1234_...")
+#[cfg(windows)]
 fn parse_pid_from_instance(name: &str) -> Option<u32> {
-    // Look for "pid_"
-    if let Some(idx) = name.find("pid_") {
-        let rest = &name[idx + 4..];
-        // Find next underscore or end of string
-        let end_idx = rest.find('_').unwrap_or(rest.len());
-        let pid_str = &rest[..end_idx];
-        return pid_str.parse::<u32>().ok();
-    }
-    None
+    let rest = &name[name.find("pid_")? + 4..];
+    let end = rest.find('_').unwrap_or(rest.len());
+    rest[..end].parse::<u32>().ok()
 }
 
 // Public API
+pub fn monitor() -> SharedGpuMonitor {
+    GPU_MONITOR
+        .get_or_init(|| Arc::new(Mutex::new(GpuMonitor::new())))
+        .clone()
+}
+
+/// Whether per-process GPU usage is being measured on this machine
+pub fn is_available() -> bool {
+    monitor().lock().unwrap().support == GpuSupport::Available
+}
+
+pub fn status() -> GpuMonitorStatus {
+    monitor().lock().unwrap().status()
+}
+
 pub fn get_gpu_usages() -> HashMap<u32, f32> {
-    let mut monitor = GPU_MONITOR.lock().unwrap();
-    if monitor.is_none() {
-        *monitor = Some(GpuMonitor::new());
+    // PDH collection is comparatively expensive; skip it entirely in low-power mode
+    if crate::power::is_active() {
+        return HashMap::new();
+    }
+
+    let usage = monitor().lock().unwrap().get_usage();
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_monitor_is_shared() {
+        assert!(Arc::ptr_eq(&monitor(), &monitor()));
     }
 
-    if let Some(mon) = monitor.as_ref() {
-        mon.get_usage()
-    } else {
-        HashMap::new()
+    #[cfg(not(windows))]
+    #[test]
+    fn reports_unsupported_without_a_source() {
+        assert_eq!(status().support, GpuSupport::Unsupported);
+        assert!(monitor().lock().unwrap().get_usage().is_empty());
     }
 }
@@ -0,0 +1,406 @@
+// What a hung process is blocked on, so the user can see it before deciding to kill it.
+// Windows follows each thread's wait chain with Wait Chain Traversal (locks, ALPC, SendMessage,
+// sockets, other processes) and flags deadlocks; Linux reports each sleeping thread's kernel
+// wait and, where it can tell, the file, socket or child it waits on.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::sampler;
+
+#[derive(Serialize, Clone)]
+pub struct WaitNode {
+    pub kind: String, // e.g. "thread", "mutex", "critical section", "socket I/O", "read"
+    pub status: String, // e.g. "blocked", "owned", "running"
+    pub name: Option<String>,
+    pub pid: Option<u32>,
+    pub tid: Option<u32>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ThreadWait {
+    pub tid: u32,
+    pub chain: Vec<WaitNode>, // What the thread waits on, then what that waits on, and so on
+    pub cycle: bool,          // The chain loops back: a deadlock
+}
+
+#[derive(Serialize, Clone)]
+pub struct HangReport {
+    pub pid: u32,
+    pub not_responding: Option<bool>, // Windows: a visible window isn't pumping messages
+    pub deadlock: bool,
+    pub threads: Vec<ThreadWait>, // Only threads that are waiting on something
+    pub summary: Vec<String>,
+}
+
+#[cfg(windows)]
+mod wct {
+    use std::ffi::c_void;
+
+    use super::WaitNode;
+
+    const WCT_MAX_NODE_COUNT: usize = 16;
+    const WCT_OBJNAME_LENGTH: usize = 128;
+    const WCT_OUT_OF_PROC_FLAG: u32 = 0x1;
+    const WCT_OUT_OF_PROC_CS_FLAG: u32 = 0x4;
+    const WCT_NETWORK_IO_FLAG: u32 = 0x8;
+    const WCT_THREAD_TYPE: i32 = 8;
+
+    // Layouts from wct.h; only a few fields are read
+    #[allow(dead_code)]
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct LockObject {
+        object_name: [u16; WCT_OBJNAME_LENGTH],
+        timeout: i64,
+        alertable: i32,
+    }
+
+    #[allow(dead_code)]
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct ThreadObject {
+        process_id: u32,
+        thread_id: u32,
+        wait_time: u32,
+        context_switches: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    union NodeObject {
+        lock: LockObject,
+        thread: ThreadObject,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct NodeInfo {
+        object_type: i32,
+        object_status: i32,
+        object: NodeObject,
+    }
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn OpenThreadWaitChainSession(flags: u32, callback: *const c_void) -> *mut c_void;
+        fn CloseThreadWaitChainSession(session: *mut c_void);
+        fn GetThreadWaitChain(
+            session: *mut c_void,
+            context: usize,
+            flags: u32,
+            thread_id: u32,
+            node_count: *mut u32,
+            nodes: *mut NodeInfo,
+            is_cycle: *mut i32,
+        ) -> i32;
+    }
+
+    fn kind_name(object_type: i32) -> &'static str {
+        match object_type {
+            1 => "critical section",
+            2 => "SendMessage",
+            3 => "mutex",
+            4 => "ALPC",
+            5 => "COM",
+            6 => "thread wait",
+            7 => "process wait",
+            8 => "thread",
+            9 => "COM activation",
+            11 => "socket I/O",
+            12 => "SMB I/O",
+            _ => "unknown",
+        }
+    }
+
+    fn status_name(status: i32) -> &'static str {
+        match status {
+            1 => "no access",
+            2 => "running",
+            3 => "blocked",
+            4 | 5 => "pid only",
+            6 => "owned",
+            7 => "not owned",
+            8 => "abandoned",
+            10 => "error",
+            _ => "unknown",
+        }
+    }
+
+    /// A synchronous wait chain session, closed on drop
+    pub struct Session(*mut c_void);
+
+    impl Session {
+        pub fn open() -> Result<Self, String> {
+            let session = unsafe { OpenThreadWaitChainSession(0, std::ptr::null()) };
+            if session.is_null() {
+                return Err("Wait chain analysis is unavailable".into());
+            }
+            Ok(Self(session))
+        }
+
+        /// The chain starting at `tid` (the thread itself first), and whether it loops
+        pub fn chain(&self, tid: u32) -> Option<(Vec<WaitNode>, bool)> {
+            let mut nodes: [NodeInfo; WCT_MAX_NODE_COUNT] = unsafe { std::mem::zeroed() };
+            let mut count = WCT_MAX_NODE_COUNT as u32;
+            let mut cycle = 0i32;
+            let flags = WCT_OUT_OF_PROC_FLAG | WCT_OUT_OF_PROC_CS_FLAG | WCT_NETWORK_IO_FLAG;
+            let ok = unsafe {
+                GetThreadWaitChain(
+                    self.0,
+                    0,
+                    flags,
+                    tid,
+                    &mut count,
+                    nodes.as_mut_ptr(),
+                    &mut cycle,
+                )
+            };
+            if ok == 0 {
+                return None;
+            }
+            let chain = nodes[..(count as usize).min(WCT_MAX_NODE_COUNT)]
+                .iter()
+                .map(|node| {
+                    let (name, pid, tid) = if node.object_type == WCT_THREAD_TYPE {
+                        let thread = unsafe { node.object.thread };
+                        (None, Some(thread.process_id), Some(thread.thread_id))
+                    } else {
+                        let raw = unsafe { node.object.lock.object_name };
+                        let len = raw.iter().position(|&c| c == 0).unwrap_or(raw.len());
+                        let name = String::from_utf16_lossy(&raw[..len]);
+                        ((!name.is_empty()).then_some(name), None, None)
+                    };
+                    WaitNode {
+                        kind: kind_name(node.object_type).to_string(),
+                        status: status_name(node.object_status).to_string(),
+                        name,
+                        pid,
+                        tid,
+                    }
+                })
+                .collect();
+            Some((chain, cycle != 0))
+        }
+    }
+
+    impl Drop for Session {
+        fn drop(&mut self) {
+            unsafe { CloseThreadWaitChainSession(self.0) };
+        }
+    }
+
+    /// Whether any visible window of `pid` has stopped responding; None without windows
+    pub fn not_responding(pid: u32) -> Option<bool> {
+        use windows::Win32::Foundation::{BOOL, HWND, LPARAM};
+        use windows::Win32::UI::WindowsAndMessaging::{
+            EnumWindows, GetWindowThreadProcessId, IsHungAppWindow, IsWindowVisible,
+        };
+
+        struct Search {
+            pid: u32,
+            windows: u32,
+            hung: bool,
+        }
+
+        unsafe extern "system" fn visit(window: HWND, param: LPARAM) -> BOOL {
+            let search = &mut *(param.0 as *mut Search);
+            let mut owner = 0u32;
+            GetWindowThreadProcessId(window, Some(&mut owner as *mut u32));
+            if owner == search.pid && IsWindowVisible(window).as_bool() {
+                search.windows += 1;
+                search.hung |= IsHungAppWindow(window).as_bool();
+            }
+            true.into()
+        }
+
+        let mut search = Search {
+            pid,
+            windows: 0,
+            hung: false,
+        };
+        unsafe { EnumWindows(Some(visit), LPARAM(&mut search as *mut Search as isize)) }.ok()?;
+        (search.windows > 0).then_some(search.hung)
+    }
+}
+
+#[cfg(windows)]
+fn thread_waits(pid: u32) -> Result<(Vec<ThreadWait>, Option<bool>), String> {
+    let session = wct::Session::open()?;
+    let waits = crate::threads::thread_ids(pid)?
+        .into_iter()
+        .filter_map(|tid| {
+            let (mut chain, cycle) = session.chain(tid)?;
+            // The first node is the thread itself; a lone or running thread waits on nothing
+            if chain.len() < 2 || chain[0].status == "running" {
+                return None;
+            }
+            chain.remove(0);
+            Some(ThreadWait { tid, chain, cycle })
+        })
+        .collect();
+    Ok((waits, wct::not_responding(pid)))
+}
+
+/// Blocking syscalls worth naming, and whether their first argument is a file descriptor
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn syscall_name(number: u64) -> Option<(&'static str, bool)> {
+    Some(match number {
+        0 => ("read", true),
+        1 => ("write", true),
+        7 => ("poll", false),
+        23 => ("select", false),
+        35 | 230 => ("sleep", false),
+        42 => ("connect", true),
+        43 | 288 => ("accept", true),
+        44 | 46 => ("send", true),
+        45 | 47 => ("receive", true),
+        61 => ("wait for child", false),
+        73 => ("flock", true),
+        74 => ("fsync", true),
+        202 => ("futex (lock or condition)", false),
+        232 | 281 => ("epoll wait", false),
+        271 => ("poll", false),
+        _ => return None,
+    })
+}
+
+#[cfg(all(target_os = "linux", not(target_arch = "x86_64")))]
+fn syscall_name(_number: u64) -> Option<(&'static str, bool)> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn thread_waits(pid: u32) -> Result<(Vec<ThreadWait>, Option<bool>), String> {
+    let mut waits = Vec::new();
+    for tid in crate::threads::thread_ids(pid)? {
+        let task = format!("/proc/{}/task/{}", pid, tid);
+        let Ok(stat) = std::fs::read_to_string(format!("{}/stat", task)) else {
+            continue;
+        };
+        let state = stat
+            .rfind(')')
+            .and_then(|close| stat[close + 1..].split_whitespace().next())
+            .unwrap_or("");
+        let status = match state {
+            "S" => "sleeping",
+            "D" => "disk wait",
+            "T" | "t" => "stopped",
+            _ => continue, // Running or exiting
+        };
+        let wchan = std::fs::read_to_string(format!("{}/wchan", task))
+            .ok()
+            .filter(|w| !w.is_empty() && w != "0");
+
+        // "202 0x7f... 0x80 ..." - the syscall number and its arguments (needs ptrace access)
+        let syscall = std::fs::read_to_string(format!("{}/syscall", task)).unwrap_or_default();
+        let mut fields = syscall.split_whitespace();
+        let number = fields.next().and_then(|n| n.parse::<u64>().ok());
+        let first_arg = fields
+            .next()
+            .and_then(|a| u64::from_str_radix(a.trim_start_matches("0x"), 16).ok());
+        let node = match number.and_then(syscall_name) {
+            Some((name, takes_fd)) => {
+                let target = first_arg.filter(|_| takes_fd).and_then(|fd| {
+                    std::fs::read_link(format!("/proc/{}/fd/{}", pid, fd))
+                        .ok()
+                        .map(|path| path.to_string_lossy().to_string())
+                });
+                WaitNode {
+                    kind: name.to_string(),
+                    status: status.to_string(),
+                    name: target.or(wchan),
+                    // wait4's first argument is the child being waited on (-1 = any)
+                    pid: first_arg
+                        .filter(|_| number == Some(61))
+                        .and_then(|child| u32::try_from(child).ok()),
+                    tid: None,
+                }
+            }
+            None => WaitNode {
+                kind: "kernel wait".to_string(),
+                status: status.to_string(),
+                name: wchan,
+                pid: None,
+                tid: None,
+            },
+        };
+        waits.push(ThreadWait {
+            tid,
+            chain: vec![node],
+            cycle: false,
+        });
+    }
+    Ok((waits, None))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn thread_waits(_pid: u32) -> Result<(Vec<ThreadWait>, Option<bool>), String> {
+    Err("Hang analysis is not supported on this platform".into())
+}
+
+/// "Thread 1234 waits on: mutex \Sessions\1\Foo (owned) -> thread 5678 in other.exe (PID 42)"
+fn describe(wait: &ThreadWait, pid: u32, names: &HashMap<u32, String>) -> String {
+    let nodes: Vec<String> = wait
+        .chain
+        .iter()
+        .map(|node| {
+            let mut text = node.kind.clone();
+            if let Some(tid) = node.tid {
+                text.push_str(&format!(" {}", tid));
+            }
+            if let Some(name) = &node.name {
+                text.push_str(&format!(" {}", name));
+            }
+            match node.pid {
+                Some(other) if other != pid => text.push_str(&format!(
+                    " in {} (PID {})",
+                    names.get(&other).map_or("?", String::as_str),
+                    other
+                )),
+                _ => {}
+            }
+            format!("{} ({})", text, node.status)
+        })
+        .collect();
+    let deadlock = if wait.cycle { " - deadlock" } else { "" };
+    format!(
+        "Thread {} waits on: {}{}",
+        wait.tid,
+        nodes.join(" -> "),
+        deadlock
+    )
+}
+
+// Public API
+/// What each waiting thread of `pid` is blocked on, with deadlocks and other processes named
+pub fn analyze(pid: u32) -> Result<HangReport, String> {
+    let (threads, not_responding) = thread_waits(pid)?;
+    let names: HashMap<u32, String> = sampler::with_processes(
+        |_| false,
+        |sys, _| {
+            sys.processes()
+                .values()
+                .map(|p| (p.pid().as_u32(), p.name().to_string_lossy().to_string()))
+                .collect()
+        },
+    );
+    let deadlock = threads.iter().any(|t| t.cycle);
+    // Deadlocked and cross-process waits first; they're the likely cause
+    let mut summary: Vec<(bool, String)> = threads
+        .iter()
+        .map(|t| {
+            let interesting = t.cycle || t.chain.iter().any(|n| n.pid.is_some_and(|p| p != pid));
+            (interesting, describe(t, pid, &names))
+        })
+        .collect();
+    summary.sort_by_key(|(interesting, _)| !interesting);
+    tracing::info!(pid, waiting = threads.len(), deadlock, "hang analysis");
+    Ok(HangReport {
+        pid,
+        not_responding,
+        deadlock,
+        threads,
+        summary: summary.into_iter().map(|(_, line)| line).collect(),
+    })
+}
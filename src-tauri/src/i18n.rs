@@ -0,0 +1,69 @@
+// Embedded message catalogs for backend result strings, log reasons and notifications
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+const DEFAULT_LANGUAGE: &str = "en";
+
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../resources/i18n/en.json")),
+    ("th", include_str!("../resources/i18n/th.json")),
+];
+
+static MESSAGES: OnceLock<HashMap<&'static str, HashMap<String, String>>> = OnceLock::new();
+// Kept outside AppState so messages can be built while the state lock is held
+static LANGUAGE: RwLock<String> = RwLock::new(String::new());
+
+fn messages() -> &'static HashMap<&'static str, HashMap<String, String>> {
+    MESSAGES.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(lang, json)| (*lang, serde_json::from_str(json).unwrap_or_default()))
+            .collect()
+    })
+}
+
+// Public API
+pub fn available() -> Vec<String> {
+    CATALOGS.iter().map(|(lang, _)| lang.to_string()).collect()
+}
+
+/// Switch language; region suffixes are ignored ("th-TH" -> "th")
+pub fn set_language(locale: &str) -> Result<String, String> {
+    let lang = locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    if !messages().contains_key(lang.as_str()) {
+        return Err(format!("Unsupported language: {}", locale));
+    }
+    *LANGUAGE.write().unwrap() = lang.clone();
+    Ok(lang)
+}
+
+/// Message `id` in the current language with `{key}` placeholders filled in.
+/// Falls back to English, then to the id itself.
+pub fn t(id: &str, args: &[(&str, String)]) -> String {
+    let lang = LANGUAGE.read().unwrap().clone();
+    let catalogs = messages();
+    let template = catalogs
+        .get(lang.as_str())
+        .and_then(|m| m.get(id))
+        .or_else(|| catalogs.get(DEFAULT_LANGUAGE).and_then(|m| m.get(id)))
+        .map(|s| s.as_str())
+        .unwrap_or(id);
+
+    args.iter()
+        .fold(template.to_string(), |text, (key, value)| {
+            text.replace(&format!("{{{}}}", key), value)
+        })
+}
+
+/// The usual `{cpu}` / `{gpu}` arguments, formatted to one decimal
+pub fn usage(cpu: f32, gpu: f32) -> [(&'static str, String); 2] {
+    [
+        ("cpu", format!("{:.1}", cpu)),
+        ("gpu", format!("{:.1}", gpu)),
+    ]
+}
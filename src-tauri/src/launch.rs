@@ -0,0 +1,270 @@
+// Starting processes: plain restarts of a killed app ("undo", deep links, leak restarts), and
+// launches under another account so admins can bring a service app back as its service user.
+// Windows uses CreateProcessWithLogonW; Linux goes through sudo.
+//
+// Undo only restarts what the app itself killed recently, looked up by a random id, so a link
+// can't be used to start an arbitrary program.
+
+use serde::Serialize;
+use std::hash::BuildHasher;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use sysinfo::Process;
+
+// How long a killed process can be restarted, and how many are kept
+const RECENT_KEEP: Duration = Duration::from_secs(60 * 60);
+const RECENT_MAX: usize = 50;
+
+#[derive(Serialize, Clone)]
+pub struct Launched {
+    pub pid: u32,
+    pub username: String,
+}
+
+/// A process the app killed, with what is needed to start it the same way again
+#[derive(Serialize, Clone)]
+pub struct RecentKill {
+    pub id: u64, // Random, below 2^53 so it survives a trip through JavaScript
+    pub name: String,
+    pub exe_path: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    #[serde(skip)]
+    killed: Instant,
+}
+
+static RECENT: Mutex<Vec<RecentKill>> = Mutex::new(Vec::new());
+
+fn random_id() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    std::collections::hash_map::RandomState::new().hash_one(nanos) & ((1 << 53) - 1)
+}
+
+/// Quote one argument for a Windows command line (CommandLineToArgvW rules)
+#[cfg(windows)]
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Backslashes before a quote are escaped, then the quote itself
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(windows)]
+fn spawn_as(path: &str, args: &[String], username: &str, password: &str) -> Result<u32, String> {
+    use windows::core::{PCWSTR, PWSTR};
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        CreateProcessWithLogonW, LOGON_WITH_PROFILE, PROCESS_CREATION_FLAGS, PROCESS_INFORMATION,
+        STARTUPINFOW,
+    };
+
+    let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    // "DOMAIN\user" or "user@domain" as given; a bare name is a local account
+    let (user, domain) = match username.split_once('\\') {
+        Some((domain, user)) => (user.to_string(), Some(domain.to_string())),
+        None if username.contains('@') => (username.to_string(), None),
+        None => (username.to_string(), Some(".".to_string())),
+    };
+    let user = wide(&user);
+    let domain = domain.map(|d| wide(&d));
+    let password = wide(password);
+    let application = wide(path);
+    let mut command_line = wide(
+        &std::iter::once(path)
+            .chain(args.iter().map(String::as_str))
+            .map(quote_arg)
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    let startup = STARTUPINFOW {
+        cb: std::mem::size_of::<STARTUPINFOW>() as u32,
+        ..Default::default()
+    };
+    let mut info = PROCESS_INFORMATION::default();
+    unsafe {
+        CreateProcessWithLogonW(
+            PCWSTR(user.as_ptr()),
+            domain
+                .as_ref()
+                .map_or(PCWSTR::null(), |d| PCWSTR(d.as_ptr())),
+            PCWSTR(password.as_ptr()),
+            LOGON_WITH_PROFILE,
+            PCWSTR(application.as_ptr()),
+            PWSTR(command_line.as_mut_ptr()),
+            PROCESS_CREATION_FLAGS(0),
+            None,
+            PCWSTR::null(),
+            &startup,
+            &mut info,
+        )
+        .map_err(|e| format!("Failed to start {} as {}: {}", path, username, e))?;
+        CloseHandle(info.hThread).ok();
+        CloseHandle(info.hProcess).ok();
+    }
+    Ok(info.dwProcessId)
+}
+
+#[cfg(unix)]
+fn spawn_as(path: &str, args: &[String], username: &str, password: &str) -> Result<u32, String> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    // The password, if any, is the invoking user's sudo password; without one sudo must not ask.
+    // -k makes sudo read it even with cached credentials, or the password written to stdin
+    // would reach the started program instead.
+    let mut command = crate::containers::hidden_command("sudo");
+    if password.is_empty() {
+        command.arg("-n");
+    } else {
+        command.args(["-S", "-k", "-p", ""]);
+    }
+    let mut child = command
+        .args(["-u", username, "--", path])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run sudo: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        if !password.is_empty() {
+            writeln!(stdin, "{}", password).ok();
+        }
+    }
+    // A refusal (no sudoers rule, wrong password) comes back right away
+    for _ in 0..10 {
+        if let Ok(Some(status)) = child.try_wait() {
+            if !status.success() {
+                return Err(format!("sudo refused to start {} as {}", path, username));
+            }
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    // sudo stays the parent of the program; this is sudo's PID
+    let pid = child.id();
+    std::thread::spawn(move || child.wait());
+    Ok(pid)
+}
+
+#[cfg(not(any(windows, unix)))]
+fn spawn_as(
+    _path: &str,
+    _args: &[String],
+    _username: &str,
+    _password: &str,
+) -> Result<u32, String> {
+    Err("Launching as another user is not supported on this platform".into())
+}
+
+// Public API
+/// Start `exe_path` with `args` as the current user
+pub fn start(exe_path: &str, args: &[String], cwd: Option<&Path>) -> Result<String, String> {
+    let mut command = std::process::Command::new(exe_path);
+    command.args(args);
+    if let Some(cwd) = cwd.filter(|dir| dir.is_dir()) {
+        command.current_dir(cwd);
+    }
+    command
+        .spawn()
+        .map(|_| format!("{} started", exe_path))
+        .map_err(|e| format!("Failed to start {}: {}", exe_path, e))
+}
+
+/// Remember `p` as just killed so it can be restarted; None when its executable is unknown
+pub fn remember_kill(p: &Process) -> Option<u64> {
+    let exe_path = p.exe()?.to_string_lossy().to_string();
+    let entry = RecentKill {
+        id: random_id(),
+        name: p.name().to_string_lossy().to_string(),
+        exe_path,
+        // The first element is the program itself
+        args: p
+            .cmd()
+            .iter()
+            .skip(1)
+            .map(|a| a.to_string_lossy().to_string())
+            .collect(),
+        cwd: p.cwd().map(|d| d.to_string_lossy().to_string()),
+        killed: Instant::now(),
+    };
+    let id = entry.id;
+    let mut recent = RECENT.lock().unwrap();
+    recent.retain(|r| r.killed.elapsed() < RECENT_KEEP);
+    if recent.len() >= RECENT_MAX {
+        recent.remove(0);
+    }
+    recent.push(entry);
+    Some(id)
+}
+
+/// Processes killed within the last hour that can still be restarted, newest first
+pub fn recent_kills() -> Vec<RecentKill> {
+    let mut recent = RECENT.lock().unwrap();
+    recent.retain(|r| r.killed.elapsed() < RECENT_KEEP);
+    recent.iter().rev().cloned().collect()
+}
+
+/// Start a recently killed process again with its original command line; each id works once
+pub fn restart(id: u64) -> Result<String, String> {
+    let entry = {
+        let mut recent = RECENT.lock().unwrap();
+        recent.retain(|r| r.killed.elapsed() < RECENT_KEEP);
+        let index = recent
+            .iter()
+            .position(|r| r.id == id)
+            .ok_or("Nothing to restart - it was restarted already or killed over an hour ago")?;
+        recent.remove(index)
+    };
+    tracing::info!(name = %entry.name, exe = %entry.exe_path, "restarting killed process");
+    start(
+        &entry.exe_path,
+        &entry.args,
+        entry.cwd.as_deref().map(Path::new),
+    )
+}
+
+/// Start `path` with `args` under `username`. The password is used once and never stored.
+pub fn launch_as_user(
+    path: &str,
+    args: &[String],
+    username: &str,
+    password: &str,
+) -> Result<Launched, String> {
+    let (path, username) = (path.trim(), username.trim());
+    if path.is_empty() || username.is_empty() {
+        return Err("Enter a program and a user name".into());
+    }
+    if !std::path::Path::new(path).is_file() {
+        return Err(format!("{} not found", path));
+    }
+    let pid = spawn_as(path, args, username, password)?;
+    tracing::info!(path, username, pid, "launched as user");
+    Ok(Launched {
+        pid,
+        username: username.to_string(),
+    })
+}
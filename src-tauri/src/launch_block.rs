@@ -0,0 +1,316 @@
+// "Block launch" rules: keep a program from running at all. Every such rule is killed the moment
+// the creation watcher sees it start. On Windows, with admin rights, a rule naming an exact exe
+// also gets an Image File Execution Options "Debugger" entry pointing back at us, so Windows
+// never starts the program and we log the attempt instead. Entries are kept in step with the
+// rules in the background, so removing or changing a rule always lifts the block. Rules that
+// `validate` refuses are never applied, however they arrived (config file, sync, restore).
+// All our entries come off when the app exits and when it is uninstalled (LIFT_FLAG, run by
+// the installer hook in windows/hooks.nsh), so a moved or removed app can't leave a program
+// permanently unlaunchable.
+
+use chrono::Local;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::matcher::Matcher;
+use crate::system_actions::RuleAction;
+use crate::{notify_event, protection, read_state, record_activity, with_state, ActivityLog};
+
+/// Passed by Windows in place of the blocked program, followed by its command line
+pub const BLOCKED_FLAG: &str = "--blocked-launch";
+/// Remove every block entry we made and exit; run by the uninstaller
+pub const LIFT_FLAG: &str = "--lift-launch-blocks";
+const SYNC_INTERVAL: Duration = Duration::from_secs(5);
+// Never blockable beyond the protected list: without these there is no desktop, or no us
+const CRITICAL_NAMES: &[&str] = &[
+    "explorer.exe",
+    "dwm.exe",
+    "svchost.exe",
+    "conhost.exe",
+    "userinit.exe",
+    "logonui.exe",
+    "sihost.exe",
+    "msedgewebview2.exe",
+];
+
+#[derive(Serialize, Clone)]
+pub struct BlockedLaunch {
+    pub rule: String,
+    pub method: String, // "ifeo" when Windows refuses to start it, "watcher" when killed at launch
+    pub note: Option<String>, // Why the stronger block isn't in place
+}
+
+/// Sent as "launch-blocked" when Windows handed us a blocked program instead of starting it
+#[derive(Serialize, Clone)]
+struct LaunchBlocked {
+    name: String,
+    rule: String,
+}
+
+// Rule names the current entries were made for, and what each got
+static STATUS: Mutex<Option<(Vec<String>, Vec<BlockedLaunch>)>> = Mutex::new(None);
+
+/// Exe name for an Image File Execution Options entry; only exact names like "game.exe" work
+#[cfg(windows)]
+fn ifeo_name(rule: &str) -> Option<String> {
+    let name = rule.trim().to_lowercase();
+    let plain = !name.contains(['\\', '/', '*', '?', '"']);
+    (plain && name.len() > 4 && name.ends_with(".exe")).then_some(name)
+}
+
+#[cfg(windows)]
+mod ifeo {
+    use crate::onboarding::registry;
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegCreateKeyExW, RegDeleteKeyValueW, RegSetValueExW, HKEY, HKEY_LOCAL_MACHINE,
+        KEY_SET_VALUE, REG_OPTION_NON_VOLATILE, REG_SZ,
+    };
+
+    const IFEO: &str = r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Image File Execution Options";
+
+    /// What Windows runs instead, with the blocked program's command line appended
+    pub fn debugger() -> Result<String, String> {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        Ok(format!("\"{}\" {}", exe.display(), super::BLOCKED_FLAG))
+    }
+
+    /// Exe names with one of our Debugger entries, lower-case, and the command each runs
+    pub fn ours() -> Vec<(String, String)> {
+        registry::subkeys(HKEY_LOCAL_MACHINE, IFEO)
+            .into_iter()
+            .filter_map(|key| {
+                let value = registry::string(
+                    HKEY_LOCAL_MACHINE,
+                    &format!(r"{}\{}", IFEO, key),
+                    "Debugger",
+                )?;
+                value
+                    .contains(super::BLOCKED_FLAG)
+                    .then(|| (key.to_lowercase(), value))
+            })
+            .collect()
+    }
+
+    pub fn set(exe_name: &str, debugger: &str) -> Result<(), String> {
+        let mut key = HKEY::default();
+        let data: Vec<u8> = debugger
+            .encode_utf16()
+            .chain(Some(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        unsafe {
+            RegCreateKeyExW(
+                HKEY_LOCAL_MACHINE,
+                &HSTRING::from(format!(r"{}\{}", IFEO, exe_name)),
+                0,
+                PCWSTR::null(),
+                REG_OPTION_NON_VOLATILE,
+                KEY_SET_VALUE,
+                None,
+                &mut key,
+                None,
+            )
+            .ok()
+            .map_err(|e| e.message().to_string())?;
+            let result = RegSetValueExW(key, &HSTRING::from("Debugger"), 0, REG_SZ, Some(&data));
+            let _ = RegCloseKey(key);
+            result.ok().map_err(|e| e.message().to_string())
+        }
+    }
+
+    /// Remove only our Debugger value; the key may hold other settings for the program
+    pub fn remove(exe_name: &str) -> Result<(), String> {
+        unsafe {
+            RegDeleteKeyValueW(
+                HKEY_LOCAL_MACHINE,
+                &HSTRING::from(format!(r"{}\{}", IFEO, exe_name)),
+                &HSTRING::from("Debugger"),
+            )
+        }
+        .ok()
+        .map_err(|e| e.message().to_string())
+    }
+}
+
+#[cfg(windows)]
+const NOT_EXACT: &str = "Name it by its exact file name (e.g. game.exe) for Windows to refuse \
+    to start it; until then it is killed at launch";
+
+/// Bring the Image File Execution Options entries in line with `rules`
+#[cfg(windows)]
+fn apply(rules: &[String]) -> Vec<BlockedLaunch> {
+    let debugger = ifeo::debugger();
+    let existing = ifeo::ours();
+    // Entries whose rule is gone, or no longer blocks, come off first
+    for (name, _) in &existing {
+        if !rules
+            .iter()
+            .any(|rule| ifeo_name(rule).as_ref() == Some(name))
+        {
+            match ifeo::remove(name) {
+                Ok(()) => tracing::info!(name, "launch block lifted"),
+                Err(err) => tracing::warn!(name, %err, "failed to lift launch block"),
+            }
+        }
+    }
+    rules
+        .iter()
+        .map(|rule| {
+            let result = match (ifeo_name(rule), &debugger) {
+                (None, _) => Err(NOT_EXACT.to_string()),
+                (Some(_), Err(err)) => Err(err.clone()),
+                // Already in place, pointing at this copy of the app
+                (Some(name), Ok(debugger))
+                    if existing.iter().any(|(n, v)| *n == name && v == debugger) =>
+                {
+                    Ok(())
+                }
+                (Some(name), Ok(debugger)) => ifeo::set(&name, debugger).map_err(|err| {
+                    format!(
+                        "Run as administrator for Windows to refuse to start it ({}); \
+                        until then it is killed at launch",
+                        err
+                    )
+                }),
+            };
+            BlockedLaunch {
+                rule: rule.clone(),
+                method: if result.is_ok() { "ifeo" } else { "watcher" }.to_string(),
+                note: result.err(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn apply(rules: &[String]) -> Vec<BlockedLaunch> {
+    rules
+        .iter()
+        .map(|rule| BlockedLaunch {
+            rule: rule.clone(),
+            method: "watcher".to_string(),
+            note: None,
+        })
+        .collect()
+}
+
+fn block_rules() -> Vec<String> {
+    read_state(|state| {
+        state
+            .blacklist
+            .iter()
+            .filter(|rule| rule.action == RuleAction::BlockLaunch)
+            .filter(|rule| match validate(rule) {
+                Ok(()) => true,
+                Err(err) => {
+                    tracing::warn!(rule = %rule.name, %err, "launch block refused");
+                    false
+                }
+            })
+            .map(|rule| rule.name.clone())
+            .collect()
+    })
+}
+
+/// Re-apply when the set of block rules changed since last time (or `force`)
+fn sync_rules(force: bool) -> Vec<BlockedLaunch> {
+    let rules = block_rules();
+    let mut status = STATUS.lock().unwrap();
+    if let Some((applied, blocked)) = status.as_ref() {
+        if !force && *applied == rules {
+            return blocked.clone();
+        }
+    }
+    let blocked = apply(&rules);
+    *status = Some((rules, blocked.clone()));
+    blocked
+}
+
+// Public API
+pub fn start() {
+    thread::spawn(|| loop {
+        sync_rules(false);
+        thread::sleep(SYNC_INTERVAL);
+    });
+}
+
+/// Apply block rules now, e.g. right after one was added or removed
+pub fn sync() -> Vec<BlockedLaunch> {
+    sync_rules(true)
+}
+
+pub fn status() -> Vec<BlockedLaunch> {
+    sync_rules(false)
+}
+
+/// Take off every Image File Execution Options entry pointing at us (app exit, uninstall).
+/// The next sync puts them back if the app keeps running.
+pub fn lift_all() {
+    #[cfg(windows)]
+    for (name, _) in ifeo::ours() {
+        match ifeo::remove(&name) {
+            Ok(()) => tracing::info!(name, "launch block lifted"),
+            Err(err) => tracing::warn!(name, %err, "failed to lift launch block"),
+        }
+    }
+    *STATUS.lock().unwrap() = None;
+}
+
+/// Refuse a block that would catch a system process or this app
+pub fn validate(rule: &crate::BlacklistEntry) -> Result<(), String> {
+    let matcher = Matcher::for_rule(rule);
+    let own = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.file_name().map(|n| n.to_string_lossy().to_lowercase()));
+    let hit = protection::protected_names()
+        .iter()
+        .chain(CRITICAL_NAMES)
+        .map(|name| name.to_string())
+        .chain(own)
+        .find(|name| matcher.matches(name));
+    match hit {
+        Some(name) => Err(format!(
+            "\"{}\" would also block {}, which Windows or this app needs",
+            rule.name, name
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Windows ran us in place of a blocked program: log the attempt against its rule
+pub fn record_attempt(program: &str) {
+    let name = program
+        .rsplit(['\\', '/'])
+        .next()
+        .unwrap_or(program)
+        .to_string();
+    let rule = read_state(|state| {
+        state
+            .blacklist
+            .iter()
+            .filter(|rule| rule.action == RuleAction::BlockLaunch)
+            .find(|rule| Matcher::for_rule(rule).matches(&name))
+            .map(|rule| rule.name.clone())
+    })
+    .unwrap_or_else(|| name.clone());
+    tracing::info!(program, rule, "launch refused");
+
+    let log = ActivityLog {
+        name: name.clone(),
+        detected_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        was_killed: true,
+        reason: crate::i18n::t("reason.launch_refused", &[("rule", rule.clone())]),
+        exe_path: Some(program.to_string()),
+        ..Default::default()
+    };
+    with_state(|state| {
+        if let Some(entry) = state.blacklist.iter_mut().find(|e| e.name == rule) {
+            entry.kill_count += 1;
+        }
+        record_activity(state, log);
+    });
+    notify_event("launch-blocked", LaunchBlocked { name, rule });
+}
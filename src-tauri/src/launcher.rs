@@ -0,0 +1,292 @@
+// Work out what keeps starting a process by walking up its parent chain: a service (Windows
+// service or systemd unit), a scheduled task or cron job, or just another program. Used to
+// point the user at the real source of a respawn loop, and by "kill launcher" rules.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::process_ctl::{self, EscalationPolicy};
+use crate::sessions::{self, SessionScope};
+use crate::{notes, protection};
+
+const MAX_DEPTH: usize = 8;
+pub const MAX_KILL_DEPTH: u32 = 5;
+// Never killed as a launcher: they start everything in the session, or are the shell or
+// terminal the user started the process from
+const SESSION_HOSTS: &[&str] = &[
+    "explorer.exe",
+    "svchost.exe",
+    "sihost.exe",
+    "userinit.exe",
+    "dwm.exe",
+    "cmd.exe",
+    "powershell.exe",
+    "pwsh.exe",
+    "windowsterminal.exe",
+    "openconsole.exe",
+    "conhost.exe",
+    "gnome-shell",
+    "plasmashell",
+    "gnome-session-binary",
+    "xfce4-session",
+    "systemd",
+    "sshd",
+    "login",
+    "sh",
+    "bash",
+    "zsh",
+    "fish",
+    "dash",
+    "tmux: server",
+    "screen",
+    "gnome-terminal-server",
+    "konsole",
+    "xfce4-terminal",
+    "xterm",
+    "alacritty",
+    "kitty",
+    "wezterm-gui",
+    "tilix",
+];
+// Processes that run scheduled tasks and cron jobs
+const TASK_HOSTS: &[&str] = &[
+    "taskhostw.exe",
+    "taskeng.exe",
+    "taskhost.exe",
+    "cron",
+    "crond",
+    "anacron",
+    "atd",
+];
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Launcher {
+    // Windows service(s) or systemd unit
+    Service {
+        name: String,
+    },
+    // Task Scheduler or cron started it; `host` is the process that ran it
+    ScheduledTask {
+        host: String,
+    },
+    // Nothing system-wide above it: the parent program
+    Process {
+        name: String,
+        pid: u32,
+        exe_path: Option<String>,
+    },
+    Unknown,
+}
+
+/// Services hosted by each PID in `chain`
+#[cfg(windows)]
+fn services(_chain: &[u32]) -> HashMap<u32, Vec<String>> {
+    use windows::core::PCWSTR;
+    use windows::Win32::System::Services::{
+        CloseServiceHandle, EnumServicesStatusExW, OpenSCManagerW, ENUM_SERVICE_STATUS_PROCESSW,
+        SC_ENUM_PROCESS_INFO, SC_MANAGER_ENUMERATE_SERVICE, SERVICE_ACTIVE, SERVICE_WIN32,
+    };
+
+    let mut map: HashMap<u32, Vec<String>> = HashMap::new();
+    unsafe {
+        let Ok(scm) = OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_ENUMERATE_SERVICE)
+        else {
+            return map;
+        };
+        let (mut needed, mut count, mut resume) = (0u32, 0u32, 0u32);
+        // The first call only reports the buffer size
+        let _ = EnumServicesStatusExW(
+            scm,
+            SC_ENUM_PROCESS_INFO,
+            SERVICE_WIN32,
+            SERVICE_ACTIVE,
+            None,
+            &mut needed,
+            &mut count,
+            Some(&mut resume),
+            PCWSTR::null(),
+        );
+        // u64s keep the entries aligned
+        let mut buffer = vec![0u64; (needed as usize).div_ceil(8)];
+        let bytes =
+            std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len() * 8);
+        resume = 0;
+        if EnumServicesStatusExW(
+            scm,
+            SC_ENUM_PROCESS_INFO,
+            SERVICE_WIN32,
+            SERVICE_ACTIVE,
+            Some(bytes),
+            &mut needed,
+            &mut count,
+            Some(&mut resume),
+            PCWSTR::null(),
+        )
+        .is_ok()
+        {
+            let entries = std::slice::from_raw_parts(
+                buffer.as_ptr() as *const ENUM_SERVICE_STATUS_PROCESSW,
+                count as usize,
+            );
+            for entry in entries {
+                let pid = entry.ServiceStatusProcess.dwProcessId;
+                if pid != 0 {
+                    map.entry(pid)
+                        .or_default()
+                        .push(entry.lpServiceName.to_string().unwrap_or_default());
+                }
+            }
+        }
+        let _ = CloseServiceHandle(scm);
+    }
+    map
+}
+
+/// The systemd unit each PID in `chain` runs under, from its cgroup path
+#[cfg(target_os = "linux")]
+fn services(chain: &[u32]) -> HashMap<u32, Vec<String>> {
+    chain
+        .iter()
+        .filter_map(|pid| {
+            let cgroup = std::fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+            // The user manager (user@1000.service) holds every desktop app; not a launcher
+            let unit = cgroup.lines().find_map(|line| {
+                line.splitn(3, ':')
+                    .nth(2)?
+                    .rsplit('/')
+                    .find(|part| part.ends_with(".service") && !part.starts_with("user@"))
+                    .map(str::to_string)
+            })?;
+            Some((*pid, vec![unit]))
+        })
+        .collect()
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn services(_chain: &[u32]) -> HashMap<u32, Vec<String>> {
+    HashMap::new()
+}
+
+// Public API
+/// PIDs above `pid`, nearest first, at most `depth` of them
+pub fn ancestors(sys: &System, pid: u32, depth: usize) -> Vec<u32> {
+    let mut chain = vec![];
+    let mut child = sys.process(Pid::from_u32(pid));
+    while let Some(p) = child {
+        let Some(parent) = p.parent().and_then(|ppid| sys.process(ppid)) else {
+            break;
+        };
+        // A parent that started after its child is a reused PID, not the real parent
+        let pid = parent.pid().as_u32();
+        if chain.len() >= depth || chain.contains(&pid) || parent.start_time() > p.start_time() {
+            break;
+        }
+        chain.push(pid);
+        child = Some(parent);
+    }
+    chain
+}
+
+/// The guards every kill goes through, plus the session hosts
+fn may_kill(p: &Process, allow_protected: bool, session_scope: &SessionScope) -> bool {
+    let name = p.name().to_string_lossy().to_lowercase();
+    !SESSION_HOSTS.contains(&name.as_str())
+        && protection::check(p, allow_protected).is_ok()
+        && notes::check_process(p).is_ok()
+        && session_scope.allows(sessions::session_of(p.pid().as_u32()))
+}
+
+/// Launchers of `pid` to kill, nearest first: up to `depth` levels, stopping below the first
+/// protected, never-touch or out-of-scope process, session host or part of this app
+pub fn to_kill(
+    sys: &System,
+    pid: u32,
+    depth: u32,
+    own_pids: &HashSet<u32>,
+    allow_protected: bool,
+    session_scope: &SessionScope,
+) -> Vec<u32> {
+    ancestors(sys, pid, depth as usize)
+        .into_iter()
+        .take_while(|&pid| {
+            !own_pids.contains(&pid)
+                && sys
+                    .process(Pid::from_u32(pid))
+                    .is_some_and(|p| may_kill(p, allow_protected, session_scope))
+        })
+        .collect()
+}
+
+/// Kill `launchers` (from `to_kill`, nearest first) top-down, so none of them can start the
+/// others again. They are checked again with their executables known, and the chain stops
+/// below the first one that is guarded after all. Returns "name (PID n)" for each one killed.
+pub fn kill_chain(
+    launchers: &[u32],
+    escalation: &EscalationPolicy,
+    session_scope: &SessionScope,
+) -> Vec<String> {
+    if launchers.is_empty() {
+        return vec![];
+    }
+    let pids: Vec<Pid> = launchers.iter().copied().map(Pid::from_u32).collect();
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&pids),
+        true,
+        ProcessRefreshKind::everything(),
+    );
+    let allow_protected = protection::override_enabled();
+    let allowed: Vec<&Process> = pids
+        .iter()
+        .map_while(|pid| sys.process(*pid))
+        .take_while(|p| may_kill(p, allow_protected, session_scope))
+        .collect();
+    allowed
+        .into_iter()
+        .rev()
+        .filter(|p| process_ctl::terminate(p, escalation).is_ok())
+        .map(|p| format!("{} (PID {})", p.name().to_string_lossy(), p.pid()))
+        .collect()
+}
+
+/// What most likely started `pid`
+pub fn identify(sys: &System, pid: u32) -> Launcher {
+    let chain: Vec<u32> = std::iter::once(pid)
+        .chain(ancestors(sys, pid, MAX_DEPTH))
+        .collect();
+    let name_of = |pid: u32| {
+        sys.process(Pid::from_u32(pid))
+            .map(|p| p.name().to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+    let services = services(&chain);
+
+    // A task host anywhere above wins: cron jobs also run inside cron's own unit
+    let task_host = chain.iter().skip(1).find(|&&pid| {
+        TASK_HOSTS.contains(&name_of(pid).to_lowercase().as_str())
+            || services
+                .get(&pid)
+                .is_some_and(|names| names.iter().any(|n| n.eq_ignore_ascii_case("Schedule")))
+    });
+    if let Some(&pid) = task_host {
+        return Launcher::ScheduledTask { host: name_of(pid) };
+    }
+    if let Some(names) = chain.iter().find_map(|pid| services.get(pid)) {
+        return Launcher::Service {
+            name: names.join(", "),
+        };
+    }
+    let parent = chain
+        .get(1)
+        .and_then(|&pid| sys.process(Pid::from_u32(pid)));
+    match parent {
+        Some(parent) => Launcher::Process {
+            name: parent.name().to_string_lossy().to_string(),
+            pid: parent.pid().as_u32(),
+            exe_path: parent.exe().map(|e| e.to_string_lossy().to_string()),
+        },
+        None => Launcher::Unknown,
+    }
+}
@@ -0,0 +1,272 @@
+// Handle leak detector. Records every process's handle count (file descriptors on Linux) once
+// a minute and raises "handle-leak" when a process goes over a hard limit or keeps growing
+// across the whole window. Optionally restarts the leaking process with its own command line.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System, Uid};
+
+use crate::counters::Counters;
+use crate::{
+    notify_event, process_ctl, process_log, protection, read_state, record_activity, sampler,
+    sessions, with_state, ActivityLog,
+};
+
+const TICK: Duration = Duration::from_secs(60);
+const MIN_SAMPLES: usize = 5;
+// Share of steps across the window that must not go down; a leak rarely gives handles back
+const STEADY_SHARE: f32 = 0.8;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HandleLeakPolicy {
+    pub enabled: bool,
+    pub max_handles: u32, // Alert above this count regardless of trend (0 = off)
+    pub growth_per_hour: u32, // Alert when handles grow at least this fast across the window
+    pub window_minutes: u32,
+    pub auto_restart: bool, // Kill and start again with the same command line
+}
+
+impl Default for HandleLeakPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_handles: 10_000,
+            growth_per_hour: 1_000,
+            window_minutes: 30,
+            auto_restart: false,
+        }
+    }
+}
+
+impl HandleLeakPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_handles != 0 && self.max_handles < 100 {
+            return Err("Handle limit must be at least 100 (or 0 for none)".into());
+        }
+        if self.growth_per_hour < 10 {
+            return Err("Growth threshold must be at least 10 handles an hour".into());
+        }
+        if !(5..=1440).contains(&self.window_minutes) {
+            return Err("Window must be between 5 minutes and 24 hours".into());
+        }
+        Ok(())
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.window_minutes as u64 * 60)
+    }
+}
+
+/// Sent as "handle-leak" for each process found leaking
+#[derive(Serialize, Clone)]
+struct HandleLeakEvent {
+    pid: u32,
+    name: String,
+    handles: u32,
+    per_hour: f32,
+    restarted: bool,
+}
+
+struct Track {
+    samples: VecDeque<(Instant, u32)>,
+    alerted_at: Option<Instant>,
+}
+
+impl Track {
+    /// Handles gained per hour when the whole window shows steady growth
+    fn growth_per_hour(&self, window: Duration) -> Option<f32> {
+        let (first_at, first) = *self.samples.front()?;
+        let (last_at, last) = *self.samples.back()?;
+        let elapsed = last_at.duration_since(first_at);
+        // Needs most of a window behind it; a fresh process ramping up isn't a leak
+        if self.samples.len() < MIN_SAMPLES || elapsed < window.mul_f32(0.9) || last <= first {
+            return None;
+        }
+        let steps = self.samples.len() - 1;
+        let steady = self
+            .samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .filter(|((_, a), (_, b))| b >= a)
+            .count();
+        if (steady as f32) < steps as f32 * STEADY_SHARE {
+            return None;
+        }
+        Some((last - first) as f32 / (elapsed.as_secs_f32() / 3600.0))
+    }
+}
+
+/// Kill `p` and start it again with the same arguments and working folder; None when it
+/// can't be restarted. Only the user's own processes in this session are: anything else would
+/// come back under this app's account.
+fn restart(
+    p: &Process,
+    own_user: Option<&Uid>,
+    escalation: &process_ctl::EscalationPolicy,
+) -> Option<Result<(), String>> {
+    if crate::overrides::dry_run() {
+        return None;
+    }
+    if own_user.is_none()
+        || p.user_id() != own_user
+        || sessions::session_of(p.pid().as_u32()) != sessions::current_session()
+    {
+        return Some(Err("it belongs to another user or session".into()));
+    }
+    // The sampler doesn't keep working folders
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[p.pid()]),
+        true,
+        ProcessRefreshKind::everything(),
+    );
+    let p = sys
+        .process(p.pid())
+        .filter(|fresh| fresh.start_time() == p.start_time())?;
+    let exe = p.exe()?.to_string_lossy().to_string();
+    // The first element is the program itself
+    let args: Vec<String> = p
+        .cmd()
+        .iter()
+        .skip(1)
+        .map(|a| a.to_string_lossy().to_string())
+        .collect();
+    let cwd = p.cwd().map(|d| d.to_path_buf());
+    Some(
+        process_ctl::terminate(p, escalation)
+            .map_err(|_| "kill failed".to_string())
+            .and_then(|_| crate::launch::start(&exe, &args, cwd.as_deref()).map(|_| ())),
+    )
+}
+
+fn tick(sys: &mut System, tracks: &mut HashMap<(u32, u64), Track>) {
+    let policy = read_state(|state| state.settings.handle_leaks.clone());
+    if !policy.enabled {
+        tracks.clear();
+        return;
+    }
+    let window = policy.window();
+    sampler::refresh(sys, |_| false);
+    let own_pids = protection::own_pids(sys);
+    let counters = Counters::new();
+    let now = Instant::now();
+
+    let mut alive = HashSet::new();
+    let mut leaking: Vec<(u32, u32, f32)> = Vec::new();
+    for p in sys.processes().values() {
+        let pid = p.pid().as_u32();
+        // Protected system processes hold thousands of handles by design and can't be restarted
+        if own_pids.contains(&pid) || protection::check(p, false).is_err() {
+            continue;
+        }
+        let Some(handles) = counters.handles(pid) else {
+            continue;
+        };
+        let key = (pid, p.start_time());
+        alive.insert(key);
+        let track = tracks.entry(key).or_insert_with(|| Track {
+            samples: VecDeque::new(),
+            alerted_at: None,
+        });
+        track.samples.push_back((now, handles));
+        while track
+            .samples
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > window)
+        {
+            track.samples.pop_front();
+        }
+
+        // One alert per window while the leak lasts
+        if track.alerted_at.is_some_and(|at| at.elapsed() < window) {
+            continue;
+        }
+        let per_hour = track.growth_per_hour(window).unwrap_or(0.0);
+        let over_limit = policy.max_handles > 0 && handles >= policy.max_handles;
+        if over_limit || per_hour >= policy.growth_per_hour as f32 {
+            track.alerted_at = Some(now);
+            leaking.push((pid, handles, per_hour));
+        }
+    }
+    tracks.retain(|key, _| alive.contains(key));
+    if leaking.is_empty() {
+        return;
+    }
+
+    // Full details (exe, user) only for the processes being reported, and ours for our user
+    let own_pid = std::process::id();
+    let pids: HashSet<u32> = leaking.iter().map(|(pid, _, _)| *pid).collect();
+    sampler::refresh(sys, |p| {
+        pids.contains(&p.pid().as_u32()) || p.pid().as_u32() == own_pid
+    });
+    let own_user = sys
+        .process(Pid::from_u32(own_pid))
+        .and_then(|p| p.user_id().cloned());
+    let users = sysinfo::Users::new_with_refreshed_list();
+    let escalation = read_state(|state| state.settings.kill_escalation.clone());
+    let mut logs: Vec<ActivityLog> = vec![];
+    for (pid, handles, per_hour) in leaking {
+        let Some(p) = sys.process(Pid::from_u32(pid)) else {
+            continue;
+        };
+        let result = if policy.auto_restart {
+            restart(p, own_user.as_ref(), &escalation)
+        } else {
+            None
+        };
+        let restarted = matches!(result, Some(Ok(())));
+        let mut reason = if per_hour > 0.0 {
+            format!(
+                "Handle leak: {} handles, growing {:.0}/hour over {} min",
+                handles, per_hour, policy.window_minutes
+            )
+        } else {
+            format!(
+                "Handle leak: {} handles, over the limit of {}",
+                handles, policy.max_handles
+            )
+        };
+        match &result {
+            Some(Ok(())) => reason.push_str(" - restarted"),
+            Some(Err(err)) => reason.push_str(&format!(" - restart failed: {}", err)),
+            None => {}
+        }
+        tracing::warn!(pid, handles, per_hour, restarted, "handle leak");
+        let name = p.name().to_string_lossy().to_string();
+        logs.push(ActivityLog {
+            was_killed: restarted,
+            reason,
+            ..process_log(sys, &users, p)
+        });
+        notify_event(
+            "handle-leak",
+            HandleLeakEvent {
+                pid,
+                name,
+                handles,
+                per_hour,
+                restarted,
+            },
+        );
+    }
+    with_state(|state| {
+        for log in logs {
+            record_activity(state, log);
+        }
+    });
+}
+
+// Public API
+pub fn start_recorder() {
+    thread::spawn(|| {
+        let mut sys = System::new();
+        let mut tracks: HashMap<(u32, u64), Track> = HashMap::new();
+        loop {
+            tick(&mut sys, &mut tracks);
+            thread::sleep(crate::power::interval(TICK));
+        }
+    });
+}
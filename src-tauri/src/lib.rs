@@ -1,24 +1,120 @@
+use backend::{KillErrorKind, KillOutcome, ProcessBackend, SysinfoBackend};
 use chrono::Local;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Mutex;
-use sysinfo::{Disks, System};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Duration;
+use sysinfo::System;
+use system_actions::RuleAction;
+use tauri::{Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_opener::OpenerExt;
 
+pub mod agent;
+mod app_limits;
+mod audio;
+pub mod backend;
+mod backup;
+mod baseline;
+mod boost;
+mod boot_impact;
+mod capabilities;
+mod capture;
+mod columns;
+mod config_file;
+mod confirm;
+mod conflicts;
+mod containers;
+mod counters;
+mod cpu_schedule;
+mod creation;
+mod diagnostics;
+mod digest;
+mod dns;
+mod dumps;
+mod environment;
+mod event_log;
+mod firewall;
+mod focus;
+mod format;
 mod gpu; // Import GPU module
+mod hang;
+mod i18n;
+mod launch;
+mod launch_block;
+mod launcher;
+mod leaks;
+mod lock;
+mod logging;
+mod maintenance;
+mod matcher;
+mod metrics;
+mod mini_monitor;
+mod modules;
+mod mqtt;
+mod netstat;
+mod notes;
+mod notifications;
+mod notifier;
+mod onboarding;
+mod overrides;
+mod power;
+mod power_requests;
+mod pressure;
+mod process_ctl;
+mod process_info;
+mod profiler;
+mod protection;
+mod quiet_hours;
+mod refresher;
+mod remote;
+mod report;
+mod respawn;
+mod retry;
+mod runtime;
+mod sampler;
+mod scheduler;
+mod scripting;
+mod self_usage;
+mod sessions;
+mod snapshots;
+mod stale_rules;
+mod storage;
+mod sync;
+mod syslog;
+mod system_actions;
+mod templates;
+mod threads;
+mod tray;
 
 // ============= Data Structures =============
 
-#[derive(Serialize)]
 pub struct ProcRow {
     pid: u32,
     name: String,
     cpu: f32,
     memory_kb: u64,
-    gpu: f32, // Added GPU usage
+    gpu: f32,              // Added GPU usage
+    label: Option<String>, // From a pinned process
+    note: Option<String>,
+    session_id: Option<u32>,       // Terminal Services session (Windows)
+    capture: Vec<capture::Device>, // Camera / microphone in use
+    // Note and risk rating for its executable
+    exe_note: Option<notes::ProcessNote>,
+    columns: columns::Columns,
 }
 
-#[derive(Serialize, Clone)]
+impl Serialize for ProcRow {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        columns::serialize_columns!(serializer, self,
+            always: [pid, name],
+            optional: [cpu, memory_kb, gpu, label, note, session_id, capture, exe_note])
+    }
+}
+
+#[derive(Clone)]
 pub struct ProcessGroup {
     name: String,
     process_count: u32,
@@ -26,6 +122,84 @@ pub struct ProcessGroup {
     total_cpu: f32,
     total_memory_kb: u64,
     total_gpu: f32, // Added GPU
+    total_threads: u32,
+    total_handles: u32, // Handles on Windows, file descriptors on Linux; growth hints at a leak
+    label: Option<String>, // From a pinned process
+    note: Option<String>,
+    capture: Vec<capture::Device>, // Across all instances
+    // Note and risk rating of the first instance's executable that has one
+    exe_note: Option<notes::ProcessNote>,
+    columns: columns::Columns,
+}
+
+impl Serialize for ProcessGroup {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        columns::serialize_columns!(serializer, self,
+            always: [name],
+            optional: [process_count, pids, total_cpu, total_memory_kb, total_gpu,
+                total_threads, total_handles, label, note, capture, exe_note])
+    }
+}
+
+impl ProcessGroup {
+    fn new(name: String, columns: &columns::Columns) -> Self {
+        Self {
+            name,
+            process_count: 0,
+            pids: vec![],
+            total_cpu: 0.0,
+            total_memory_kb: 0,
+            total_gpu: 0.0,
+            total_threads: 0,
+            total_handles: 0,
+            label: None,
+            note: None,
+            capture: vec![],
+            exe_note: None,
+            columns: columns.clone(),
+        }
+    }
+
+    fn apply_pin(&mut self, pins: &[PinnedProcess]) {
+        if let Some(pin) = find_pin(pins, &self.name) {
+            self.label = pin.label.clone();
+            self.note = pin.note.clone();
+        }
+    }
+
+    fn add_exe_note(&mut self, process_notes: &[notes::ProcessNote], p: &sysinfo::Process) {
+        if self.exe_note.is_none() && self.columns.has("exe_note") {
+            let exe = p.exe().map(|e| e.to_string_lossy());
+            self.exe_note = notes::find(process_notes, exe.as_deref()).cloned();
+        }
+    }
+
+    fn add(&mut self, pid: u32, cpu: f32, memory_kb: u64, gpu: f32) {
+        self.process_count += 1;
+        self.pids.push(pid);
+        self.total_cpu += cpu;
+        self.total_memory_kb += memory_kb;
+        self.total_gpu += gpu;
+    }
+
+    fn add_capture(&mut self, devices: &[capture::Device]) {
+        for device in devices {
+            if !self.capture.contains(device) {
+                self.capture.push(*device);
+            }
+        }
+        self.capture.sort();
+    }
+
+    /// Add `pid`'s threads and handles, when those columns were asked for
+    fn add_counts(&mut self, pid: u32, counters: &counters::Counters) {
+        if self.columns.has("total_threads") {
+            self.total_threads += counters.threads(pid).unwrap_or(0);
+        }
+        if self.columns.has("total_handles") {
+            self.total_handles += counters.handles(pid).unwrap_or(0);
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -34,10 +208,18 @@ pub struct SystemStats {
     memory_total_gb: f64,
     memory_used_gb: f64,
     memory_percent: f32,
+    vm_memory_gb: f64, // Memory held by vmmem (WSL2 / Hyper-V), included in memory_used_gb
+    // Raw values; format them with the `format` hints
+    memory_total_bytes: u64,
+    memory_used_bytes: u64,
+    vm_memory_bytes: u64,
+    format: format::FormatHints,
     disks: Vec<DiskInfo>,
+    network: Vec<metrics::NetworkInfo>,
+    sensors: Vec<metrics::SensorReading>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct DiskInfo {
     name: String,
     mount_point: String,
@@ -45,13 +227,31 @@ pub struct DiskInfo {
     used_gb: f64,
     free_gb: f64,
     usage_percent: f32,
+    total_bytes: u64,
+    used_bytes: u64,
+    free_bytes: u64,
+}
+
+/// A process the user pinned with a display name and a note, matched by exact name
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PinnedProcess {
+    pub name: String,
+    pub label: Option<String>,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+fn find_pin<'a>(pins: &'a [PinnedProcess], name: &str) -> Option<&'a PinnedProcess> {
+    let name = matcher::normalize(name, false);
+    pins.iter()
+        .find(|pin| matcher::normalize(&pin.name, false) == name)
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct BlacklistEntry {
     pub name: String,
     pub auto_kill: bool,
-    pub cpu_threshold: f32, // Kill only when CPU > this value (0 = always kill)
+    pub cpu_threshold: f32, // Kill only when CPU > this value (0 = see cpu_triggered)
     #[serde(default = "default_hundred")]
     pub gpu_threshold: f32, // Kill when GPU > this value (101 = disabled, 0 = always)
     #[serde(default = "default_true")]
@@ -60,6 +260,98 @@ pub struct BlacklistEntry {
     pub log_kills_only: bool,
     pub created_at: String,
     pub kill_count: u32,
+    #[serde(default)]
+    pub on_kill_script: Option<String>, // Shell command run after each successful kill
+    #[serde(default)]
+    pub rule_script: Option<String>, // Rhai source defining should_kill(process)
+    #[serde(default)]
+    pub require_confirmation_first_time: bool, // Ask before the rule's first kill
+    #[serde(default)]
+    pub confirmed: bool, // First kill approved, rule now runs unattended
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>, // Process names containing any of these never match
+    #[serde(default)]
+    pub case_sensitive: bool, // Match the name exactly as typed instead of case-folded
+    #[serde(default)]
+    pub memory_threshold_mb: u64, // Kill when memory > this value (0 = disabled)
+    #[serde(default)]
+    pub scope: RuleScope,
+    #[serde(default)]
+    pub snoozed_until: Option<String>, // Auto-kill paused until this time
+    #[serde(default)]
+    pub action: RuleAction, // Kill, or lock the workstation / warn instead
+    #[serde(default)]
+    pub last_matched: Option<String>, // Last time any process matched this rule
+    #[serde(default)]
+    pub conditions: environment::Conditions, // Power/network/memory state required to act
+    #[serde(default)]
+    pub cpu_priority: Option<u32>, // CPU pressure responder order, lowest first (None = not used)
+    #[serde(default)]
+    pub skip_if_playing_audio: bool, // Spare processes with an active audio session
+    #[serde(default)]
+    pub skip_if_capturing: bool, // Spare processes using the camera or microphone
+    #[serde(default)]
+    pub dump_before_kill: bool, // Save a stack dump under dumps/ before each auto-kill
+    #[serde(default)]
+    pub cpu_schedule: Vec<cpu_schedule::ThresholdPeriod>, // Time-of-day overrides of cpu_threshold
+    #[serde(default)]
+    pub anomaly_trigger: Option<baseline::AnomalyTrigger>, // Act at a multiple of usual usage
+    #[serde(default = "default_one")]
+    pub launcher_depth: u32, // Parent levels a "kill launcher" rule may climb
+}
+
+/// Whether thresholds apply to each process or to the sum across all matching processes
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleScope {
+    #[default]
+    Process,
+    Group,
+}
+
+impl BlacklistEntry {
+    fn new(name: String, auto_kill: bool, cpu_threshold: f32) -> Self {
+        Self {
+            exclude_patterns: matcher::default_excludes(&name.to_lowercase()),
+            name,
+            auto_kill,
+            cpu_threshold,
+            gpu_threshold: 101.0, // Default to disabled
+            log_enabled: true,
+            log_kills_only: false,
+            created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            kill_count: 0,
+            on_kill_script: None,
+            rule_script: None,
+            require_confirmation_first_time: false,
+            confirmed: false,
+            case_sensitive: false,
+            memory_threshold_mb: 0,
+            scope: RuleScope::Process,
+            snoozed_until: None,
+            action: RuleAction::Kill,
+            last_matched: None,
+            conditions: environment::Conditions::default(),
+            cpu_priority: None,
+            skip_if_playing_audio: false,
+            skip_if_capturing: false,
+            dump_before_kill: false,
+            cpu_schedule: vec![],
+            anomaly_trigger: None,
+            launcher_depth: 1,
+        }
+    }
+
+    /// Whether `cpu` trips the CPU trigger at `threshold`. A threshold of 0 kills on sight,
+    /// unless a GPU, memory or anomaly trigger is set: then CPU just isn't checked.
+    fn cpu_triggered(&self, threshold: f32, cpu: f32) -> bool {
+        if threshold > 0.0 {
+            return cpu >= threshold;
+        }
+        self.gpu_threshold > 100.0
+            && self.memory_threshold_mb == 0
+            && self.anomaly_trigger.is_none()
+    }
 }
 
 fn default_true() -> bool {
@@ -68,8 +360,11 @@ fn default_true() -> bool {
 fn default_hundred() -> f32 {
     101.0
 }
+fn default_one() -> u32 {
+    1
+}
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct ActivityLog {
     pub name: String,
     pub pid: u32,
@@ -78,25 +373,194 @@ pub struct ActivityLog {
     pub detected_at: String,
     pub was_killed: bool,
     pub reason: String,
+    #[serde(default)]
+    pub freed_memory_kb: u64, // Memory the process held when it was killed
+    #[serde(default)]
+    pub memory_kb: u64,
+    #[serde(default)]
+    pub exe_path: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub parent_name: Option<String>,
+    #[serde(default)]
+    pub retry_history: Vec<String>, // Earlier failed attempts when a kill needed retries
+    #[serde(default)]
+    pub respawned: bool, // The same executable came back shortly after the kill
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub lock_pin_hash: Option<String>, // None = actions are not locked
+    pub unlock_timeout_secs: u64,
+    pub quiet_hours: quiet_hours::QuietHours,
+    pub focus: focus::FocusSettings,
+    pub allow_protected_kills: bool, // Expert override for the built-in protected list
+    pub kill_notifications: bool,    // Windows toast after each auto-kill
+    pub language: String,            // Backend messages, see resources/i18n
+    pub units: format::UnitSettings,
+    pub low_power_mode: bool, // Slower polling and batched writes on battery or when hidden
+    pub self_budget: self_usage::SelfBudget,
+    pub kill_escalation: process_ctl::EscalationPolicy,
+    pub session_scope: sessions::SessionScope, // Sessions the watcher and app limits act on
+    pub respawn_window_secs: u64, // How long killed apps are watched for a restart (0 = off)
+    pub respawn_loop_limit: u32,  // Restarts an hour that pause killing an app (0 = off)
+    pub stale_rule_days: u32, // Suggest disabling rules that haven't matched in this long (0 = off)
+    pub instant_kill: bool,   // Apply kill-on-sight rules as soon as a process starts
+    pub refresh_intervals: refresher::RefreshIntervals,
+    pub memory_pressure: pressure::PressurePolicy,
+    pub cpu_pressure: pressure::CpuPressurePolicy,
+    pub handle_leaks: leaks::HandleLeakPolicy,
+    // Usage far above a process's learned baseline
+    pub anomalies: baseline::AnomalyPolicy,
+    pub log_level: String, // Diagnostic log file, see logging.rs
+    pub event_log: bool,   // Mirror kills to the Windows Application Event Log
+    pub syslog: bool,      // Mirror activity log entries to journald/syslog (Linux)
+    pub mqtt: mqtt::MqttSettings,
+    pub bot: notifier::BotSettings, // Telegram/Discord kill messages and commands
+    pub email_digest: digest::EmailDigest,
+    pub maintenance_windows: Vec<maintenance::MaintenanceWindow>,
+    pub sync: sync::SyncSettings, // Rules and settings shared through WebDAV/S3/Gist
+    pub tray_icon: tray::TrayIconStyle,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            lock_pin_hash: None,
+            unlock_timeout_secs: 300,
+            quiet_hours: quiet_hours::QuietHours::default(),
+            focus: focus::FocusSettings::default(),
+            allow_protected_kills: false,
+            kill_notifications: true,
+            language: "en".to_string(),
+            units: format::UnitSettings::default(),
+            low_power_mode: false,
+            self_budget: self_usage::SelfBudget::default(),
+            kill_escalation: process_ctl::EscalationPolicy::default(),
+            session_scope: sessions::SessionScope::default(),
+            respawn_window_secs: 30,
+            respawn_loop_limit: 5,
+            stale_rule_days: 0,
+            instant_kill: false,
+            refresh_intervals: refresher::RefreshIntervals::default(),
+            memory_pressure: pressure::PressurePolicy::default(),
+            cpu_pressure: pressure::CpuPressurePolicy::default(),
+            handle_leaks: leaks::HandleLeakPolicy::default(),
+            anomalies: baseline::AnomalyPolicy::default(),
+            log_level: "info".to_string(),
+            event_log: false,
+            syslog: false,
+            mqtt: mqtt::MqttSettings::default(),
+            bot: notifier::BotSettings::default(),
+            email_digest: digest::EmailDigest::default(),
+            maintenance_windows: vec![],
+            sync: sync::SyncSettings::default(),
+            tray_icon: tray::TrayIconStyle::default(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct AppState {
     pub blacklist: Vec<BlacklistEntry>,
     pub activity_logs: Vec<ActivityLog>,
+    #[serde(default)]
+    pub schedules: Vec<scheduler::Schedule>,
+    #[serde(default)]
+    pub remote_hosts: Vec<remote::RemoteHost>,
+    #[serde(default)]
+    pub settings: Settings,
+    #[serde(default)]
+    pub app_limits: Vec<app_limits::AppLimit>,
+    #[serde(default)]
+    pub app_usage: app_limits::AppUsage,
+    #[serde(default)]
+    pub watchlist: Vec<String>,
+    #[serde(default)]
+    pub pins: Vec<PinnedProcess>,
+    #[serde(default)]
+    pub boot_impact: Option<boot_impact::BootReport>,
+    #[serde(default)]
+    pub network_blocks: Vec<firewall::NetworkBlock>, // Firewall rules created by this app
+    #[serde(default)]
+    pub onboarded: bool, // The first-run wizard was finished or skipped
+    #[serde(default)]
+    pub process_notes: Vec<notes::ProcessNote>, // Keyed by executable path
 }
 
-// Global state
-static APP_STATE: Mutex<Option<AppState>> = Mutex::new(None);
+/// App state shared by commands and background tasks. setup() registers it with
+/// `app.manage()`, so commands can also take it as `tauri::State<StateStore>`; clones share
+/// the same state.
+#[derive(Clone)]
+pub struct StateStore {
+    state: Arc<RwLock<AppState>>,
+    // Set when a write was deferred in low-power mode; cleared by flush()
+    dirty: Arc<AtomicBool>,
+    persistent: bool, // False for a store that never touches the data folder (tests)
+}
 
-fn get_data_path() -> PathBuf {
-    let mut path = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
-    path.push("tauri-app");
+impl StateStore {
+    pub fn new(state: AppState) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(state)),
+            dirty: Arc::new(AtomicBool::new(false)),
+            persistent: true,
+        }
+    }
+
+    /// A store that is never saved to disk
+    pub fn in_memory(state: AppState) -> Self {
+        Self {
+            persistent: false,
+            ..Self::new(state)
+        }
+    }
+
+    pub fn read<R>(&self, f: impl FnOnce(&AppState) -> R) -> R {
+        f(&self.state.read().unwrap())
+    }
+
+    /// Mutate and save, unless low-power mode is batching writes
+    pub fn write<R>(&self, f: impl FnOnce(&mut AppState) -> R) -> R {
+        let mut state = self.state.write().unwrap();
+        let result = f(&mut state);
+        if self.persistent {
+            if power::is_active() {
+                self.dirty.store(true, Ordering::Relaxed);
+            } else {
+                save_state(&state);
+            }
+        }
+        result
+    }
+
+    /// Write state deferred by low-power mode
+    pub fn flush(&self) {
+        if self.dirty.swap(false, Ordering::Relaxed) {
+            save_state(&self.state.read().unwrap());
+        }
+    }
+}
+
+// Loaded on first use; setup() hands the same store to Tauri and the headless agent uses it directly
+static STORE: OnceLock<StateStore> = OnceLock::new();
+// Set in setup() so background threads can emit events to the UI
+static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+
+/// Per-user folder for the config and everything else the app keeps on disk
+/// (next to the executable in portable mode, or wherever the user moved it)
+fn data_dir() -> PathBuf {
+    let path = overrides::data_dir().unwrap_or_else(storage::resolve);
     fs::create_dir_all(&path).ok();
-    path.push("blacklist_data.json");
     path
 }
 
+fn get_data_path() -> PathBuf {
+    data_dir().join("blacklist_data.json")
+}
+
 fn load_state() -> AppState {
     let path = get_data_path();
     if path.exists() {
@@ -116,564 +580,3707 @@ fn save_state(state: &AppState) {
     }
 }
 
+fn store() -> &'static StateStore {
+    // Tests run on in-memory state
+    #[cfg(test)]
+    if let Some(store) = tests::installed_store() {
+        return store;
+    }
+    STORE.get_or_init(|| StateStore::new(load_state()))
+}
+
 fn with_state<F, R>(f: F) -> R
 where
     F: FnOnce(&mut AppState) -> R,
 {
-    let mut guard = APP_STATE.lock().unwrap();
-    if guard.is_none() {
-        *guard = Some(load_state());
+    store().write(f)
+}
+
+fn flush_state() {
+    store().flush();
+}
+
+/// Bring the running services in line with `settings` after they were replaced wholesale
+/// (config.toml, a restored backup)
+fn apply_settings(settings: &Settings) {
+    i18n::set_language(&settings.language).ok();
+    power::set_enabled(settings.low_power_mode);
+    refresher::apply(&settings.refresh_intervals);
+    mqtt::reload();
+    notifier::reload();
+}
+
+/// Like `with_state` but read-only, so nothing is written back to disk
+fn read_state<F, R>(f: F) -> R
+where
+    F: FnOnce(&AppState) -> R,
+{
+    store().read(f)
+}
+
+/// Emit an event to the frontend (no-op when running headless)
+fn emit_event<S: Serialize + Clone>(event: &str, payload: S) {
+    if let Some(handle) = APP_HANDLE.get() {
+        handle.emit(event, payload).ok();
+    }
+}
+
+/// Emit a user-facing notification event, suppressed during quiet hours
+fn notify_event<S: Serialize + Clone>(event: &str, payload: S) {
+    if !quiet_hours::is_active() {
+        emit_event(event, payload);
+    }
+}
+
+/// Log entry for `p` with process details filled in; callers set usage, outcome and reason
+fn process_log(sys: &System, users: &sysinfo::Users, p: &sysinfo::Process) -> ActivityLog {
+    ActivityLog {
+        name: p.name().to_string_lossy().to_string(),
+        pid: p.pid().as_u32(),
+        cpu_usage: 0.0,
+        gpu_usage: 0.0,
+        detected_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        was_killed: false,
+        reason: String::new(),
+        freed_memory_kb: 0,
+        memory_kb: p.memory() / 1024,
+        exe_path: p.exe().map(|e| e.to_string_lossy().to_string()),
+        user: p
+            .user_id()
+            .and_then(|uid| users.get_user_by_id(uid))
+            .map(|u| u.name().to_string()),
+        parent_name: p
+            .parent()
+            .and_then(|ppid| sys.process(ppid))
+            .map(|pp| pp.name().to_string_lossy().to_string()),
+        retry_history: Vec::new(),
+        respawned: false,
+    }
+}
+
+/// Append to the activity log, keeping only the last 1000 entries
+fn record_activity(state: &mut AppState, log: ActivityLog) {
+    if log.was_killed && state.settings.event_log {
+        event_log::report_kill(&log);
+    }
+    syslog::mirror(&log, state.settings.syslog);
+    if log.was_killed {
+        mqtt::publish_kill(&log);
+        notifier::notify_kill(&log, &state.settings.bot);
+    }
+    state.activity_logs.push(log);
+    if state.activity_logs.len() > 1000 {
+        state.activity_logs = state
+            .activity_logs
+            .split_off(state.activity_logs.len() - 1000);
+    }
+}
+
+/// Heavy commands give up after this long instead of holding up the UI
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run `f` on the blocking pool so a slow sysinfo refresh can't stall other commands
+async fn run_blocking<T, F>(name: &str, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    run_blocking_for(name, COMMAND_TIMEOUT, f).await
+}
+
+/// `run_blocking` for commands that are slow by nature (dumps, sampling)
+async fn run_blocking_for<T, F>(name: &str, timeout: Duration, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let result = match tokio::time::timeout(timeout, tauri::async_runtime::spawn_blocking(f)).await
+    {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(err)) => Err(format!("{} failed: {}", name, err)),
+        Err(_) => Err(format!("{} timed out after {}s", name, timeout.as_secs())),
+    };
+    if let Err(err) = &result {
+        tracing::warn!(command = name, "{}", err);
     }
-    let state = guard.as_mut().unwrap();
-    let result = f(state);
-    save_state(state);
     result
 }
 
 // ============= System Stats Commands =============
 
 #[tauri::command]
-fn get_system_stats() -> SystemStats {
-    let mut sys = System::new_all();
-    sys.refresh_all();
+async fn get_system_stats() -> Result<SystemStats, String> {
+    run_blocking("get_system_stats", system_stats).await
+}
+
+/// Open or close the always-on-top mini monitor; async so the window is built off the main thread
+#[tauri::command]
+async fn toggle_mini_monitor(app: tauri::AppHandle) -> Result<bool, String> {
+    mini_monitor::toggle(&app)
+}
+
+fn system_stats() -> SystemStats {
+    use metrics::MetricKind;
+
+    // Each part comes from the background refresher at its own rate when it's running
+    let sample = refresher::cached_stats().unwrap_or_else(|| {
+        metrics::collect(&[MetricKind::System, MetricKind::Network, MetricKind::Sensors])
+    });
+    let disks =
+        refresher::cached_disks().unwrap_or_else(|| metrics::collect(&[MetricKind::Disks]).disks);
 
-    let cpu_usage = sys.global_cpu_usage();
-    let memory_total = sys.total_memory();
-    let memory_used = sys.used_memory();
-    let memory_total_gb = memory_total as f64 / 1024.0 / 1024.0 / 1024.0;
-    let memory_used_gb = memory_used as f64 / 1024.0 / 1024.0 / 1024.0;
-    let memory_percent = if memory_total > 0 {
-        (memory_used as f32 / memory_total as f32) * 100.0
+    let memory_total_gb = sample.memory_total as f64 / 1024.0 / 1024.0 / 1024.0;
+    let memory_used_gb = sample.memory_used as f64 / 1024.0 / 1024.0 / 1024.0;
+    let memory_percent = if sample.memory_total > 0 {
+        (sample.memory_used as f32 / sample.memory_total as f32) * 100.0
     } else {
         0.0
     };
 
-    let disks = Disks::new_with_refreshed_list();
-    let disk_info: Vec<DiskInfo> = disks
-        .iter()
-        .map(|d| {
-            let total = d.total_space();
-            let free = d.available_space();
-            let used = total.saturating_sub(free);
-            DiskInfo {
-                name: d.name().to_string_lossy().to_string(),
-                mount_point: d.mount_point().to_string_lossy().to_string(),
-                total_gb: total as f64 / 1024.0 / 1024.0 / 1024.0,
-                used_gb: used as f64 / 1024.0 / 1024.0 / 1024.0,
-                free_gb: free as f64 / 1024.0 / 1024.0 / 1024.0,
-                usage_percent: if total > 0 {
-                    (used as f32 / total as f32) * 100.0
-                } else {
-                    0.0
-                },
-            }
-        })
-        .collect();
-
     SystemStats {
-        cpu_usage,
+        cpu_usage: sample.cpu_usage,
         memory_total_gb,
         memory_used_gb,
         memory_percent,
-        disks: disk_info,
+        vm_memory_gb: sample.vm_memory as f64 / 1024.0 / 1024.0 / 1024.0,
+        memory_total_bytes: sample.memory_total,
+        memory_used_bytes: sample.memory_used,
+        vm_memory_bytes: sample.vm_memory,
+        format: read_state(|state| state.settings.units.hints()),
+        disks,
+        network: sample.network,
+        sensors: sample.sensors,
     }
 }
 
-// ============= Process Watching Commands =============
+#[tauri::command]
+fn format_bytes(bytes: u64) -> String {
+    format::format_bytes(bytes, &read_state(|state| state.settings.units.clone()))
+}
+
+#[tauri::command]
+fn format_duration(seconds: u64) -> String {
+    format::format_duration(seconds)
+}
+
+#[tauri::command]
+fn get_unit_settings() -> format::UnitSettings {
+    read_state(|state| state.settings.units.clone())
+}
+
+/// GB vs GiB, decimal separator and precision used by `format_bytes` and the stats hints
+#[tauri::command]
+fn set_unit_settings(units: format::UnitSettings) -> Result<String, String> {
+    lock::require_unlocked()?;
+    units.validate()?;
+    with_state(|state| {
+        state.settings.units = units;
+    });
+    Ok("Unit settings saved".to_string())
+}
 
 #[tauri::command]
-fn watched_processes(names: Vec<String>) -> Vec<ProcRow> {
-    let watch: Vec<String> = names
+fn list_metric_providers() -> Vec<metrics::MetricProviderInfo> {
+    metrics::list_providers()
+}
+
+// ============= Process Watching Commands =============
+
+/// Matchers for the given names, or for the stored watch list when none are passed
+fn watch_matchers(names: Option<Vec<String>>) -> Vec<matcher::Matcher> {
+    names
+        .unwrap_or_else(|| read_state(|state| state.watchlist.clone()))
         .into_iter()
         .map(|s| resolve_process_name(&s))
         .filter(|s| !s.is_empty())
-        .collect();
+        .map(|s| matcher::Matcher::for_name(&s))
+        .collect()
+}
 
-    if watch.is_empty() {
-        return vec![];
+#[tauri::command]
+fn get_watchlist() -> Vec<String> {
+    read_state(|state| state.watchlist.clone())
+}
+
+#[tauri::command]
+fn add_watch(name: String) -> Result<Vec<String>, String> {
+    lock::require_unlocked()?;
+    let name = resolve_process_name(&name);
+    if name.is_empty() {
+        return Err("Name cannot be empty".into());
     }
 
-    let mut sys = System::new_all();
-    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+    with_state(|state| {
+        if state
+            .watchlist
+            .iter()
+            .any(|w| w.to_lowercase() == name.to_lowercase())
+        {
+            return Err("Already in watch list".into());
+        }
+        state.watchlist.push(name.clone());
+        Ok(state.watchlist.clone())
+    })
+}
+
+#[tauri::command]
+fn remove_watch(name: String) -> Result<Vec<String>, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        let len_before = state.watchlist.len();
+        state
+            .watchlist
+            .retain(|w| w.to_lowercase() != name.to_lowercase());
+        if state.watchlist.len() < len_before {
+            Ok(state.watchlist.clone())
+        } else {
+            Err("Not found in watch list".into())
+        }
+    })
+}
+
+/// `columns` limits which fields are returned (and computed); pid and name are always included
+#[tauri::command]
+fn watched_processes(names: Option<Vec<String>>, columns: Option<Vec<String>>) -> Vec<ProcRow> {
+    let columns = columns::Columns::new(columns);
+    let watch = watch_matchers(names);
+    let pins = read_state(|state| state.pins.clone());
+    let process_notes = read_state(|state| state.process_notes.clone());
 
-    let cpu_count = sys.cpus().len() as f32;
-    let cpu_count = if cpu_count > 0.0 { cpu_count } else { 1.0 };
+    if watch.is_empty() {
+        return vec![];
+    }
 
     // Fetch GPU usage
-    let gpu_usages = gpu::get_gpu_usages();
+    let gpu_usages = if columns.has("gpu") {
+        metrics::process_gpu_usages()
+    } else {
+        Default::default()
+    };
+    let is_watched =
+        |p: &sysinfo::Process| watch.iter().any(|w| w.matches(&p.name().to_string_lossy()));
 
-    sys.processes()
-        .iter()
-        .filter_map(|(pid, p)| {
-            let pname = p.name().to_string_lossy().to_lowercase();
-            if watch.iter().any(|w| pname.contains(w) || w == &pname) {
+    sampler::with_processes(is_watched, |sys, cpu_count| {
+        let capture_usage = if columns.has("capture") {
+            capture::usage(sys, is_watched)
+        } else {
+            Default::default()
+        };
+        sys.processes()
+            .iter()
+            .filter(|(_, p)| is_watched(p))
+            .map(|(pid, p)| {
                 let normalized_cpu = p.cpu_usage() / cpu_count;
                 let gpu = *gpu_usages.get(&pid.as_u32()).unwrap_or(&0.0);
-                Some(ProcRow {
+                let name = p.name().to_string_lossy().to_string();
+                let pin = find_pin(&pins, &name);
+                ProcRow {
                     pid: pid.as_u32(),
-                    name: p.name().to_string_lossy().to_string(),
                     cpu: normalized_cpu,
                     memory_kb: p.memory() / 1024,
                     gpu,
-                })
-            } else {
-                None
-            }
-        })
-        .collect()
+                    label: pin.and_then(|pin| pin.label.clone()),
+                    note: pin.and_then(|pin| pin.note.clone()),
+                    session_id: columns
+                        .has("session_id")
+                        .then(|| sessions::session_of(pid.as_u32()))
+                        .flatten(),
+                    capture: capture_usage
+                        .get(&pid.as_u32())
+                        .cloned()
+                        .unwrap_or_default(),
+                    exe_note: notes::find(
+                        &process_notes,
+                        p.exe().map(|e| e.to_string_lossy()).as_deref(),
+                    )
+                    .cloned(),
+                    name,
+                    columns: columns.clone(),
+                }
+            })
+            .collect()
+    })
 }
 
 #[tauri::command]
 fn kill_pid(pid: u32) -> Result<String, String> {
-    let mut sys = System::new_all();
-    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
-
-    let pid = sysinfo::Pid::from_u32(pid);
-    let process = sys.process(pid).ok_or("Process not found")?;
-    let process_name = process.name().to_string_lossy().to_string();
-
-    // On Windows, Signal::Term is not supported - use kill() directly which sends SIGKILL
-    let ok = process.kill();
+    lock::require_unlocked()?;
+    kill_process_by_pid(pid)
+}
 
-    if ok {
-        Ok(format!(
-            "PID {} ({}) terminated",
-            pid.as_u32(),
-            process_name
-        ))
-    } else {
-        Err(format!(
-            "Failed to kill {} - requires Administrator privileges",
-            process_name
-        ))
+/// "End task": politely ask `pid` to close (WM_CLOSE / SIGTERM, never escalated) and report
+/// whether it exited within `timeout_ms` (default 5s). `kill_pid` is the forceful option.
+#[tauri::command]
+async fn request_close(
+    pid: u32,
+    timeout_ms: Option<u64>,
+) -> Result<process_ctl::CloseOutcome, String> {
+    lock::require_unlocked()?;
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(5000));
+    if timeout > Duration::from_secs(60) {
+        return Err("Timeout must be at most 60 seconds".into());
+    }
+    let process = SysinfoBackend.process(pid).ok_or("Process not found")?;
+    if process.own {
+        return Err("Refusing to close AutomateKillTask's own process".into());
+    }
+    if process.protected && !protection::override_enabled() {
+        return Err(protection::ProtectedProcess {
+            name: process.name,
+            pid,
+        }
+        .into());
     }
+    notes::check(process.exe_path.as_deref())?;
+
+    let start_time = process.start_time;
+    let outcome = run_blocking_for("request_close", timeout + COMMAND_TIMEOUT, move || {
+        process_ctl::request_close(pid, start_time, timeout)
+    })
+    .await?;
+    tracing::info!(pid, name = %process.name, result = ?outcome, "close requested");
+    outcome.map_err(|kind| match kind {
+        KillErrorKind::NotFound => "Process not found".to_string(),
+        _ => i18n::t("result.close_failed", &[("name", process.name)]),
+    })
 }
 
-/// Kill all processes in a group by name
+/// Kill the app that had focus before the mini monitor was clicked
 #[tauri::command]
-fn kill_process_group(name: String) -> Result<String, String> {
-    let mut sys = System::new_all();
-    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+fn kill_foreground() -> Result<String, String> {
+    lock::require_unlocked()?;
+    let app = mini_monitor::last_foreground().ok_or("No foreground app seen yet")?;
+    kill_pid_with(
+        &SysinfoBackend,
+        app.pid,
+        Some(app.start_time),
+        protection::override_enabled(),
+    )
+}
 
-    let name_lower = name.to_lowercase();
-    let mut killed_count = 0;
-    let mut failed_count = 0;
+/// Full memory dump of a process, to `path` or under dumps/ when none is given
+#[tauri::command]
+async fn dump_process(pid: u32, path: Option<String>) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let name = sampler::with_processes(
+        |_| false,
+        |sys, _| {
+            sys.process(sysinfo::Pid::from_u32(pid))
+                .map(|p| p.name().to_string_lossy().to_string())
+        },
+    )
+    .ok_or("Process not found")?;
+    let path = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    // Full dumps of big processes take a while
+    run_blocking_for("dump_process", Duration::from_secs(120), move || {
+        dumps::dump_process(pid, &name, path.as_deref().map(std::path::Path::new))
+    })
+    .await?
+    .map(|path| path.to_string_lossy().to_string())
+}
 
-    for (_pid, p) in sys.processes().iter() {
-        let pname = p.name().to_string_lossy().to_lowercase();
-        if pname.contains(&name_lower) || pname == name_lower {
-            // On Windows, use kill() directly instead of Signal::Term
-            let ok = p.kill();
-            if ok {
-                killed_count += 1;
-            } else {
-                failed_count += 1;
-            }
+/// Where a busy process spends its CPU time, from its thread stacks over `seconds`
+#[tauri::command]
+async fn sample_process(pid: u32, seconds: u32) -> Result<profiler::StackSample, String> {
+    let timeout = Duration::from_secs(profiler::MAX_SECONDS as u64) + COMMAND_TIMEOUT;
+    run_blocking_for("sample_process", timeout, move || {
+        profiler::sample_process(pid, seconds)
+    })
+    .await?
+}
+
+/// Threads of a process with state, CPU time and start module, busiest first
+#[tauri::command]
+async fn get_process_threads(pid: u32) -> Result<Vec<threads::ThreadInfo>, String> {
+    run_blocking("get_process_threads", move || threads::process_threads(pid)).await?
+}
+
+/// Processes that have a DLL/shared library with `name` in its file name loaded
+#[tauri::command]
+async fn find_module(name: String) -> Result<modules::ModuleSearch, String> {
+    run_blocking_for("find_module", Duration::from_secs(60), move || {
+        modules::find(&name)
+    })
+    .await?
+}
+
+/// What a hung process is blocked on (locks, other processes, I/O), before deciding to kill it
+#[tauri::command]
+async fn analyze_hang(pid: u32) -> Result<hang::HangReport, String> {
+    run_blocking_for("analyze_hang", Duration::from_secs(30), move || {
+        hang::analyze(pid)
+    })
+    .await?
+}
+
+fn kill_process_by_pid(pid: u32) -> Result<String, String> {
+    kill_pid_with(&SysinfoBackend, pid, None, protection::override_enabled())
+}
+
+/// `expected_start` pins the kill to the process seen earlier (e.g. when a confirmation was queued)
+fn kill_pid_with(
+    backend: &impl ProcessBackend,
+    pid: u32,
+    expected_start: Option<u64>,
+    allow_protected: bool,
+) -> Result<String, String> {
+    let process = backend.process(pid).ok_or("Process not found")?;
+    if expected_start.is_some_and(|start| start != process.start_time) {
+        return Err(format!(
+            "PID {} now belongs to a different process ({}), not killed",
+            pid, process.name
+        ));
+    }
+    if process.own {
+        return Err("Refusing to kill AutomateKillTask's own process".into());
+    }
+    if process.protected && !allow_protected {
+        return Err(protection::ProtectedProcess {
+            name: process.name,
+            pid,
+        }
+        .into());
+    }
+    notes::check(process.exe_path.as_deref())?;
+
+    let killed = backend.kill(&process);
+    tracing::info!(pid, name = %process.name, result = ?killed, "manual kill");
+    match killed {
+        Ok(()) => Ok(i18n::t(
+            "result.pid_terminated",
+            &[
+                ("pid", pid.to_string()),
+                ("name", process.name),
+                // Captured before the kill, it is gone afterwards
+                (
+                    "memory",
+                    format!("{:.1}", process.memory_kb as f64 / 1024.0),
+                ),
+            ],
+        )),
+        // Exited on its own between the lookup and the kill
+        Err(KillErrorKind::NotFound) => Err("Process not found".to_string()),
+        // Got the signal but survived it
+        Err(KillErrorKind::StillRunning) => Err(i18n::t(
+            "result.kill_still_running",
+            &[("name", process.name)],
+        )),
+        Err(_) => Err(i18n::t(
+            "result.kill_failed_admin",
+            &[("name", process.name)],
+        )),
+    }
+}
+
+/// Kill all processes in a group by name, reporting what happened to each instance
+#[tauri::command]
+fn kill_process_group(name: String) -> Result<Vec<KillOutcome>, String> {
+    lock::require_unlocked()?;
+    Ok(kill_matching_with(
+        &SysinfoBackend,
+        &matcher::Matcher::for_name(&name),
+        protection::override_enabled(),
+    ))
+}
+
+/// Same as `kill_process_group` but summarized into one message (CLI, schedules, agent)
+fn kill_processes_by_name(name: &str) -> Result<String, String> {
+    summarize_kills(&kill_matching_with(
+        &SysinfoBackend,
+        &matcher::Matcher::for_name(name),
+        protection::override_enabled(),
+    ))
+}
+
+/// Result of `kill_pids`: one summary for the whole selection plus what happened to each PID
+#[derive(Serialize)]
+struct SelectionKill {
+    summary: String,
+    success: bool, // At least one process was killed
+    outcomes: Vec<KillOutcome>,
+}
+
+/// Kill a multi-row selection in one call, with the same guards as `kill_pid`
+#[tauri::command]
+async fn kill_pids(pids: Vec<u32>) -> Result<SelectionKill, String> {
+    lock::require_unlocked()?;
+    let allow_protected = protection::override_enabled();
+    // Each kill may wait out the escalation steps
+    run_blocking_for("kill_pids", Duration::from_secs(60), move || {
+        kill_pids_with(&SysinfoBackend, pids, allow_protected)
+    })
+    .await
+}
+
+fn kill_pids_with(
+    backend: &(impl ProcessBackend + Sync),
+    mut pids: Vec<u32>,
+    allow_protected: bool,
+) -> SelectionKill {
+    pids.sort_unstable();
+    pids.dedup();
+    let selected = pids.len();
+    let mut outcomes = vec![];
+    let mut targets = vec![];
+    for pid in pids {
+        match backend.process(pid) {
+            Some(p) => targets.push(p),
+            None => outcomes.push(KillOutcome {
+                pid,
+                name: String::new(),
+                success: false,
+                error_kind: Some(KillErrorKind::NotFound),
+                freed_memory_kb: 0,
+            }),
         }
     }
 
+    // In parallel, so one stubborn process doesn't hold up the rest
+    let results: Vec<(backend::ProcessInfo, KillOutcome)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = targets
+            .into_iter()
+            .map(|p| scope.spawn(move || (p.clone(), kill_info_with(backend, p, allow_protected))))
+            .collect();
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    });
+
+    let detected_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let reason = i18n::t(
+        "reason.killed_selection",
+        &[("count", selected.to_string())],
+    );
+    let logs: Vec<ActivityLog> = results
+        .iter()
+        .filter(|(_, o)| o.success)
+        .map(|(p, o)| ActivityLog {
+            name: p.name.clone(),
+            pid: p.pid,
+            cpu_usage: p.cpu,
+            detected_at: detected_at.clone(),
+            was_killed: true,
+            reason: reason.clone(),
+            freed_memory_kb: o.freed_memory_kb,
+            memory_kb: p.memory_kb,
+            exe_path: p.exe_path.clone(),
+            ..Default::default()
+        })
+        .collect();
+    if !logs.is_empty() {
+        with_state(|state| {
+            for log in logs {
+                record_activity(state, log);
+            }
+        });
+    }
+
+    outcomes.extend(results.into_iter().map(|(_, o)| o));
+    outcomes.sort_by_key(|o| o.pid);
+    let (summary, success) = match summarize_kills(&outcomes) {
+        Ok(summary) => (summary, true),
+        Err(summary) => (summary, false),
+    };
+    SelectionKill {
+        summary,
+        success,
+        outcomes,
+    }
+}
+
+fn kill_matching_with(
+    backend: &impl ProcessBackend,
+    matcher: &matcher::Matcher,
+    allow_protected: bool,
+) -> Vec<KillOutcome> {
+    backend
+        .processes(&|name| matcher.matches(name))
+        .into_iter()
+        .map(|p| kill_info_with(backend, p, allow_protected))
+        .collect()
+}
+
+fn kill_info_with(
+    backend: &impl ProcessBackend,
+    p: backend::ProcessInfo,
+    allow_protected: bool,
+) -> KillOutcome {
+    let result = if p.own {
+        Err(KillErrorKind::OwnProcess)
+    } else if p.protected && !allow_protected {
+        Err(KillErrorKind::Protected)
+    } else if notes::check(p.exe_path.as_deref()).is_err() {
+        Err(KillErrorKind::NeverTouch)
+    } else {
+        backend.kill(&p)
+    };
+    tracing::info!(pid = p.pid, name = %p.name, result = ?result, "manual kill");
+    KillOutcome {
+        pid: p.pid,
+        success: result.is_ok(),
+        error_kind: result.err(),
+        freed_memory_kb: if result.is_ok() { p.memory_kb } else { 0 },
+        name: p.name,
+    }
+}
+
+fn summarize_kills(outcomes: &[KillOutcome]) -> Result<String, String> {
+    let killed_count = outcomes.iter().filter(|o| o.success).count();
+    // Protected and "never touch" processes were skipped on purpose, not failed
+    let failed: Vec<&KillOutcome> = outcomes
+        .iter()
+        .filter(|o| {
+            !o.success
+                && !matches!(
+                    o.error_kind,
+                    Some(KillErrorKind::Protected | KillErrorKind::NeverTouch)
+                )
+        })
+        .collect();
+    let failed_count = failed.len();
+    let freed_kb: u64 = outcomes.iter().map(|o| o.freed_memory_kb).sum();
+    let protected = outcomes
+        .iter()
+        .find(|o| o.error_kind == Some(KillErrorKind::Protected));
+    let never_touch = outcomes
+        .iter()
+        .find(|o| o.error_kind == Some(KillErrorKind::NeverTouch));
+
     if killed_count > 0 {
-        Ok(format!(
-            "Killed {} processes, {} failed",
-            killed_count, failed_count
+        Ok(i18n::t(
+            "result.group_killed",
+            &[
+                ("killed", killed_count.to_string()),
+                ("failed", failed_count.to_string()),
+                ("memory", format!("{:.1}", freed_kb as f64 / 1024.0)),
+            ],
+        ))
+    } else if failed_count > 0
+        && failed
+            .iter()
+            .all(|o| o.error_kind == Some(KillErrorKind::StillRunning))
+    {
+        Err(i18n::t(
+            "result.group_still_running",
+            &[("failed", failed_count.to_string())],
         ))
     } else if failed_count > 0 {
-        Err(format!(
-            "Failed to kill {} processes (permission denied?)",
-            failed_count
+        Err(i18n::t(
+            "result.group_failed",
+            &[("failed", failed_count.to_string())],
         ))
+    } else if let Some(o) = protected {
+        Err(protection::ProtectedProcess {
+            name: o.name.clone(),
+            pid: o.pid,
+        }
+        .into())
+    } else if let Some(o) = never_touch {
+        Err(i18n::t("result.never_touch", &[("name", o.name.clone())]))
     } else {
-        Err("No matching processes found".into())
+        Err(i18n::t("result.no_match", &[]))
     }
 }
 
-/// Get ALL running processes grouped by name (for browse modal)
+/// Get ALL running processes grouped by name (for browse modal).
+/// `columns` limits which fields are returned; name is always included.
 #[tauri::command]
-fn get_all_process_list() -> Vec<ProcessGroup> {
+fn get_all_process_list(columns: Option<Vec<String>>) -> Vec<ProcessGroup> {
     use std::collections::HashMap;
 
-    let mut sys = System::new_all();
-    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
-
-    // Get CPU count for normalization
-    let cpu_count = sys.cpus().len() as f32;
-    let cpu_count = if cpu_count > 0.0 { cpu_count } else { 1.0 };
-
+    let columns = columns::Columns::new(columns);
     let mut groups: HashMap<String, ProcessGroup> = HashMap::new();
 
     // Fetch GPU usage
-    let gpu_usages = gpu::get_gpu_usages();
-
-    for (pid, p) in sys.processes() {
-        let name = p.name().to_string_lossy().to_string();
-        let cpu = p.cpu_usage() / cpu_count;
-        let memory = p.memory();
-        let gpu = *gpu_usages.get(&pid.as_u32()).unwrap_or(&0.0);
+    let gpu_usages = if columns.has("total_gpu") {
+        metrics::process_gpu_usages()
+    } else {
+        Default::default()
+    };
 
-        let entry = groups.entry(name.clone()).or_insert(ProcessGroup {
-            name: name,
-            process_count: 0,
-            pids: Vec::new(),
-            total_cpu: 0.0,
-            total_memory_kb: 0,
-            total_gpu: 0.0,
-        });
+    let counters = counters::Counters::new();
+    let process_notes = read_state(|state| state.process_notes.clone());
+    sampler::with_all_processes(|sys, cpu_count| {
+        let capture_usage = if columns.has("capture") {
+            capture::usage(sys, |_| true)
+        } else {
+            Default::default()
+        };
+        for (pid, p) in sys.processes() {
+            let name = p.name().to_string_lossy().to_string();
+            let cpu = p.cpu_usage() / cpu_count;
+            let memory = p.memory();
+            let gpu = *gpu_usages.get(&pid.as_u32()).unwrap_or(&0.0);
 
-        entry.process_count += 1;
-        entry.pids.push(pid.as_u32());
-        entry.total_cpu += cpu;
-        entry.total_memory_kb += memory;
-        entry.total_gpu += gpu;
-    }
+            let group = groups
+                .entry(name.clone())
+                .or_insert_with(|| ProcessGroup::new(name, &columns));
+            group.add(pid.as_u32(), cpu, memory, gpu);
+            group.add_counts(pid.as_u32(), &counters);
+            group.add_exe_note(&process_notes, p);
+            if let Some(devices) = capture_usage.get(&pid.as_u32()) {
+                group.add_capture(devices);
+            }
+        }
+    });
 
+    let pins = read_state(|state| state.pins.clone());
     let mut result: Vec<ProcessGroup> = groups.into_values().collect();
+    for group in result.iter_mut() {
+        group.apply_pin(&pins);
+    }
     // Sort by name alphabetically
     result.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
     result
 }
 
-/// Get processes grouped by name (like Task Manager)
+/// One instance within a process group
+#[derive(Serialize)]
+pub struct GroupInstance {
+    pid: u32,
+    cpu: f32,
+    memory_kb: u64,
+    gpu: f32,
+    threads: Option<u32>, // None when the OS doesn't report it or access is denied
+    handles: Option<u32>,
+    start_time: u64,
+    exe_path: Option<String>,
+    exe_note: Option<notes::ProcessNote>,
+}
+
+/// Per-instance breakdown of the group `name` (exact process name), highest handle count first
 #[tauri::command]
-fn grouped_processes(names: Vec<String>) -> Vec<ProcessGroup> {
-    use std::collections::HashMap;
+fn get_group_details(name: String) -> Vec<GroupInstance> {
+    let gpu_usages = metrics::process_gpu_usages();
+    let counters = counters::Counters::new();
+    let is_member = |p: &sysinfo::Process| p.name().to_string_lossy() == name.as_str();
+    let process_notes = read_state(|state| state.process_notes.clone());
 
-    let watch: Vec<String> = names
-        .into_iter()
-        .map(|s| resolve_process_name(&s))
-        .filter(|s| !s.is_empty())
-        .collect();
+    let mut instances: Vec<GroupInstance> = sampler::with_processes(is_member, |sys, cpu_count| {
+        sys.processes()
+            .values()
+            .filter(|p| is_member(p))
+            .map(|p| {
+                let pid = p.pid().as_u32();
+                let exe_path = p.exe().map(|e| e.to_string_lossy().to_string());
+                GroupInstance {
+                    pid,
+                    cpu: p.cpu_usage() / cpu_count,
+                    memory_kb: p.memory() / 1024,
+                    gpu: *gpu_usages.get(&pid).unwrap_or(&0.0),
+                    threads: counters.threads(pid),
+                    handles: counters.handles(pid),
+                    start_time: p.start_time(),
+                    exe_note: notes::find(&process_notes, exe_path.as_deref()).cloned(),
+                    exe_path,
+                }
+            })
+            .collect()
+    });
+    instances.sort_by_key(|i| std::cmp::Reverse(i.handles));
+    instances
+}
 
-    if watch.is_empty() {
-        return vec![];
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupSort {
+    #[default]
+    Cpu,
+    Memory,
+    Gpu,
+    Count,
+    Name,
+}
+
+/// Ordering and filters for `grouped_processes`; groups below any minimum are left out
+#[derive(Default)]
+pub struct GroupQuery {
+    pub sort: GroupSort, // Highest first, except by name
+    pub min_cpu: f32,
+    pub min_memory_mb: u64,
+    pub min_gpu: f32,
+    pub include_all: bool, // Every running process, not just the watch list
+}
+
+impl GroupQuery {
+    fn keeps(&self, group: &ProcessGroup) -> bool {
+        group.total_cpu >= self.min_cpu
+            && group.total_memory_kb >= self.min_memory_mb.saturating_mul(1024)
+            && group.total_gpu >= self.min_gpu
     }
 
-    let mut sys = System::new_all();
-    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+    fn order(&self, a: &ProcessGroup, b: &ProcessGroup) -> std::cmp::Ordering {
+        match self.sort {
+            GroupSort::Cpu => b.total_cpu.total_cmp(&a.total_cpu),
+            GroupSort::Memory => b.total_memory_kb.cmp(&a.total_memory_kb),
+            GroupSort::Gpu => b.total_gpu.total_cmp(&a.total_gpu),
+            GroupSort::Count => b.process_count.cmp(&a.process_count),
+            GroupSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        }
+    }
+}
+
+/// Get processes grouped by name (like Task Manager).
+/// `columns` limits which fields are returned; name is always included, thread and handle
+/// totals only when listed. Sorted by `sort_by` (default CPU), without groups under the `min_*` totals; `include_all`
+/// lists every running process instead of the watch list.
+#[tauri::command]
+async fn grouped_processes(
+    names: Option<Vec<String>>,
+    columns: Option<Vec<String>>,
+    sort_by: Option<GroupSort>,
+    min_cpu: Option<f32>,
+    min_memory_mb: Option<u64>,
+    min_gpu: Option<f32>,
+    include_all: Option<bool>,
+) -> Result<Vec<ProcessGroup>, String> {
+    let columns = columns::Columns::new(columns);
+    let query = GroupQuery {
+        sort: sort_by.unwrap_or_default(),
+        min_cpu: min_cpu.unwrap_or(0.0),
+        min_memory_mb: min_memory_mb.unwrap_or(0),
+        min_gpu: min_gpu.unwrap_or(0.0),
+        include_all: include_all.unwrap_or(false),
+    };
+    run_blocking("grouped_processes", move || {
+        process_groups(names, &columns, &query)
+    })
+    .await
+}
 
-    let cpu_count = sys.cpus().len() as f32;
-    let cpu_count = if cpu_count > 0.0 { cpu_count } else { 1.0 };
+fn process_groups(
+    names: Option<Vec<String>>,
+    columns: &columns::Columns,
+    query: &GroupQuery,
+) -> Vec<ProcessGroup> {
+    use std::collections::HashMap;
+
+    let watch = watch_matchers(names);
+
+    if watch.is_empty() && !query.include_all {
+        return vec![];
+    }
 
     // Group processes by base name (without .exe)
     let mut groups: HashMap<String, ProcessGroup> = HashMap::new();
 
     // Fetch GPU usage
-    let gpu_usages = gpu::get_gpu_usages();
-
-    for (pid, p) in sys.processes().iter() {
-        let pname = p.name().to_string_lossy().to_string();
-        let pname_lower = pname.to_lowercase();
+    let gpu_usages = if columns.has("total_gpu")
+        || query.min_gpu > 0.0
+        || matches!(query.sort, GroupSort::Gpu)
+    {
+        metrics::process_gpu_usages()
+    } else {
+        Default::default()
+    };
+    let is_watched = |p: &sysinfo::Process| {
+        query.include_all || watch.iter().any(|w| w.matches(&p.name().to_string_lossy()))
+    };
 
-        if watch
-            .iter()
-            .any(|w| pname_lower.contains(w) || w == &pname_lower)
-        {
+    let counters = counters::Counters::new();
+    let process_notes = read_state(|state| state.process_notes.clone());
+    sampler::with_processes(is_watched, |sys, cpu_count| {
+        let capture_usage = if columns.has("capture") {
+            capture::usage(sys, is_watched)
+        } else {
+            Default::default()
+        };
+        for (pid, p) in sys.processes().iter().filter(|(_, p)| is_watched(p)) {
+            let pname = p.name().to_string_lossy().to_string();
             let normalized_cpu = p.cpu_usage() / cpu_count;
             let memory_kb = p.memory() / 1024;
             let gpu = *gpu_usages.get(&pid.as_u32()).unwrap_or(&0.0);
 
             // Use the original name as key (preserves case)
-            let base_name = pname.clone();
-
-            let group = groups.entry(base_name.clone()).or_insert(ProcessGroup {
-                name: base_name,
-                process_count: 0,
-                pids: vec![],
-                total_cpu: 0.0,
-                total_memory_kb: 0,
-                total_gpu: 0.0,
-            });
+            let group = groups
+                .entry(pname.clone())
+                .or_insert_with(|| ProcessGroup::new(pname, columns));
+            group.add(pid.as_u32(), normalized_cpu, memory_kb, gpu);
+            group.add_counts(pid.as_u32(), &counters);
+            group.add_exe_note(&process_notes, p);
+            if let Some(devices) = capture_usage.get(&pid.as_u32()) {
+                group.add_capture(devices);
+            }
+        }
+    });
+
+    let pins = read_state(|state| state.pins.clone());
+    let mut result: Vec<ProcessGroup> = groups
+        .into_values()
+        .filter(|group| query.keeps(group))
+        .collect();
+    for group in result.iter_mut() {
+        group.apply_pin(&pins);
+    }
+    result.sort_by(|a, b| query.order(a, b));
+    result
+}
+
+/// Whether a rule blocks its program on sight; a block that would catch something Windows or
+/// this app needs is ignored
+fn is_launch_block(rule: &BlacklistEntry) -> bool {
+    rule.action == RuleAction::BlockLaunch && launch_block::validate(rule).is_ok()
+}
+
+/// Whether a rule may act at all right now, whatever it matches: auto-kill is on (blocked
+/// programs always go) and quiet hours' detect-only, a dry run, a maintenance window or a
+/// snooze doesn't hold it. Shared by the watcher and the pressure responders.
+fn rule_may_act(
+    rule: &BlacklistEntry,
+    now: &str,
+    detect_only: bool,
+    maintenance: Option<&maintenance::MaintenanceWindow>,
+) -> bool {
+    (rule.auto_kill || is_launch_block(rule))
+        && !detect_only
+        && !overrides::dry_run()
+        && maintenance.is_none_or(|w| !w.pauses(&rule.name))
+        && rule
+            .snoozed_until
+            .as_ref()
+            .is_none_or(|until| until.as_str() <= now)
+}
 
-            group.process_count += 1;
-            group.pids.push(pid.as_u32());
-            group.total_cpu += normalized_cpu;
-            group.total_memory_kb += memory_kb;
-            group.total_gpu += gpu;
+/// Totals across every process a rule matches, for group-scoped thresholds
+fn rule_group(
+    sys: &System,
+    rule: &BlacklistEntry,
+    cpu_count: f32,
+    gpu_usages: &std::collections::HashMap<u32, f32>,
+    own_pids: &std::collections::HashSet<u32>,
+    session_scope: &sessions::SessionScope,
+) -> ProcessGroup {
+    let matcher = matcher::Matcher::for_rule(rule);
+    let mut group = ProcessGroup::new(rule.name.clone(), &columns::Columns::default());
+    for (pid, p) in sys.processes() {
+        let pid = pid.as_u32();
+        if own_pids.contains(&pid)
+            || !matcher.matches(&p.name().to_string_lossy())
+            || !session_scope.allows(sessions::session_of(pid))
+        {
+            continue;
         }
+        group.add(
+            pid,
+            p.cpu_usage() / cpu_count,
+            p.memory() / 1024,
+            *gpu_usages.get(&pid).unwrap_or(&0.0),
+        );
     }
+    group
+}
 
-    // Convert to vec and sort by CPU usage (highest first)
-    let mut result: Vec<ProcessGroup> = groups.into_values().collect();
-    result.sort_by(|a, b| {
-        b.total_cpu
-            .partial_cmp(&a.total_cpu)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-    result
+// ============= Port Commands =============
+
+/// A process bound to a port
+#[derive(Serialize)]
+pub struct PortOwner {
+    pid: u32,
+    name: String,
+    protocol: netstat::Protocol,
+    local_address: String,
+    exe_path: Option<String>,
 }
 
-// ============= Blacklist Commands =============
+/// Processes listening on `port`, TCP or UDP (see `Socket::is_listening`), one entry per PID
+/// and protocol
+fn port_owners(backend: &impl ProcessBackend, port: u16) -> Result<Vec<PortOwner>, String> {
+    let mut sockets: Vec<netstat::Socket> = netstat::sockets()?
+        .into_iter()
+        .filter(|s| s.local_port == port && s.is_listening())
+        .collect();
+    sockets.sort_by_key(|s| (s.pid, s.protocol == netstat::Protocol::Udp));
+    sockets.dedup_by_key(|s| (s.pid, s.protocol));
+
+    Ok(sockets
+        .into_iter()
+        .filter_map(|s| {
+            let p = backend.process(s.pid)?;
+            Some(PortOwner {
+                pid: s.pid,
+                name: p.name,
+                protocol: s.protocol,
+                local_address: s.local_address,
+                exe_path: p.exe_path,
+            })
+        })
+        .collect())
+}
 
+/// Who is holding `port` ("port 3000 is already in use")
 #[tauri::command]
-fn get_blacklist() -> Vec<BlacklistEntry> {
-    with_state(|state| state.blacklist.clone())
+fn find_process_by_port(port: u16) -> Result<Vec<PortOwner>, String> {
+    port_owners(&SysinfoBackend, port)
 }
 
+/// Kill every process listening on or bound to `port`
 #[tauri::command]
-fn add_to_blacklist(name: String, auto_kill: bool, cpu_threshold: f32) -> Result<String, String> {
-    let name = resolve_process_name(&name);
-    if name.is_empty() {
-        return Err("Name cannot be empty".into());
+fn kill_by_port(port: u16) -> Result<Vec<KillOutcome>, String> {
+    lock::require_unlocked()?;
+    let backend = SysinfoBackend;
+    let allow_protected = protection::override_enabled();
+    let mut pids: Vec<u32> = port_owners(&backend, port)?.iter().map(|o| o.pid).collect();
+    pids.dedup();
+    if pids.is_empty() {
+        return Err(format!("Nothing is listening on port {}", port));
     }
+    Ok(pids
+        .into_iter()
+        .filter_map(|pid| backend.process(pid))
+        .map(|p| kill_info_with(&backend, p, allow_protected))
+        .collect())
+}
 
-    with_state(|state| {
-        if state
-            .blacklist
-            .iter()
-            .any(|e| e.name.to_lowercase() == name.to_lowercase())
-        {
-            return Err("Already in blacklist".into());
-        }
+/// A socket with its remote address resolved to a hostname, when known
+#[derive(Serialize)]
+pub struct ConnectionRow {
+    #[serde(flatten)]
+    socket: netstat::Socket,
+    remote_host: Option<String>,
+    resolution: Option<dns::Resolution>, // None without a remote address
+}
 
-        state.blacklist.push(BlacklistEntry {
-            name: name.clone(),
-            auto_kill,
-            cpu_threshold,
-            gpu_threshold: 101.0, // Default to disabled
-            log_enabled: true,
-            log_kills_only: false,
-            created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-            kill_count: 0,
-        });
-        Ok(format!("{} added to blacklist", name))
+/// Sockets `pid` has open, connected ones first, so a hog can be checked before it's killed.
+/// Hostnames still being looked up arrive later as "dns-resolved" events.
+#[tauri::command]
+fn get_process_connections(pid: u32) -> Result<Vec<ConnectionRow>, String> {
+    let mut sockets: Vec<netstat::Socket> = netstat::sockets()?
+        .into_iter()
+        .filter(|s| s.pid == pid)
+        .collect();
+    sockets.sort_by_key(|s| (s.remote_address.is_none(), s.local_port));
+    Ok(sockets
+        .into_iter()
+        .map(|socket| {
+            let (remote_host, resolution) = match &socket.remote_address {
+                Some(address) => {
+                    let (host, resolution) = dns::lookup(address);
+                    (host, Some(resolution))
+                }
+                None => (None, None),
+            };
+            ConnectionRow {
+                socket,
+                remote_host,
+                resolution,
+            }
+        })
+        .collect())
+}
+
+// ============= Power Request Commands =============
+
+/// Processes, services and drivers keeping the machine from sleeping or turning the display off
+#[tauri::command]
+async fn get_power_requests() -> Result<Vec<power_requests::PowerRequest>, String> {
+    run_blocking("get_power_requests", power_requests::list).await?
+}
+
+/// Have Windows disregard a requester's sleep/display requests (or honour them again)
+#[tauri::command]
+fn ignore_power_request(
+    requester: power_requests::Requester,
+    name: String,
+    ignore: bool,
+) -> Result<String, String> {
+    lock::require_unlocked()?;
+    power_requests::set_ignored(requester, &name, ignore)?;
+    Ok(if ignore {
+        format!("Ignoring power requests from {}", name)
+    } else {
+        format!("Honouring power requests from {} again", name)
     })
 }
 
+// ============= Firewall Commands =============
+
+/// Block outbound traffic for the executable at `path` - a gentler alternative to killing
 #[tauri::command]
-fn remove_from_blacklist(name: String) -> Result<String, String> {
-    with_state(|state| {
-        let len_before = state.blacklist.len();
+fn block_process_network(path: String) -> Result<firewall::NetworkBlock, String> {
+    lock::require_unlocked()?;
+    let path = path.trim().to_string();
+    if read_state(|state| {
         state
-            .blacklist
-            .retain(|e| e.name.to_lowercase() != name.to_lowercase());
-        if state.blacklist.len() < len_before {
-            Ok(format!("{} removed from blacklist", name))
-        } else {
-            Err("Not found in blacklist".into())
-        }
+            .network_blocks
+            .iter()
+            .any(|b| b.path.eq_ignore_ascii_case(&path))
+    }) {
+        return Err("Already blocked".into());
+    }
+    let block = firewall::block(&path)?;
+    with_state(|state| state.network_blocks.push(block.clone()));
+    Ok(block)
+}
+
+#[tauri::command]
+fn unblock_process_network(path: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let block = read_state(|state| {
+        state
+            .network_blocks
+            .iter()
+            .find(|b| b.path.eq_ignore_ascii_case(path.trim()))
+            .cloned()
     })
+    .ok_or("No block rule for this executable")?;
+    firewall::unblock(&block)?;
+    with_state(|state| state.network_blocks.retain(|b| b.path != block.path));
+    Ok(format!("Network access restored for {}", block.path))
 }
 
+/// Firewall rules this app has created
 #[tauri::command]
-fn toggle_auto_kill(name: String) -> Result<bool, String> {
+fn list_network_blocks() -> Vec<firewall::NetworkBlock> {
+    read_state(|state| state.network_blocks.clone())
+}
+
+// ============= Pinned Process Commands =============
+
+#[tauri::command]
+fn get_pinned_processes() -> Vec<PinnedProcess> {
+    read_state(|state| state.pins.clone())
+}
+
+/// Pin a process (or update its pin) with an optional display name and note
+#[tauri::command]
+fn pin_process(
+    name: String,
+    label: Option<String>,
+    note: Option<String>,
+) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Name cannot be empty".into());
+    }
+    let label = label
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty());
+    let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+
     with_state(|state| {
-        for entry in state.blacklist.iter_mut() {
-            if entry.name.to_lowercase() == name.to_lowercase() {
-                entry.auto_kill = !entry.auto_kill;
-                return Ok(entry.auto_kill);
+        let key = matcher::normalize(&name, false);
+        match state
+            .pins
+            .iter_mut()
+            .find(|pin| matcher::normalize(&pin.name, false) == key)
+        {
+            Some(pin) => {
+                pin.label = label;
+                pin.note = note;
             }
+            None => state.pins.push(PinnedProcess {
+                name: name.clone(),
+                label,
+                note,
+                created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            }),
         }
-        Err("Not found in blacklist".into())
-    })
+    });
+    Ok(format!("{} pinned", name))
 }
 
 #[tauri::command]
-fn toggle_blacklist_log(name: String) -> Result<bool, String> {
+fn unpin_process(name: String) -> Result<String, String> {
+    lock::require_unlocked()?;
     with_state(|state| {
-        for entry in state.blacklist.iter_mut() {
-            if entry.name.to_lowercase() == name.to_lowercase() {
-                entry.log_enabled = !entry.log_enabled;
-                return Ok(entry.log_enabled);
+        let key = matcher::normalize(&name, false);
+        let len_before = state.pins.len();
+        state
+            .pins
+            .retain(|pin| matcher::normalize(&pin.name, false) != key);
+        if state.pins.len() < len_before {
+            Ok(format!("{} unpinned", name))
+        } else {
+            Err("Not pinned".into())
+        }
+    })
+}
+
+// ============= Process Note Commands =============
+
+#[tauri::command]
+fn get_process_notes(store: tauri::State<StateStore>) -> Vec<notes::ProcessNote> {
+    store.read(|state| state.process_notes.clone())
+}
+
+/// Note and risk rating for an executable; an empty note rated "unknown" removes it
+#[tauri::command]
+fn set_process_note(
+    exe_path: String,
+    note: String,
+    risk: notes::Risk,
+) -> Result<Option<notes::ProcessNote>, String> {
+    lock::require_unlocked()?;
+    notes::set(&exe_path, &note, risk)
+}
+
+/// Description and safety guidance for a common process, from the bundled offline database
+#[tauri::command]
+fn lookup_process_info(name: String) -> Option<process_info::ProcessInfo> {
+    process_info::lookup(&name)
+}
+
+// ============= Snapshot Commands =============
+
+/// Record the current process list for a later `diff_snapshots`
+#[tauri::command]
+fn take_snapshot(label: Option<String>) -> snapshots::SnapshotInfo {
+    snapshots::take(label)
+}
+
+#[tauri::command]
+fn list_snapshots() -> Vec<snapshots::SnapshotInfo> {
+    snapshots::list()
+}
+
+#[tauri::command]
+fn diff_snapshots(a: u32, b: u32) -> Result<snapshots::SnapshotDiff, String> {
+    snapshots::diff(a, b)
+}
+
+// ============= Boot Impact Commands =============
+
+/// Per-app resource usage during the first minutes after the last recorded boot
+#[tauri::command]
+fn get_boot_impact() -> Option<boot_impact::BootReport> {
+    boot_impact::report()
+}
+
+/// Whether to offer the first-run wizard: never finished, and nothing configured by hand either
+#[tauri::command]
+fn needs_onboarding() -> bool {
+    read_state(|state| !state.onboarded && state.blacklist.is_empty() && state.watchlist.is_empty())
+}
+
+/// Proposed watchlist, rules and defaults for the first-run wizard; takes a few seconds
+#[tauri::command]
+async fn run_first_time_scan() -> Result<onboarding::FirstRunProposal, String> {
+    run_blocking("run_first_time_scan", onboarding::scan).await
+}
+
+#[tauri::command]
+fn finish_onboarding() -> Result<(), String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        state.onboarded = true;
+    });
+    Ok(())
+}
+
+// ============= Blacklist Commands =============
+
+#[tauri::command]
+fn get_blacklist() -> Vec<BlacklistEntry> {
+    with_state(|state| state.blacklist.clone())
+}
+
+#[tauri::command]
+fn add_to_blacklist(
+    name: String,
+    auto_kill: bool,
+    cpu_threshold: f32,
+) -> Result<conflicts::RuleChange, String> {
+    lock::require_unlocked()?;
+    let message = insert_blacklist_entry(&name, auto_kill, cpu_threshold)?;
+    Ok(rule_change(message, &resolve_process_name(&name)))
+}
+
+/// Attach the conflicts `rule` is now involved in to an add/update result
+fn rule_change(message: String, rule: &str) -> conflicts::RuleChange {
+    conflicts::RuleChange {
+        message,
+        conflicts: read_state(|state| conflicts::involving(&state.blacklist, rule)),
+    }
+}
+
+/// Every blacklist rule that is shadowed by, overlaps or undoes the whitelist of another
+#[tauri::command]
+fn validate_rules() -> Vec<conflicts::RuleConflict> {
+    read_state(|state| conflicts::validate(&state.blacklist))
+}
+
+fn insert_blacklist_entry(
+    name: &str,
+    auto_kill: bool,
+    cpu_threshold: f32,
+) -> Result<String, String> {
+    let name = resolve_process_name(name);
+    if name.is_empty() {
+        return Err("Name cannot be empty".into());
+    }
+
+    with_state(|state| {
+        if state
+            .blacklist
+            .iter()
+            .any(|e| e.name.to_lowercase() == name.to_lowercase())
+        {
+            return Err("Already in blacklist".into());
+        }
+
+        state
+            .blacklist
+            .push(BlacklistEntry::new(name.clone(), auto_kill, cpu_threshold));
+        Ok(format!("{} added to blacklist", name))
+    })
+}
+
+#[tauri::command]
+fn remove_from_blacklist(name: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    delete_blacklist_entry(&name)
+}
+
+fn delete_blacklist_entry(name: &str) -> Result<String, String> {
+    with_state(|state| {
+        let len_before = state.blacklist.len();
+        state
+            .blacklist
+            .retain(|e| e.name.to_lowercase() != name.to_lowercase());
+        if state.blacklist.len() < len_before {
+            confirm::forget_rule(name);
+            Ok(format!("{} removed from blacklist", name))
+        } else {
+            Err("Not found in blacklist".into())
+        }
+    })
+}
+
+#[tauri::command]
+fn toggle_auto_kill(name: String) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.auto_kill = !entry.auto_kill;
+                return Ok(entry.auto_kill);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+#[tauri::command]
+fn toggle_blacklist_log(name: String) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.log_enabled = !entry.log_enabled;
+                return Ok(entry.log_enabled);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+#[tauri::command]
+fn toggle_log_kills_only(name: String) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.log_kills_only = !entry.log_kills_only;
+                return Ok(entry.log_kills_only);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+#[tauri::command]
+fn set_cpu_threshold(name: String, threshold: f32) -> Result<f32, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.cpu_threshold = threshold.max(0.0).min(101.0);
+                return Ok(entry.cpu_threshold);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+#[tauri::command]
+fn set_gpu_threshold(name: String, threshold: f32) -> Result<f32, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                // 101.0 means disabled
+                entry.gpu_threshold = threshold.max(0.0).min(101.0);
+                return Ok(entry.gpu_threshold);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+#[tauri::command]
+fn set_on_kill_script(name: String, script: Option<String>) -> Result<String, String> {
+    lock::require_unlocked()?;
+    // Treat a blank script as "no script"
+    let script = script
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.on_kill_script = script;
+                return Ok(match &entry.on_kill_script {
+                    Some(_) => format!("On-kill script set for {}", entry.name),
+                    None => format!("On-kill script removed from {}", entry.name),
+                });
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+/// Attach a rhai script whose `should_kill(process)` decides kills for this rule
+#[tauri::command]
+fn set_rule_script(name: String, script: Option<String>) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let script = script.filter(|s| !s.trim().is_empty());
+    if let Some(source) = &script {
+        scripting::compile(source)?;
+    }
+
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.rule_script = script;
+                return Ok(match &entry.rule_script {
+                    Some(_) => format!("Rule script set for {}", entry.name),
+                    None => format!("Rule script removed from {}", entry.name),
+                });
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+/// Process names containing any of these patterns are ignored by the rule (e.g. "msedgewebview2")
+#[tauri::command]
+fn set_exclude_patterns(
+    name: String,
+    patterns: Vec<String>,
+) -> Result<conflicts::RuleChange, String> {
+    lock::require_unlocked()?;
+    // Stored as entered; the matcher folds case unless the rule is case-sensitive
+    let patterns: Vec<String> = patterns
+        .iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.exclude_patterns = patterns.clone();
+                return Ok(format!("Exclude patterns updated for {}", entry.name));
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+    .map(|message| rule_change(message, &name))
+}
+
+/// Pause a rule's auto-kill for `minutes` (0 resumes it now)
+#[tauri::command]
+fn snooze_rule(name: String, minutes: u32) -> Result<String, String> {
+    lock::require_unlocked()?;
+    snooze_blacklist_entry(&name, minutes)
+}
+
+fn snooze_blacklist_entry(name: &str, minutes: u32) -> Result<String, String> {
+    let until = (minutes > 0).then(|| {
+        (Local::now() + chrono::Duration::minutes(minutes as i64))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    });
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.snoozed_until = until.clone();
+                return Ok(match &until {
+                    Some(t) => format!("{} snoozed until {}", entry.name, t),
+                    None => format!("{} resumed", entry.name),
+                });
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+/// Stop `rule` from matching `process_name` by adding it to the rule's exclude patterns
+#[tauri::command]
+fn whitelist_process(rule: String, process_name: String) -> Result<conflicts::RuleChange, String> {
+    lock::require_unlocked()?;
+    whitelist_in_rule(&rule, &process_name).map(|message| rule_change(message, &rule))
+}
+
+fn whitelist_in_rule(rule: &str, process_name: &str) -> Result<String, String> {
+    let pattern = process_name.trim().to_string();
+    if pattern.is_empty() {
+        return Err("Name cannot be empty".into());
+    }
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == rule.to_lowercase() {
+                let key = matcher::normalize(&pattern, entry.case_sensitive);
+                if !entry
+                    .exclude_patterns
+                    .iter()
+                    .any(|e| matcher::normalize(e, entry.case_sensitive) == key)
+                {
+                    entry.exclude_patterns.push(pattern.clone());
+                }
+                return Ok(format!("{} whitelisted for {}", process_name, entry.name));
             }
         }
         Err("Not found in blacklist".into())
     })
 }
 
+/// Processes killed in the last hour that `restart_process` can start again
+#[tauri::command]
+fn get_recent_kills() -> Vec<launch::RecentKill> {
+    launch::recent_kills()
+}
+
+/// Start a recently killed process again with its original command line ("undo")
+#[tauri::command]
+fn restart_process(id: u64) -> Result<String, String> {
+    lock::require_unlocked()?;
+    launch::restart(id)
+}
+
+/// Start a program under another account, e.g. a service app as its service user
+#[tauri::command]
+async fn launch_as_user(
+    path: String,
+    args: Vec<String>,
+    username: String,
+    password: Option<String>,
+) -> Result<launch::Launched, String> {
+    lock::require_unlocked()?;
+    run_blocking("launch_as_user", move || {
+        launch::launch_as_user(&path, &args, &username, password.as_deref().unwrap_or(""))
+    })
+    .await?
+}
+
+#[tauri::command]
+fn get_language() -> (String, Vec<String>) {
+    (
+        read_state(|state| state.settings.language.clone()),
+        i18n::available(),
+    )
+}
+
+/// Language for log reasons, results and notifications, e.g. "en" or "th"
+#[tauri::command]
+fn set_language(app: tauri::AppHandle, locale: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let lang = i18n::set_language(&locale)?;
+    with_state(|state| {
+        state.settings.language = lang.clone();
+    });
+    tray::relabel(&app);
+    Ok(lang)
+}
+
+#[tauri::command]
+fn get_log_level() -> (String, String) {
+    (
+        read_state(|state| state.settings.log_level.clone()),
+        logging::log_dir().to_string_lossy().to_string(),
+    )
+}
+
+/// Zip recent logs, the sanitized config and system details for a bug report; returns the path
+#[tauri::command]
+async fn create_diagnostics_bundle() -> Result<String, String> {
+    run_blocking("create_diagnostics_bundle", || {
+        diagnostics::create_bundle().map(|path| path.to_string_lossy().to_string())
+    })
+    .await?
+}
+
+/// Zip the state, stats history and config.toml to `path` for migration or safekeeping
+#[tauri::command]
+async fn backup_all(path: String) -> Result<backup::Manifest, String> {
+    run_blocking("backup_all", move || {
+        backup::backup_all(std::path::Path::new(path.trim()))
+    })
+    .await?
+}
+
+/// Replace all app data with a backup; the current data is kept in backups/ first
+#[tauri::command]
+async fn restore_all(path: String) -> Result<backup::RestoreResult, String> {
+    lock::require_unlocked()?;
+    run_blocking("restore_all", move || {
+        backup::restore_all(std::path::Path::new(path.trim()))
+    })
+    .await?
+}
+
+/// Level of the diagnostic log file: "off", "error", "warn", "info", "debug" or "trace"
+#[tauri::command]
+fn set_log_level(level: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let level = logging::set_level(&level)?;
+    with_state(|state| {
+        state.settings.log_level = level.clone();
+    });
+    tracing::info!(level = %level, "log level changed");
+    Ok(level)
+}
+
+/// Mirror every kill to the Windows Application Event Log. Enabling also tries to register
+/// the event source (needs admin rights once).
+#[tauri::command]
+fn set_event_log_enabled(enabled: bool) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    if !cfg!(windows) && enabled {
+        return Err("The Windows Event Log is only available on Windows".into());
+    }
+    if enabled {
+        if let Err(err) = event_log::register_source() {
+            tracing::warn!("Event source not registered: {}", err);
+        }
+    }
+    with_state(|state| {
+        state.settings.event_log = enabled;
+    });
+    Ok(enabled)
+}
+
+#[tauri::command]
+fn get_event_log_enabled() -> bool {
+    read_state(|state| state.settings.event_log)
+}
+
+/// Mirror activity log entries to journald, or syslog where there is no journal (Linux)
+#[tauri::command]
+fn set_syslog_enabled(enabled: bool) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    if !cfg!(target_os = "linux") && enabled {
+        return Err("journald/syslog output is only available on Linux".into());
+    }
+    with_state(|state| {
+        state.settings.syslog = enabled;
+    });
+    Ok(enabled)
+}
+
+#[tauri::command]
+fn get_syslog_enabled() -> bool {
+    read_state(|state| state.settings.syslog)
+}
+
+#[tauri::command]
+fn set_kill_notifications(enabled: bool) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        state.settings.kill_notifications = enabled;
+    });
+    Ok(enabled)
+}
+
+#[tauri::command]
+fn get_session_info() -> sessions::SessionInfo {
+    sessions::SessionInfo {
+        current: sessions::current_session(),
+        console: sessions::console_session(),
+        scope: read_state(|state| state.settings.session_scope.clone()),
+    }
+}
+
+/// Restrict enforcement to the interactive session or a list of session IDs
+#[tauri::command]
+fn set_session_scope(scope: sessions::SessionScope) -> Result<String, String> {
+    lock::require_unlocked()?;
+    if matches!(&scope, sessions::SessionScope::Sessions { ids } if ids.is_empty()) {
+        return Err("Pick at least one session".into());
+    }
+    with_state(|state| {
+        state.settings.session_scope = scope;
+    });
+    Ok("Session scope saved".to_string())
+}
+
+#[tauri::command]
+fn get_refresh_intervals() -> refresher::RefreshIntervals {
+    read_state(|state| state.settings.refresh_intervals.clone())
+}
+
+/// How often the background refresher samples stats, processes, GPU and disks
+#[tauri::command]
+fn set_refresh_intervals(intervals: refresher::RefreshIntervals) -> Result<String, String> {
+    lock::require_unlocked()?;
+    intervals.validate()?;
+    refresher::apply(&intervals);
+    with_state(|state| {
+        state.settings.refresh_intervals = intervals;
+    });
+    Ok("Refresh intervals saved".to_string())
+}
+
+#[tauri::command]
+fn get_memory_pressure_policy() -> pressure::PressurePolicy {
+    read_state(|state| state.settings.memory_pressure.clone())
+}
+
+/// Free-memory threshold and the ranked list of processes to trim, suspend or kill below it
+#[tauri::command]
+fn set_memory_pressure_policy(policy: pressure::PressurePolicy) -> Result<String, String> {
+    lock::require_unlocked()?;
+    policy.validate()?;
+    with_state(|state| {
+        state.settings.memory_pressure = policy;
+    });
+    Ok("Memory pressure policy saved".to_string())
+}
+
+#[tauri::command]
+fn get_mqtt_settings() -> mqtt::MqttSettings {
+    read_state(|state| state.settings.mqtt.clone())
+}
+
+/// Broker, topic prefix and stats interval for the MQTT publisher; reconnects right away
+#[tauri::command]
+fn set_mqtt_settings(settings: mqtt::MqttSettings) -> Result<String, String> {
+    lock::require_unlocked()?;
+    settings.validate()?;
+    with_state(|state| {
+        state.settings.mqtt = settings;
+    });
+    mqtt::reload();
+    Ok("MQTT settings saved".to_string())
+}
+
+#[tauri::command]
+fn get_mqtt_connected() -> bool {
+    mqtt::is_connected()
+}
+
+#[tauri::command]
+fn get_bot_settings() -> notifier::BotSettings {
+    read_state(|state| state.settings.bot.clone())
+}
+
+/// Telegram/Discord bot: where kill messages go and who may send commands
+#[tauri::command]
+fn set_bot_settings(settings: notifier::BotSettings) -> Result<String, String> {
+    lock::require_unlocked()?;
+    settings.validate()?;
+    with_state(|state| {
+        state.settings.bot = settings;
+    });
+    notifier::reload();
+    Ok("Bot settings saved".to_string())
+}
+
+#[tauri::command]
+fn get_email_digest() -> digest::EmailDigest {
+    read_state(|state| state.settings.email_digest.clone())
+}
+
+/// SMTP server, recipients and schedule of the activity digest
+#[tauri::command]
+fn set_email_digest(settings: digest::EmailDigest) -> Result<String, String> {
+    lock::require_unlocked()?;
+    settings.validate()?;
+    with_state(|state| {
+        // The schedule's own bookkeeping isn't the UI's to change
+        let last_sent = state.settings.email_digest.last_sent.take();
+        state.settings.email_digest = digest::EmailDigest {
+            last_sent,
+            ..settings
+        };
+    });
+    Ok("Email digest settings saved".to_string())
+}
+
+#[tauri::command]
+fn get_sync_settings() -> sync::SyncSettings {
+    read_state(|state| state.settings.sync.clone())
+}
+
+/// Where rules and settings are synced to, and how often
+#[tauri::command]
+fn set_sync_settings(settings: sync::SyncSettings) -> Result<String, String> {
+    lock::require_unlocked()?;
+    settings.validate()?;
+    with_state(|state| {
+        // Bookkeeping stays, unless the storage changed and it no longer applies
+        let current = &state.settings.sync;
+        let same_target = current.same_target(&settings);
+        state.settings.sync = sync::SyncSettings {
+            last_synced: current.last_synced.clone().filter(|_| same_target),
+            last_hash: current.last_hash.clone().filter(|_| same_target),
+            local_changed_at: current.local_changed_at.clone(),
+            ..settings
+        };
+    });
+    Ok("Sync settings saved".to_string())
+}
+
+#[tauri::command]
+fn get_sync_status() -> sync::SyncStatus {
+    sync::status()
+}
+
+#[tauri::command]
+async fn sync_now() -> Result<sync::SyncOutcome, String> {
+    lock::require_unlocked()?;
+    run_blocking("sync_now", sync::sync_now).await?
+}
+
+/// Scripts another machine set on synced rules, held until accepted here
+#[tauri::command]
+fn get_pending_sync_scripts() -> Vec<sync::PendingScript> {
+    sync::pending_scripts()
+}
+
+#[tauri::command]
+fn accept_sync_script(rule: String, field: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    sync::accept_script(&rule, &field)?;
+    Ok(format!("Script applied to {}", rule))
+}
+
+/// Add the synced rules this machine is missing and push the result (manual conflict merge)
+#[tauri::command]
+async fn merge_synced_rules() -> Result<String, String> {
+    lock::require_unlocked()?;
+    let added = run_blocking("merge_synced_rules", sync::merge).await??;
+    Ok(format!("{} rule(s) merged from the synced copy", added))
+}
+
+/// Send the digest for the current period right away (also a test of the SMTP settings)
+#[tauri::command]
+async fn send_email_digest_now() -> Result<String, String> {
+    let settings = read_state(|state| state.settings.email_digest.clone());
+    run_blocking("send_email_digest_now", move || digest::send_now(&settings)).await??;
+    Ok("Digest sent".to_string())
+}
+
+/// HTML report of the last 7 days into `folder` (default: reports/ in the data folder),
+/// opened in the default browser when `open` is set
+#[tauri::command]
+async fn generate_weekly_report(
+    app: tauri::AppHandle,
+    folder: Option<String>,
+    open: bool,
+) -> Result<String, String> {
+    let folder = folder
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty());
+    let path = run_blocking("generate_weekly_report", move || {
+        report::generate_weekly_report(folder.as_deref().map(std::path::Path::new))
+    })
+    .await??;
+    let path = path.to_string_lossy().to_string();
+    if open {
+        app.opener()
+            .open_path(&path, None::<&str>)
+            .map_err(|e| format!("Report saved to {} but could not be opened: {}", path, e))?;
+    }
+    Ok(path)
+}
+
+/// Send a test message with the given (unsaved) settings
+#[tauri::command]
+async fn test_bot(settings: notifier::BotSettings) -> Result<String, String> {
+    run_blocking("test_bot", move || notifier::test(&settings)).await??;
+    Ok("Test message sent".to_string())
+}
+
+#[tauri::command]
+fn get_cpu_pressure_policy() -> pressure::CpuPressurePolicy {
+    read_state(|state| state.settings.cpu_pressure.clone())
+}
+
+/// Total CPU level and duration after which prioritized rules are acted on one at a time
+#[tauri::command]
+fn set_cpu_pressure_policy(policy: pressure::CpuPressurePolicy) -> Result<String, String> {
+    lock::require_unlocked()?;
+    policy.validate()?;
+    with_state(|state| {
+        state.settings.cpu_pressure = policy;
+    });
+    Ok("CPU pressure policy saved".to_string())
+}
+
+#[tauri::command]
+fn get_handle_leak_policy() -> leaks::HandleLeakPolicy {
+    read_state(|state| state.settings.handle_leaks.clone())
+}
+
+/// Handle count limit and growth rate that flag a leak, and whether to restart the process
+#[tauri::command]
+fn set_handle_leak_policy(policy: leaks::HandleLeakPolicy) -> Result<String, String> {
+    lock::require_unlocked()?;
+    policy.validate()?;
+    with_state(|state| {
+        state.settings.handle_leaks = policy;
+    });
+    Ok("Handle leak policy saved".to_string())
+}
+
+#[tauri::command]
+fn get_anomaly_policy() -> baseline::AnomalyPolicy {
+    read_state(|state| state.settings.anomalies.clone())
+}
+
+/// How far above its baseline a process must go to raise "anomaly-detected"
+#[tauri::command]
+fn set_anomaly_policy(policy: baseline::AnomalyPolicy) -> Result<String, String> {
+    lock::require_unlocked()?;
+    policy.validate()?;
+    with_state(|state| {
+        state.settings.anomalies = policy;
+    });
+    Ok("Anomaly policy saved".to_string())
+}
+
+#[tauri::command]
+fn get_baselines() -> Vec<baseline::BaselineInfo> {
+    baseline::list()
+}
+
+#[tauri::command]
+fn reset_baseline(name: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    if baseline::reset(&name) {
+        Ok(format!("Baseline for {} reset", name))
+    } else {
+        Err("No baseline learned for that process".into())
+    }
+}
+
+#[tauri::command]
+fn get_launch_watch_status() -> creation::LaunchWatchStatus {
+    creation::status()
+}
+
+/// Kill processes matching an auto-kill rule with no CPU threshold the moment they start
+#[tauri::command]
+fn set_instant_kill(enabled: bool) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        state.settings.instant_kill = enabled;
+    });
+    Ok(enabled)
+}
+
+/// Order in which the CPU pressure responder acts on this rule (lower first, None = never)
+#[tauri::command]
+fn set_rule_cpu_priority(name: String, priority: Option<u32>) -> Result<Option<u32>, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.cpu_priority = priority;
+                return Ok(priority);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+#[tauri::command]
+fn get_kill_escalation() -> process_ctl::EscalationPolicy {
+    read_state(|state| state.settings.kill_escalation.clone())
+}
+
+/// SIGTERM-then-SIGKILL and how long each step waits for the process to exit
+#[tauri::command]
+fn set_kill_escalation(policy: process_ctl::EscalationPolicy) -> Result<String, String> {
+    lock::require_unlocked()?;
+    if policy.exit_timeout_ms > 10_000 {
+        return Err("Exit timeout must be 10 seconds or less".into());
+    }
+    with_state(|state| {
+        state.settings.kill_escalation = policy;
+    });
+    Ok("Kill escalation saved".to_string())
+}
+
+/// Seconds to watch a killed app for a restart (0 = off)
+#[tauri::command]
+fn set_respawn_window(seconds: u64) -> Result<u64, String> {
+    lock::require_unlocked()?;
+    if seconds > 3600 {
+        return Err("Respawn window must be an hour or less".into());
+    }
+    with_state(|state| {
+        state.settings.respawn_window_secs = seconds;
+    });
+    Ok(seconds)
+}
+
+/// Restarts an hour after which an app counts as a respawn loop and is left alone (0 = off)
+#[tauri::command]
+fn set_respawn_loop_limit(limit: u32) -> Result<u32, String> {
+    lock::require_unlocked()?;
+    if limit == 1 {
+        return Err("Allow at least 2 restarts an hour (or 0 for no limit)".into());
+    }
+    with_state(|state| {
+        state.settings.respawn_loop_limit = limit;
+    });
+    Ok(limit)
+}
+
+#[tauri::command]
+fn get_respawn_loops() -> Vec<respawn::RespawnLoop> {
+    respawn::loops()
+}
+
+/// Start killing a rule's looping apps again
+#[tauri::command]
+fn resume_respawn_loop(rule: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    if respawn::resume(&rule) {
+        Ok(format!("Killing resumed for {}", rule))
+    } else {
+        Err("No respawn loop for that rule".into())
+    }
+}
+
+/// Turn off auto-kill for rules that haven't matched anything in `days` days
+#[tauri::command]
+fn prune_stale_rules(days: u32) -> Result<Vec<String>, String> {
+    lock::require_unlocked()?;
+    if days == 0 {
+        return Err("Days must be at least 1".into());
+    }
+    Ok(with_state(|state| stale_rules::disable(state, days)))
+}
+
+/// Days without a match before a rule is suggested for disabling (0 = never suggest)
+#[tauri::command]
+fn set_stale_rule_days(days: u32) -> Result<u32, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        state.settings.stale_rule_days = days;
+    });
+    Ok(days)
+}
+
+#[tauri::command]
+fn set_low_power_mode(enabled: bool) -> Result<power::PowerStatus, String> {
+    lock::require_unlocked()?;
+    power::set_enabled(enabled);
+    with_state(|state| {
+        state.settings.low_power_mode = enabled;
+    });
+    // Don't leave a deferred write behind when switching it off
+    flush_state();
+    Ok(power::status())
+}
+
+#[tauri::command]
+fn get_tray_icon_style() -> tray::TrayIconStyle {
+    read_state(|state| state.settings.tray_icon)
+}
+
+/// Picked up by the tray on its next update
+#[tauri::command]
+fn set_tray_icon_style(style: tray::TrayIconStyle) -> Result<tray::TrayIconStyle, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        state.settings.tray_icon = style;
+    });
+    Ok(style)
+}
+
+/// Reported by the frontend on visibility changes
+#[tauri::command]
+fn set_window_hidden(hidden: bool) -> power::PowerStatus {
+    power::set_window_hidden(hidden);
+    if !power::is_active() {
+        flush_state();
+    }
+    power::status()
+}
+
+#[tauri::command]
+fn get_power_status() -> power::PowerStatus {
+    power::status()
+}
+
+/// CPU, memory and IPC call rate of this app, from the watchdog's last sample
+#[tauri::command]
+fn get_self_usage() -> self_usage::SelfUsage {
+    self_usage::latest()
+}
+
+#[tauri::command]
+fn get_self_budget() -> self_usage::SelfBudget {
+    read_state(|state| state.settings.self_budget.clone())
+}
+
+#[tauri::command]
+fn set_self_budget(budget: self_usage::SelfBudget) -> Result<String, String> {
+    lock::require_unlocked()?;
+    if budget.cpu_percent <= 0.0 || budget.memory_mb == 0 {
+        return Err("Budget limits must be greater than zero".into());
+    }
+    with_state(|state| {
+        state.settings.self_budget = budget;
+    });
+    Ok("Resource budget saved".to_string())
+}
+
+/// Memory threshold in MB (0 = disabled) and whether thresholds apply per process or to the group
+#[tauri::command]
+fn set_rule_scope(
+    name: String,
+    scope: RuleScope,
+    memory_threshold_mb: u64,
+) -> Result<String, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.scope = scope;
+                entry.memory_threshold_mb = memory_threshold_mb;
+                return Ok(format!("Thresholds updated for {}", entry.name));
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+#[tauri::command]
+fn set_case_sensitive(name: String, enabled: bool) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.case_sensitive = enabled;
+                return Ok(enabled);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+/// Leave matching processes alone while they're playing audio
+#[tauri::command]
+fn set_skip_if_playing_audio(name: String, enabled: bool) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.skip_if_playing_audio = enabled;
+                return Ok(enabled);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+/// Leave matching processes alone while they use the camera or microphone
+#[tauri::command]
+fn set_skip_if_capturing(name: String, enabled: bool) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.skip_if_capturing = enabled;
+                return Ok(enabled);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+/// Save a stack dump of matching processes before the watcher kills them
+#[tauri::command]
+fn set_dump_before_kill(name: String, enabled: bool) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.dump_before_kill = enabled;
+                return Ok(enabled);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+/// Processes using the webcam or microphone right now
+#[tauri::command]
+async fn get_av_capture_processes() -> Result<Vec<capture::CaptureUse>, String> {
+    run_blocking("get_av_capture_processes", capture::in_use).await
+}
+
+#[tauri::command]
+async fn get_audio_sessions() -> Result<Vec<audio::AudioSession>, String> {
+    run_blocking("get_audio_sessions", audio::sessions).await?
+}
+
+#[tauri::command]
+fn set_require_confirmation(name: String, enabled: bool) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    confirm::forget_rule(&name);
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.require_confirmation_first_time = enabled;
+                entry.confirmed = false;
+                return Ok(enabled);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+/// Kill, lock the workstation or show a full-screen warning when the rule fires
+#[tauri::command]
+fn set_rule_action(name: String, action: RuleAction) -> Result<RuleAction, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                if action == RuleAction::BlockLaunch {
+                    launch_block::validate(entry)?;
+                }
+                entry.action = action;
+                return Ok(action);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })?;
+    launch_block::sync();
+    Ok(action)
+}
+
+/// Rules blocking a program from running, and how each block is enforced
+#[tauri::command]
+fn get_blocked_launches() -> Vec<launch_block::BlockedLaunch> {
+    launch_block::status()
+}
+
+/// Let a blocked program run again: the rule goes back to a plain kill rule with auto-kill off
+#[tauri::command]
+fn unblock_launch(name: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        let entry = state
+            .blacklist
+            .iter_mut()
+            .find(|e| e.name.to_lowercase() == name.to_lowercase())
+            .ok_or("Not found in blacklist")?;
+        entry.action = RuleAction::Kill;
+        entry.auto_kill = false;
+        Ok::<_, String>(())
+    })?;
+    launch_block::sync();
+    Ok(format!("{} unblocked", name))
+}
+
+/// Only act while the machine is in this state, e.g. on a metered connection or on battery
+#[tauri::command]
+fn set_rule_conditions(
+    name: String,
+    conditions: environment::Conditions,
+) -> Result<environment::Conditions, String> {
+    lock::require_unlocked()?;
+    if conditions
+        .memory_above_percent
+        .is_some_and(|p| !(0.0..100.0).contains(&p))
+    {
+        return Err("Memory pressure must be between 0 and 100%".into());
+    }
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.conditions = conditions.clone();
+                return Ok(conditions);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+/// Act when a matching process uses a multiple of its usual CPU or memory (None = off)
+#[tauri::command]
+fn set_rule_anomaly_trigger(
+    name: String,
+    trigger: Option<baseline::AnomalyTrigger>,
+) -> Result<Option<baseline::AnomalyTrigger>, String> {
+    lock::require_unlocked()?;
+    if let Some(trigger) = &trigger {
+        trigger.validate()?;
+    }
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.anomaly_trigger = trigger.clone();
+                return Ok(trigger);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+/// How many parent levels a "kill launcher" rule climbs to find what started the process
+#[tauri::command]
+fn set_rule_launcher_depth(name: String, depth: u32) -> Result<u32, String> {
+    lock::require_unlocked()?;
+    if !(1..=launcher::MAX_KILL_DEPTH).contains(&depth) {
+        return Err(format!(
+            "Depth must be between 1 and {}",
+            launcher::MAX_KILL_DEPTH
+        ));
+    }
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.launcher_depth = depth;
+                return Ok(depth);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+/// Time-of-day CPU thresholds; outside every period the rule's own threshold applies
+#[tauri::command]
+fn set_cpu_schedule(
+    name: String,
+    schedule: Vec<cpu_schedule::ThresholdPeriod>,
+) -> Result<Vec<cpu_schedule::ThresholdPeriod>, String> {
+    lock::require_unlocked()?;
+    cpu_schedule::validate(&schedule)?;
+    with_state(|state| {
+        for entry in state.blacklist.iter_mut() {
+            if entry.name.to_lowercase() == name.to_lowercase() {
+                entry.cpu_schedule = schedule.clone();
+                return Ok(schedule);
+            }
+        }
+        Err("Not found in blacklist".into())
+    })
+}
+
+#[tauri::command]
+fn get_environment() -> environment::Environment {
+    environment::current()
+}
+
+/// Task Manager style efficiency mode (EcoQoS throttling and idle priority) for one process
+#[tauri::command]
+fn set_efficiency_mode(pid: u32, enabled: bool) -> Result<String, String> {
+    lock::require_unlocked()?;
+    process_ctl::set_efficiency_mode(pid, enabled)?;
+    Ok(format!(
+        "Efficiency mode {} for PID {}",
+        if enabled { "on" } else { "off" },
+        pid
+    ))
+}
+
+#[tauri::command]
+fn dismiss_fullscreen_warning() -> Result<(), String> {
+    system_actions::dismiss_warning()
+}
+
+/// Whether the previous session crashed and what enforcement it had in flight
+#[tauri::command]
+fn get_last_shutdown_info() -> Option<runtime::ShutdownInfo> {
+    runtime::last_shutdown()
+}
+
+#[tauri::command]
+fn list_pending_kills() -> Vec<confirm::PendingKill> {
+    confirm::list()
+}
+
+/// Answer a `confirm-kill-request`. Approving kills the process and lets the rule run unattended;
+/// declining turns auto-kill off for the rule.
+#[tauri::command]
+fn confirm_pending_kill(id: u32, approve: bool) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let request = confirm::take(id).ok_or("No pending kill with that id")?;
+
+    if !approve {
+        return with_state(|state| {
+            match state
+                .blacklist
+                .iter_mut()
+                .find(|e| e.name.to_lowercase() == request.rule.to_lowercase())
+            {
+                Some(entry) => {
+                    entry.auto_kill = false;
+                    Ok(format!(
+                        "Kill declined, auto-kill turned off for {}",
+                        entry.name
+                    ))
+                }
+                None => Err("Not found in blacklist".into()),
+            }
+        });
+    }
+
+    // The process may have exited while waiting; the rule is confirmed either way
+    let result = kill_pid_with(
+        &SysinfoBackend,
+        request.pid,
+        Some(request.start_time),
+        protection::override_enabled(),
+    );
+    let [cpu, gpu] = i18n::usage(request.cpu_usage, request.gpu_usage);
+    let reason = i18n::t(
+        "reason.killed_confirmed",
+        &[cpu, gpu, ("rule", request.rule.clone())],
+    );
+    with_state(|state| {
+        if let Some(entry) = state
+            .blacklist
+            .iter_mut()
+            .find(|e| e.name.to_lowercase() == request.rule.to_lowercase())
+        {
+            entry.confirmed = true;
+            if result.is_ok() {
+                entry.kill_count += 1;
+            }
+        }
+        if result.is_ok() {
+            record_activity(
+                state,
+                ActivityLog {
+                    name: request.name.clone(),
+                    pid: request.pid,
+                    cpu_usage: request.cpu_usage,
+                    gpu_usage: request.gpu_usage,
+                    detected_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                    was_killed: true,
+                    reason,
+                    ..Default::default()
+                },
+            );
+        }
+    });
+
+    Ok(match result {
+        Ok(msg) => format!("Rule {} confirmed. {}", request.rule, msg),
+        Err(err) => format!("Rule {} confirmed ({})", request.rule, err),
+    })
+}
+
+// ============= Scheduler Commands =============
+
+#[tauri::command]
+fn create_schedule(
+    label: String,
+    action: scheduler::ScheduleAction,
+    trigger: scheduler::ScheduleTrigger,
+) -> Result<scheduler::Schedule, String> {
+    lock::require_unlocked()?;
+    scheduler::validate_trigger(&trigger)?;
+
+    with_state(|state| {
+        let id = state.schedules.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+        let schedule = scheduler::Schedule {
+            id,
+            label: label.trim().to_string(),
+            action,
+            trigger,
+            created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            last_run: None,
+            last_result: None,
+        };
+        state.schedules.push(schedule.clone());
+        Ok(schedule)
+    })
+}
+
+#[tauri::command]
+fn list_schedules() -> Vec<scheduler::Schedule> {
+    read_state(|state| state.schedules.clone())
+}
+
+#[tauri::command]
+fn delete_schedule(id: u32) -> Result<String, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        let len_before = state.schedules.len();
+        state.schedules.retain(|s| s.id != id);
+        if state.schedules.len() < len_before {
+            Ok(format!("Schedule {} deleted", id))
+        } else {
+            Err("Schedule not found".into())
+        }
+    })
+}
+
+// ============= Rule Template Commands =============
+
+#[tauri::command]
+fn list_rule_templates() -> Vec<templates::RuleTemplate> {
+    templates::list_templates()
+}
+
+/// Create the blacklist entries of a bundled template (existing entries are kept as-is)
+#[tauri::command]
+fn apply_rule_template(id: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let template = templates::find_template(&id).ok_or("Template not found")?;
+
+    with_state(|state| {
+        let mut added = 0;
+        let mut skipped = 0;
+
+        for rule in &template.rules {
+            let name = resolve_process_name(&rule.name);
+            if name.is_empty()
+                || state
+                    .blacklist
+                    .iter()
+                    .any(|e| e.name.to_lowercase() == name.to_lowercase())
+            {
+                skipped += 1;
+                continue;
+            }
+
+            let mut entry =
+                BlacklistEntry::new(name, rule.auto_kill, rule.cpu_threshold.clamp(0.0, 101.0));
+            entry.gpu_threshold = rule.gpu_threshold.clamp(0.0, 101.0);
+            state.blacklist.push(entry);
+            added += 1;
+        }
+
+        Ok(format!(
+            "{}: added {} rules, {} already present",
+            template.title, added, skipped
+        ))
+    })
+}
+
+#[tauri::command]
+fn get_activity_logs() -> Vec<ActivityLog> {
+    with_state(|state| {
+        // Return last 100 logs, newest first
+        let mut logs = state.activity_logs.clone();
+        logs.reverse();
+        logs.truncate(100);
+        logs
+    })
+}
+
+#[derive(Serialize)]
+pub struct ActivityLogPage {
+    pub total: usize, // Matches before limit/offset
+    pub logs: Vec<ActivityLog>,
+}
+
+/// Query the activity log, newest first. Dates are "YYYY-MM-DD" (or with a time) and inclusive.
+#[tauri::command]
+fn filter_activity_logs(
+    name: Option<String>,
+    killed_only: Option<bool>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> ActivityLogPage {
+    let name = name
+        .map(|n| n.trim().to_lowercase())
+        .filter(|n| !n.is_empty());
+    let killed_only = killed_only.unwrap_or(false);
+
+    read_state(|state| {
+        let matches: Vec<&ActivityLog> = state
+            .activity_logs
+            .iter()
+            .rev()
+            .filter(|log| {
+                name.as_ref()
+                    .is_none_or(|n| log.name.to_lowercase().contains(n))
+            })
+            .filter(|log| !killed_only || log.was_killed)
+            .filter(|log| {
+                date_from
+                    .as_ref()
+                    .is_none_or(|from| log.detected_at.as_str() >= from.as_str())
+            })
+            .filter(|log| {
+                // Compare only as much of the timestamp as was given so a bare date covers the whole day
+                date_to.as_ref().is_none_or(|to| {
+                    let len = to.len().min(log.detected_at.len());
+                    &log.detected_at[..len] <= to.as_str()
+                })
+            })
+            .collect();
+
+        ActivityLogPage {
+            total: matches.len(),
+            logs: matches
+                .into_iter()
+                .skip(offset.unwrap_or(0))
+                .take(limit.unwrap_or(100))
+                .cloned()
+                .collect(),
+        }
+    })
+}
+
+#[tauri::command]
+fn clear_activity_logs() -> Result<String, String> {
+    lock::require_unlocked()?;
+    Ok(with_state(|state| {
+        state.activity_logs.clear();
+        "Logs cleared".to_string()
+    }))
+}
+
+#[tauri::command]
+async fn check_and_kill_blacklist() -> Result<Vec<ActivityLog>, String> {
+    lock::require_unlocked()?;
+    run_blocking("check_and_kill_blacklist", run_blacklist_check).await
+}
+
+fn run_blacklist_check() -> Vec<ActivityLog> {
+    // Only processes some rule can match need fresh stats
+    let rules: Vec<matcher::Matcher> = read_state(|state| {
+        state
+            .blacklist
+            .iter()
+            .map(matcher::Matcher::for_rule)
+            .collect()
+    });
+    let (mut logs, kills) = sampler::with_processes(
+        |p| {
+            let name = p.name().to_string_lossy();
+            rules.iter().any(|m| m.matches(&name))
+        },
+        check_sampled_blacklist,
+    );
+    // Killing can wait seconds per process; other commands shouldn't queue behind it
+    logs.extend(carry_out_kills(kills));
+    logs
+}
+
+/// A kill the watcher decided on, carried out once the state and sampler locks are released
+struct PlannedKill {
+    rule: String,
+    pid: u32,
+    start_time: u64,
+    reason: String,      // Why it is killed, before launchers and dumps are added
+    launchers: Vec<u32>, // Nearest first
+    dump: bool,          // Stack dump first (rule's dump_before_kill)
+    log_enabled: bool,   // Rule's log_enabled; kills are logged even with log_kills_only
+    log: ActivityLog,    // Filled in from the sample, completed with the outcome
+}
+
+fn carry_out_kills(kills: Vec<PlannedKill>) -> Vec<ActivityLog> {
+    if kills.is_empty() {
+        return vec![];
+    }
+    let escalation = read_state(|state| state.settings.kill_escalation.clone());
+    let respawn_window = read_state(|state| state.settings.respawn_window_secs);
+    let session_scope = read_state(|state| state.settings.session_scope.clone());
+    let toasts_enabled =
+        read_state(|state| state.settings.kill_notifications) && !quiet_hours::is_active();
+    // Fresh handles with exe, command line and cwd, for the kill and the restart entry
+    let pids: Vec<sysinfo::Pid> = kills
+        .iter()
+        .map(|k| sysinfo::Pid::from_u32(k.pid))
+        .collect();
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::Some(&pids),
+        true,
+        sysinfo::ProcessRefreshKind::everything(),
+    );
+
+    let mut logs: Vec<(bool, ActivityLog)> = vec![];
+    // (rule, process name, pid, cpu, gpu) of each kill, for counts and on-kill scripts
+    let mut killed: Vec<(String, String, u32, f32, f32)> = vec![];
+    let mut kill_toasts: Vec<notifications::KilledApp> = vec![];
+    for kill in kills {
+        let Some(p) = sys
+            .process(sysinfo::Pid::from_u32(kill.pid))
+            .filter(|p| p.start_time() == kill.start_time)
+        else {
+            tracing::debug!(rule = %kill.rule, pid = kill.pid, "exited before the kill");
+            continue;
+        };
+        let pname = p.name().to_string_lossy().to_string();
+        let dump = kill
+            .dump
+            .then(|| dumps::dump_before_kill(kill.pid, &pname))
+            .and_then(Result::ok);
+        // Launchers go first so none of them can start it again
+        let launchers = launcher::kill_chain(&kill.launchers, &escalation, &session_scope);
+        // Only counts once the PID has really exited
+        let was_killed = match process_ctl::terminate(p, &escalation) {
+            Ok(()) => true,
+            // Gone on its own since the refresh: nothing failed and nothing to retry
+            Err(KillErrorKind::NotFound) => {
+                tracing::debug!(rule = %kill.rule, pid = kill.pid, "exited before the kill");
+                continue;
+            }
+            Err(_) => false,
+        };
+        let attempt = retry::record(kill.pid, kill.start_time, was_killed, &kill.log.detected_at);
+        let reason = if was_killed {
+            tracing::info!(rule = %kill.rule, process = %pname, pid = kill.pid, "killed");
+            killed.push((
+                kill.rule.clone(),
+                pname.clone(),
+                kill.pid,
+                kill.log.cpu_usage,
+                kill.log.gpu_usage,
+            ));
+            respawn::watch(&kill.rule, p, &kill.log.detected_at, respawn_window);
+            let restart_id = launch::remember_kill(p);
+            if toasts_enabled {
+                kill_toasts.push(notifications::KilledApp {
+                    rule: kill.rule.clone(),
+                    name: pname.clone(),
+                    restart_id,
+                });
+            }
+            let reason = if launchers.is_empty() {
+                kill.reason
+            } else {
+                i18n::t(
+                    "reason.with_launchers",
+                    &[("reason", kill.reason), ("launchers", launchers.join(", "))],
+                )
+            };
+            match dump {
+                Some(path) => i18n::t(
+                    "reason.with_dump",
+                    &[
+                        ("reason", reason),
+                        ("path", path.to_string_lossy().to_string()),
+                    ],
+                ),
+                None => reason,
+            }
+        } else {
+            tracing::warn!(
+                rule = %kill.rule,
+                pid = kill.pid,
+                attempt = attempt.number,
+                "kill failed"
+            );
+            let [cpu, gpu] = i18n::usage(kill.log.cpu_usage, kill.log.gpu_usage);
+            let failed = i18n::t("reason.kill_failed", &[cpu, gpu]);
+            match attempt.next_retry_secs {
+                Some(secs) => i18n::t(
+                    "reason.kill_retry",
+                    &[
+                        ("reason", failed),
+                        ("seconds", secs.to_string()),
+                        ("attempt", attempt.number.to_string()),
+                        ("max", retry::MAX_ATTEMPTS.to_string()),
+                    ],
+                ),
+                None => i18n::t(
+                    "reason.kill_gave_up",
+                    &[("reason", failed), ("attempts", attempt.number.to_string())],
+                ),
+            }
+        };
+        logs.push((
+            kill.log_enabled,
+            ActivityLog {
+                was_killed,
+                reason,
+                freed_memory_kb: if was_killed { kill.log.memory_kb } else { 0 },
+                retry_history: attempt.history,
+                ..kill.log
+            },
+        ));
+    }
+
+    let (scripts, new_logs) = with_state(|state| {
+        let mut scripts = vec![];
+        for (rule, name, pid, cpu, gpu) in killed {
+            if let Some(entry) = state.blacklist.iter_mut().find(|e| e.name == rule) {
+                entry.kill_count += 1;
+                if let Some(script) = &entry.on_kill_script {
+                    scripts.push((script.clone(), name, pid, cpu, gpu));
+                }
+            }
+        }
+        let mut new_logs = vec![];
+        for (log_enabled, log) in logs {
+            if log_enabled {
+                new_logs.push(log.clone());
+                record_activity(state, log);
+            } else if log.was_killed && state.settings.event_log {
+                // The Event Log gets every auto-kill, even from rules with logging off
+                event_log::report_kill(&log);
+            }
+        }
+        (scripts, new_logs)
+    });
+    for (script, name, pid, cpu, gpu) in scripts {
+        run_on_kill_script(&script, &name, pid, cpu, gpu);
+    }
+    notifications::kill_toast(&kill_toasts);
+    new_logs
+}
+
+#[tracing::instrument(level = "trace", skip_all)]
+fn check_sampled_blacklist(sys: &System, cpu_count: f32) -> (Vec<ActivityLog>, Vec<PlannedKill>) {
+    // Fetch GPU usage
+    let gpu_usages = metrics::process_gpu_usages();
+    let users = sysinfo::Users::new_with_refreshed_list();
+
+    // Keep the focus app boosted and its background hogs held back
+    focus::enforce(sys);
+    // Keep new instances of network-blocked executables inside their block
+    firewall::enforce(sys);
+
+    // Quiet hours can downgrade every kill to detect-only
+    let detect_only = quiet_hours::detect_only_now();
+    // So can a maintenance window, for all rules or all but a chosen few
+    let maintenance = maintenance::active_now();
+    // Started with --dry-run: report what would be killed, never act
+    let dry_run = overrides::dry_run();
+    let allow_protected = protection::override_enabled();
+    // Never match ourselves or our WebView2 hosts ("edge" would otherwise catch msedgewebview2)
+    let own_pids = protection::own_pids(sys);
+
+    let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let time_of_day = Local::now().time();
+
+    let mut new_logs: Vec<ActivityLog> = vec![];
+    let mut planned_kills: Vec<PlannedKill> = vec![];
+    let mut confirm_requests: Vec<confirm::PendingKill> = vec![];
+    // Lock/warning actions, run once the state lock is released
+    let mut rule_actions: Vec<(RuleAction, String, String)> = vec![];
+    let is_running = |pid: u32, start_time: u64| {
+        sys.process(sysinfo::Pid::from_u32(pid))
+            .is_some_and(|p| p.start_time() == start_time)
+    };
+    retry::forget_exited(is_running);
+    system_actions::forget_exited(is_running);
+    // On a terminal server, leave other users' sessions alone
+    let session_scope = read_state(|state| state.settings.session_scope.clone());
+    let respawn_window = read_state(|state| state.settings.respawn_window_secs);
+    let respawns = respawn::check(sys, respawn_window);
+    let loop_limit = read_state(|state| state.settings.respawn_loop_limit);
+    let respawn_loops = respawn::detect_loops(sys, &respawns, loop_limit);
+    // Environment checks can be slow, so only look when some rule depends on them
+    let environment = read_state(|state| {
+        state
+            .blacklist
+            .iter()
+            .any(|rule| !rule.conditions.is_empty())
+    })
+    .then(environment::current);
+    let audio_pids = read_state(|state| state.blacklist.iter().any(|r| r.skip_if_playing_audio))
+        .then(audio::playing_pids)
+        .unwrap_or_default();
+    // Only processes a camera/microphone-aware rule matches are inspected
+    let capture_rules: Vec<matcher::Matcher> = read_state(|state| {
+        state
+            .blacklist
+            .iter()
+            .filter(|r| r.skip_if_capturing)
+            .map(matcher::Matcher::for_rule)
+            .collect()
+    });
+    let capture_usage = if capture_rules.is_empty() {
+        Default::default()
+    } else {
+        capture::usage(sys, |p| {
+            let name = p.name().to_string_lossy();
+            capture_rules.iter().any(|m| m.matches(&name))
+        })
+    };
+
+    with_state(|state| {
+        for r in &respawns {
+            if let Some(log) =
+                state.activity_logs.iter_mut().rev().find(|log| {
+                    log.was_killed && log.pid == r.pid && log.detected_at == r.killed_at
+                })
+            {
+                log.respawned = true;
+            }
+        }
+
+        let rules: Vec<BlacklistEntry> = state.blacklist.clone();
+        // Group-scoped rules compare the sum across every matching instance
+        let groups: Vec<Option<ProcessGroup>> = rules
+            .iter()
+            .map(|rule| {
+                (rule.scope == RuleScope::Group).then(|| {
+                    rule_group(sys, rule, cpu_count, &gpu_usages, &own_pids, &session_scope)
+                })
+            })
+            .collect();
+
+        for (pid, p) in sys.processes().iter() {
+            if !session_scope.allows(sessions::session_of(pid.as_u32())) {
+                continue;
+            }
+            let pname = p.name().to_string_lossy();
+            let process_cpu = p.cpu_usage() / cpu_count; // Normalized CPU
+            let process_gpu = *gpu_usages.get(&pid.as_u32()).unwrap_or(&0.0);
+
+            for (rule, group) in rules.iter().zip(&groups) {
+                let matcher = matcher::Matcher::for_rule(rule);
+                if matcher.matches(&pname) && own_pids.contains(&pid.as_u32()) {
+                    if protection::should_warn_self(pid.as_u32()) {
+                        record_activity(
+                            state,
+                            ActivityLog {
+                                reason: i18n::t(
+                                    "reason.self_match",
+                                    &[("rule", rule.name.clone())],
+                                ),
+                                ..process_log(sys, &users, p)
+                            },
+                        );
+                    }
+                    break;
+                }
+                if matcher.matches(&pname) {
+                    if let Some(entry) = state.blacklist.iter_mut().find(|e| e.name == rule.name) {
+                        entry.last_matched = Some(now.clone());
+                    }
+                    let (cpu_value, gpu_value, memory_kb) = match group {
+                        Some(g) => (g.total_cpu, g.total_gpu, g.total_memory_kb),
+                        None => (process_cpu, process_gpu, p.memory() / 1024),
+                    };
+                    // Check if CPU exceeds threshold, as scheduled for now
+                    let cpu_threshold = cpu_schedule::threshold_at(
+                        &rule.cpu_schedule,
+                        rule.cpu_threshold,
+                        time_of_day,
+                    );
+                    let check_cpu = rule.cpu_triggered(cpu_threshold, cpu_value);
+                    // Check if GPU exceeds threshold (default 101.0 = disabled)
+                    let check_gpu = rule.gpu_threshold <= 100.0 && gpu_value >= rule.gpu_threshold;
+                    // Check if memory exceeds threshold (0 = disabled)
+                    let check_memory = rule.memory_threshold_mb > 0
+                        && memory_kb / 1024 >= rule.memory_threshold_mb;
+                    // Check usage against the process's learned baseline (None = not set)
+                    let anomaly = rule.anomaly_trigger.as_ref().and_then(|trigger| {
+                        baseline::check(
+                            trigger,
+                            &pname,
+                            process_cpu,
+                            p.memory() as f32 / (1024.0 * 1024.0),
+                        )
+                    });
+
+                    // A rule script replaces the built-in threshold checks
+                    let script_result = rule.rule_script.as_ref().map(|script| {
+                        let ctx = scripting::ProcessContext {
+                            name: p.name().to_string_lossy().to_string(),
+                            pid: pid.as_u32(),
+                            cpu: process_cpu,
+                            gpu: process_gpu,
+                            memory_kb: p.memory() / 1024,
+                            cmdline: p
+                                .cmd()
+                                .iter()
+                                .map(|a| a.to_string_lossy())
+                                .collect::<Vec<_>>()
+                                .join(" "),
+                        };
+                        scripting::should_kill(script, &ctx)
+                    });
+
+                    let snoozed = rule
+                        .snoozed_until
+                        .as_ref()
+                        .filter(|until| until.as_str() > now.as_str());
+                    // Blocked programs go on sight, whatever the thresholds say
+                    let blocks_launch = is_launch_block(rule);
+                    let triggered = blocks_launch
+                        || match &script_result {
+                            Some(result) => *result == Ok(true),
+                            None => check_cpu || check_gpu || check_memory || anomaly.is_some(),
+                        };
+                    let conditions_met = environment
+                        .as_ref()
+                        .is_none_or(|env| rule.conditions.met(env));
+                    let maintenance_window = maintenance
+                        .as_ref()
+                        .filter(|w| w.pauses(&rule.name))
+                        .map(|w| w.name.clone());
+                    let should_kill = rule_may_act(rule, &now, detect_only, maintenance.as_ref())
+                        && conditions_met
+                        && triggered;
+
+                    // Rules flagged for confirmation hold their first kill until the user approves
+                    let awaiting_confirmation =
+                        should_kill && rule.require_confirmation_first_time && !rule.confirmed;
+                    let protected = if should_kill {
+                        protection::check(p, allow_protected).err()
+                    } else {
+                        None
+                    };
+                    // The user rated this executable "never touch" in the process notes
+                    let never_touch = should_kill && {
+                        let exe = p.exe().map(|e| e.to_string_lossy());
+                        notes::guard(&state.process_notes, exe.as_deref()).is_err()
+                    };
+                    let playing_audio = should_kill
+                        && rule.skip_if_playing_audio
+                        && audio_pids.contains(&pid.as_u32());
+                    let capturing = should_kill
+                        && rule.skip_if_capturing
+                        && capture_usage.contains_key(&pid.as_u32());
+                    // An app caught in a respawn loop is left alone until its launcher is dealt with
+                    let respawn_loop =
+                        should_kill && !blocks_launch && respawn::is_looping(&rule.name, &pname);
+                    let should_kill = should_kill
+                        && !respawn_loop
+                        && !awaiting_confirmation
+                        && protected.is_none()
+                        && !never_touch
+                        && !playing_audio
+                        && !capturing;
+                    // A failed kill backs off instead of being retried on every poll
+                    if should_kill && !retry::is_due(pid.as_u32(), p.start_time()) {
+                        tracing::debug!(rule = %rule.name, pid = pid.as_u32(), "retry not due yet");
+                        break;
+                    }
+                    // Lock/warning rules act at most once per cooldown and efficiency mode once
+                    // per process; either way the process keeps running
+                    let takes_action = should_kill
+                        && !matches!(
+                            rule.action,
+                            RuleAction::Kill | RuleAction::BlockLaunch | RuleAction::KillLauncher
+                        );
+                    let claimed = takes_action
+                        && match rule.action {
+                            RuleAction::Efficiency => {
+                                system_actions::claim_process(pid.as_u32(), p.start_time())
+                            }
+                            _ => system_actions::claim(&rule.name),
+                        };
+                    if takes_action && !claimed {
+                        tracing::debug!(rule = %rule.name, pid = pid.as_u32(), "action on cooldown");
+                        break;
+                    }
+                    let reason = if takes_action && rule.action == RuleAction::Efficiency {
+                        match process_ctl::set_efficiency_mode(pid.as_u32(), true) {
+                            Ok(()) => {
+                                let [cpu, gpu] = i18n::usage(process_cpu, process_gpu);
+                                i18n::t(
+                                    "reason.action_efficiency",
+                                    &[cpu, gpu, ("rule", rule.name.clone())],
+                                )
+                            }
+                            Err(err) => err,
+                        }
+                    } else if takes_action {
+                        rule_actions.push((rule.action, rule.name.clone(), pname.to_string()));
+                        let id = match rule.action {
+                            RuleAction::LockWorkstation => "reason.action_locked",
+                            _ => "reason.action_warned",
+                        };
+                        let [cpu, gpu] = i18n::usage(process_cpu, process_gpu);
+                        i18n::t(id, &[cpu, gpu, ("rule", rule.name.clone())])
+                    } else if should_kill {
+                        // Killed once the state and sampler locks are released, see carry_out_kills
+                        let values = i18n::usage(cpu_value, gpu_value);
+                        let reason_str = if blocks_launch {
+                            i18n::t("reason.launch_blocked", &[("rule", rule.name.clone())])
+                        } else if script_result.is_some() {
+                            i18n::t(
+                                "reason.killed_script",
+                                &i18n::usage(process_cpu, process_gpu),
+                            )
+                        } else if let Some(a) = anomaly.as_ref().filter(|_| !check_cpu) {
+                            i18n::t(
+                                "reason.killed_anomaly",
+                                &[
+                                    ("metric", a.metric.clone()),
+                                    ("ratio", format!("{:.1}", a.ratio)),
+                                ],
+                            )
+                        } else if check_cpu && check_gpu {
+                            i18n::t("reason.killed_cpu_gpu", &values)
+                        } else if check_gpu {
+                            i18n::t("reason.killed_gpu", &values)
+                        } else if check_memory && !check_cpu {
+                            i18n::t(
+                                "reason.killed_memory",
+                                &[("memory", (memory_kb / 1024).to_string())],
+                            )
+                        } else {
+                            i18n::t("reason.killed", &values)
+                        };
+                        let reason_str = match group {
+                            Some(g) => i18n::t(
+                                "reason.group_total",
+                                &[
+                                    ("reason", reason_str),
+                                    ("count", g.process_count.to_string()),
+                                ],
+                            ),
+                            None => reason_str,
+                        };
+                        let launchers = if rule.action == RuleAction::KillLauncher {
+                            launcher::to_kill(
+                                sys,
+                                pid.as_u32(),
+                                rule.launcher_depth,
+                                &own_pids,
+                                allow_protected,
+                                &session_scope,
+                            )
+                        } else {
+                            vec![]
+                        };
+                        planned_kills.push(PlannedKill {
+                            rule: rule.name.clone(),
+                            pid: pid.as_u32(),
+                            start_time: p.start_time(),
+                            reason: reason_str,
+                            launchers,
+                            dump: rule.dump_before_kill,
+                            log_enabled: rule.log_enabled,
+                            log: ActivityLog {
+                                cpu_usage: process_cpu,
+                                gpu_usage: process_gpu,
+                                detected_at: now.clone(),
+                                ..process_log(sys, &users, p)
+                            },
+                        });
+                        break;
+                    } else if let Some(err) = &protected {
+                        err.to_string()
+                    } else if never_touch {
+                        i18n::t("reason.never_touch", &i18n::usage(process_cpu, process_gpu))
+                    } else if respawn_loop {
+                        i18n::t(
+                            "reason.respawn_loop",
+                            &i18n::usage(process_cpu, process_gpu),
+                        )
+                    } else if capturing {
+                        i18n::t("reason.capturing", &i18n::usage(process_cpu, process_gpu))
+                    } else if playing_audio {
+                        i18n::t(
+                            "reason.playing_audio",
+                            &i18n::usage(process_cpu, process_gpu),
+                        )
+                    } else if awaiting_confirmation {
+                        if let Some(request) = confirm::request(
+                            &rule.name,
+                            &p.name().to_string_lossy(),
+                            (pid.as_u32(), p.start_time()),
+                            process_cpu,
+                            process_gpu,
+                        ) {
+                            confirm_requests.push(request);
+                        }
+                        i18n::t(
+                            "reason.awaiting_confirmation",
+                            &i18n::usage(process_cpu, process_gpu),
+                        )
+                    } else if let (true, Some(until)) = (rule.auto_kill, snoozed) {
+                        i18n::t("reason.snoozed", &[("until", until.clone())])
+                    } else if let (true, Some(window)) = (rule.auto_kill, &maintenance_window) {
+                        let [cpu, gpu] = i18n::usage(process_cpu, process_gpu);
+                        i18n::t(
+                            "reason.maintenance",
+                            &[cpu, gpu, ("window", window.clone())],
+                        )
+                    } else if rule.auto_kill && detect_only {
+                        i18n::t(
+                            "reason.quiet_detect_only",
+                            &i18n::usage(process_cpu, process_gpu),
+                        )
+                    } else if rule.auto_kill && triggered && !conditions_met {
+                        i18n::t(
+                            "reason.conditions_unmet",
+                            &i18n::usage(process_cpu, process_gpu),
+                        )
+                    } else if rule.auto_kill && triggered && dry_run {
+                        i18n::t("reason.dry_run", &i18n::usage(process_cpu, process_gpu))
+                    } else if let Some(Err(err)) = &script_result {
+                        err.clone()
+                    } else if rule.auto_kill {
+                        i18n::t("reason.safe", &i18n::usage(process_cpu, process_gpu))
+                    } else {
+                        i18n::t("reason.detected", &[])
+                    };
+
+                    // Every decision, with the inputs behind it, for "why wasn't it killed" reports
+                    tracing::debug!(
+                        rule = %rule.name,
+                        process = %pname,
+                        pid = pid.as_u32(),
+                        cpu = cpu_value,
+                        gpu = gpu_value,
+                        memory_mb = memory_kb / 1024,
+                        triggered,
+                        auto_kill = rule.auto_kill,
+                        detect_only,
+                        conditions_met,
+                        reason = %reason,
+                        "rule matched"
+                    );
+
+                    // Kills are logged by carry_out_kills; the rest unless the rule logs kills only
+                    if rule.log_enabled && !rule.log_kills_only {
+                        let log = ActivityLog {
+                            cpu_usage: process_cpu,
+                            gpu_usage: process_gpu,
+                            reason,
+                            detected_at: now.clone(),
+                            ..process_log(sys, &users, p)
+                        };
+                        new_logs.push(log.clone());
+                        record_activity(state, log);
+                    }
+                    break;
+                }
+            }
+        }
+    });
+
+    for request in confirm_requests {
+        emit_event("confirm-kill-request", request);
+    }
+    for r in respawns {
+        notify_event("app-respawned", r);
+    }
+    for l in respawn_loops {
+        notify_event("respawn-loop", l);
+    }
+    for (action, rule, name) in rule_actions {
+        system_actions::run(action, &rule, &name).ok();
+    }
+
+    (new_logs, planned_kills)
+}
+
+/// Run a rule's on-kill script without waiting for it to finish.
+/// Details of the killed process are passed as AKT_* environment variables.
+fn run_on_kill_script(script: &str, name: &str, pid: u32, cpu: f32, gpu: f32) {
+    use std::process::Command;
+
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut c = Command::new("cmd");
+        c.args(["/C", script]);
+        c
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut c = Command::new("sh");
+        c.args(["-c", script]);
+        c
+    };
+
+    cmd.env("AKT_PROCESS_NAME", name)
+        .env("AKT_PID", pid.to_string())
+        .env("AKT_CPU", format!("{:.1}", cpu))
+        .env("AKT_GPU", format!("{:.1}", gpu))
+        .spawn()
+        .ok();
+}
+
+fn resolve_process_name(input: &str) -> String {
+    let s = input.trim().to_lowercase();
+    match s.as_str() {
+        "edge" | "microsoft edge" | "msedge" => "msedge".to_string(),
+        "chrome" | "google chrome" => "chrome".to_string(),
+        "code" | "vscode" | "vs code" => "code".to_string(),
+        "calc" | "calculator" => "calculator".to_string(),
+        "notepad" => "notepad".to_string(),
+        "task manager" | "taskmgr" => "taskmgr".to_string(),
+        "cmd" | "command prompt" => "cmd".to_string(),
+        "powershell" => "powershell".to_string(),
+        // Keep the original case so case-sensitive rules match what was typed
+        _ => input.trim().to_string(),
+    }
+}
+
+// ============= Container Commands =============
+
+#[tauri::command]
+async fn list_containers() -> Result<Vec<containers::ContainerInfo>, String> {
+    run_blocking("list_containers", containers::list_containers).await?
+}
+
+// docker stop waits 10 seconds before killing, and wsl takes a while to bring distros down
+const CONTAINER_STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[tauri::command]
+async fn stop_container(id: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    run_blocking_for("stop_container", CONTAINER_STOP_TIMEOUT, move || {
+        containers::stop_container(&id)
+    })
+    .await?
+}
+
+#[tauri::command]
+async fn list_wsl_distros() -> Result<Vec<containers::WslDistro>, String> {
+    run_blocking("list_wsl_distros", containers::list_wsl_distros).await?
+}
+
+#[tauri::command]
+async fn terminate_wsl_distro(name: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    run_blocking_for("terminate_wsl_distro", CONTAINER_STOP_TIMEOUT, move || {
+        containers::terminate_wsl_distro(&name)
+    })
+    .await?
+}
+
+/// Runs `wsl --shutdown`, which stops every distro, so the caller has to pass `confirm`
+#[tauri::command]
+async fn shrink_wsl_memory(confirm: bool) -> Result<String, String> {
+    lock::require_unlocked()?;
+    run_blocking_for("shrink_wsl_memory", CONTAINER_STOP_TIMEOUT, move || {
+        containers::shrink_wsl_memory(confirm)
+    })
+    .await?
+}
+
+// ============= Remote Host Commands =============
+
+#[tauri::command]
+fn add_remote_host(name: String, address: String, token: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let name = name.trim().to_string();
+    let address = address.trim().trim_end_matches('/').to_string();
+    if name.is_empty() || address.is_empty() {
+        return Err("Name and address are required".into());
+    }
+    if !address.starts_with("http://") && !address.starts_with("https://") {
+        return Err("Address must start with http:// or https://".into());
+    }
+
+    with_state(|state| {
+        if state
+            .remote_hosts
+            .iter()
+            .any(|h| h.name.to_lowercase() == name.to_lowercase())
+        {
+            return Err("Host already registered".into());
+        }
+
+        state.remote_hosts.push(remote::RemoteHost {
+            name: name.clone(),
+            address,
+            token,
+            added_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        });
+        Ok(format!("{} registered", name))
+    })
+}
+
+#[tauri::command]
+fn remove_remote_host(name: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        let len_before = state.remote_hosts.len();
+        state
+            .remote_hosts
+            .retain(|h| h.name.to_lowercase() != name.to_lowercase());
+        if state.remote_hosts.len() < len_before {
+            Ok(format!("{} removed", name))
+        } else {
+            Err("Host not found".into())
+        }
+    })
+}
+
+#[tauri::command]
+fn list_remote_hosts() -> Vec<remote::RemoteHostInfo> {
+    read_state(|state| state.remote_hosts.iter().map(Into::into).collect())
+}
+
+#[tauri::command]
+fn remote_get_system_stats(host: String) -> Result<serde_json::Value, String> {
+    remote::get_stats(&host)
+}
+
+#[tauri::command]
+fn remote_grouped_processes(host: String, names: Vec<String>) -> Result<serde_json::Value, String> {
+    remote::grouped_processes(&host, &names)
+}
+
+#[tauri::command]
+fn remote_get_blacklist(host: String) -> Result<serde_json::Value, String> {
+    remote::get_blacklist(&host)
+}
+
+#[tauri::command]
+fn remote_add_to_blacklist(
+    host: String,
+    name: String,
+    auto_kill: bool,
+    cpu_threshold: f32,
+) -> Result<serde_json::Value, String> {
+    lock::require_unlocked()?;
+    remote::add_to_blacklist(&host, &name, auto_kill, cpu_threshold)
+}
+
+#[tauri::command]
+fn remote_remove_from_blacklist(host: String, name: String) -> Result<serde_json::Value, String> {
+    lock::require_unlocked()?;
+    remote::remove_from_blacklist(&host, &name)
+}
+
+#[tauri::command]
+fn remote_kill_pid(host: String, pid: u32) -> Result<serde_json::Value, String> {
+    lock::require_unlocked()?;
+    remote::kill_pid(&host, pid)
+}
+
+#[tauri::command]
+fn remote_kill_process_group(host: String, name: String) -> Result<serde_json::Value, String> {
+    lock::require_unlocked()?;
+    remote::kill_process_group(&host, &name)
+}
+
+// ============= Quiet Hours Commands =============
+
+#[tauri::command]
+fn get_quiet_hours() -> quiet_hours::QuietHours {
+    read_state(|state| state.settings.quiet_hours.clone())
+}
+
+#[tauri::command]
+fn set_quiet_hours(config: quiet_hours::QuietHours) -> Result<String, String> {
+    lock::require_unlocked()?;
+    config.validate()?;
+    with_state(|state| {
+        state.settings.quiet_hours = config;
+    });
+    Ok("Quiet hours saved".to_string())
+}
+
+#[tauri::command]
+fn is_quiet_hours_active() -> bool {
+    quiet_hours::is_active()
+}
+
+// ============= Config File Commands =============
+
+#[tauri::command]
+fn get_config_file_status() -> config_file::ConfigFileStatus {
+    config_file::status()
+}
+
+/// Write settings and rules to config.toml for editing; edits are picked up while running
+#[tauri::command]
+fn export_config_file() -> Result<String, String> {
+    lock::require_unlocked()?;
+    config_file::export()
+}
+
+// ============= Maintenance Window Commands =============
+
+#[tauri::command]
+fn get_maintenance_windows() -> Vec<maintenance::MaintenanceWindow> {
+    read_state(|state| state.settings.maintenance_windows.clone())
+}
+
+#[tauri::command]
+fn set_maintenance_windows(windows: Vec<maintenance::MaintenanceWindow>) -> Result<String, String> {
+    lock::require_unlocked()?;
+    maintenance::validate_all(&windows)?;
+    with_state(|state| {
+        state.settings.maintenance_windows = windows;
+    });
+    Ok("Maintenance windows saved".to_string())
+}
+
+/// Name of the maintenance window in effect right now
+#[tauri::command]
+fn active_maintenance_window() -> Option<String> {
+    maintenance::active_now().map(|w| w.name)
+}
+
+// ============= Boost Commands =============
+
+/// One-click boost: stop every auto-kill blacklist entry and trim heavy processes
 #[tauri::command]
-fn toggle_log_kills_only(name: String) -> Result<bool, String> {
-    with_state(|state| {
-        for entry in state.blacklist.iter_mut() {
-            if entry.name.to_lowercase() == name.to_lowercase() {
-                entry.log_kills_only = !entry.log_kills_only;
-                return Ok(entry.log_kills_only);
-            }
-        }
-        Err("Not found in blacklist".into())
-    })
+fn boost_now(suspend: Option<bool>) -> Result<boost::BoostSummary, String> {
+    lock::require_unlocked()?;
+    Ok(boost::boost_now(suspend.unwrap_or(false)))
 }
 
 #[tauri::command]
-fn set_cpu_threshold(name: String, threshold: f32) -> Result<f32, String> {
-    with_state(|state| {
-        for entry in state.blacklist.iter_mut() {
-            if entry.name.to_lowercase() == name.to_lowercase() {
-                entry.cpu_threshold = threshold.max(0.0).min(101.0);
-                return Ok(entry.cpu_threshold);
-            }
-        }
-        Err("Not found in blacklist".into())
-    })
+fn end_boost() -> Result<String, String> {
+    lock::require_unlocked()?;
+    Ok(format!("Resumed {} processes", boost::end_boost()))
 }
 
+// ============= Focus Mode Commands =============
+
+/// Boost `name` while it runs; None turns focus mode off
 #[tauri::command]
-fn set_gpu_threshold(name: String, threshold: f32) -> Result<f32, String> {
+fn set_focus_app(name: Option<String>) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let name = name
+        .map(|n| resolve_process_name(&n))
+        .filter(|n| !n.is_empty());
+
+    focus::release();
     with_state(|state| {
-        for entry in state.blacklist.iter_mut() {
-            if entry.name.to_lowercase() == name.to_lowercase() {
-                // 101.0 means disabled
-                entry.gpu_threshold = threshold.max(0.0).min(101.0);
-                return Ok(entry.gpu_threshold);
-            }
-        }
-        Err("Not found in blacklist".into())
+        state.settings.focus.app = name.clone();
+    });
+    Ok(match name {
+        Some(n) => format!("Focus mode on for {}", n),
+        None => "Focus mode off".to_string(),
     })
 }
 
 #[tauri::command]
-fn get_activity_logs() -> Vec<ActivityLog> {
+fn set_background_hogs(names: Vec<String>, action: focus::HogAction) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let hogs: Vec<String> = names
+        .iter()
+        .map(|n| resolve_process_name(n))
+        .filter(|n| !n.is_empty())
+        .collect();
+
+    focus::release();
     with_state(|state| {
-        // Return last 100 logs, newest first
-        let mut logs = state.activity_logs.clone();
-        logs.reverse();
-        logs.truncate(100);
-        logs
-    })
+        state.settings.focus.hogs = hogs.clone();
+        state.settings.focus.hog_action = action;
+    });
+    Ok(format!("{} background hogs saved", hogs.len()))
 }
 
 #[tauri::command]
-fn clear_activity_logs() -> String {
-    with_state(|state| {
-        state.activity_logs.clear();
-        "Logs cleared".to_string()
-    })
+fn get_focus_status() -> focus::FocusStatus {
+    focus::status()
 }
 
+// ============= App Limit Commands =============
+
+/// Allow `name` to run `minutes` per day; `minutes` = 0 removes the limit
 #[tauri::command]
-fn check_and_kill_blacklist() -> Vec<ActivityLog> {
-    let mut sys = System::new_all();
-    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+fn set_app_limit(
+    name: String,
+    minutes: u32,
+    action: app_limits::LimitAction,
+) -> Result<String, String> {
+    lock::require_unlocked()?;
+    let name = resolve_process_name(&name);
+    if name.is_empty() {
+        return Err("Name cannot be empty".into());
+    }
 
-    // Get CPU count for normalization
-    let cpu_count = sys.cpus().len() as f32;
-    let cpu_count = if cpu_count > 0.0 { cpu_count } else { 1.0 };
+    with_state(|state| {
+        state
+            .app_limits
+            .retain(|l| l.name.to_lowercase() != name.to_lowercase());
+        if minutes == 0 {
+            return;
+        }
 
-    // Fetch GPU usage
-    let gpu_usages = gpu::get_gpu_usages();
+        state.app_limits.push(app_limits::AppLimit {
+            name: name.clone(),
+            daily_minutes: minutes,
+            action,
+            created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        });
+    });
+    if minutes == 0 {
+        // Nothing holds its suspended instances any more
+        app_limits::resume_limit(&name);
+        return Ok(format!("Daily limit removed from {}", name));
+    }
+    Ok(format!("{} limited to {} min per day", name, minutes))
+}
 
-    let mut new_logs: Vec<ActivityLog> = vec![];
+#[tauri::command]
+fn get_app_usage_today() -> Vec<app_limits::AppUsageRow> {
+    app_limits::usage_today()
+}
 
-    with_state(|state| {
-        let blacklist_info: Vec<(String, bool, f32, f32, bool, bool)> = state
-            .blacklist
-            .iter()
-            .map(|e| {
-                (
-                    e.name.to_lowercase(),
-                    e.auto_kill,
-                    e.cpu_threshold,
-                    e.gpu_threshold,
-                    e.log_enabled,
-                    e.log_kills_only,
-                )
-            })
-            .collect();
+#[tauri::command]
+fn reset_app_limits() -> Result<String, String> {
+    lock::require_unlocked()?;
+    app_limits::reset_usage();
+    Ok("Today's app usage reset".to_string())
+}
 
-        for (pid, p) in sys.processes().iter() {
-            let pname = p.name().to_string_lossy().to_lowercase();
-            let process_cpu = p.cpu_usage() / cpu_count; // Normalized CPU
-            let process_gpu = *gpu_usages.get(&pid.as_u32()).unwrap_or(&0.0);
+// ============= Lock Commands =============
 
-            for (bl_name, auto_kill, cpu_threshold, gpu_threshold, log_enabled, log_kills_only) in
-                &blacklist_info
-            {
-                if pname.contains(bl_name) || bl_name == &pname {
-                    // Check if CPU exceeds threshold (0 = always kill)
-                    let check_cpu = *cpu_threshold <= 0.0 || process_cpu >= *cpu_threshold;
-                    // Check if GPU exceeds threshold (default 101.0 = disabled)
-                    let check_gpu = *gpu_threshold <= 100.0 && process_gpu >= *gpu_threshold;
-
-                    let should_kill = *auto_kill && (check_cpu || check_gpu);
-
-                    let (was_killed, reason) = if should_kill {
-                        // On Windows, use kill() directly instead of Signal::Term
-                        let killed = p.kill();
-                        if killed {
-                            if let Some(entry) = state
-                                .blacklist
-                                .iter_mut()
-                                .find(|e| e.name.to_lowercase() == *bl_name)
-                            {
-                                entry.kill_count += 1;
-                            }
-                            let reason_str = if check_cpu && check_gpu {
-                                format!(
-                                    "Killed (CPU: {:.1}%, GPU: {:.1}%)",
-                                    process_cpu, process_gpu
-                                )
-                            } else if check_gpu {
-                                format!("Killed (GPU: {:.1}%)", process_gpu)
-                            } else {
-                                format!("Killed (CPU: {:.1}%)", process_cpu)
-                            };
-                            (true, reason_str)
-                        } else {
-                            (
-                                false,
-                                format!(
-                                    "Kill failed - requires Admin (CPU: {:.1}%, GPU: {:.1}%)",
-                                    process_cpu, process_gpu
-                                ),
-                            )
-                        }
-                    } else if *auto_kill {
-                        (
-                            false,
-                            format!("Safe (CPU: {:.1}%, GPU: {:.1}%)", process_cpu, process_gpu),
-                        )
-                    } else {
-                        (false, "Detected".to_string())
-                    };
+#[tauri::command]
+fn set_lock_pin(current_pin: Option<String>, new_pin: Option<String>) -> Result<String, String> {
+    lock::set_pin(current_pin, new_pin)
+}
 
-                    let log = ActivityLog {
-                        name: p.name().to_string_lossy().to_string(),
-                        pid: pid.as_u32(),
-                        cpu_usage: process_cpu,
-                        gpu_usage: process_gpu,
-                        detected_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-                        was_killed,
-                        reason,
-                    };
+#[tauri::command]
+fn unlock_actions(pin: String) -> Result<u64, String> {
+    lock::unlock(&pin)
+}
+
+#[tauri::command]
+fn lock_actions() -> String {
+    lock::lock();
+    "Locked".to_string()
+}
 
-                    // Only log if logging is enabled AND (log_kills_only is false OR was_killed is true)
-                    let should_log = *log_enabled && (!*log_kills_only || was_killed);
+#[tauri::command]
+fn get_lock_status() -> lock::LockStatus {
+    lock::status()
+}
 
-                    if should_log {
-                        new_logs.push(log.clone());
-                        state.activity_logs.push(log);
-                    }
-                    break;
-                }
-            }
-        }
+#[tauri::command]
+fn set_unlock_timeout(minutes: u64) -> Result<u64, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        state.settings.unlock_timeout_secs = minutes.clamp(1, 24 * 60) * 60;
+        Ok(state.settings.unlock_timeout_secs)
+    })
+}
 
-        // Keep only last 1000 logs
-        if state.activity_logs.len() > 1000 {
-            state.activity_logs = state
-                .activity_logs
-                .split_off(state.activity_logs.len() - 1000);
-        }
+#[tauri::command]
+fn set_allow_protected_kills(enabled: bool) -> Result<bool, String> {
+    lock::require_unlocked()?;
+    with_state(|state| {
+        state.settings.allow_protected_kills = enabled;
     });
-
-    new_logs
+    Ok(enabled)
 }
 
-fn resolve_process_name(input: &str) -> String {
-    let s = input.trim().to_lowercase();
-    match s.as_str() {
-        "edge" | "microsoft edge" | "msedge" => "msedge".to_string(),
-        "chrome" | "google chrome" => "chrome".to_string(),
-        "code" | "vscode" | "vs code" => "code".to_string(),
-        "calc" | "calculator" => "calculator".to_string(),
-        "notepad" => "notepad".to_string(),
-        "task manager" | "taskmgr" => "taskmgr".to_string(),
-        "cmd" | "command prompt" => "cmd".to_string(),
-        "powershell" => "powershell".to_string(),
-        _ => s,
-    }
+#[tauri::command]
+fn get_allow_protected_kills() -> bool {
+    protection::override_enabled()
 }
 
 // ============= Admin Check =============
 
+/// Whether per-process GPU usage is measured here; "unsupported" means the UI can hide GPU columns
+#[tauri::command]
+fn gpu_monitor_status(monitor: tauri::State<gpu::SharedGpuMonitor>) -> gpu::GpuMonitorStatus {
+    monitor.lock().unwrap().status()
+}
+
+/// Elevation, GPU counters, killing other users' processes, notifications and autostart,
+/// with a note for each one that is degraded
+#[tauri::command]
+async fn check_capabilities() -> Result<capabilities::Capabilities, String> {
+    run_blocking("check_capabilities", capabilities::check).await
+}
+
 #[tauri::command]
 fn is_running_as_admin() -> bool {
     #[cfg(windows)]
@@ -741,32 +4348,793 @@ fn is_running_as_admin() -> bool {
     }
 }
 
+// ============= Command Line =============
+
+/// Run `--kill <name>`, `--kill-pid <pid>` and `--boost` from the command line. A second launch
+/// forwards its arguments here instead of starting another watcher.
+fn handle_cli_args(args: &[String]) {
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let result = match arg.as_str() {
+            "--kill" => match args.next() {
+                Some(name) => lock::require_unlocked().and_then(|_| kill_processes_by_name(name)),
+                None => Err("--kill needs a process name".into()),
+            },
+            "--kill-pid" => match args.next().and_then(|pid| pid.parse::<u32>().ok()) {
+                Some(pid) => lock::require_unlocked().and_then(|_| kill_process_by_pid(pid)),
+                None => Err("--kill-pid needs a numeric PID".into()),
+            },
+            // Windows ran us instead of a blocked program; the rest is that program's command
+            // line, never our own options
+            launch_block::BLOCKED_FLAG => {
+                if let Some(program) = args.next() {
+                    launch_block::record_attempt(program);
+                }
+                return;
+            }
+            "--boost" => lock::require_unlocked().map(|_| {
+                let summary = boost::boost_now(false);
+                format!(
+                    "Boost: {} killed, {:.0} MB freed",
+                    summary.killed, summary.memory_freed_mb
+                )
+            }),
+            _ => continue,
+        };
+        emit_event("cli-result", result.unwrap_or_else(|err| err));
+    }
+}
+
+/// Startup overrides from the environment and command line, for a "managed" banner
+#[tauri::command]
+fn get_startup_overrides() -> overrides::Overrides {
+    overrides::current()
+}
+
+/// Folder holding the state, logs and config.toml, and whether it's the portable one
+#[tauri::command]
+fn get_data_location() -> storage::DataLocation {
+    storage::location()
+}
+
+/// Move the state, logs and history to `path` (e.g. a synced folder) and keep them there.
+/// The current log file stays where it is until the next start.
+#[tauri::command]
+fn set_data_directory(path: String) -> Result<String, String> {
+    lock::require_unlocked()?;
+    // Deferred writes go to the old folder first, so they're part of the copy
+    flush_state();
+    let moved = with_state(|_| storage::migrate(std::path::Path::new(path.trim())))?;
+    Ok(format!("Data moved to {}", moved.display()))
+}
+
+// ============= Deep Links =============
+
+fn query_value(url: &tauri::Url, key: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.to_string())
+}
+
+// Links that kill, change rules or snooze them, waiting for the user to confirm them in the
+// window. Any web page can open an akt:// link, so these never run straight from the URL.
+static PENDING_LINKS: Mutex<(u32, Vec<(u32, tauri::Url)>)> = Mutex::new((1, Vec::new()));
+
+// Longest snooze a link can ask for
+const MAX_LINK_SNOOZE_MINUTES: u32 = 24 * 60;
+
+/// Payload of "deep-link-confirm"
+#[derive(Serialize, Clone)]
+struct DeepLinkRequest {
+    id: u32,
+    url: String,
+}
+
+fn needs_confirmation(url: &tauri::Url) -> bool {
+    matches!(
+        (
+            url.host_str().unwrap_or_default(),
+            url.path().trim_matches('/')
+        ),
+        ("kill", _) | ("blacklist", _) | ("boost", _) | ("whitelist", _) | ("snooze", _)
+    )
+}
+
+/// Handle an opened `akt://` link. Restarting a recent kill by id and buttons of our own
+/// toasts (carrying an unused token) run right away; anything else, including whitelist and
+/// snooze, is held and "deep-link-confirm" asks the window first.
+fn handle_deep_link(url: &tauri::Url) {
+    let from_toast = query_value(url, "token").is_some_and(|token| notifications::redeem(&token));
+    if from_toast || !needs_confirmation(url) {
+        run_deep_link(url);
+        return;
+    }
+    let id = {
+        let mut pending = PENDING_LINKS.lock().unwrap();
+        let id = pending.0;
+        pending.0 += 1;
+        pending.1.push((id, url.clone()));
+        id
+    };
+    if let Some(window) = APP_HANDLE
+        .get()
+        .and_then(|app| app.get_webview_window("main"))
+    {
+        window.unminimize().ok();
+        window.show().ok();
+        window.set_focus().ok();
+    }
+    emit_event(
+        "deep-link-confirm",
+        DeepLinkRequest {
+            id,
+            url: url.to_string(),
+        },
+    );
+}
+
+/// Answer a "deep-link-confirm"; the link runs only when approved
+#[tauri::command]
+fn confirm_deep_link(id: u32, approve: bool) -> Result<(), String> {
+    let url = {
+        let mut pending = PENDING_LINKS.lock().unwrap();
+        let idx = pending
+            .1
+            .iter()
+            .position(|(i, _)| *i == id)
+            .ok_or("No pending link with that id")?;
+        pending.1.remove(idx).1
+    };
+    if approve {
+        run_deep_link(&url);
+    }
+    Ok(())
+}
+
+/// Run an `akt://` link, e.g. `akt://kill?name=chrome` or `akt://blacklist/add?name=node&cpu=50`
+fn run_deep_link(url: &tauri::Url) {
+    let host = url.host_str().unwrap_or_default();
+    let path = url.path().trim_matches('/');
+    let name = query_value(url, "name");
+
+    let result = lock::require_unlocked().and_then(|_| match (host, path) {
+        ("kill", "") => match (name, query_value(url, "pid")) {
+            (Some(name), _) => kill_processes_by_name(&name),
+            (None, Some(pid)) => match pid.parse::<u32>() {
+                Ok(pid) => kill_process_by_pid(pid),
+                Err(_) => Err("pid must be a number".into()),
+            },
+            (None, None) => Err("kill needs a name or pid".into()),
+        },
+        ("blacklist", "add") => {
+            let name = name.ok_or("blacklist/add needs a name")?;
+            let cpu = query_value(url, "cpu")
+                .and_then(|c| c.parse::<f32>().ok())
+                .unwrap_or(0.0)
+                .clamp(0.0, 100.0);
+            let auto_kill = query_value(url, "auto").is_none_or(|a| a != "false" && a != "0");
+            insert_blacklist_entry(&name, auto_kill, cpu)
+        }
+        ("blacklist", "remove") => {
+            delete_blacklist_entry(&name.ok_or("blacklist/remove needs a name")?)
+        }
+        // Ids of recent kills, comma-separated for a batch
+        ("restart", "") => query_value(url, "ids")
+            .ok_or("restart needs ids")?
+            .split(',')
+            .map(|id| {
+                id.trim()
+                    .parse::<u64>()
+                    .map_err(|_| "ids must be numbers".to_string())
+                    .and_then(launch::restart)
+            })
+            .collect::<Result<Vec<String>, String>>()
+            .map(|started| started.join("; ")),
+        ("whitelist", "") => whitelist_in_rule(
+            &query_value(url, "rule").ok_or("whitelist needs a rule")?,
+            &name.ok_or("whitelist needs a name")?,
+        ),
+        ("snooze", "") => snooze_blacklist_entry(
+            &query_value(url, "rule").ok_or("snooze needs a rule")?,
+            query_value(url, "minutes")
+                .and_then(|m| m.parse::<u32>().ok())
+                .unwrap_or(60)
+                .min(MAX_LINK_SNOOZE_MINUTES),
+        ),
+        ("boost", "") => {
+            let summary = boost::boost_now(false);
+            Ok(format!(
+                "Boost: {} killed, {:.0} MB freed",
+                summary.killed, summary.memory_freed_mb
+            ))
+        }
+        _ => Err(format!("Unknown link: {}", url)),
+    });
+    emit_event("deep-link-result", result.unwrap_or_else(|err| err));
+}
+
 // ============= App Entry =============
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Before anything loads the state, since --data-dir moves it
+    let args = match overrides::init(&std::env::args().collect::<Vec<_>>()) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    if args.iter().any(|arg| arg == launch_block::LIFT_FLAG) {
+        launch_block::lift_all();
+        return;
+    }
+    // Read by WebView2 when the first window is created; covers the mini monitor too
+    #[cfg(windows)]
+    if let Some(dir) = storage::webview_data_dir() {
+        std::env::set_var("WEBVIEW2_USER_DATA_FOLDER", dir);
+    }
+    let handler: fn(tauri::ipc::Invoke) -> bool = tauri::generate_handler![
+        get_watchlist,
+        add_watch,
+        remove_watch,
+        watched_processes,
+        get_pinned_processes,
+        pin_process,
+        unpin_process,
+        get_process_notes,
+        set_process_note,
+        lookup_process_info,
+        take_snapshot,
+        list_snapshots,
+        diff_snapshots,
+        get_boot_impact,
+        needs_onboarding,
+        run_first_time_scan,
+        finish_onboarding,
+        grouped_processes,
+        get_all_process_list,
+        get_group_details,
+        find_process_by_port,
+        kill_by_port,
+        get_process_connections,
+        get_power_requests,
+        ignore_power_request,
+        block_process_network,
+        unblock_process_network,
+        list_network_blocks,
+        kill_pid,
+        request_close,
+        kill_foreground,
+        kill_process_group,
+        kill_pids,
+        get_system_stats,
+        toggle_mini_monitor,
+        list_metric_providers,
+        format_bytes,
+        format_duration,
+        get_unit_settings,
+        set_unit_settings,
+        get_blacklist,
+        add_to_blacklist,
+        remove_from_blacklist,
+        toggle_auto_kill,
+        toggle_blacklist_log,
+        toggle_log_kills_only,
+        set_cpu_threshold,
+        set_gpu_threshold,
+        set_on_kill_script,
+        set_rule_script,
+        set_exclude_patterns,
+        set_case_sensitive,
+        set_skip_if_playing_audio,
+        get_audio_sessions,
+        set_skip_if_capturing,
+        set_dump_before_kill,
+        dump_process,
+        sample_process,
+        get_process_threads,
+        find_module,
+        analyze_hang,
+        get_av_capture_processes,
+        set_rule_scope,
+        snooze_rule,
+        whitelist_process,
+        validate_rules,
+        get_recent_kills,
+        restart_process,
+        confirm_deep_link,
+        launch_as_user,
+        set_kill_notifications,
+        set_event_log_enabled,
+        get_event_log_enabled,
+        set_syslog_enabled,
+        get_syslog_enabled,
+        set_low_power_mode,
+        get_tray_icon_style,
+        set_tray_icon_style,
+        get_kill_escalation,
+        get_session_info,
+        set_session_scope,
+        set_kill_escalation,
+        get_refresh_intervals,
+        set_refresh_intervals,
+        get_memory_pressure_policy,
+        set_memory_pressure_policy,
+        get_mqtt_settings,
+        set_mqtt_settings,
+        get_mqtt_connected,
+        get_bot_settings,
+        set_bot_settings,
+        test_bot,
+        get_email_digest,
+        set_email_digest,
+        send_email_digest_now,
+        generate_weekly_report,
+        get_sync_settings,
+        set_sync_settings,
+        get_sync_status,
+        sync_now,
+        merge_synced_rules,
+        get_pending_sync_scripts,
+        accept_sync_script,
+        get_cpu_pressure_policy,
+        set_cpu_pressure_policy,
+        get_handle_leak_policy,
+        set_handle_leak_policy,
+        get_anomaly_policy,
+        set_anomaly_policy,
+        get_baselines,
+        reset_baseline,
+        get_launch_watch_status,
+        set_instant_kill,
+        set_rule_cpu_priority,
+        set_respawn_window,
+        set_respawn_loop_limit,
+        get_respawn_loops,
+        resume_respawn_loop,
+        prune_stale_rules,
+        set_stale_rule_days,
+        set_window_hidden,
+        get_power_status,
+        get_self_usage,
+        get_self_budget,
+        set_self_budget,
+        get_language,
+        get_log_level,
+        set_log_level,
+        create_diagnostics_bundle,
+        backup_all,
+        restore_all,
+        set_language,
+        set_require_confirmation,
+        set_rule_action,
+        get_blocked_launches,
+        unblock_launch,
+        set_efficiency_mode,
+        set_rule_conditions,
+        set_cpu_schedule,
+        set_rule_anomaly_trigger,
+        set_rule_launcher_depth,
+        get_environment,
+        dismiss_fullscreen_warning,
+        list_pending_kills,
+        get_last_shutdown_info,
+        confirm_pending_kill,
+        list_rule_templates,
+        apply_rule_template,
+        create_schedule,
+        list_schedules,
+        delete_schedule,
+        list_containers,
+        stop_container,
+        list_wsl_distros,
+        terminate_wsl_distro,
+        shrink_wsl_memory,
+        add_remote_host,
+        remove_remote_host,
+        list_remote_hosts,
+        remote_get_system_stats,
+        remote_grouped_processes,
+        remote_get_blacklist,
+        remote_add_to_blacklist,
+        remote_remove_from_blacklist,
+        remote_kill_pid,
+        remote_kill_process_group,
+        set_lock_pin,
+        unlock_actions,
+        lock_actions,
+        get_lock_status,
+        set_unlock_timeout,
+        set_allow_protected_kills,
+        get_allow_protected_kills,
+        get_quiet_hours,
+        set_quiet_hours,
+        is_quiet_hours_active,
+        get_startup_overrides,
+        get_data_location,
+        set_data_directory,
+        get_config_file_status,
+        export_config_file,
+        get_maintenance_windows,
+        set_maintenance_windows,
+        active_maintenance_window,
+        boost_now,
+        end_boost,
+        set_focus_app,
+        set_background_hogs,
+        get_focus_status,
+        set_app_limit,
+        get_app_usage_today,
+        reset_app_limits,
+        get_activity_logs,
+        filter_activity_logs,
+        clear_activity_logs,
+        check_and_kill_blacklist,
+        is_running_as_admin,
+        check_capabilities,
+        gpu_monitor_status
+    ];
+
     tauri::Builder::default()
+        // Must be registered first so a second launch exits before starting its own watchers
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                window.unminimize().ok();
+                window.show().ok();
+                window.set_focus().ok();
+            }
+            handle_cli_args(&args);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![
-            watched_processes,
-            grouped_processes,
-            get_all_process_list,
-            kill_pid,
-            kill_process_group,
-            get_system_stats,
-            get_blacklist,
-            add_to_blacklist,
-            remove_from_blacklist,
-            toggle_auto_kill,
-            toggle_blacklist_log,
-            toggle_log_kills_only,
-            set_cpu_threshold,
-            set_gpu_threshold,
-            get_activity_logs,
-            clear_activity_logs,
-            check_and_kill_blacklist,
-            is_running_as_admin
+        .setup(move |app| {
+            APP_HANDLE.set(app.handle().clone()).ok();
+            logging::init(&overrides::log_level(&read_state(|state| {
+                state.settings.log_level.clone()
+            })));
+            // Same instances the background tasks use, for commands taking tauri::State
+            app.manage(store().clone());
+            app.manage(gpu::monitor());
+            i18n::set_language(&read_state(|state| state.settings.language.clone())).ok();
+            power::set_enabled(read_state(|state| state.settings.low_power_mode));
+            power::start_monitor();
+            refresher::start(&read_state(|state| {
+                state.settings.refresh_intervals.clone()
+            }));
+            self_usage::start_watchdog();
+            tray::start(app.handle());
+
+            // Installers register the scheme; this covers dev builds. Portable mode leaves the
+            // host's registry and desktop entries alone.
+            #[cfg(any(windows, target_os = "linux"))]
+            if !storage::is_portable() {
+                app.deep_link().register_all().ok();
+            }
+            app.deep_link().on_open_url(|event| {
+                for url in event.urls() {
+                    handle_deep_link(&url);
+                }
+            });
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                for url in urls {
+                    handle_deep_link(&url);
+                }
+            }
+
+            // Before the watcher starts, so restored retries are in place for its first poll
+            runtime::start();
+            mqtt::start();
+            notifier::start();
+            digest::start();
+            config_file::start();
+            sync::start();
+            metrics::register_providers();
+            scheduler::start_executor();
+            app_limits::start_tracker();
+            pressure::start_responder();
+            leaks::start_recorder();
+            baseline::start_recorder();
+            creation::start();
+            launch_block::start();
+            boot_impact::start_recorder();
+            stale_rules::start_checker();
+            handle_cli_args(&args);
+            Ok(())
+        })
+        // Wrapped so the self-usage watchdog can count IPC calls
+        .invoke_handler(move |invoke| {
+            self_usage::record_ipc_call();
+            tracing::trace!(command = invoke.message.command(), "ipc call");
+            handler(invoke)
+        })
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_, event| {
+            if let tauri::RunEvent::Exit = event {
+                launch_block::lift_all();
+                flush_state();
+                runtime::mark_clean_exit();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::MockBackend;
+    use std::cell::Cell;
+
+    thread_local! {
+        static STORE: Cell<Option<&'static StateStore>> = const { Cell::new(None) };
+    }
+
+    /// This thread's state, or a shared empty one for threads a test spawns; either way the
+    /// user's data folder is never read or written
+    pub(crate) fn installed_store() -> Option<&'static StateStore> {
+        static SHARED: OnceLock<StateStore> = OnceLock::new();
+        STORE
+            .with(Cell::get)
+            .or_else(|| Some(SHARED.get_or_init(|| StateStore::in_memory(AppState::default()))))
+    }
+
+    /// Give this test thread its own in-memory state, in place of the user's data folder
+    pub(crate) fn install(state: AppState) -> &'static StateStore {
+        let store: &'static StateStore = Box::leak(Box::new(StateStore::in_memory(state)));
+        STORE.with(|s| s.set(Some(store)));
+        store
+    }
+
+    pub(crate) fn rule(name: &str) -> BlacklistEntry {
+        BlacklistEntry::new(name.to_string(), true, 0.0)
+    }
+
+    #[test]
+    fn writes_are_seen_by_readers_and_clones() {
+        let store = install(AppState::default());
+        let managed = store.clone();
+        with_state(|state| state.blacklist.push(rule("chrome")));
+        assert_eq!(read_state(|state| state.blacklist.len()), 1);
+        assert_eq!(
+            managed.read(|state| state.blacklist[0].name.clone()),
+            "chrome"
+        );
+    }
+
+    #[test]
+    fn in_memory_store_has_nothing_to_flush() {
+        let store = install(AppState::default());
+        with_state(|state| state.blacklist.push(rule("node")));
+        flush_state();
+        assert!(!store.dirty.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn each_test_thread_has_its_own_state() {
+        install(AppState::default());
+        with_state(|state| state.blacklist.push(rule("outer")));
+        let inner = std::thread::spawn(|| {
+            install(AppState::default());
+            read_state(|state| state.blacklist.len())
+        })
+        .join()
+        .unwrap();
+        assert_eq!(inner, 0);
+        assert_eq!(read_state(|state| state.blacklist.len()), 1);
+    }
+
+    // ============= Kill logic against MockBackend =============
+
+    fn process(pid: u32, name: &str) -> backend::ProcessInfo {
+        backend::ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cpu: 0.0,
+            memory_kb: 10 * 1024,
+            start_time: 1_000 + pid as u64,
+            exe_path: Some(format!("/opt/{}/{}", name, name)),
+            own: false,
+            protected: false,
+        }
+    }
+
+    fn pids(backend: &MockBackend) -> Vec<u32> {
+        backend.running().iter().map(|p| p.pid).collect()
+    }
+
+    fn never_touch(exe_path: &str) -> notes::ProcessNote {
+        notes::ProcessNote {
+            exe_path: exe_path.to_string(),
+            note: String::new(),
+            risk: notes::Risk::NeverTouch,
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn kills_a_pid_once() {
+        install(AppState::default());
+        let backend = MockBackend::new(vec![process(10, "chrome"), process(11, "node")]);
+        assert!(kill_pid_with(&backend, 10, None, false).is_ok());
+        assert_eq!(pids(&backend), vec![11]);
+        assert!(kill_pid_with(&backend, 10, None, false).is_err());
+    }
+
+    #[test]
+    fn a_recycled_pid_is_left_alone() {
+        install(AppState::default());
+        let backend = MockBackend::new(vec![process(10, "chrome")]);
+        assert!(kill_pid_with(&backend, 10, Some(1), false).is_err());
+        assert_eq!(pids(&backend), vec![10]);
+        assert!(kill_pid_with(&backend, 10, Some(1_010), false).is_ok());
+    }
+
+    #[test]
+    fn own_protected_and_never_touch_processes_survive() {
+        install(AppState::default());
+        let mut own = process(1, "automatekilltask");
+        own.own = true;
+        let mut protected = process(2, "csrss");
+        protected.protected = true;
+        let backend = MockBackend::new(vec![own, protected, process(3, "updater")]);
+        with_state(|state| {
+            state
+                .process_notes
+                .push(never_touch("/opt/updater/updater"))
+        });
+
+        assert!(kill_pid_with(&backend, 1, None, true).is_err());
+        assert!(kill_pid_with(&backend, 2, None, false).is_err());
+        assert!(kill_pid_with(&backend, 3, None, true).is_err());
+        assert_eq!(pids(&backend), vec![1, 2, 3]);
+        // The protection override covers the protected list, nothing else
+        assert!(kill_pid_with(&backend, 2, None, true).is_ok());
+        assert_eq!(pids(&backend), vec![1, 3]);
+    }
+
+    #[test]
+    fn group_kill_follows_the_rule_matcher() {
+        install(AppState::default());
+        let mut chrome = rule("Chrome");
+        chrome.exclude_patterns = vec!["helper".into()];
+        with_state(|state| state.blacklist.push(chrome));
+        let mut protected = process(4, "chrome_crashpad");
+        protected.protected = true;
+        let backend = MockBackend::new(vec![
+            process(1, "chrome"),
+            process(2, "CHROME"),
+            process(3, "chrome_helper"),
+            protected,
+            process(5, "firefox"),
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .fail_kill(2);
+
+        let mut outcomes =
+            kill_matching_with(&backend, &matcher::Matcher::for_name("chrome"), false);
+        outcomes.sort_by_key(|o| o.pid);
+        let kinds: Vec<_> = outcomes.iter().map(|o| (o.pid, o.error_kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (1, None),
+                (2, Some(KillErrorKind::AccessDenied)),
+                (4, Some(KillErrorKind::Protected)),
+            ]
+        );
+        assert_eq!(outcomes[0].freed_memory_kb, 10 * 1024);
+        assert_eq!(pids(&backend), vec![2, 3, 4, 5]);
+        assert!(summarize_kills(&outcomes).is_ok());
+    }
+
+    #[test]
+    fn selection_kill_reports_every_pid_and_logs_the_kills() {
+        install(AppState::default());
+        let backend = MockBackend::new(vec![process(1, "a"), process(2, "b")]).fail_kill(2);
+
+        let result = kill_pids_with(&backend, vec![3, 1, 2, 1], false);
+        let kinds: Vec<_> = result
+            .outcomes
+            .iter()
+            .map(|o| (o.pid, o.error_kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (1, None),
+                (2, Some(KillErrorKind::AccessDenied)),
+                (3, Some(KillErrorKind::NotFound)),
+            ]
+        );
+        assert!(result.success);
+        let logged = read_state(|state| {
+            state
+                .activity_logs
+                .iter()
+                .map(|log| (log.pid, log.was_killed))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(logged, vec![(1, true)]);
+    }
+
+    #[test]
+    fn nothing_killed_is_an_error() {
+        install(AppState::default());
+        let backend = MockBackend::new(vec![process(1, "a")]).fail_kill(1);
+        let outcomes = kill_matching_with(&backend, &matcher::Matcher::new("a"), false);
+        assert!(summarize_kills(&outcomes).is_err());
+        assert!(summarize_kills(&[]).is_err());
+    }
+
+    #[test]
+    fn survivors_are_reported_as_failed_not_missing() {
+        install(AppState::default());
+        let outcome = |pid, error_kind| KillOutcome {
+            pid,
+            name: "a".into(),
+            success: false,
+            error_kind: Some(error_kind),
+            freed_memory_kb: 0,
+        };
+        let no_match = i18n::t("result.no_match", &[]);
+        let still_running = summarize_kills(&[outcome(1, KillErrorKind::StillRunning)]);
+        assert_eq!(
+            still_running,
+            Err(i18n::t(
+                "result.group_still_running",
+                &[("failed", "1".into())]
+            ))
+        );
+        let own = summarize_kills(&[outcome(1, KillErrorKind::OwnProcess)]);
+        assert!(own.is_err_and(|err| err != no_match));
+    }
+
+    // ============= Rule gates =============
+
+    #[test]
+    fn zero_cpu_threshold_only_kills_on_sight_alone() {
+        let mut rule = rule("chrome");
+        assert!(rule.cpu_triggered(0.0, 0.0));
+        rule.memory_threshold_mb = 500;
+        assert!(!rule.cpu_triggered(0.0, 90.0));
+        rule.cpu_threshold = 50.0;
+        assert!(rule.cpu_triggered(50.0, 90.0));
+        assert!(!rule.cpu_triggered(50.0, 10.0));
+
+        let mut gpu_only = rule("blender");
+        gpu_only.gpu_threshold = 80.0;
+        assert!(!gpu_only.cpu_triggered(0.0, 90.0));
+    }
+
+    #[test]
+    fn rules_act_only_when_armed_and_not_snoozed() {
+        let now = "2026-01-01 12:00:00";
+        let mut armed = rule("chrome");
+        assert!(rule_may_act(&armed, now, false, None));
+        assert!(!rule_may_act(&armed, now, true, None));
+
+        armed.snoozed_until = Some("2026-01-01 13:00:00".into());
+        assert!(!rule_may_act(&armed, now, false, None));
+        armed.snoozed_until = Some("2026-01-01 11:00:00".into());
+        assert!(rule_may_act(&armed, now, false, None));
+
+        let mut log_only = rule("node");
+        log_only.auto_kill = false;
+        assert!(!rule_may_act(&log_only, now, false, None));
+    }
+
+    #[test]
+    fn a_huge_memory_minimum_filters_instead_of_overflowing() {
+        let mut group = ProcessGroup::new("chrome".into(), &columns::Columns::default());
+        group.add(10, 5.0, 2_000_000, 0.0);
+        let mut query = GroupQuery {
+            sort: GroupSort::Cpu,
+            min_cpu: 0.0,
+            min_memory_mb: 1_000,
+            min_gpu: 0.0,
+            include_all: false,
+        };
+        assert!(query.keeps(&group));
+        query.min_memory_mb = u64::MAX;
+        assert!(!query.keeps(&group));
+    }
 }
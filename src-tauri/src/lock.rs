@@ -0,0 +1,140 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{read_state, with_state};
+
+// Brute-force protection: after this many wrong PINs, refuse attempts for a while
+const MAX_FAILED_ATTEMPTS: u32 = 5;
+const LOCKOUT: Duration = Duration::from_secs(60);
+
+// When the current unlock session ends (None = locked)
+static UNLOCKED_UNTIL: Mutex<Option<Instant>> = Mutex::new(None);
+// (failed attempts, time of the last failure)
+static FAILED_ATTEMPTS: Mutex<(u32, Option<Instant>)> = Mutex::new((0, None));
+
+#[derive(Serialize)]
+pub struct LockStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+    pub seconds_remaining: u64,
+    pub timeout_secs: u64,
+}
+
+// Stored as "salt$sha256(salt + pin)"
+fn hash_pin(pin: &str, salt: &str) -> String {
+    let digest = Sha256::digest(format!("{}{}", salt, pin).as_bytes());
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}${}", salt, hex)
+}
+
+fn verify_pin(pin: &str, stored: &str) -> bool {
+    match stored.split_once('$') {
+        Some((salt, _)) => hash_pin(pin, salt) == stored,
+        None => false,
+    }
+}
+
+fn new_salt() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{:x}{:x}", nanos, std::process::id())
+}
+
+fn session_remaining() -> Option<Duration> {
+    UNLOCKED_UNTIL
+        .lock()
+        .unwrap()
+        .and_then(|until| until.checked_duration_since(Instant::now()))
+}
+
+fn check_pin(pin: &str) -> Result<(), String> {
+    let mut failed = FAILED_ATTEMPTS.lock().unwrap();
+    if failed.0 >= MAX_FAILED_ATTEMPTS {
+        match failed.1 {
+            Some(last) if last.elapsed() < LOCKOUT => {
+                return Err("Too many wrong PINs, try again in a minute".into());
+            }
+            _ => *failed = (0, None),
+        }
+    }
+
+    let stored = read_state(|state| state.settings.lock_pin_hash.clone());
+    match stored {
+        Some(hash) if !verify_pin(pin, &hash) => {
+            *failed = (failed.0 + 1, Some(Instant::now()));
+            Err("Wrong PIN".into())
+        }
+        _ => {
+            *failed = (0, None);
+            Ok(())
+        }
+    }
+}
+
+// Public API
+/// Guard for every command that kills, suspends or changes rules and settings; passes when no
+/// PIN is configured. Window plumbing (visibility, the mini monitor) and read-only exports are
+/// exempt.
+pub fn require_unlocked() -> Result<(), String> {
+    let enabled = read_state(|state| state.settings.lock_pin_hash.is_some());
+    if !enabled || session_remaining().is_some() {
+        Ok(())
+    } else {
+        Err("Locked - enter the PIN to continue".into())
+    }
+}
+
+/// Set, change or remove (new_pin = None) the PIN. Changing an existing PIN needs the current one.
+pub fn set_pin(current_pin: Option<String>, new_pin: Option<String>) -> Result<String, String> {
+    check_pin(current_pin.as_deref().unwrap_or_default())?;
+
+    let new_pin = new_pin.map(|p| p.trim().to_string());
+    if let Some(pin) = &new_pin {
+        if pin.len() < 4 {
+            return Err("PIN must be at least 4 characters".into());
+        }
+    }
+
+    with_state(|state| {
+        state.settings.lock_pin_hash = new_pin.as_ref().map(|p| hash_pin(p, &new_salt()));
+    });
+    lock();
+
+    Ok(match new_pin {
+        Some(_) => "PIN set, actions are now locked".to_string(),
+        None => "PIN removed".to_string(),
+    })
+}
+
+/// Start an unlock session; returns its length in seconds
+pub fn unlock(pin: &str) -> Result<u64, String> {
+    check_pin(pin)?;
+    let timeout = read_state(|state| state.settings.unlock_timeout_secs);
+    *UNLOCKED_UNTIL.lock().unwrap() = Some(Instant::now() + Duration::from_secs(timeout));
+    Ok(timeout)
+}
+
+pub fn lock() {
+    *UNLOCKED_UNTIL.lock().unwrap() = None;
+}
+
+pub fn status() -> LockStatus {
+    let (enabled, timeout_secs) = read_state(|state| {
+        (
+            state.settings.lock_pin_hash.is_some(),
+            state.settings.unlock_timeout_secs,
+        )
+    });
+    let remaining = session_remaining();
+
+    LockStatus {
+        enabled,
+        unlocked: !enabled || remaining.is_some(),
+        seconds_remaining: remaining.map(|r| r.as_secs()).unwrap_or(0),
+        timeout_secs,
+    }
+}
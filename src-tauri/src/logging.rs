@@ -0,0 +1,90 @@
+// Diagnostic log: `tracing` events written to daily-rotated files under the data dir, so users
+// can attach a debug log to reports about kills that did (or didn't) happen
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, Registry};
+
+pub const LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+const KEEP_FILES: usize = 7;
+
+struct Logger {
+    level: reload::Handle<LevelFilter, Registry>,
+    _guard: WorkerGuard, // Flushes the writer thread when dropped
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+pub fn parse(level: &str) -> Result<LevelFilter, String> {
+    match level.trim().to_lowercase().as_str() {
+        "off" => Ok(LevelFilter::OFF),
+        "error" => Ok(LevelFilter::ERROR),
+        "warn" => Ok(LevelFilter::WARN),
+        "info" => Ok(LevelFilter::INFO),
+        "debug" => Ok(LevelFilter::DEBUG),
+        "trace" => Ok(LevelFilter::TRACE),
+        _ => Err(format!(
+            "Unknown log level: {} (expected off, {})",
+            level,
+            LEVELS.join(", ")
+        )),
+    }
+}
+
+// Public API
+pub fn log_dir() -> PathBuf {
+    crate::data_dir().join("logs")
+}
+
+/// Install the file logger at `level`; later calls only change the level
+pub fn init(level: &str) {
+    let filter = parse(level).unwrap_or(LevelFilter::INFO);
+    if LOGGER.get().is_some() {
+        set_level(level).ok();
+        return;
+    }
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("akt")
+        .filename_suffix("log")
+        .max_log_files(KEEP_FILES)
+        .build(log_dir());
+    let appender = match appender {
+        Ok(appender) => appender,
+        Err(_) => return, // No writable data dir; run without a log file
+    };
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let (level_layer, handle) = reload::Layer::new(filter);
+    let installed = tracing_subscriber::registry()
+        .with(level_layer)
+        .with(fmt::layer().with_writer(writer).with_ansi(false))
+        .try_init()
+        .is_ok();
+    if installed {
+        LOGGER.get_or_init(|| Logger {
+            level: handle,
+            _guard: guard,
+        });
+        tracing::info!(
+            version = env!("CARGO_PKG_VERSION"),
+            level,
+            "logging started"
+        );
+    }
+}
+
+/// Change the level of the running logger; returns the normalized name
+pub fn set_level(level: &str) -> Result<String, String> {
+    let filter = parse(level)?;
+    if let Some(logger) = LOGGER.get() {
+        logger
+            .level
+            .modify(|current| *current = filter)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(level.trim().to_lowercase())
+}
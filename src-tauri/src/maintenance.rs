@@ -0,0 +1,302 @@
+// Maintenance windows: recurring periods (a subset of iCalendar RRULE) during which enforcement
+// pauses, or narrows to a chosen set of rules, so backups and patch runs aren't killed
+// halfway through
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::read_state;
+
+// Longest window we accept; also bounds how many days back an occurrence can still be running
+const MAX_DURATION_MINUTES: u32 = 7 * 24 * 60;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowMode {
+    #[default]
+    Pause, // Every auto-kill becomes detect-only
+    OnlyRules, // Only the listed rules keep enforcing
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MaintenanceWindow {
+    pub name: String,
+    pub enabled: bool,
+    pub start: String, // First occurrence, "YYYY-MM-DD HH:MM" local time
+    pub duration_minutes: u32,
+    pub rrule: String, // e.g. "FREQ=MONTHLY;BYDAY=2TU"; empty for a one-off window
+    pub mode: WindowMode,
+    pub rules: Vec<String>, // Rules still enforced in OnlyRules mode
+}
+
+impl Default for MaintenanceWindow {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: true,
+            start: String::new(),
+            duration_minutes: 60,
+            rrule: String::new(),
+            mode: WindowMode::Pause,
+            rules: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// The supported RRULE parts: FREQ, INTERVAL, BYDAY (with ordinals for MONTHLY, e.g. "-1SU"),
+/// BYMONTHDAY and UNTIL
+struct Recurrence {
+    freq: Freq,
+    interval: u32,
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+    until: Option<NaiveDate>,
+}
+
+fn parse_start(text: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").ok()
+}
+
+fn parse_weekday(text: &str) -> Result<Weekday, String> {
+    Ok(match text {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(format!("Unknown weekday \"{}\" in BYDAY", text)),
+    })
+}
+
+fn parse_rrule(text: &str) -> Result<Recurrence, String> {
+    let mut freq = None;
+    let mut recurrence = Recurrence {
+        freq: Freq::Daily,
+        interval: 1,
+        by_day: vec![],
+        by_month_day: vec![],
+        until: None,
+    };
+    let text = text.trim().trim_start_matches("RRULE:");
+
+    for part in text.split(';').filter(|p| !p.is_empty()) {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| format!("Expected KEY=VALUE, got \"{}\"", part))?;
+        match key.to_uppercase().as_str() {
+            "FREQ" => {
+                freq = Some(match value.to_uppercase().as_str() {
+                    "DAILY" => Freq::Daily,
+                    "WEEKLY" => Freq::Weekly,
+                    "MONTHLY" => Freq::Monthly,
+                    _ => return Err("FREQ must be DAILY, WEEKLY or MONTHLY".into()),
+                })
+            }
+            "INTERVAL" => {
+                recurrence.interval = value
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or("INTERVAL must be a positive number")?
+            }
+            "BYDAY" => {
+                for day in value.to_uppercase().split(',') {
+                    if !day.is_ascii() {
+                        return Err(format!("Invalid BYDAY entry \"{}\"", day));
+                    }
+                    let split = day.len().saturating_sub(2);
+                    let (ordinal, weekday) = day.split_at(split);
+                    let ordinal = if ordinal.is_empty() {
+                        None
+                    } else {
+                        Some(
+                            ordinal
+                                .trim_start_matches('+')
+                                .parse::<i32>()
+                                .ok()
+                                .filter(|n| *n != 0 && n.abs() <= 5)
+                                .ok_or_else(|| format!("Invalid BYDAY entry \"{}\"", day))?,
+                        )
+                    };
+                    recurrence.by_day.push((ordinal, parse_weekday(weekday)?));
+                }
+            }
+            "BYMONTHDAY" => {
+                for day in value.split(',') {
+                    recurrence.by_month_day.push(
+                        day.parse::<i32>()
+                            .ok()
+                            .filter(|n| *n != 0 && n.abs() <= 31)
+                            .ok_or_else(|| format!("Invalid BYMONTHDAY entry \"{}\"", day))?,
+                    );
+                }
+            }
+            "UNTIL" => {
+                // Date form only; a time part ("T235959Z") is ignored
+                let date = value.get(..8).unwrap_or(value);
+                recurrence.until = Some(
+                    NaiveDate::parse_from_str(date, "%Y%m%d")
+                        .map_err(|_| "UNTIL must be a date like 20261231")?,
+                );
+            }
+            "COUNT" => return Err("COUNT is not supported; use UNTIL instead".into()),
+            other => return Err(format!("Unsupported RRULE part \"{}\"", other)),
+        }
+    }
+
+    recurrence.freq = freq.ok_or("RRULE needs a FREQ")?;
+    if recurrence.freq != Freq::Monthly && recurrence.by_day.iter().any(|(n, _)| n.is_some()) {
+        return Err("Numbered BYDAY entries (e.g. 2TU) only work with FREQ=MONTHLY".into());
+    }
+    Ok(recurrence)
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = match date.month() {
+        12 => (date.year() + 1, 1),
+        m => (date.year(), m + 1),
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|d| d.pred_opt())
+        .map_or(31, |d| d.day())
+}
+
+impl Recurrence {
+    /// Whether an occurrence starts on `date`, given the first one started on `first`
+    fn occurs_on(&self, first: NaiveDate, date: NaiveDate) -> bool {
+        if date < first || self.until.is_some_and(|until| date > until) {
+            return false;
+        }
+        let weekday_ok = |default: bool| {
+            if self.by_day.is_empty() {
+                return default;
+            }
+            self.by_day.iter().any(|(ordinal, weekday)| {
+                date.weekday() == *weekday
+                    && ordinal.is_none_or(|n| {
+                        if n > 0 {
+                            (date.day() as i32 - 1) / 7 + 1 == n
+                        } else {
+                            (days_in_month(date) as i32 - date.day() as i32) / 7 + 1 == -n
+                        }
+                    })
+            })
+        };
+        let interval = self.interval as i64;
+
+        match self.freq {
+            Freq::Daily => (date - first).num_days() % interval == 0 && weekday_ok(true),
+            Freq::Weekly => {
+                let week_of =
+                    |d: NaiveDate| d - Duration::days(d.weekday().num_days_from_monday() as i64);
+                (week_of(date) - week_of(first)).num_weeks() % interval == 0
+                    && weekday_ok(date.weekday() == first.weekday())
+            }
+            Freq::Monthly => {
+                let months = (date.year() - first.year()) as i64 * 12 + date.month() as i64
+                    - first.month() as i64;
+                if months % interval != 0 {
+                    return false;
+                }
+                let day_ok = self.by_month_day.iter().any(|&d| {
+                    let day = if d > 0 {
+                        d
+                    } else {
+                        days_in_month(date) as i32 + d + 1
+                    };
+                    date.day() as i32 == day
+                });
+                match (self.by_day.is_empty(), self.by_month_day.is_empty()) {
+                    (true, true) => date.day() == first.day(),
+                    (false, true) => weekday_ok(false),
+                    (true, false) => day_ok,
+                    (false, false) => day_ok && weekday_ok(false),
+                }
+            }
+        }
+    }
+}
+
+impl MaintenanceWindow {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Maintenance windows need a name".into());
+        }
+        if parse_start(&self.start).is_none() {
+            return Err("Start must be in YYYY-MM-DD HH:MM format".into());
+        }
+        if !(1..=MAX_DURATION_MINUTES).contains(&self.duration_minutes) {
+            return Err("Duration must be between 1 minute and 7 days".into());
+        }
+        if !self.rrule.trim().is_empty() {
+            parse_rrule(&self.rrule)?;
+        }
+        if self.mode == WindowMode::OnlyRules && self.rules.is_empty() {
+            return Err("Pick at least one rule to keep enforcing".into());
+        }
+        Ok(())
+    }
+
+    fn is_active_at(&self, now: NaiveDateTime) -> bool {
+        let Some(first) = parse_start(&self.start) else {
+            return false;
+        };
+        if !self.enabled || now < first {
+            return false;
+        }
+        let duration = Duration::minutes(self.duration_minutes as i64);
+        if self.rrule.trim().is_empty() {
+            return now < first + duration;
+        }
+        let Ok(recurrence) = parse_rrule(&self.rrule) else {
+            return false;
+        };
+
+        // An occurrence that started on any of the last few days may still be running
+        let mut date = (now - duration).date();
+        while date <= now.date() {
+            let started = date.and_time(first.time());
+            if recurrence.occurs_on(first.date(), date)
+                && started <= now
+                && now < started + duration
+            {
+                return true;
+            }
+            match date.succ_opt() {
+                Some(next) => date = next,
+                None => break,
+            }
+        }
+        false
+    }
+
+    /// Whether `rule` is held back while this window is active
+    pub fn pauses(&self, rule: &str) -> bool {
+        match self.mode {
+            WindowMode::Pause => true,
+            WindowMode::OnlyRules => !self.rules.iter().any(|r| r == rule),
+        }
+    }
+}
+
+pub fn validate_all(windows: &[MaintenanceWindow]) -> Result<(), String> {
+    windows.iter().try_for_each(MaintenanceWindow::validate)
+}
+
+// Public API
+/// The maintenance window in effect right now, if any (the first one listed wins)
+pub fn active_now() -> Option<MaintenanceWindow> {
+    let windows = read_state(|state| state.settings.maintenance_windows.clone());
+    let now = Local::now().naive_local();
+    windows.into_iter().find(|w| w.is_active_at(now))
+}
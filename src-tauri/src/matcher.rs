@@ -0,0 +1,137 @@
+// Process name matching shared by commands, the watcher and background tasks
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{read_state, BlacklistEntry};
+
+/// NFKC-normalize (so composed/decomposed and full-width forms compare equal), then fold case
+/// unless the comparison is case-sensitive
+pub fn normalize(name: &str, case_sensitive: bool) -> String {
+    let name: String = name.nfkc().collect();
+    if case_sensitive {
+        name
+    } else {
+        name.to_lowercase()
+    }
+}
+
+/// Exclusions applied when a name's substring match is known to be too broad
+pub fn default_excludes(name: &str) -> Vec<String> {
+    match name {
+        // "msedge" also matches every app's WebView2 host
+        "msedge" => vec!["msedgewebview2".to_string()],
+        _ => vec![],
+    }
+}
+
+/// Substring match on the normalized process name, minus exclude patterns
+pub struct Matcher {
+    pattern: String,
+    excludes: Vec<String>,
+    case_sensitive: bool,
+}
+
+impl Matcher {
+    /// Ad-hoc name, with the built-in default exclusions
+    pub fn new(pattern: &str) -> Self {
+        let pattern = normalize(pattern, false);
+        let excludes = default_excludes(&pattern);
+        Self {
+            pattern,
+            excludes,
+            case_sensitive: false,
+        }
+    }
+
+    pub fn for_rule(rule: &BlacklistEntry) -> Self {
+        Self {
+            pattern: normalize(&rule.name, rule.case_sensitive),
+            excludes: rule
+                .exclude_patterns
+                .iter()
+                .map(|e| normalize(e, rule.case_sensitive))
+                .collect(),
+            case_sensitive: rule.case_sensitive,
+        }
+    }
+
+    /// Use the blacklist rule's exclusions when `name` is a rule. Reads state, so don't call it
+    /// from inside `with_state`.
+    pub fn for_name(name: &str) -> Self {
+        let key = normalize(name, false);
+        let rule = read_state(|state| {
+            state
+                .blacklist
+                .iter()
+                .find(|e| normalize(&e.name, false) == key)
+                .cloned()
+        });
+        match rule {
+            Some(rule) => Self::for_rule(&rule),
+            None => Self::new(name),
+        }
+    }
+
+    pub fn matches(&self, process_name: &str) -> bool {
+        let name = normalize(process_name, self.case_sensitive);
+        (name.contains(&self.pattern) || name == self.pattern)
+            && !self.excludes.iter().any(|e| name.contains(e.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, case_sensitive: bool, excludes: &[&str]) -> BlacklistEntry {
+        let mut rule = BlacklistEntry::new(name.to_string(), true, 0.0);
+        rule.case_sensitive = case_sensitive;
+        rule.exclude_patterns = excludes.iter().map(|e| e.to_string()).collect();
+        rule
+    }
+
+    #[test]
+    fn folds_case_by_default() {
+        let matcher = Matcher::for_rule(&rule("Chrome", false, &[]));
+        assert!(matcher.matches("chrome.exe"));
+        assert!(matcher.matches("CHROME"));
+        assert!(!matcher.matches("firefox"));
+    }
+
+    #[test]
+    fn case_sensitive_rules_match_exactly() {
+        let matcher = Matcher::for_rule(&rule("Code", true, &[]));
+        assert!(matcher.matches("Code Helper"));
+        assert!(!matcher.matches("code"));
+    }
+
+    #[test]
+    fn excludes_follow_the_rule_case() {
+        let folded = Matcher::for_rule(&rule("code", false, &["Helper"]));
+        assert!(!folded.matches("code helper"));
+        assert!(folded.matches("code"));
+
+        let exact = Matcher::for_rule(&rule("Code", true, &["Helper"]));
+        assert!(!exact.matches("Code Helper"));
+        assert!(exact.matches("Code helper"));
+    }
+
+    #[test]
+    fn unicode_forms_compare_equal() {
+        // Composed and decomposed é
+        let matcher = Matcher::for_rule(&rule("caf\u{e9}", false, &[]));
+        assert!(matcher.matches("Cafe\u{301}.exe"));
+        // Full-width letters
+        assert!(Matcher::new("chrome").matches("\u{ff23}\u{ff28}\u{ff32}\u{ff2f}\u{ff2d}\u{ff25}"));
+        // Non-ASCII case folding
+        assert!(Matcher::new("\u{441}\u{43b}\u{443}\u{436}\u{431}\u{430}")
+            .matches("\u{421}\u{43b}\u{443}\u{436}\u{431}\u{430}.exe"));
+    }
+
+    #[test]
+    fn msedge_skips_webview2_hosts() {
+        let matcher = Matcher::new("msedge");
+        assert!(matcher.matches("msedge.exe"));
+        assert!(!matcher.matches("msedgewebview2.exe"));
+    }
+}
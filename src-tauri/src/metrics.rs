@@ -0,0 +1,273 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use sysinfo::{Components, Disks, Networks, System};
+
+use crate::{containers, gpu, sampler, DiskInfo};
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    System,
+    Disks,
+    ProcessGpu,
+    Network,
+    Sensors,
+}
+
+#[derive(Serialize, Clone)]
+pub struct NetworkInfo {
+    pub interface: String,
+    pub received_bytes: u64,
+    pub transmitted_bytes: u64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SensorReading {
+    pub label: String,
+    pub temperature_c: Option<f32>,
+    pub critical_c: Option<f32>,
+}
+
+/// Everything collected in one pass; each provider fills in its own part
+#[derive(Default, Clone)]
+pub struct MetricSample {
+    pub cpu_usage: f32,
+    pub memory_total: u64,
+    pub memory_used: u64,
+    pub vm_memory: u64, // Held by vmmem / vmmemWSL (Hyper-V and WSL2 guests)
+    pub disks: Vec<DiskInfo>,
+    pub process_gpu: HashMap<u32, f32>,
+    pub network: Vec<NetworkInfo>,
+    pub sensors: Vec<SensorReading>,
+}
+
+#[derive(Serialize)]
+pub struct MetricProviderInfo {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub kind: MetricKind,
+    pub available: bool,
+}
+
+pub trait MetricProvider: Send + Sync {
+    fn id(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn kind(&self) -> MetricKind;
+    fn is_available(&self) -> bool;
+    fn collect(&self, sample: &mut MetricSample);
+}
+
+// ============= Built-in Providers =============
+
+struct SysinfoProvider {
+    // Kept between samples so global CPU usage covers the time since the last poll
+    sys: Mutex<System>,
+}
+
+impl MetricProvider for SysinfoProvider {
+    fn id(&self) -> &'static str {
+        "sysinfo"
+    }
+    fn description(&self) -> &'static str {
+        "CPU and memory usage via sysinfo"
+    }
+    fn kind(&self) -> MetricKind {
+        MetricKind::System
+    }
+    fn is_available(&self) -> bool {
+        sysinfo::IS_SUPPORTED_SYSTEM
+    }
+    fn collect(&self, sample: &mut MetricSample) {
+        let mut sys = self.sys.lock().unwrap();
+        sys.refresh_cpu_usage();
+        sys.refresh_memory();
+
+        sample.cpu_usage = sys.global_cpu_usage();
+        sample.memory_total = sys.total_memory();
+        sample.memory_used = sys.used_memory();
+        sample.vm_memory = sampler::with_processes(
+            |p| containers::is_vm_process(&p.name().to_string_lossy()),
+            |sys, _| containers::vm_memory_bytes(sys),
+        );
+    }
+}
+
+struct DisksProvider;
+
+impl MetricProvider for DisksProvider {
+    fn id(&self) -> &'static str {
+        "disks"
+    }
+    fn description(&self) -> &'static str {
+        "Disk space per mount point via sysinfo"
+    }
+    fn kind(&self) -> MetricKind {
+        MetricKind::Disks
+    }
+    fn is_available(&self) -> bool {
+        sysinfo::IS_SUPPORTED_SYSTEM
+    }
+    fn collect(&self, sample: &mut MetricSample) {
+        let disks = Disks::new_with_refreshed_list();
+        sample.disks = disks
+            .iter()
+            .map(|d| {
+                let total = d.total_space();
+                let free = d.available_space();
+                let used = total.saturating_sub(free);
+                DiskInfo {
+                    name: d.name().to_string_lossy().to_string(),
+                    mount_point: d.mount_point().to_string_lossy().to_string(),
+                    total_gb: total as f64 / 1024.0 / 1024.0 / 1024.0,
+                    used_gb: used as f64 / 1024.0 / 1024.0 / 1024.0,
+                    free_gb: free as f64 / 1024.0 / 1024.0 / 1024.0,
+                    usage_percent: if total > 0 {
+                        (used as f32 / total as f32) * 100.0
+                    } else {
+                        0.0
+                    },
+                    total_bytes: total,
+                    used_bytes: used,
+                    free_bytes: free,
+                }
+            })
+            .collect();
+    }
+}
+
+struct PdhGpuProvider;
+
+impl MetricProvider for PdhGpuProvider {
+    fn id(&self) -> &'static str {
+        "pdh_gpu"
+    }
+    fn description(&self) -> &'static str {
+        "Per-process GPU engine utilization via Windows PDH counters"
+    }
+    fn kind(&self) -> MetricKind {
+        MetricKind::ProcessGpu
+    }
+    fn is_available(&self) -> bool {
+        gpu::is_available()
+    }
+    fn collect(&self, sample: &mut MetricSample) {
+        sample.process_gpu = gpu::get_gpu_usages();
+    }
+}
+
+struct NetworkProvider;
+
+impl MetricProvider for NetworkProvider {
+    fn id(&self) -> &'static str {
+        "network"
+    }
+    fn description(&self) -> &'static str {
+        "Per-interface traffic totals via sysinfo"
+    }
+    fn kind(&self) -> MetricKind {
+        MetricKind::Network
+    }
+    fn is_available(&self) -> bool {
+        sysinfo::IS_SUPPORTED_SYSTEM
+    }
+    fn collect(&self, sample: &mut MetricSample) {
+        let networks = Networks::new_with_refreshed_list();
+        sample.network = networks
+            .iter()
+            .map(|(name, data)| NetworkInfo {
+                interface: name.clone(),
+                received_bytes: data.total_received(),
+                transmitted_bytes: data.total_transmitted(),
+            })
+            .collect();
+    }
+}
+
+struct SensorsProvider {
+    // Listed once; enumerating sensors is the slow part, reading them again is cheap
+    components: Mutex<Components>,
+}
+
+impl MetricProvider for SensorsProvider {
+    fn id(&self) -> &'static str {
+        "sensors"
+    }
+    fn description(&self) -> &'static str {
+        "Hardware temperature sensors via sysinfo"
+    }
+    fn kind(&self) -> MetricKind {
+        MetricKind::Sensors
+    }
+    fn is_available(&self) -> bool {
+        !self.components.lock().unwrap().is_empty()
+    }
+    fn collect(&self, sample: &mut MetricSample) {
+        let mut components = self.components.lock().unwrap();
+        components.refresh(true);
+        sample.sensors = components
+            .iter()
+            .map(|c| SensorReading {
+                label: c.label().to_string(),
+                temperature_c: c.temperature(),
+                critical_c: c.critical(),
+            })
+            .collect();
+    }
+}
+
+// ============= Registry =============
+
+static PROVIDERS: OnceLock<Vec<Box<dyn MetricProvider>>> = OnceLock::new();
+
+fn default_providers() -> Vec<Box<dyn MetricProvider>> {
+    vec![
+        Box::new(SysinfoProvider {
+            sys: Mutex::new(System::new()),
+        }),
+        Box::new(DisksProvider),
+        Box::new(PdhGpuProvider),
+        Box::new(NetworkProvider),
+        Box::new(SensorsProvider {
+            components: Mutex::new(Components::new_with_refreshed_list()),
+        }),
+    ]
+}
+
+fn providers() -> &'static [Box<dyn MetricProvider>] {
+    PROVIDERS.get_or_init(default_providers)
+}
+
+// Public API
+/// Register the built-in providers; new providers (NVML, ADL, WSL...) go in `default_providers`
+pub fn register_providers() {
+    providers();
+}
+
+/// Run every available provider of the requested kinds
+pub fn collect(kinds: &[MetricKind]) -> MetricSample {
+    let mut sample = MetricSample::default();
+    for provider in providers() {
+        if kinds.contains(&provider.kind()) && provider.is_available() {
+            provider.collect(&mut sample);
+        }
+    }
+    sample
+}
+
+/// From the background refresher when it's running, otherwise sampled now
+pub fn process_gpu_usages() -> HashMap<u32, f32> {
+    crate::refresher::cached_gpu().unwrap_or_else(|| collect(&[MetricKind::ProcessGpu]).process_gpu)
+}
+
+pub fn list_providers() -> Vec<MetricProviderInfo> {
+    providers()
+        .iter()
+        .map(|p| MetricProviderInfo {
+            id: p.id(),
+            description: p.description(),
+            kind: p.kind(),
+            available: p.is_available(),
+        })
+        .collect()
+}
@@ -0,0 +1,149 @@
+// Small always-on-top "mini monitor" window with CPU/RAM/GPU and a button to kill the app in
+// the foreground. It runs the same frontend as the main window (main.ts picks the view by
+// window label) and lives on its own: closing or hiding the main window leaves it open.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::{emit_event, protection, refresher, sampler};
+
+const LABEL: &str = "mini";
+
+#[derive(Serialize, Clone)]
+pub struct ForegroundApp {
+    pub pid: u32,
+    pub start_time: u64, // Pins the kill button to this process if the PID is reused
+    pub name: String,
+}
+
+/// Payload of "live-stats", sent while the mini monitor is open
+#[derive(Serialize, Clone)]
+pub struct LiveStats {
+    pub cpu_usage: f32,
+    pub memory_percent: f32,
+    pub memory_used_gb: f64,
+    pub gpu_usage: Option<f32>, // None where per-process GPU usage isn't measured
+    pub foreground: Option<ForegroundApp>,
+}
+
+static STREAMING: AtomicBool = AtomicBool::new(false);
+// Last foreground app that wasn't us; clicking the kill button brings our own window forward
+static LAST_FOREGROUND: Mutex<Option<ForegroundApp>> = Mutex::new(None);
+
+/// PID owning the focused window
+fn foreground_pid() -> Option<u32> {
+    #[cfg(windows)]
+    {
+        use windows::Win32::UI::WindowsAndMessaging::{
+            GetForegroundWindow, GetWindowThreadProcessId,
+        };
+
+        let window = unsafe { GetForegroundWindow() };
+        if window.0 == 0 {
+            return None;
+        }
+        let mut pid = 0u32;
+        unsafe { GetWindowThreadProcessId(window, Some(&mut pid as *mut u32)) };
+        (pid != 0).then_some(pid)
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // X11 only; Wayland doesn't tell other clients which window has focus
+        let output = std::process::Command::new("xdotool")
+            .args(["getactivewindow", "getwindowpid"])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        None
+    }
+}
+
+/// Remember the focused app unless it is this one
+fn track_foreground() {
+    let Some(pid) = foreground_pid() else {
+        return;
+    };
+    if LAST_FOREGROUND
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|app| app.pid == pid)
+    {
+        return;
+    }
+    let app = sampler::with_processes(
+        |_| false,
+        |sys, _| {
+            let p = sys.process(sysinfo::Pid::from_u32(pid))?;
+            (!protection::own_pids(sys).contains(&pid)).then(|| ForegroundApp {
+                pid,
+                start_time: p.start_time(),
+                name: p.name().to_string_lossy().to_string(),
+            })
+        },
+    );
+    if app.is_some() {
+        *LAST_FOREGROUND.lock().unwrap() = app;
+    }
+}
+
+fn live_stats() -> LiveStats {
+    let stats = crate::system_stats();
+    let gpu_usage = crate::gpu::is_available().then(|| {
+        let usage = refresher::cached_gpu().unwrap_or_else(crate::gpu::get_gpu_usages);
+        usage.values().sum::<f32>().min(100.0)
+    });
+    LiveStats {
+        cpu_usage: stats.cpu_usage,
+        memory_percent: stats.memory_percent,
+        memory_used_gb: stats.memory_used_gb,
+        gpu_usage,
+        foreground: LAST_FOREGROUND.lock().unwrap().clone(),
+    }
+}
+
+/// Send "live-stats" at the stats refresh rate until the window is closed
+fn stream(app: AppHandle) {
+    if STREAMING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || {
+        while app.get_webview_window(LABEL).is_some() {
+            track_foreground();
+            emit_event("live-stats", live_stats());
+            thread::sleep(refresher::interval(refresher::Source::Stats));
+        }
+        STREAMING.store(false, Ordering::SeqCst);
+    });
+}
+
+// Public API
+/// Open the mini monitor, or close it if it is open; returns whether it is open now
+pub fn toggle(app: &AppHandle) -> Result<bool, String> {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        window.close().map_err(|e| e.to_string())?;
+        return Ok(false);
+    }
+    WebviewWindowBuilder::new(app, LABEL, WebviewUrl::App("index.html".into()))
+        .title("Mini monitor")
+        .inner_size(240.0, 170.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    stream(app.clone());
+    Ok(true)
+}
+
+/// The app that had focus before the mini monitor was clicked
+pub fn last_foreground() -> Option<ForegroundApp> {
+    LAST_FOREGROUND.lock().unwrap().clone()
+}
@@ -0,0 +1,136 @@
+// Loaded modules (DLLs, shared libraries) per process, and which processes have a given one
+// loaded - e.g. the updater or injector DLL that keeps a file locked or adds CPU load everywhere.
+// Windows walks each process's module list; Linux reads the file mappings in /proc/<pid>/maps.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::matcher::Matcher;
+use crate::sampler;
+
+struct Module {
+    name: String,
+    path: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ModuleUser {
+    pub pid: u32,
+    pub process_name: String,
+    pub module: String,
+    pub path: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ModuleSearch {
+    pub users: Vec<ModuleUser>,
+    pub denied: usize, // Processes whose modules couldn't be read (run as admin/root to see them)
+}
+
+#[cfg(windows)]
+fn read_modules(pid: u32) -> Option<Vec<Module>> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Module32FirstW, Module32NextW, MODULEENTRY32W, TH32CS_SNAPMODULE,
+        TH32CS_SNAPMODULE32,
+    };
+
+    let wide = |chars: &[u16]| {
+        let len = chars.iter().position(|&c| c == 0).unwrap_or(chars.len());
+        String::from_utf16_lossy(&chars[..len])
+    };
+    let mut modules = Vec::new();
+    unsafe {
+        let snapshot =
+            CreateToolhelp32Snapshot(TH32CS_SNAPMODULE | TH32CS_SNAPMODULE32, pid).ok()?;
+        let mut entry = MODULEENTRY32W {
+            dwSize: std::mem::size_of::<MODULEENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut more = Module32FirstW(snapshot, &mut entry).is_ok();
+        while more {
+            modules.push(Module {
+                name: wide(&entry.szModule),
+                path: wide(&entry.szExePath),
+            });
+            more = Module32NextW(snapshot, &mut entry).is_ok();
+        }
+        CloseHandle(snapshot).ok();
+    }
+    Some(modules)
+}
+
+#[cfg(target_os = "linux")]
+fn read_modules(pid: u32) -> Option<Vec<Module>> {
+    let maps = std::fs::read_to_string(format!("/proc/{}/maps", pid)).ok()?;
+    // "7f3a2b000000-7f3a2b021000 r-xp 00000000 08:01 1234   /usr/lib/libc.so.6"; a library
+    // is mapped several times (code, data, ...) but listed once
+    let mut paths: Vec<&str> = maps
+        .lines()
+        .filter_map(|line| line.find('/').map(|at| line[at..].trim_end()))
+        .collect();
+    paths.sort_unstable();
+    paths.dedup();
+    Some(
+        paths
+            .into_iter()
+            .map(|path| Module {
+                name: std::path::Path::new(path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.to_string()),
+                path: path.to_string(),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn read_modules(_pid: u32) -> Option<Vec<Module>> {
+    None
+}
+
+// Public API
+/// Every process with a module whose file name contains `name` (case-insensitive)
+pub fn find(name: &str) -> Result<ModuleSearch, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Enter a module name, e.g. version.dll".into());
+    }
+    let matcher = Matcher::new(name);
+    let processes: HashMap<u32, String> = sampler::with_processes(
+        |_| false,
+        |sys, _| {
+            sys.processes()
+                .values()
+                .map(|p| (p.pid().as_u32(), p.name().to_string_lossy().to_string()))
+                .collect()
+        },
+    );
+
+    let mut users = Vec::new();
+    let mut denied = 0;
+    for (pid, process_name) in processes {
+        // None when access is denied or the process is gone
+        let Some(modules) = read_modules(pid) else {
+            denied += 1;
+            continue;
+        };
+        for module in modules.into_iter().filter(|m| matcher.matches(&m.name)) {
+            users.push(ModuleUser {
+                pid,
+                process_name: process_name.clone(),
+                module: module.name,
+                path: module.path,
+            });
+        }
+    }
+    users.sort_by(|a, b| {
+        a.process_name
+            .to_lowercase()
+            .cmp(&b.process_name.to_lowercase())
+            .then(a.pid.cmp(&b.pid))
+    });
+    tracing::debug!(name, found = users.len(), denied, "module search");
+    Ok(ModuleSearch { users, denied })
+}
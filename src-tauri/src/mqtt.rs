@@ -0,0 +1,285 @@
+// Opt-in MQTT client for home automation: publishes system stats and kill events, and can
+// take kill commands from a command topic. Topics live under a configurable prefix:
+//   <prefix>/status          "online" / "offline" (retained, offline via last will)
+//   <prefix>/stats           system stats JSON every `stats_interval_secs`
+//   <prefix>/kill            one JSON activity log entry per kill
+//   <prefix>/command         {"action": "kill_pid", "pid": 123} or {"action": "kill", "name": "x"},
+//                            each with "secret": the configured command secret
+//   <prefix>/command/result  {"ok": true, "message": "..."} for each command
+// Anyone who can publish to the broker could otherwise kill processes, so commands need the
+// shared secret, and TLS unless the broker is on this machine.
+
+use rumqttc::{
+    Client, Connection, Event, LastWill, MqttOptions, Packet, QoS, RecvTimeoutError,
+    TlsConfiguration, Transport,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{agent, lock, read_state, ActivityLog};
+
+const IDLE_CHECK: Duration = Duration::from_secs(2);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const POLL: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MqttSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub topic_prefix: String,
+    pub stats_interval_secs: u64,
+    pub tls: bool, // Connect over TLS, verifying the broker against the system's trusted roots
+    pub remote_commands: bool, // Subscribe to <prefix>/command and run the kills it asks for
+    pub command_secret: Option<String>, // Required in every command payload
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            client_id: "automatekilltask".to_string(),
+            username: None,
+            password: None,
+            topic_prefix: "automatekilltask".to_string(),
+            stats_interval_secs: 10,
+            tls: false,
+            remote_commands: false,
+            command_secret: None,
+        }
+    }
+}
+
+impl MqttSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.host.trim().is_empty() {
+            return Err("MQTT broker host is required".into());
+        }
+        if self.port == 0 {
+            return Err("MQTT broker port must be between 1 and 65535".into());
+        }
+        if self.client_id.trim().is_empty() {
+            return Err("MQTT client ID is required".into());
+        }
+        let prefix = self.topic_prefix.trim_matches('/');
+        if prefix.is_empty() || prefix.contains(['#', '+']) {
+            return Err("Topic prefix must be non-empty and contain no wildcards".into());
+        }
+        if !(1..=3600).contains(&self.stats_interval_secs) {
+            return Err("Stats interval must be between 1 second and 1 hour".into());
+        }
+        if self.remote_commands {
+            if self.secret().is_none() {
+                return Err("Remote commands need a command secret".into());
+            }
+            if !self.tls && !self.is_local() {
+                return Err("Remote commands from another machine need TLS".into());
+            }
+        }
+        Ok(())
+    }
+
+    fn secret(&self) -> Option<&str> {
+        self.command_secret
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    }
+
+    fn is_local(&self) -> bool {
+        matches!(self.host.trim(), "localhost" | "127.0.0.1" | "::1")
+    }
+
+    fn topic(&self, suffix: &str) -> String {
+        format!("{}/{}", self.topic_prefix.trim_matches('/'), suffix)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum Command {
+    KillPid { pid: u32 },
+    Kill { name: String },
+}
+
+#[derive(Deserialize)]
+struct SignedCommand {
+    #[serde(default)]
+    secret: String,
+    #[serde(flatten)]
+    command: Command,
+}
+
+// The live client and the settings it was connected with, for publishing from other threads
+static CLIENT: Mutex<Option<(Client, MqttSettings)>> = Mutex::new(None);
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+// Bumped whenever the settings change so the running session reconnects with the new ones
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn run_command(payload: &[u8], settings: &MqttSettings) -> Result<String, String> {
+    let signed: SignedCommand =
+        serde_json::from_slice(payload).map_err(|e| format!("Invalid command: {}", e))?;
+    let expected = settings
+        .secret()
+        .ok_or("Remote commands are not configured")?;
+    if !agent::same_secret(&signed.secret, expected) {
+        return Err("Wrong command secret".into());
+    }
+    lock::require_unlocked()?;
+    match signed.command {
+        Command::KillPid { pid } => crate::kill_process_by_pid(pid),
+        Command::Kill { name } => crate::kill_processes_by_name(&name),
+    }
+}
+
+fn publish_stats(client: &Client, settings: &MqttSettings) {
+    if let Ok(payload) = serde_json::to_vec(&crate::system_stats()) {
+        client
+            .try_publish(settings.topic("stats"), QoS::AtMostOnce, false, payload)
+            .ok();
+    }
+}
+
+/// Handle one event; false ends the session
+fn handle(
+    event: Result<Event, rumqttc::ConnectionError>,
+    client: &Client,
+    settings: &MqttSettings,
+) -> bool {
+    match event {
+        Ok(Event::Incoming(Packet::ConnAck(_))) => {
+            CONNECTED.store(true, Ordering::Relaxed);
+            tracing::info!(host = %settings.host, "MQTT connected");
+            client
+                .try_publish(settings.topic("status"), QoS::AtLeastOnce, true, "online")
+                .ok();
+            if settings.remote_commands {
+                client
+                    .try_subscribe(settings.topic("command"), QoS::AtLeastOnce)
+                    .ok();
+            }
+            true
+        }
+        Ok(Event::Incoming(Packet::Publish(publish))) => {
+            let result = run_command(&publish.payload, settings);
+            tracing::info!(result = ?result, "MQTT command");
+            let reply = match result {
+                Ok(message) => json!({ "ok": true, "message": message }),
+                Err(message) => json!({ "ok": false, "message": message }),
+            };
+            client
+                .try_publish(
+                    settings.topic("command/result"),
+                    QoS::AtLeastOnce,
+                    false,
+                    reply.to_string(),
+                )
+                .ok();
+            true
+        }
+        Ok(_) => true,
+        Err(err) => {
+            tracing::warn!(host = %settings.host, "MQTT connection failed: {}", err);
+            false
+        }
+    }
+}
+
+/// One connection, until it fails or the settings change
+fn session(settings: MqttSettings, generation: u64) {
+    let mut options = MqttOptions::new(
+        settings.client_id.trim(),
+        settings.host.trim(),
+        settings.port,
+    );
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        settings.topic("status"),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if settings.tls {
+        options.set_transport(Transport::tls_with_config(TlsConfiguration::Native));
+    }
+    if let Some(username) = settings.username.as_ref().filter(|u| !u.is_empty()) {
+        options.set_credentials(username, settings.password.clone().unwrap_or_default());
+    }
+    let (client, mut connection): (Client, Connection) = Client::new(options, 64);
+    *CLIENT.lock().unwrap() = Some((client.clone(), settings.clone()));
+
+    let interval = Duration::from_secs(settings.stats_interval_secs);
+    let mut next_stats = Instant::now();
+    loop {
+        if GENERATION.load(Ordering::Relaxed) != generation {
+            client.try_disconnect().ok();
+            break;
+        }
+        if CONNECTED.load(Ordering::Relaxed) && Instant::now() >= next_stats {
+            publish_stats(&client, &settings);
+            next_stats = Instant::now() + interval;
+        }
+        match connection.recv_timeout(POLL) {
+            Ok(event) => {
+                if !handle(event, &client, &settings) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    CONNECTED.store(false, Ordering::Relaxed);
+    *CLIENT.lock().unwrap() = None;
+}
+
+// Public API
+/// Keep a broker connection while MQTT is enabled, reconnecting after failures
+pub fn start() {
+    thread::spawn(|| loop {
+        let generation = GENERATION.load(Ordering::Relaxed);
+        let settings = read_state(|state| state.settings.mqtt.clone());
+        if !settings.enabled {
+            thread::sleep(IDLE_CHECK);
+            continue;
+        }
+        session(settings, generation);
+        if GENERATION.load(Ordering::Relaxed) == generation {
+            thread::sleep(RECONNECT_DELAY);
+        }
+    });
+}
+
+/// Reconnect with the saved settings (or disconnect, if MQTT was turned off)
+pub fn reload() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn is_connected() -> bool {
+    CONNECTED.load(Ordering::Relaxed)
+}
+
+/// Publish a kill to <prefix>/kill; never blocks, drops the event when not connected
+pub fn publish_kill(log: &ActivityLog) {
+    if !CONNECTED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some((client, settings)) = CLIENT.lock().unwrap().as_ref() {
+        if let Ok(payload) = serde_json::to_vec(log) {
+            client
+                .try_publish(settings.topic("kill"), QoS::AtLeastOnce, false, payload)
+                .ok();
+        }
+    }
+}
@@ -0,0 +1,274 @@
+// TCP/UDP socket table with owning PIDs: IP Helper tables on Windows, `ss` on Linux
+
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Socket {
+    pub protocol: Protocol,
+    pub local_address: String,
+    pub local_port: u16,
+    pub remote_address: Option<String>, // None for listeners and UDP
+    pub remote_port: Option<u16>,
+    pub state: Option<String>, // TCP state in snake_case, e.g. "listen", "established"
+    pub pid: u32,
+}
+
+impl Socket {
+    /// Listening TCP socket, or an unconnected UDP socket bound on a wildcard or loopback
+    /// address. Clients' UDP sockets are connected or bound to the outgoing interface.
+    pub fn is_listening(&self) -> bool {
+        match self.protocol {
+            Protocol::Tcp => self.state.as_deref() == Some("listen"),
+            Protocol::Udp => {
+                self.remote_address.is_none()
+                    && self.local_port != 0
+                    && is_wildcard_or_loopback(&self.local_address)
+            }
+        }
+    }
+}
+
+/// "*", "0.0.0.0", "::", "127.0.0.1", "::1"; ss may add the interface, as in "0.0.0.0%lo"
+fn is_wildcard_or_loopback(address: &str) -> bool {
+    let address = address.split('%').next().unwrap_or(address);
+    address == "*"
+        || address
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_unspecified() || ip.is_loopback())
+}
+
+// Public API
+/// Every socket whose owner we are allowed to see
+pub fn sockets() -> Result<Vec<Socket>, String> {
+    #[cfg(windows)]
+    {
+        Ok(windows_sockets())
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let output = std::process::Command::new("ss")
+            .args(["-H", "-tuanp"])
+            .output()
+            .map_err(|e| format!("Failed to run ss: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("ss exited with {}", output.status));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .flat_map(parse_ss_line)
+            .collect())
+    }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        Err("Socket listing is not supported on this platform".into())
+    }
+}
+
+// ============= Linux =============
+
+/// "127.0.0.1:3000", "[::]:22", "*:5353" -> (address, port); port is None for "*"
+#[cfg(target_os = "linux")]
+fn split_endpoint(endpoint: &str) -> Option<(String, Option<u16>)> {
+    let (address, port) = endpoint.rsplit_once(':')?;
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    Some((address.to_string(), port.parse().ok()))
+}
+
+/// One `ss -H -tuanp` line; a socket shared by several processes yields one entry per PID.
+/// Lines without a users:(...) part (other users' sockets without root) are skipped.
+#[cfg(target_os = "linux")]
+fn parse_ss_line(line: &str) -> Vec<Socket> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [netid, state, _, _, local, peer, process @ ..] = fields.as_slice() else {
+        return vec![];
+    };
+    let protocol = match *netid {
+        "tcp" => Protocol::Tcp,
+        "udp" => Protocol::Udp,
+        _ => return vec![],
+    };
+    let (Some((local_address, Some(local_port))), Some((peer_address, peer_port))) =
+        (split_endpoint(local), split_endpoint(peer))
+    else {
+        return vec![];
+    };
+    let state = match (protocol, *state) {
+        (Protocol::Udp, _) => None,
+        (_, "ESTAB") => Some("established".to_string()),
+        (_, other) => Some(other.to_lowercase().replace('-', "_")),
+    };
+    let remote = peer_port.map(|port| (peer_address, port));
+
+    process
+        .join(" ")
+        .split("pid=")
+        .skip(1)
+        .filter_map(|rest| rest.split([',', ')']).next()?.parse::<u32>().ok())
+        .map(|pid| Socket {
+            protocol,
+            local_address: local_address.clone(),
+            local_port,
+            remote_address: remote.as_ref().map(|(address, _)| address.clone()),
+            remote_port: remote.as_ref().map(|(_, port)| *port),
+            state: state.clone(),
+            pid,
+        })
+        .collect()
+}
+
+// ============= Windows =============
+
+#[cfg(windows)]
+const AF_INET: u32 = 2;
+#[cfg(windows)]
+const AF_INET6: u32 = 23;
+
+#[cfg(windows)]
+fn tcp_state(state: u32) -> String {
+    match state {
+        1 => "closed",
+        2 => "listen",
+        3 => "syn_sent",
+        4 => "syn_received",
+        5 => "established",
+        6 => "fin_wait1",
+        7 => "fin_wait2",
+        8 => "close_wait",
+        9 => "closing",
+        10 => "last_ack",
+        11 => "time_wait",
+        _ => "delete_tcb",
+    }
+    .to_string()
+}
+
+/// Rows of an IP Helper table: a u32 entry count followed by the rows.
+/// `fetch` is called with a buffer and its size, and returns a Win32 error code.
+#[cfg(windows)]
+fn read_table<Row: Copy>(fetch: impl Fn(*mut std::ffi::c_void, &mut u32) -> u32) -> Vec<Row> {
+    let mut size = 0;
+    fetch(std::ptr::null_mut(), &mut size);
+    // The table can grow between the size query and the read, so retry a couple of times
+    for _ in 0..3 {
+        let mut buffer = vec![0u32; size as usize / 4 + 1];
+        if fetch(buffer.as_mut_ptr().cast(), &mut size) == 0 {
+            let count = buffer[0] as usize;
+            let rows = unsafe { buffer.as_ptr().add(1) as *const Row };
+            return (0..count)
+                .map(|i| unsafe { std::ptr::read_unaligned(rows.add(i)) })
+                .collect();
+        }
+    }
+    vec![]
+}
+
+#[cfg(windows)]
+fn windows_sockets() -> Vec<Socket> {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use windows::Win32::NetworkManagement::IpHelper::{
+        GetExtendedTcpTable, GetExtendedUdpTable, MIB_TCP6ROW_OWNER_PID, MIB_TCPROW_OWNER_PID,
+        MIB_UDP6ROW_OWNER_PID, MIB_UDPROW_OWNER_PID, TCP_TABLE_OWNER_PID_ALL, UDP_TABLE_OWNER_PID,
+    };
+
+    // Ports are stored in network byte order in the low 16 bits
+    let port = |p: u32| u16::from_be(p as u16);
+    let v4 = |a: u32| Ipv4Addr::from(a.to_ne_bytes()).to_string();
+    let v6 = |a: [u8; 16]| Ipv6Addr::from(a).to_string();
+    let tcp = |af| {
+        move |buf: *mut std::ffi::c_void, size: &mut u32| unsafe {
+            GetExtendedTcpTable(Some(buf), size, false, af, TCP_TABLE_OWNER_PID_ALL, 0)
+        }
+    };
+    let udp = |af| {
+        move |buf: *mut std::ffi::c_void, size: &mut u32| unsafe {
+            GetExtendedUdpTable(Some(buf), size, false, af, UDP_TABLE_OWNER_PID, 0)
+        }
+    };
+    let remote = |state: u32, address: String, p: u32| {
+        // Listeners report 0.0.0.0:0 as their peer
+        (state != 2).then(|| (address, port(p)))
+    };
+
+    let mut sockets = Vec::new();
+    for row in read_table::<MIB_TCPROW_OWNER_PID>(tcp(AF_INET)) {
+        let peer = remote(row.dwState, v4(row.dwRemoteAddr), row.dwRemotePort);
+        sockets.push(Socket {
+            protocol: Protocol::Tcp,
+            local_address: v4(row.dwLocalAddr),
+            local_port: port(row.dwLocalPort),
+            remote_address: peer.as_ref().map(|(a, _)| a.clone()),
+            remote_port: peer.map(|(_, p)| p),
+            state: Some(tcp_state(row.dwState)),
+            pid: row.dwOwningPid,
+        });
+    }
+    for row in read_table::<MIB_TCP6ROW_OWNER_PID>(tcp(AF_INET6)) {
+        let peer = remote(row.dwState, v6(row.ucRemoteAddr), row.dwRemotePort);
+        sockets.push(Socket {
+            protocol: Protocol::Tcp,
+            local_address: v6(row.ucLocalAddr),
+            local_port: port(row.dwLocalPort),
+            remote_address: peer.as_ref().map(|(a, _)| a.clone()),
+            remote_port: peer.map(|(_, p)| p),
+            state: Some(tcp_state(row.dwState)),
+            pid: row.dwOwningPid,
+        });
+    }
+    for row in read_table::<MIB_UDPROW_OWNER_PID>(udp(AF_INET)) {
+        sockets.push(Socket {
+            protocol: Protocol::Udp,
+            local_address: v4(row.dwLocalAddr),
+            local_port: port(row.dwLocalPort),
+            remote_address: None,
+            remote_port: None,
+            state: None,
+            pid: row.dwOwningPid,
+        });
+    }
+    for row in read_table::<MIB_UDP6ROW_OWNER_PID>(udp(AF_INET6)) {
+        sockets.push(Socket {
+            protocol: Protocol::Udp,
+            local_address: v6(row.ucLocalAddr),
+            local_port: port(row.dwLocalPort),
+            remote_address: None,
+            remote_port: None,
+            state: None,
+            pid: row.dwOwningPid,
+        });
+    }
+    sockets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn udp(local_address: &str, remote_address: Option<&str>) -> Socket {
+        Socket {
+            protocol: Protocol::Udp,
+            local_address: local_address.to_string(),
+            local_port: 5353,
+            remote_address: remote_address.map(str::to_string),
+            remote_port: remote_address.map(|_| 53),
+            state: None,
+            pid: 1,
+        }
+    }
+
+    #[test]
+    fn only_unconnected_udp_on_wildcard_or_loopback_listens() {
+        assert!(udp("0.0.0.0", None).is_listening());
+        assert!(udp("::", None).is_listening());
+        assert!(udp("*", None).is_listening());
+        assert!(udp("127.0.0.53%lo", None).is_listening());
+        assert!(!udp("192.168.1.20", None).is_listening());
+        assert!(!udp("0.0.0.0", Some("1.1.1.1")).is_listening());
+    }
+}
@@ -0,0 +1,87 @@
+// User notes and risk ratings for executables, keyed by path so a rating sticks to the exact
+// program rather than anything sharing its name. "Never touch" is a guard: manual kills, rules
+// and every background responder refuse processes rated that way.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::{read_state, with_state};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Risk {
+    #[default]
+    Unknown,
+    SafeToKill,
+    NeverTouch,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProcessNote {
+    pub exe_path: String,
+    pub note: String,
+    pub risk: Risk,
+    pub updated_at: String,
+}
+
+fn same_path(a: &str, b: &str) -> bool {
+    if cfg!(windows) {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
+// Public API
+/// The note for `exe_path`, if any
+pub fn find<'a>(notes: &'a [ProcessNote], exe_path: Option<&str>) -> Option<&'a ProcessNote> {
+    let exe_path = exe_path?;
+    notes.iter().find(|n| same_path(&n.exe_path, exe_path))
+}
+
+/// Err with the user's note when the executable is rated "never touch"
+pub fn guard(notes: &[ProcessNote], exe_path: Option<&str>) -> Result<(), String> {
+    match find(notes, exe_path) {
+        Some(n) if n.risk == Risk::NeverTouch => Err(if n.note.is_empty() {
+            format!("{} is marked \"never touch\"", n.exe_path)
+        } else {
+            format!("{} is marked \"never touch\": {}", n.exe_path, n.note)
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Same guard as `guard`, reading the notes from state
+pub fn check(exe_path: Option<&str>) -> Result<(), String> {
+    read_state(|state| guard(&state.process_notes, exe_path))
+}
+
+/// `check` for a sampled process; every path that kills, suspends or trims goes through it
+pub fn check_process(p: &sysinfo::Process) -> Result<(), String> {
+    check(p.exe().map(|e| e.to_string_lossy()).as_deref())
+}
+
+/// Add or replace the note for `exe_path`; an empty note rated "unknown" removes it
+pub fn set(exe_path: &str, note: &str, risk: Risk) -> Result<Option<ProcessNote>, String> {
+    let exe_path = exe_path.trim();
+    if exe_path.is_empty() {
+        return Err("Executable path cannot be empty".into());
+    }
+    let note = note.trim().to_string();
+    with_state(|state| {
+        state
+            .process_notes
+            .retain(|n| !same_path(&n.exe_path, exe_path));
+        if note.is_empty() && risk == Risk::Unknown {
+            return Ok(None);
+        }
+        let entry = ProcessNote {
+            exe_path: exe_path.to_string(),
+            note,
+            risk,
+            updated_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        };
+        state.process_notes.push(entry.clone());
+        Ok(Some(entry))
+    })
+}
@@ -0,0 +1,195 @@
+// Windows toast shown after an auto-kill, with buttons that link back into the app via akt://
+//
+// Each toast's links carry a single-use token, so its buttons run without asking while the same
+// link opened from anywhere else still waits for confirmation in the window.
+
+#[cfg(windows)]
+use crate::i18n::t;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// How long a toast's buttons work without confirmation, and how many toasts are kept
+const TOKEN_KEEP: Duration = Duration::from_secs(24 * 60 * 60);
+#[cfg(windows)]
+const TOKEN_MAX: usize = 50;
+
+static TOKENS: Mutex<Vec<(u64, Instant)>> = Mutex::new(Vec::new());
+
+/// Issue a token for a new toast's links
+#[cfg(windows)]
+fn issue_token() -> u64 {
+    use std::hash::BuildHasher;
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let token = std::collections::hash_map::RandomState::new().hash_one(nanos);
+    let mut tokens = TOKENS.lock().unwrap();
+    tokens.retain(|(_, issued)| issued.elapsed() < TOKEN_KEEP);
+    if tokens.len() >= TOKEN_MAX {
+        tokens.remove(0);
+    }
+    tokens.push((token, Instant::now()));
+    token
+}
+
+/// Identifier the installer registers as the app's AppUserModelID
+#[cfg(windows)]
+const APP_ID: &str = "com.ritti.tauri-app";
+
+#[cfg(windows)]
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Build an akt:// link; buttons use protocol activation so clicks reach `handle_deep_link`
+#[cfg(windows)]
+fn action_link(action: &str, params: &[(&str, &str)]) -> String {
+    let mut url = tauri::Url::parse(&format!("akt://{}", action)).expect("valid akt url");
+    for (key, value) in params {
+        url.query_pairs_mut().append_pair(key, value);
+    }
+    url.to_string()
+}
+
+#[cfg(windows)]
+fn show(xml: &str) -> windows::core::Result<()> {
+    use windows::core::HSTRING;
+    use windows::Data::Xml::Dom::XmlDocument;
+    use windows::UI::Notifications::{ToastNotification, ToastNotificationManager};
+
+    let doc = XmlDocument::new()?;
+    doc.LoadXml(&HSTRING::from(xml))?;
+    let toast = ToastNotification::CreateToastNotification(&doc)?;
+    ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))?.Show(&toast)
+}
+
+// Public API
+/// Use up the token from a toast link; true if it came from one of our toasts and wasn't used
+pub fn redeem(token: &str) -> bool {
+    let Ok(token) = token.parse::<u64>() else {
+        return false;
+    };
+    let mut tokens = TOKENS.lock().unwrap();
+    tokens.retain(|(_, issued)| issued.elapsed() < TOKEN_KEEP);
+    match tokens.iter().position(|(t, _)| *t == token) {
+        Some(idx) => {
+            tokens.remove(idx);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Whether Windows will show our toasts; None where the app shows no notifications
+pub fn permitted() -> Option<bool> {
+    #[cfg(windows)]
+    {
+        use windows::core::HSTRING;
+        use windows::UI::Notifications::{NotificationSetting, ToastNotificationManager};
+
+        ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))
+            .and_then(|notifier| notifier.Setting())
+            .map(|setting| setting == NotificationSetting::Enabled)
+            .ok()
+    }
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+/// A kill to report in the next toast
+pub struct KilledApp {
+    pub rule: String,
+    pub name: String,
+    pub restart_id: Option<u64>, // From `launch::remember_kill`
+}
+
+/// One toast for a batch of kills, with "Undo/restart", "Whitelist this process" and
+/// "Snooze rule 1h" buttons. Whitelisting needs a single kill, snoozing a single rule. The
+/// buttons share one token, so only the first one clicked skips confirmation.
+pub fn kill_toast(kills: &[KilledApp]) {
+    #[cfg(windows)]
+    {
+        let Some(first) = kills.first() else {
+            return;
+        };
+        let one_rule = kills.iter().all(|k| k.rule == first.rule);
+        let token = issue_token().to_string();
+        let ids: Vec<String> = kills
+            .iter()
+            .filter_map(|k| k.restart_id.map(|id| id.to_string()))
+            .collect();
+
+        let mut actions = String::new();
+        if !ids.is_empty() {
+            actions += &format!(
+                r#"<action content="{}" activationType="protocol" arguments="{}"/>"#,
+                escape_xml(&t("toast.undo", &[])),
+                escape_xml(&action_link(
+                    "restart",
+                    &[("ids", &ids.join(",")), ("token", &token)]
+                ))
+            );
+        }
+        if kills.len() == 1 {
+            actions += &format!(
+                r#"<action content="{}" activationType="protocol" arguments="{}"/>"#,
+                escape_xml(&t("toast.whitelist", &[])),
+                escape_xml(&action_link(
+                    "whitelist",
+                    &[
+                        ("rule", &first.rule),
+                        ("name", &first.name),
+                        ("token", &token)
+                    ]
+                ))
+            );
+        }
+        if one_rule {
+            actions += &format!(
+                r#"<action content="{}" activationType="protocol" arguments="{}"/>"#,
+                escape_xml(&t("toast.snooze", &[])),
+                escape_xml(&action_link(
+                    "snooze",
+                    &[("rule", &first.rule), ("minutes", "60"), ("token", &token)]
+                ))
+            );
+        }
+
+        let title = if kills.len() == 1 {
+            t("toast.killed", &[("name", first.name.clone())])
+        } else {
+            let mut names: Vec<&str> = kills.iter().map(|k| k.name.as_str()).collect();
+            names.dedup();
+            t(
+                "toast.killed_many",
+                &[
+                    ("count", kills.len().to_string()),
+                    ("names", names.join(", ")),
+                ],
+            )
+        };
+        let detail = if one_rule {
+            t("toast.matched_rule", &[("rule", first.rule.clone())])
+        } else {
+            t("toast.matched_rules", &[])
+        };
+        let xml = format!(
+            r#"<toast><visual><binding template="ToastGeneric"><text>{}</text><text>{}</text></binding></visual><actions>{}</actions></toast>"#,
+            escape_xml(&title),
+            escape_xml(&detail),
+            actions
+        );
+        show(&xml).ok();
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = kills;
+    }
+}
@@ -0,0 +1,290 @@
+// Chat bot notifier: kill events pushed to a Telegram chat or Discord channel, and simple
+// commands (/kill, /killpid, /stats) read back from it. Commands only run for allowlisted
+// user IDs; both services are polled over plain HTTPS, no gateway connection.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::{i18n, lock, read_state, ActivityLog};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_QUEUED: usize = 50; // Oldest kill messages are dropped past this while offline
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BotKind {
+    #[default]
+    Telegram,
+    Discord,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct BotSettings {
+    pub enabled: bool,
+    pub kind: BotKind,
+    pub token: String,
+    pub chat_id: String, // Telegram chat ID or Discord channel ID
+    pub notify_kills: bool,
+    pub remote_commands: bool,
+    pub allowed_users: Vec<String>, // User IDs whose commands are run
+}
+
+impl BotSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.token.trim().is_empty() || self.chat_id.trim().is_empty() {
+            return Err("A bot token and chat/channel ID are required".into());
+        }
+        if self.remote_commands && self.allowed_users.iter().all(|u| u.trim().is_empty()) {
+            return Err("Remote commands need at least one allowed user ID".into());
+        }
+        Ok(())
+    }
+}
+
+/// A message from the chat
+struct Incoming {
+    user_id: String,
+    text: String,
+}
+
+// Kill messages waiting for the worker, so the watcher never waits on the network
+static QUEUE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+// Bumped when settings change, so the worker starts over without replaying old commands
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build()
+}
+
+fn describe(err: ureq::Error) -> String {
+    match err {
+        ureq::Error::Status(code, _) => format!("HTTP {}", code),
+        e => e.to_string(),
+    }
+}
+
+fn telegram(settings: &BotSettings, method: &str) -> String {
+    format!(
+        "https://api.telegram.org/bot{}/{}",
+        settings.token.trim(),
+        method
+    )
+}
+
+fn discord(settings: &BotSettings, path: &str) -> String {
+    format!(
+        "https://discord.com/api/v10/channels/{}/{}",
+        settings.chat_id.trim(),
+        path
+    )
+}
+
+fn send(settings: &BotSettings, text: &str) -> Result<(), String> {
+    let result = match settings.kind {
+        BotKind::Telegram => agent()
+            .post(&telegram(settings, "sendMessage"))
+            .send_json(json!({ "chat_id": settings.chat_id.trim(), "text": text })),
+        BotKind::Discord => agent()
+            .post(&discord(settings, "messages"))
+            .set("Authorization", &format!("Bot {}", settings.token.trim()))
+            // Discord caps messages at 2000 characters
+            .send_json(json!({ "content": text.chars().take(2000).collect::<String>() })),
+    };
+    result.map(|_| ()).map_err(describe)
+}
+
+/// New messages since `cursor` (Telegram update offset / Discord message ID). Without a cursor
+/// the first poll only finds the current position, so commands sent while we were away never
+/// run; afterwards every message counts, even if the chat was empty at that first poll.
+fn fetch(settings: &BotSettings, cursor: &mut Option<u64>) -> Result<Vec<Incoming>, String> {
+    let first = cursor.is_none();
+    let mut position = cursor.unwrap_or(0);
+    let mut messages = Vec::new();
+    match settings.kind {
+        BotKind::Telegram => {
+            let updates: Value = agent()
+                .get(&telegram(settings, "getUpdates"))
+                // -1 returns only the latest update
+                .query(
+                    "offset",
+                    &cursor.map_or("-1".to_string(), |c| (c + 1).to_string()),
+                )
+                .query("timeout", "0")
+                .call()
+                .map_err(describe)?
+                .into_json()
+                .map_err(|e| e.to_string())?;
+            for update in updates["result"].as_array().into_iter().flatten() {
+                position = position.max(update["update_id"].as_u64().unwrap_or(0));
+                let message = &update["message"];
+                let chat_id = message["chat"]["id"].as_i64().map(|id| id.to_string());
+                if chat_id.as_deref() != Some(settings.chat_id.trim()) {
+                    continue;
+                }
+                messages.push(Incoming {
+                    user_id: message["from"]["id"].to_string(),
+                    text: message["text"].as_str().unwrap_or_default().to_string(),
+                });
+            }
+        }
+        BotKind::Discord => {
+            let mut request = agent()
+                .get(&discord(settings, "messages"))
+                .set("Authorization", &format!("Bot {}", settings.token.trim()));
+            request = if first {
+                request.query("limit", "1")
+            } else {
+                request
+                    .query("after", &position.to_string())
+                    .query("limit", "50")
+            };
+            let list: Value = request
+                .call()
+                .map_err(describe)?
+                .into_json()
+                .map_err(|e| e.to_string())?;
+            // Newest first
+            for message in list.as_array().into_iter().flatten().rev() {
+                let id = message["id"].as_str().and_then(|id| id.parse().ok());
+                position = position.max(id.unwrap_or(0));
+                if message["author"]["bot"].as_bool() == Some(true) {
+                    continue;
+                }
+                messages.push(Incoming {
+                    user_id: message["author"]["id"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    text: message["content"].as_str().unwrap_or_default().to_string(),
+                });
+            }
+        }
+    }
+    *cursor = Some(position);
+    Ok(if first { Vec::new() } else { messages })
+}
+
+fn stats_message() -> String {
+    let stats = crate::system_stats();
+    format!(
+        "CPU {:.0}% | Memory {:.1}/{:.1} GB ({:.0}%)",
+        stats.cpu_usage, stats.memory_used_gb, stats.memory_total_gb, stats.memory_percent
+    )
+}
+
+/// Reply to one command; None for chatter that isn't a command
+fn run_command(text: &str) -> Option<String> {
+    let mut parts = text.trim().splitn(2, ' ');
+    // Telegram appends the bot name in groups: "/kill@my_bot chrome"
+    let command = parts.next()?.split('@').next()?.to_lowercase();
+    let arg = parts.next().map(str::trim).unwrap_or_default();
+    let result = match command.as_str() {
+        "/stats" => Ok(stats_message()),
+        "/kill" if !arg.is_empty() => {
+            lock::require_unlocked().and_then(|_| crate::kill_processes_by_name(arg))
+        }
+        "/killpid" => match arg.parse::<u32>() {
+            Ok(pid) => lock::require_unlocked().and_then(|_| crate::kill_process_by_pid(pid)),
+            Err(_) => Err("Usage: /killpid <pid>".into()),
+        },
+        "/kill" | "/help" | "/start" => Ok("/stats, /kill <name>, /killpid <pid>".to_string()),
+        _ => return None,
+    };
+    Some(result.unwrap_or_else(|e| format!("Error: {}", e)))
+}
+
+fn tick(settings: &BotSettings, cursor: &mut Option<u64>) {
+    let queued: Vec<String> = std::mem::take(&mut *QUEUE.lock().unwrap());
+    for (i, text) in queued.iter().enumerate() {
+        if let Err(err) = send(settings, text) {
+            tracing::warn!("Bot message failed: {}", err);
+            // Put back what wasn't sent and retry next tick
+            let mut queue = QUEUE.lock().unwrap();
+            let unsent = queued[i..].iter().cloned();
+            let rest = std::mem::take(&mut *queue);
+            queue.extend(unsent.chain(rest));
+            let excess = queue.len().saturating_sub(MAX_QUEUED);
+            queue.drain(..excess);
+            break;
+        }
+    }
+
+    if !settings.remote_commands {
+        return;
+    }
+    match fetch(settings, cursor) {
+        Ok(messages) => {
+            for message in messages {
+                if !settings
+                    .allowed_users
+                    .iter()
+                    .any(|u| u.trim() == message.user_id)
+                {
+                    continue;
+                }
+                if let Some(reply) = run_command(&message.text) {
+                    tracing::info!(user = %message.user_id, command = %message.text, "bot command");
+                    send(settings, &reply).ok();
+                }
+            }
+        }
+        Err(err) => tracing::warn!("Bot poll failed: {}", err),
+    }
+}
+
+// Public API
+pub fn start() {
+    thread::spawn(|| {
+        let mut generation = GENERATION.load(Ordering::Relaxed);
+        let mut cursor = None;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let current = GENERATION.load(Ordering::Relaxed);
+            if current != generation {
+                generation = current;
+                cursor = None;
+            }
+            let settings = read_state(|state| state.settings.bot.clone());
+            if settings.enabled {
+                tick(&settings, &mut cursor);
+            }
+        }
+    });
+}
+
+/// Start over with the saved settings (new chat, new token...)
+pub fn reload() {
+    QUEUE.lock().unwrap().clear();
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Queue a kill message; `settings` is the saved bot configuration
+pub fn notify_kill(log: &ActivityLog, settings: &BotSettings) {
+    if !settings.enabled || !settings.notify_kills {
+        return;
+    }
+    let text = format!(
+        "🔪 {} (PID {}) - {}",
+        i18n::t("toast.killed", &[("name", log.name.clone())]),
+        log.pid,
+        log.reason
+    );
+    let mut queue = QUEUE.lock().unwrap();
+    queue.push(text);
+    let excess = queue.len().saturating_sub(MAX_QUEUED);
+    queue.drain(..excess);
+}
+
+/// Send a test message right away
+pub fn test(settings: &BotSettings) -> Result<(), String> {
+    send(settings, "AutomateKillTask notifier connected ✅")
+}
@@ -0,0 +1,468 @@
+// First-run scan that proposes a starting configuration: apps worth watching, rules worth
+// considering and a few safe defaults. Nothing is applied here; the onboarding wizard shows the
+// proposal and adds what the user ticks through the usual commands.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use crate::{boot_impact, power, protection, read_state, sampler, templates};
+
+// Long enough for CPU usage to mean something, short enough to sit through
+const SAMPLE_DURATION: Duration = Duration::from_secs(5);
+const WATCH_SUGGESTIONS: usize = 3; // Per measure: the top CPU users and the top memory users
+const HOG_CPU_PERCENT: f32 = 25.0;
+const BOOT_HOG_CPU_SECONDS: f32 = 10.0;
+
+#[derive(Serialize)]
+pub struct WatchSuggestion {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Serialize)]
+pub struct RuleSuggestion {
+    pub name: String,
+    pub auto_kill: bool,
+    pub cpu_threshold: f32,
+    pub reason: String,
+    pub template: Option<String>, // Id of the curated template it comes from
+}
+
+/// Settings worth starting with on this machine
+#[derive(Serialize)]
+pub struct SuggestedDefaults {
+    pub low_power_mode: bool, // Scanned on battery
+    pub kill_notifications: bool,
+    pub respawn_window_secs: u64,
+    pub stale_rule_days: u32,
+}
+
+#[derive(Serialize)]
+pub struct FirstRunProposal {
+    pub watchlist: Vec<WatchSuggestion>,
+    pub blacklist: Vec<RuleSuggestion>,
+    pub defaults: SuggestedDefaults,
+    pub installed_apps: usize,
+    pub startup_items: Vec<String>,
+    pub sampled_secs: u64,
+}
+
+/// One app's usage over the sample, all instances summed
+struct Usage {
+    name: String,
+    cpu: f32,
+    memory_kb: u64,
+}
+
+/// "Foo.exe" -> "foo", the form rules and the watchlist use
+fn base_name(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    name.strip_suffix(".exe").unwrap_or(&name).to_string()
+}
+
+/// Executable of a command line such as `"C:\Apps\foo.exe" --tray` or `/usr/bin/foo %U`
+fn command_name(command: &str) -> Option<String> {
+    let command = command.trim();
+    let program = match command.strip_prefix('"') {
+        Some(rest) => rest.split('"').next()?,
+        None => command.split_whitespace().next()?,
+    };
+    let file = program.rsplit(['/', '\\']).next()?;
+    (!file.is_empty()).then(|| base_name(file))
+}
+
+// Also used by launch_block.rs to find its Image File Execution Options entries
+#[cfg(windows)]
+pub mod registry {
+    use windows::core::{HSTRING, PWSTR};
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegEnumKeyExW, RegEnumValueW, RegGetValueW, RegOpenKeyExW, HKEY, KEY_READ,
+        RRF_RT_REG_SZ,
+    };
+
+    fn open(root: HKEY, path: &str) -> Option<HKEY> {
+        let mut key = HKEY::default();
+        unsafe { RegOpenKeyExW(root, &HSTRING::from(path), 0, KEY_READ, &mut key) }
+            .is_ok()
+            .then_some(key)
+    }
+
+    fn text(buf: &[u16]) -> String {
+        let end = buf.iter().position(|c| *c == 0).unwrap_or(buf.len());
+        String::from_utf16_lossy(&buf[..end])
+    }
+
+    pub fn subkeys(root: HKEY, path: &str) -> Vec<String> {
+        let mut names = Vec::new();
+        let Some(key) = open(root, path) else {
+            return names;
+        };
+        let mut buf = [0u16; 512];
+        for index in 0.. {
+            let mut len = buf.len() as u32;
+            let result = unsafe {
+                RegEnumKeyExW(
+                    key,
+                    index,
+                    PWSTR(buf.as_mut_ptr()),
+                    &mut len,
+                    None,
+                    PWSTR::null(),
+                    None,
+                    None,
+                )
+            };
+            if result.is_err() {
+                break;
+            }
+            names.push(String::from_utf16_lossy(&buf[..len as usize]));
+        }
+        unsafe {
+            let _ = RegCloseKey(key);
+        }
+        names
+    }
+
+    pub fn string(root: HKEY, path: &str, value: &str) -> Option<String> {
+        let mut buf = [0u16; 1024];
+        let mut size = (buf.len() * 2) as u32;
+        unsafe {
+            RegGetValueW(
+                root,
+                &HSTRING::from(path),
+                &HSTRING::from(value),
+                RRF_RT_REG_SZ,
+                None,
+                Some(buf.as_mut_ptr() as *mut _),
+                Some(&mut size),
+            )
+        }
+        .ok()
+        .map(|_| text(&buf))
+    }
+
+    /// String data of every value under `path`
+    pub fn values(root: HKEY, path: &str) -> Vec<String> {
+        let mut data = Vec::new();
+        let Some(key) = open(root, path) else {
+            return data;
+        };
+        let mut name = [0u16; 512];
+        let mut buf = [0u16; 1024];
+        for index in 0.. {
+            let mut name_len = name.len() as u32;
+            let mut size = (buf.len() * 2) as u32;
+            let result = unsafe {
+                RegEnumValueW(
+                    key,
+                    index,
+                    PWSTR(name.as_mut_ptr()),
+                    &mut name_len,
+                    None,
+                    None,
+                    Some(buf.as_mut_ptr() as *mut u8),
+                    Some(&mut size),
+                )
+            };
+            if result.is_err() {
+                break;
+            }
+            data.push(text(&buf[..size as usize / 2]));
+        }
+        unsafe {
+            let _ = RegCloseKey(key);
+        }
+        data
+    }
+}
+
+/// Display names of installed programs, lower-cased
+#[cfg(windows)]
+fn installed_apps() -> Vec<String> {
+    use windows::Win32::System::Registry::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+
+    const UNINSTALL: &str = r"Software\Microsoft\Windows\CurrentVersion\Uninstall";
+    const UNINSTALL_32: &str = r"Software\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall";
+
+    let mut apps = Vec::new();
+    for (root, path) in [
+        (HKEY_LOCAL_MACHINE, UNINSTALL),
+        (HKEY_LOCAL_MACHINE, UNINSTALL_32),
+        (HKEY_CURRENT_USER, UNINSTALL),
+    ] {
+        for key in registry::subkeys(root, path) {
+            if let Some(name) = registry::string(root, &format!(r"{}\{}", path, key), "DisplayName")
+            {
+                apps.push(name.to_lowercase());
+            }
+        }
+    }
+    apps.sort();
+    apps.dedup();
+    apps
+}
+
+/// Executables started at sign-in from the Run keys
+#[cfg(windows)]
+fn startup_items() -> Vec<String> {
+    use windows::Win32::System::Registry::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+
+    const RUN: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+
+    let mut items: Vec<String> = [HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE]
+        .into_iter()
+        .flat_map(|root| registry::values(root, RUN))
+        .filter_map(|command| command_name(&command))
+        .collect();
+    items.sort();
+    items.dedup();
+    items
+}
+
+/// (Name, Exec) of the .desktop files in `dirs`, skipping hidden entries
+#[cfg(target_os = "linux")]
+fn desktop_entries(dirs: &[std::path::PathBuf]) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    for dir in dirs {
+        let Ok(files) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for file in files.flatten() {
+            if file.path().extension().is_none_or(|ext| ext != "desktop") {
+                continue;
+            }
+            let Ok(text) = std::fs::read_to_string(file.path()) else {
+                continue;
+            };
+            let field = |key: &str| {
+                text.lines()
+                    .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                    .map(str::trim)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            if field("Hidden") != "true" && field("NoDisplay") != "true" {
+                entries.push((field("Name"), field("Exec")));
+            }
+        }
+    }
+    entries
+}
+
+#[cfg(target_os = "linux")]
+fn installed_apps() -> Vec<String> {
+    let mut dirs = vec![std::path::PathBuf::from("/usr/share/applications")];
+    dirs.extend(dirs::data_dir().map(|d| d.join("applications")));
+    let mut apps: Vec<String> = desktop_entries(&dirs)
+        .into_iter()
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, _)| name.to_lowercase())
+        .collect();
+    apps.sort();
+    apps.dedup();
+    apps
+}
+
+#[cfg(target_os = "linux")]
+fn startup_items() -> Vec<String> {
+    let dirs: Vec<_> = dirs::config_dir()
+        .map(|d| d.join("autostart"))
+        .into_iter()
+        .collect();
+    let mut items: Vec<String> = desktop_entries(&dirs)
+        .into_iter()
+        .filter_map(|(_, exec)| command_name(&exec))
+        .collect();
+    items.sort();
+    items.dedup();
+    items
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn installed_apps() -> Vec<String> {
+    vec![]
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn startup_items() -> Vec<String> {
+    vec![]
+}
+
+/// Usage of every app that can be acted on, over about SAMPLE_DURATION
+fn sample_usage() -> Vec<Usage> {
+    // The first pass gives CPU usage a starting point
+    sampler::with_all_processes(|_, _| ());
+    thread::sleep(SAMPLE_DURATION);
+    sampler::with_all_processes(|sys, cpu_count| {
+        let own = protection::own_pids(sys);
+        let mut usage: HashMap<String, Usage> = HashMap::new();
+        for (pid, p) in sys.processes() {
+            if own.contains(&pid.as_u32()) || protection::check(p, false).is_err() {
+                continue;
+            }
+            let name = base_name(&p.name().to_string_lossy());
+            let app = usage.entry(name.clone()).or_insert(Usage {
+                name,
+                cpu: 0.0,
+                memory_kb: 0,
+            });
+            app.cpu += p.cpu_usage() / cpu_count;
+            app.memory_kb += p.memory() / 1024;
+        }
+        usage.into_values().collect()
+    })
+}
+
+fn suggest_watchlist(usage: &mut [Usage], known: &[String]) -> Vec<WatchSuggestion> {
+    let mut suggestions: Vec<WatchSuggestion> = Vec::new();
+    let mut add = |name: &str, reason: String| {
+        if !known.iter().any(|k| k == name) && !suggestions.iter().any(|s| s.name == name) {
+            suggestions.push(WatchSuggestion {
+                name: name.to_string(),
+                reason,
+            });
+        }
+    };
+
+    usage.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+    for app in usage
+        .iter()
+        .filter(|a| a.cpu >= 1.0)
+        .take(WATCH_SUGGESTIONS)
+    {
+        add(
+            &app.name,
+            format!("Used {:.0}% CPU during the scan", app.cpu),
+        );
+    }
+    usage.sort_by(|a, b| b.memory_kb.cmp(&a.memory_kb));
+    for app in usage.iter().take(WATCH_SUGGESTIONS) {
+        add(
+            &app.name,
+            format!(
+                "Holds {:.1} GB of memory",
+                app.memory_kb as f64 / 1024.0 / 1024.0
+            ),
+        );
+    }
+    suggestions
+}
+
+fn suggest_rules(
+    usage: &[Usage],
+    installed: &[String],
+    startup: &[String],
+    known: &[String],
+) -> Vec<RuleSuggestion> {
+    let mut suggestions: Vec<RuleSuggestion> = Vec::new();
+    let mut add = |suggestion: RuleSuggestion| {
+        if !known.contains(&suggestion.name)
+            && !suggestions.iter().any(|s| s.name == suggestion.name)
+        {
+            suggestions.push(suggestion);
+        }
+    };
+
+    // Curated templates for software that is actually here
+    let present = |name: &str| {
+        installed.iter().any(|app| app.contains(name))
+            || startup.iter().any(|item| item == name)
+            || usage.iter().any(|app| app.name == name)
+    };
+    for template in templates::list_templates() {
+        for rule in template
+            .rules
+            .iter()
+            .filter(|r| present(&base_name(&r.name)))
+        {
+            add(RuleSuggestion {
+                name: base_name(&rule.name),
+                auto_kill: rule.auto_kill,
+                cpu_threshold: rule.cpu_threshold,
+                reason: template.title.clone(),
+                template: Some(template.id.clone()),
+            });
+        }
+    }
+
+    // Busy right now; proposed without auto-kill so the user sees them in action first
+    for app in usage.iter().filter(|a| a.cpu >= HOG_CPU_PERCENT) {
+        add(RuleSuggestion {
+            name: app.name.clone(),
+            auto_kill: false,
+            cpu_threshold: 50.0,
+            reason: format!("Used {:.0}% CPU during the scan", app.cpu),
+            template: None,
+        });
+    }
+
+    // Starts with the system and was expensive during the last boot
+    if let Some(report) = boot_impact::report() {
+        for app in report
+            .apps
+            .iter()
+            .filter(|a| a.cpu_seconds >= BOOT_HOG_CPU_SECONDS)
+        {
+            let name = base_name(&app.name);
+            if startup.contains(&name) {
+                add(RuleSuggestion {
+                    reason: format!(
+                        "Starts at sign-in and used {:.0}s of CPU during boot",
+                        app.cpu_seconds
+                    ),
+                    name,
+                    auto_kill: false,
+                    cpu_threshold: 0.0,
+                    template: None,
+                });
+            }
+        }
+    }
+    suggestions
+}
+
+// Public API
+/// Look over the machine and propose a starting configuration. Blocks for SAMPLE_DURATION.
+pub fn scan() -> FirstRunProposal {
+    let installed = installed_apps();
+    let startup = startup_items();
+    let mut usage = sample_usage();
+    let (watched, ruled) = read_state(|state| {
+        (
+            state
+                .watchlist
+                .iter()
+                .map(|n| base_name(n))
+                .collect::<Vec<_>>(),
+            state
+                .blacklist
+                .iter()
+                .map(|e| base_name(&e.name))
+                .collect::<Vec<_>>(),
+        )
+    });
+
+    let blacklist = suggest_rules(&usage, &installed, &startup, &ruled);
+    let watchlist = suggest_watchlist(&mut usage, &watched);
+    tracing::info!(
+        installed = installed.len(),
+        startup = startup.len(),
+        rules = blacklist.len(),
+        "first-run scan"
+    );
+    FirstRunProposal {
+        watchlist,
+        blacklist,
+        defaults: SuggestedDefaults {
+            low_power_mode: power::status().on_battery,
+            kill_notifications: true,
+            respawn_window_secs: 30,
+            stale_rule_days: 90,
+        },
+        installed_apps: installed.len(),
+        startup_items: startup,
+        sampled_secs: SAMPLE_DURATION.as_secs(),
+    }
+}
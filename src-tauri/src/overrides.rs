@@ -0,0 +1,154 @@
+// Startup overrides for headless and managed deployments. Environment variables and command-line
+// flags take precedence over the saved settings for this run only and are never written back;
+// a flag beats the matching variable.
+//
+//   --data-dir <path>       AKT_DATA_DIR           Where the state, logs and config.toml live
+//   --poll-interval <ms>    AKT_POLL_INTERVAL_MS   How often the watcher checks the blacklist
+//   --dry-run               AKT_DRY_RUN=1          Detect and log, but never act automatically
+//   --api-port <port>       AKT_API_PORT           Port of the agent's REST API
+//   --log-level <level>     AKT_LOG_LEVEL          Diagnostic log level, see logging.rs
+//   --portable              AKT_PORTABLE=1         Keep data next to the executable, see storage.rs
+
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const MIN_POLL_INTERVAL_MS: u64 = 250;
+
+#[derive(Serialize, Clone, Default)]
+pub struct Overrides {
+    pub data_dir: Option<PathBuf>,
+    pub poll_interval_ms: Option<u64>,
+    pub dry_run: bool,
+    pub api_port: Option<u16>,
+    pub log_level: Option<String>,
+    pub portable: bool,
+}
+
+// Set once at startup, before the state is first loaded
+static OVERRIDES: OnceLock<Overrides> = OnceLock::new();
+
+fn parse_poll_interval(value: &str) -> Result<u64, String> {
+    value
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|ms| *ms >= MIN_POLL_INTERVAL_MS)
+        .ok_or_else(|| {
+            format!(
+                "Poll interval must be at least {} ms, got \"{}\"",
+                MIN_POLL_INTERVAL_MS, value
+            )
+        })
+}
+
+fn parse_port(value: &str) -> Result<u16, String> {
+    value
+        .trim()
+        .parse::<u16>()
+        .ok()
+        .filter(|port| *port > 0)
+        .ok_or_else(|| format!("API port must be between 1 and 65535, got \"{}\"", value))
+}
+
+fn parse_flag(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+fn from_env(env: impl Fn(&str) -> Option<String>) -> Result<Overrides, String> {
+    let env = |key: &str| env(key).filter(|v| !v.trim().is_empty());
+    Ok(Overrides {
+        data_dir: env("AKT_DATA_DIR").map(PathBuf::from),
+        poll_interval_ms: env("AKT_POLL_INTERVAL_MS")
+            .map(|v| parse_poll_interval(&v))
+            .transpose()?,
+        dry_run: env("AKT_DRY_RUN").is_some_and(|v| parse_flag(&v)),
+        api_port: env("AKT_API_PORT").map(|v| parse_port(&v)).transpose()?,
+        log_level: env("AKT_LOG_LEVEL"),
+        portable: env("AKT_PORTABLE").is_some_and(|v| parse_flag(&v)),
+    })
+}
+
+/// Apply the flags in `args` on top of `overrides`; returns the arguments left for the caller
+fn apply_args(overrides: &mut Overrides, args: &[String]) -> Result<Vec<String>, String> {
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--data-dir" => overrides.data_dir = Some(PathBuf::from(value()?)),
+            "--poll-interval" => overrides.poll_interval_ms = Some(parse_poll_interval(&value()?)?),
+            "--dry-run" => overrides.dry_run = true,
+            "--api-port" => overrides.api_port = Some(parse_port(&value()?)?),
+            "--log-level" => overrides.log_level = Some(value()?),
+            "--portable" => overrides.portable = true,
+            // Everything after it is a blocked program's command line, see launch_block.rs
+            crate::launch_block::BLOCKED_FLAG => {
+                rest.push(arg.clone());
+                rest.extend(args.cloned());
+                break;
+            }
+            _ => rest.push(arg.clone()),
+        }
+    }
+    Ok(rest)
+}
+
+fn get() -> &'static Overrides {
+    OVERRIDES.get_or_init(Overrides::default)
+}
+
+// Public API
+/// Read the environment and take the override flags out of `args` (program name included),
+/// returning the rest. Call before anything touches the state; later calls change nothing.
+pub fn init(args: &[String]) -> Result<Vec<String>, String> {
+    let mut overrides = from_env(|key| std::env::var(key).ok())?;
+    let rest = apply_args(&mut overrides, args)?;
+    if let Some(level) = &overrides.log_level {
+        // Fail here rather than silently logging at the saved level
+        crate::logging::parse(level)?;
+    }
+    if let Some(dir) = &overrides.data_dir {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Cannot use data directory {}: {}", dir.display(), e))?;
+    }
+    OVERRIDES.set(overrides).ok();
+    Ok(rest)
+}
+
+pub fn current() -> Overrides {
+    get().clone()
+}
+
+pub fn data_dir() -> Option<PathBuf> {
+    get().data_dir.clone()
+}
+
+pub fn poll_interval() -> Option<Duration> {
+    get().poll_interval_ms.map(Duration::from_millis)
+}
+
+pub fn dry_run() -> bool {
+    get().dry_run
+}
+
+pub fn api_port() -> Option<u16> {
+    get().api_port
+}
+
+pub fn portable() -> bool {
+    get().portable
+}
+
+/// The log level to use: the override if there is one, else the saved setting
+pub fn log_level(saved: &str) -> String {
+    get().log_level.clone().unwrap_or_else(|| saved.to_string())
+}
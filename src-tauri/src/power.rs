@@ -0,0 +1,119 @@
+// Low-power mode: slower polling, no GPU counters and batched state writes
+// while on battery or while the window is hidden
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+// Every backend interval is stretched by this factor while low-power mode is active
+const INTERVAL_FACTOR: u32 = 4;
+// How often the battery is checked and deferred state writes are flushed
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+// Frontend refresh rate in normal mode
+const REFRESH_MS: u64 = 1000;
+
+// Kept outside AppState so they can be read while the state lock is held
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static ON_BATTERY: AtomicBool = AtomicBool::new(false);
+static WINDOW_HIDDEN: AtomicBool = AtomicBool::new(false);
+// Set by the self-usage watchdog while the app is over its resource budget
+static THROTTLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize)]
+pub struct PowerStatus {
+    pub enabled: bool,
+    pub on_battery: bool,
+    pub window_hidden: bool,
+    pub active: bool,
+    pub throttled: bool,
+    pub refresh_interval_ms: u64,
+}
+
+#[cfg(windows)]
+fn on_battery() -> bool {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    // ACLineStatus: 0 = offline, 1 = online, 255 = unknown
+    unsafe { GetSystemPowerStatus(&mut status).is_ok() && status.ACLineStatus == 0 }
+}
+
+#[cfg(not(windows))]
+fn on_battery() -> bool {
+    // Any "Mains"/"USB" supply reporting online=0 means we're running off the battery
+    let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    let mut has_battery = false;
+    let mut ac_online = false;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let kind = std::fs::read_to_string(path.join("type")).unwrap_or_default();
+        match kind.trim() {
+            "Battery" => has_battery = true,
+            "Mains" | "USB" => {
+                let online = std::fs::read_to_string(path.join("online")).unwrap_or_default();
+                ac_online |= online.trim() == "1";
+            }
+            _ => {}
+        }
+    }
+    has_battery && !ac_online
+}
+
+// Public API
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn set_window_hidden(hidden: bool) {
+    WINDOW_HIDDEN.store(hidden, Ordering::Relaxed);
+}
+
+/// Enabled in settings and currently on battery or hidden
+pub fn is_active() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+        && (ON_BATTERY.load(Ordering::Relaxed) || WINDOW_HIDDEN.load(Ordering::Relaxed))
+}
+
+pub fn set_throttled(throttled: bool) {
+    THROTTLED.store(throttled, Ordering::Relaxed);
+}
+
+pub fn is_throttled() -> bool {
+    THROTTLED.load(Ordering::Relaxed)
+}
+
+/// `base` stretched while low-power mode is active or the app is over budget
+pub fn interval(base: Duration) -> Duration {
+    if is_active() || is_throttled() {
+        base * INTERVAL_FACTOR
+    } else {
+        base
+    }
+}
+
+pub fn status() -> PowerStatus {
+    PowerStatus {
+        enabled: ENABLED.load(Ordering::Relaxed),
+        on_battery: ON_BATTERY.load(Ordering::Relaxed),
+        window_hidden: WINDOW_HIDDEN.load(Ordering::Relaxed),
+        active: is_active(),
+        throttled: is_throttled(),
+        refresh_interval_ms: interval(
+            crate::overrides::poll_interval().unwrap_or(Duration::from_millis(REFRESH_MS)),
+        )
+        .as_millis() as u64,
+    }
+}
+
+/// Track the power source and flush state writes deferred by `with_state`
+pub fn start_monitor() {
+    ON_BATTERY.store(on_battery(), Ordering::Relaxed);
+    thread::spawn(|| loop {
+        thread::sleep(POLL_INTERVAL);
+        ON_BATTERY.store(on_battery(), Ordering::Relaxed);
+        crate::flush_state();
+    });
+}
@@ -0,0 +1,293 @@
+// What is keeping the machine awake: `powercfg /requests` on Windows, logind inhibitors on Linux
+
+use serde::{Deserialize, Serialize};
+
+use crate::containers::hidden_command;
+#[cfg(windows)]
+use crate::{matcher::Matcher, sampler};
+
+/// What a request stops from happening
+#[cfg_attr(not(windows), allow(dead_code))] // Only powercfg reports the finer kinds
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestKind {
+    Display,   // Display-off / idle
+    System,    // Sleep
+    AwayMode,  // Away mode instead of sleep (media center style)
+    Execution, // Sleep, for a modern-standby app
+    PerfTrace,
+    ActiveLockScreen,
+    Other, // Shutdown, lid switch and other logind inhibitors
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Requester {
+    Process,
+    Service,
+    Driver,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PowerRequest {
+    pub requester: Requester,
+    pub name: String, // Executable file name, service name or driver description
+    pub path: Option<String>,
+    pub reason: Option<String>,
+    pub kinds: Vec<RequestKind>,
+    pub pids: Vec<u32>, // Running processes behind the request (processes only)
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = hidden_command(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("{} is not available: {}", program, e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        // powercfg reports errors (e.g. missing admin rights) on stdout
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Err(if stderr.is_empty() { stdout } else { stderr })
+    }
+}
+
+fn merge(requests: &mut Vec<PowerRequest>, request: PowerRequest) {
+    match requests
+        .iter_mut()
+        .find(|r| r.requester == request.requester && r.name == request.name)
+    {
+        Some(existing) => {
+            for kind in request.kinds {
+                if !existing.kinds.contains(&kind) {
+                    existing.kinds.push(kind);
+                }
+            }
+            if existing.reason.is_none() {
+                existing.reason = request.reason;
+            }
+            for pid in request.pids {
+                if !existing.pids.contains(&pid) {
+                    existing.pids.push(pid);
+                }
+            }
+        }
+        None => requests.push(request),
+    }
+}
+
+/// Parse `powercfg /requests`. Section headers ("DISPLAY:") aren't localized, but the
+/// "None." placeholder is, so only lines right after an entry are taken as its reason.
+#[cfg(windows)]
+fn parse_powercfg(output: &str) -> Vec<PowerRequest> {
+    let mut requests: Vec<PowerRequest> = Vec::new();
+    let mut kind = RequestKind::Other;
+    let mut last: Option<PowerRequest> = None;
+
+    for line in output.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        let section = line
+            .strip_suffix(':')
+            .filter(|s| s.chars().all(|c| c.is_ascii_uppercase()));
+        if let Some(section) = section {
+            if let Some(previous) = last.take() {
+                merge(&mut requests, previous);
+            }
+            kind = match section {
+                "DISPLAY" => RequestKind::Display,
+                "SYSTEM" => RequestKind::System,
+                "AWAYMODE" => RequestKind::AwayMode,
+                "EXECUTION" => RequestKind::Execution,
+                "PERFTRACE" => RequestKind::PerfTrace,
+                "ACTIVELOCKSCREEN" => RequestKind::ActiveLockScreen,
+                _ => RequestKind::Other,
+            };
+            continue;
+        }
+
+        let entry = line.strip_prefix('[').and_then(|rest| rest.split_once(']'));
+        match (entry, last.as_mut()) {
+            (Some((requester, target)), _) => {
+                if let Some(previous) = last.take() {
+                    merge(&mut requests, previous);
+                }
+                let target = target.trim();
+                let (requester, name, path) = match requester {
+                    "PROCESS" => (
+                        Requester::Process,
+                        target.rsplit('\\').next().unwrap_or(target).to_string(),
+                        Some(target.to_string()),
+                    ),
+                    // "\Device\...\svchost.exe (ServiceName)"
+                    "SERVICE" => {
+                        let (path, service) = target
+                            .rsplit_once(" (")
+                            .map(|(path, name)| (path, name.trim_end_matches(')')))
+                            .unwrap_or((target, target));
+                        (
+                            Requester::Service,
+                            service.to_string(),
+                            Some(path.to_string()),
+                        )
+                    }
+                    _ => (Requester::Driver, target.to_string(), None),
+                };
+                last = Some(PowerRequest {
+                    requester,
+                    name,
+                    path,
+                    reason: None,
+                    kinds: vec![kind],
+                    pids: Vec::new(),
+                });
+            }
+            (None, Some(request)) if request.reason.is_none() => {
+                request.reason = Some(line.to_string());
+            }
+            _ => {}
+        }
+    }
+    if let Some(previous) = last {
+        merge(&mut requests, previous);
+    }
+    requests
+}
+
+/// Fill in the PIDs of processes whose executable matches each process request.
+/// powercfg prints "\Device\HarddiskVolumeN\..." paths, so drive letters are compared away.
+#[cfg(windows)]
+fn attach_pids(requests: &mut [PowerRequest]) {
+    let names: Vec<Matcher> = requests
+        .iter()
+        .filter(|r| r.requester == Requester::Process)
+        .map(|r| Matcher::for_name(&r.name))
+        .collect();
+    if names.is_empty() {
+        return;
+    }
+    let is_wanted = |name: &str| names.iter().any(|m| m.matches(name));
+    sampler::with_processes(
+        |p| is_wanted(&p.name().to_string_lossy()),
+        |sys, _| {
+            for request in requests
+                .iter_mut()
+                .filter(|r| r.requester == Requester::Process)
+            {
+                let device_path = request.path.as_deref().unwrap_or("").to_lowercase();
+                request.pids = sys
+                    .processes()
+                    .values()
+                    .filter(|p| {
+                        p.name()
+                            .to_string_lossy()
+                            .eq_ignore_ascii_case(&request.name)
+                    })
+                    .filter(|p| {
+                        p.exe().is_none_or(|exe| {
+                            let exe = exe.to_string_lossy().to_lowercase();
+                            let without_drive = exe.get(2..).unwrap_or(&exe);
+                            device_path.is_empty() || device_path.ends_with(without_drive)
+                        })
+                    })
+                    .map(|p| p.pid().as_u32())
+                    .collect();
+            }
+        },
+    );
+}
+
+/// `busctl --json=short call ... ListInhibitors` returns
+/// {"type":"a(ssssuu)","data":[[[what, who, why, mode, uid, pid], ...]]}
+#[cfg(not(windows))]
+fn parse_inhibitors(output: &str) -> Result<Vec<PowerRequest>, String> {
+    let json: serde_json::Value =
+        serde_json::from_str(output).map_err(|e| format!("Unexpected logind reply: {}", e))?;
+    let rows = json["data"][0].as_array().cloned().unwrap_or_default();
+
+    let mut requests = Vec::new();
+    for row in rows {
+        let text = |i: usize| row[i].as_str().unwrap_or("").to_string();
+        // "delay" inhibitors only postpone sleep briefly
+        if text(3) != "block" {
+            continue;
+        }
+        let kinds = text(0)
+            .split(':')
+            .map(|what| match what {
+                "idle" => RequestKind::Display,
+                "sleep" => RequestKind::System,
+                _ => RequestKind::Other,
+            })
+            .fold(Vec::new(), |mut kinds, kind| {
+                if !kinds.contains(&kind) {
+                    kinds.push(kind);
+                }
+                kinds
+            });
+        let why = text(2);
+        merge(
+            &mut requests,
+            PowerRequest {
+                requester: Requester::Process,
+                name: text(1),
+                path: None,
+                reason: (!why.is_empty()).then_some(why),
+                kinds,
+                pids: row[5].as_u64().map(|pid| pid as u32).into_iter().collect(),
+            },
+        );
+    }
+    Ok(requests)
+}
+
+// Public API
+/// Everything currently blocking sleep or display-off (Windows needs admin rights)
+pub fn list() -> Result<Vec<PowerRequest>, String> {
+    #[cfg(windows)]
+    {
+        let mut requests = parse_powercfg(&run("powercfg", &["/requests"])?);
+        attach_pids(&mut requests);
+        Ok(requests)
+    }
+    #[cfg(not(windows))]
+    {
+        let output = run(
+            "busctl",
+            &[
+                "--json=short",
+                "call",
+                "org.freedesktop.login1",
+                "/org/freedesktop/login1",
+                "org.freedesktop.login1.Manager",
+                "ListInhibitors",
+            ],
+        )?;
+        parse_inhibitors(&output)
+    }
+}
+
+/// Tell Windows to disregard `name`'s requests (`powercfg /requestsoverride`); `ignore = false`
+/// removes the override again
+pub fn set_ignored(requester: Requester, name: &str, ignore: bool) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        let kind = match requester {
+            Requester::Process => "PROCESS",
+            Requester::Service => "SERVICE",
+            Requester::Driver => "DRIVER",
+        };
+        let mut args = vec!["/requestsoverride", kind, name];
+        if ignore {
+            args.extend(["DISPLAY", "SYSTEM", "AWAYMODE", "EXECUTION"]);
+        }
+        run("powercfg", &args).map(|_| ())
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = (requester, name, ignore);
+        Err("Ignoring power requests is only supported on Windows".into())
+    }
+}
@@ -0,0 +1,547 @@
+// Pressure responders. Memory: when free memory runs low, trim, suspend or kill processes
+// from a user-ranked sacrifice list until enough is free again (an OOM-killer analog).
+// CPU: when total CPU stays high, act on blacklist rules one at a time in priority order
+// instead of killing everything that matches.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{MemoryRefreshKind, Process, System};
+
+use crate::backend::KillErrorKind;
+use crate::matcher::Matcher;
+use crate::metrics::{self, MetricKind};
+use crate::system_actions::{self, RuleAction};
+use crate::{
+    confirm, emit_event, environment, launcher, maintenance, notes, notify_event, process_ctl,
+    process_log, protection, quiet_hours, read_state, record_activity, refresher, rule_may_act,
+    run_on_kill_script, sampler, sessions, with_state, ActivityLog, BlacklistEntry,
+};
+
+const TICK: Duration = Duration::from_secs(5);
+// A trimmed working set grows back; don't trim the same process again sooner than this
+const TRIM_COOLDOWN: Duration = Duration::from_secs(60);
+// Suspended processes are resumed once free memory is this many points above the threshold
+const RESUME_MARGIN: f32 = 10.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PressureAction {
+    Trim, // Page out the working set (Windows only)
+    Suspend,
+    Kill,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Sacrifice {
+    pub name: String,
+    pub action: PressureAction,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PressurePolicy {
+    pub enabled: bool,
+    pub min_free_percent: f32,
+    pub sacrifices: Vec<Sacrifice>, // Lowest priority first
+}
+
+impl Default for PressurePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_free_percent: 10.0,
+            sacrifices: Vec::new(),
+        }
+    }
+}
+
+impl PressurePolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1.0..=90.0).contains(&self.min_free_percent) {
+            return Err("Free memory threshold must be between 1 and 90%".into());
+        }
+        if self.sacrifices.iter().any(|s| s.name.trim().is_empty()) {
+            return Err("Sacrifice list entries need a process name".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CpuPressurePolicy {
+    pub enabled: bool,
+    pub cpu_percent: f32,
+    pub sustain_secs: u64, // How long CPU must stay above `cpu_percent` before each action
+}
+
+impl Default for CpuPressurePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cpu_percent: 90.0,
+            sustain_secs: 30,
+        }
+    }
+}
+
+impl CpuPressurePolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(10.0..=100.0).contains(&self.cpu_percent) {
+            return Err("CPU threshold must be between 10 and 100%".into());
+        }
+        if !(5..=3600).contains(&self.sustain_secs) {
+            return Err("Sustain time must be between 5 seconds and 1 hour".into());
+        }
+        Ok(())
+    }
+}
+
+/// Sent as "memory-pressure" after the responder acted
+#[derive(Serialize, Clone)]
+struct PressureEvent {
+    free_percent: f32,
+    relieved: bool,
+    actions: Vec<String>,
+}
+
+/// Sent as "cpu-pressure" after the responder acted on a rule
+#[derive(Serialize, Clone)]
+struct CpuPressureEvent {
+    cpu_percent: f32,
+    rule: String,
+    actions: Vec<String>,
+}
+
+struct Acted {
+    trimmed: HashMap<(u32, u64), Instant>,
+    suspended: Vec<(u32, u64)>, // PID and start time, so a recycled PID isn't resumed
+}
+
+static ACTED: Mutex<Option<Acted>> = Mutex::new(None);
+// When total CPU last went over the threshold; reset after each action so the next one
+// waits for the load to stay high again
+static CPU_HIGH_SINCE: Mutex<Option<Instant>> = Mutex::new(None);
+
+// Separate from the process table so it can be re-read while processes are borrowed
+fn free_percent() -> f32 {
+    let mut sys = System::new();
+    sys.refresh_memory_specifics(MemoryRefreshKind::nothing().with_ram());
+    match sys.total_memory() {
+        0 => 100.0,
+        total => sys.available_memory() as f32 / total as f32 * 100.0,
+    }
+}
+
+fn act(
+    p: &Process,
+    action: PressureAction,
+    acted: &mut Acted,
+    escalation: &process_ctl::EscalationPolicy,
+) -> Option<Result<(), String>> {
+    let key = (p.pid().as_u32(), p.start_time());
+    match action {
+        PressureAction::Trim => {
+            if acted
+                .trimmed
+                .get(&key)
+                .is_some_and(|at| at.elapsed() < TRIM_COOLDOWN)
+            {
+                return None;
+            }
+            acted.trimmed.insert(key, Instant::now());
+            Some(process_ctl::trim_memory(key.0))
+        }
+        PressureAction::Suspend => {
+            if acted.suspended.contains(&key) {
+                return None;
+            }
+            let result = process_ctl::suspend_process(key.0);
+            if result.is_ok() {
+                acted.suspended.push(key);
+            }
+            Some(result)
+        }
+        PressureAction::Kill => Some(process_ctl::terminate(p, escalation).map_err(|kind| {
+            match kind {
+                KillErrorKind::AccessDenied => "access denied",
+                KillErrorKind::StillRunning => "still running",
+                KillErrorKind::NeverTouch => "marked never touch",
+                _ => "already exited",
+            }
+            .to_string()
+        })),
+    }
+}
+
+/// Resume what we suspended; returns how many were still there to resume
+fn resume_suspended(acted: &mut Acted) -> usize {
+    acted
+        .suspended
+        .drain(..)
+        .filter(|(pid, start_time)| process_ctl::resume_same(*pid, *start_time).is_ok())
+        .count()
+}
+
+fn memory_tick(sys: &mut System) {
+    let policy = read_state(|state| state.settings.memory_pressure.clone());
+    let mut guard = ACTED.lock().unwrap();
+    let acted = guard.get_or_insert_with(|| Acted {
+        trimmed: HashMap::new(),
+        suspended: Vec::new(),
+    });
+    acted.trimmed.retain(|_, at| at.elapsed() < TRIM_COOLDOWN);
+    let free = free_percent();
+
+    if !policy.enabled || policy.sacrifices.is_empty() {
+        resume_suspended(acted);
+        return;
+    }
+    if free >= policy.min_free_percent {
+        if free >= policy.min_free_percent + RESUME_MARGIN && !acted.suspended.is_empty() {
+            let count = resume_suspended(acted);
+            notify_event(
+                "memory-pressure",
+                PressureEvent {
+                    free_percent: free,
+                    relieved: true,
+                    actions: vec![format!("Resumed {} suspended processes", count)],
+                },
+            );
+        }
+        return;
+    }
+
+    let matchers: Vec<Matcher> = policy
+        .sacrifices
+        .iter()
+        .map(|s| Matcher::new(&s.name))
+        .collect();
+    sampler::refresh(sys, |p| {
+        let name = p.name().to_string_lossy();
+        matchers.iter().any(|m| m.matches(&name))
+    });
+    let cpu_count = (sys.cpus().len() as f32).max(1.0);
+    let users = sysinfo::Users::new_with_refreshed_list();
+    let allow_protected = protection::override_enabled();
+    let escalation = read_state(|state| state.settings.kill_escalation.clone());
+    let session_scope = read_state(|state| state.settings.session_scope.clone());
+    let own_pids = protection::own_pids(sys);
+
+    let mut logs: Vec<ActivityLog> = vec![];
+    let mut actions: Vec<String> = vec![];
+    let mut free = free;
+    'ranked: for (sacrifice, matcher) in policy.sacrifices.iter().zip(&matchers) {
+        // Biggest first, so the fewest processes are touched
+        let mut targets: Vec<&Process> = sys
+            .processes()
+            .values()
+            .filter(|p| matcher.matches(&p.name().to_string_lossy()))
+            .filter(|p| !own_pids.contains(&p.pid().as_u32()))
+            .filter(|p| protection::check(p, allow_protected).is_ok())
+            .filter(|p| notes::check_process(p).is_ok())
+            .filter(|p| session_scope.allows(sessions::session_of(p.pid().as_u32())))
+            .collect();
+        targets.sort_by_key(|p| std::cmp::Reverse(p.memory()));
+
+        for p in targets {
+            let Some(result) = act(p, sacrifice.action, acted, &escalation) else {
+                continue;
+            };
+            let verb = match sacrifice.action {
+                PressureAction::Trim => "trimmed",
+                PressureAction::Suspend => "suspended",
+                PressureAction::Kill => "killed",
+            };
+            let killed = result.is_ok() && sacrifice.action == PressureAction::Kill;
+            let reason = match &result {
+                Ok(()) => format!(
+                    "Memory pressure ({:.1}% free, below {}%) - {}",
+                    free, policy.min_free_percent, verb
+                ),
+                Err(err) => format!(
+                    "Memory pressure ({:.1}% free) - could not be {}: {}",
+                    free, verb, err
+                ),
+            };
+            actions.push(format!(
+                "{} {} (PID {})",
+                verb,
+                p.name().to_string_lossy(),
+                p.pid()
+            ));
+            logs.push(ActivityLog {
+                cpu_usage: p.cpu_usage() / cpu_count,
+                was_killed: killed,
+                reason,
+                freed_memory_kb: if killed { p.memory() / 1024 } else { 0 },
+                ..process_log(sys, &users, p)
+            });
+
+            free = free_percent();
+            if free >= policy.min_free_percent {
+                break 'ranked;
+            }
+        }
+    }
+    drop(guard);
+
+    if logs.is_empty() {
+        return;
+    }
+    with_state(|state| {
+        for log in logs {
+            record_activity(state, log);
+        }
+    });
+    notify_event(
+        "memory-pressure",
+        PressureEvent {
+            free_percent: free,
+            relieved: free >= policy.min_free_percent,
+            actions,
+        },
+    );
+}
+
+/// Rules taking part in the CPU responder, lowest priority (acted on first) first. A rule the
+/// watcher would hold back (auto-kill off, detect-only, maintenance, snooze) is left out.
+fn cpu_candidates(
+    rules: &[BlacklistEntry],
+    now: &str,
+    detect_only: bool,
+    maintenance: Option<&maintenance::MaintenanceWindow>,
+) -> Vec<BlacklistEntry> {
+    let mut candidates: Vec<BlacklistEntry> = rules
+        .iter()
+        .filter(|r| r.cpu_priority.is_some())
+        .filter(|r| rule_may_act(r, now, detect_only, maintenance))
+        .cloned()
+        .collect();
+    candidates.sort_by_key(|r| r.cpu_priority);
+    candidates
+}
+
+fn cpu_tick(sys: &mut System) {
+    let policy = read_state(|state| state.settings.cpu_pressure.clone());
+    let cpu = refresher::cached_stats()
+        .unwrap_or_else(|| metrics::collect(&[MetricKind::System]))
+        .cpu_usage;
+    {
+        let mut high_since = CPU_HIGH_SINCE.lock().unwrap();
+        if !policy.enabled || cpu < policy.cpu_percent {
+            *high_since = None;
+            return;
+        }
+        let since = *high_since.get_or_insert_with(Instant::now);
+        if since.elapsed() < Duration::from_secs(policy.sustain_secs) {
+            return;
+        }
+        *high_since = Some(Instant::now());
+    }
+
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let detect_only = quiet_hours::detect_only_now();
+    let maintenance = maintenance::active_now();
+    let candidates = read_state(|state| {
+        cpu_candidates(&state.blacklist, &now, detect_only, maintenance.as_ref())
+    });
+    if candidates.is_empty() {
+        return;
+    }
+    let matchers: Vec<Matcher> = candidates.iter().map(Matcher::for_rule).collect();
+    sampler::refresh(sys, |p| {
+        let name = p.name().to_string_lossy();
+        matchers.iter().any(|m| m.matches(&name))
+    });
+    let cpu_count = (sys.cpus().len() as f32).max(1.0);
+    let users = sysinfo::Users::new_with_refreshed_list();
+    let allow_protected = protection::override_enabled();
+    let escalation = read_state(|state| state.settings.kill_escalation.clone());
+    let session_scope = read_state(|state| state.settings.session_scope.clone());
+    let own_pids = protection::own_pids(sys);
+    let env = candidates
+        .iter()
+        .any(|r| !r.conditions.is_empty())
+        .then(environment::current);
+
+    let mut logs: Vec<ActivityLog> = vec![];
+    let mut actions: Vec<String> = vec![];
+    let mut acted_rule = String::new();
+    let mut killed_processes: Vec<(String, u32, f32)> = vec![];
+    // One rule per sustained period: the lowest-priority rule with something running
+    for (rule, matcher) in candidates.iter().zip(&matchers) {
+        if !env.as_ref().is_none_or(|env| rule.conditions.met(env)) {
+            continue;
+        }
+        let targets: Vec<&Process> = sys
+            .processes()
+            .values()
+            .filter(|p| matcher.matches(&p.name().to_string_lossy()))
+            .filter(|p| !own_pids.contains(&p.pid().as_u32()))
+            .filter(|p| protection::check(p, allow_protected).is_ok())
+            .filter(|p| notes::check_process(p).is_ok())
+            .filter(|p| session_scope.allows(sessions::session_of(p.pid().as_u32())))
+            .collect();
+        if targets.is_empty() {
+            continue;
+        }
+        // Its first kill waits for the user, as in the watcher; this period is spent asking
+        if rule.require_confirmation_first_time && !rule.confirmed {
+            let p = targets[0];
+            if let Some(request) = confirm::request(
+                &rule.name,
+                &p.name().to_string_lossy(),
+                (p.pid().as_u32(), p.start_time()),
+                p.cpu_usage() / cpu_count,
+                0.0,
+            ) {
+                emit_event("confirm-kill-request", request);
+            }
+            break;
+        }
+        let locks_or_warns = !matches!(
+            rule.action,
+            RuleAction::Kill
+                | RuleAction::Efficiency
+                | RuleAction::BlockLaunch
+                | RuleAction::KillLauncher
+        );
+        if locks_or_warns && !system_actions::claim(&rule.name) {
+            continue;
+        }
+
+        acted_rule = rule.name.clone();
+        for p in targets {
+            let name = p.name().to_string_lossy().to_string();
+            // Efficiency mode goes on once per process, shared with the watcher
+            if rule.action == RuleAction::Efficiency
+                && !system_actions::claim_process(p.pid().as_u32(), p.start_time())
+            {
+                continue;
+            }
+            // As in the watcher, a launcher rule takes out what started the process first
+            let launchers = if rule.action == RuleAction::KillLauncher {
+                let chain = launcher::to_kill(
+                    sys,
+                    p.pid().as_u32(),
+                    rule.launcher_depth,
+                    &own_pids,
+                    allow_protected,
+                    &session_scope,
+                );
+                launcher::kill_chain(&chain, &escalation, &session_scope)
+            } else {
+                vec![]
+            };
+            let result = match rule.action {
+                RuleAction::Kill | RuleAction::BlockLaunch | RuleAction::KillLauncher => {
+                    process_ctl::terminate(p, &escalation).map_err(|_| "kill failed".to_string())
+                }
+                RuleAction::Efficiency => process_ctl::set_efficiency_mode(p.pid().as_u32(), true),
+                _ => system_actions::run(rule.action, &rule.name, &name),
+            };
+            let killed = result.is_ok()
+                && matches!(
+                    rule.action,
+                    RuleAction::Kill | RuleAction::BlockLaunch | RuleAction::KillLauncher
+                );
+            let verb = match rule.action {
+                RuleAction::Kill | RuleAction::BlockLaunch | RuleAction::KillLauncher => "killed",
+                RuleAction::LockWorkstation => "workstation locked",
+                RuleAction::FullScreenWarning => "warning shown",
+                RuleAction::Efficiency => "put in efficiency mode",
+            };
+            let verb = if launchers.is_empty() {
+                verb.to_string()
+            } else {
+                format!("{} with its launchers {}", verb, launchers.join(", "))
+            };
+            let reason = match &result {
+                Ok(()) => format!(
+                    "CPU pressure ({:.0}% for {}s) - rule \"{}\" (priority {}), {}",
+                    cpu,
+                    policy.sustain_secs,
+                    rule.name,
+                    rule.cpu_priority.unwrap_or_default(),
+                    verb
+                ),
+                Err(err) => format!(
+                    "CPU pressure ({:.0}%) - rule \"{}\" could not act: {}",
+                    cpu, rule.name, err
+                ),
+            };
+            if killed {
+                killed_processes.push((name.clone(), p.pid().as_u32(), p.cpu_usage() / cpu_count));
+            }
+            actions.push(format!("{} {} (PID {})", verb, name, p.pid()));
+            logs.push(ActivityLog {
+                cpu_usage: p.cpu_usage() / cpu_count,
+                was_killed: killed,
+                reason,
+                freed_memory_kb: if killed { p.memory() / 1024 } else { 0 },
+                ..process_log(sys, &users, p)
+            });
+            // Locking or warning once covers every instance
+            if locks_or_warns {
+                break;
+            }
+        }
+        break;
+    }
+
+    if logs.is_empty() {
+        return;
+    }
+    let on_kill_script = with_state(|state| {
+        for log in logs {
+            record_activity(state, log);
+        }
+        let entry = state.blacklist.iter_mut().find(|e| e.name == acted_rule)?;
+        entry.kill_count += killed_processes.len() as u32;
+        entry.on_kill_script.clone()
+    });
+    if let Some(script) = on_kill_script {
+        for (name, pid, cpu) in &killed_processes {
+            run_on_kill_script(&script, name, *pid, *cpu, 0.0);
+        }
+    }
+    notify_event(
+        "cpu-pressure",
+        CpuPressureEvent {
+            cpu_percent: cpu,
+            rule: acted_rule,
+            actions,
+        },
+    );
+}
+
+// Public API
+/// Processes suspended by the memory responder and not resumed yet, with their start times
+pub fn suspended() -> Vec<(u32, u64)> {
+    ACTED
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|acted| acted.suspended.clone())
+        .unwrap_or_default()
+}
+
+pub fn start_responder() {
+    thread::spawn(|| {
+        let mut memory_sys = System::new();
+        let mut cpu_sys = System::new();
+        loop {
+            thread::sleep(crate::power::interval(TICK));
+            // Dry runs only report; the responders act on their own
+            if crate::overrides::dry_run() {
+                continue;
+            }
+            memory_tick(&mut memory_sys);
+            cpu_tick(&mut cpu_sys);
+        }
+    });
+}
@@ -0,0 +1,495 @@
+// Process control beyond plain kill (verified termination, polite close, suspend / resume,
+// priority, efficiency mode, memory trim)
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, Process, ProcessStatus, ProcessesToUpdate, System};
+
+use crate::backend::KillErrorKind;
+
+// How often a killed PID is checked while waiting for it to exit
+const EXIT_POLL: Duration = Duration::from_millis(50);
+
+// Priorities efficiency mode replaced, by PID and start time, put back when it is turned off
+static EFFICIENCY_SAVED: Mutex<Vec<(u32, u64, SavedPriority)>> = Mutex::new(Vec::new());
+
+/// What to do when a killed process doesn't go away
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct EscalationPolicy {
+    pub graceful_first: bool, // Try SIGTERM before SIGKILL (no effect on Windows)
+    pub exit_timeout_ms: u64, // How long each step waits for the PID to exit
+}
+
+impl Default for EscalationPolicy {
+    fn default() -> Self {
+        Self {
+            graceful_first: false,
+            exit_timeout_ms: 1000,
+        }
+    }
+}
+
+/// How a polite close request ended
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseOutcome {
+    Exited,       // Closed within the timeout
+    StillRunning, // Asked but still running, e.g. showing a "save changes?" prompt
+    NoWindow,     // Windows: no visible window to send WM_CLOSE to
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Idle,
+    BelowNormal,
+    Normal,
+    AboveNormal,
+    High,
+}
+
+#[cfg(windows)]
+mod win {
+    pub type HANDLE = *mut std::ffi::c_void;
+    pub type BOOL = i32;
+    pub type DWORD = u32;
+
+    pub const PROCESS_TERMINATE: DWORD = 0x0001;
+    pub const PROCESS_SUSPEND_RESUME: DWORD = 0x0800;
+    pub const PROCESS_SET_INFORMATION: DWORD = 0x0200;
+    pub const PROCESS_SET_QUOTA: DWORD = 0x0100;
+    pub const PROCESS_QUERY_LIMITED_INFORMATION: DWORD = 0x1000;
+
+    pub const IDLE_PRIORITY_CLASS: DWORD = 0x0040;
+    pub const BELOW_NORMAL_PRIORITY_CLASS: DWORD = 0x4000;
+    pub const NORMAL_PRIORITY_CLASS: DWORD = 0x0020;
+    pub const ABOVE_NORMAL_PRIORITY_CLASS: DWORD = 0x8000;
+    pub const HIGH_PRIORITY_CLASS: DWORD = 0x0080;
+
+    // SetProcessInformation class and flags for EcoQoS (Windows 11 "Efficiency mode")
+    pub const PROCESS_POWER_THROTTLING: i32 = 4;
+    pub const PROCESS_POWER_THROTTLING_CURRENT_VERSION: DWORD = 1;
+    pub const PROCESS_POWER_THROTTLING_EXECUTION_SPEED: DWORD = 0x1;
+
+    #[repr(C)]
+    pub struct PROCESS_POWER_THROTTLING_STATE {
+        pub version: DWORD,
+        pub control_mask: DWORD,
+        pub state_mask: DWORD,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn OpenProcess(
+            dwDesiredAccess: DWORD,
+            bInheritHandle: BOOL,
+            dwProcessId: DWORD,
+        ) -> HANDLE;
+        pub fn CloseHandle(hObject: HANDLE) -> BOOL;
+        pub fn SetPriorityClass(hProcess: HANDLE, dwPriorityClass: DWORD) -> BOOL;
+        pub fn GetPriorityClass(hProcess: HANDLE) -> DWORD;
+        pub fn K32EmptyWorkingSet(hProcess: HANDLE) -> BOOL;
+        pub fn SetProcessInformation(
+            hProcess: HANDLE,
+            ProcessInformationClass: i32,
+            ProcessInformation: *const std::ffi::c_void,
+            ProcessInformationSize: DWORD,
+        ) -> BOOL;
+    }
+    #[link(name = "ntdll")]
+    extern "system" {
+        pub fn NtSuspendProcess(ProcessHandle: HANDLE) -> i32;
+        pub fn NtResumeProcess(ProcessHandle: HANDLE) -> i32;
+    }
+
+    /// Open the process with the given access and run `f` on the handle
+    pub fn with_process<F>(pid: u32, access: DWORD, f: F) -> Result<(), String>
+    where
+        F: FnOnce(HANDLE) -> bool,
+    {
+        unsafe {
+            let handle = OpenProcess(access, 0, pid);
+            if handle.is_null() {
+                return Err(format!(
+                    "Cannot open PID {} - requires Administrator privileges",
+                    pid
+                ));
+            }
+            let ok = f(handle);
+            CloseHandle(handle);
+            if ok {
+                Ok(())
+            } else {
+                Err(format!("Operation on PID {} failed", pid))
+            }
+        }
+    }
+}
+
+/// Whether `pid` still belongs to the process that started at `start_time`
+fn is_same_process(sys: &mut System, pid: Pid, start_time: u64) -> bool {
+    sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    sys.process(pid)
+        .is_some_and(|p| p.start_time() == start_time)
+}
+
+fn wait_for_exit(pid: Pid, start_time: u64, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    let mut sys = System::new();
+    loop {
+        sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+        // A recycled PID means the original is gone; a zombie is only waiting to be reaped
+        match sys.process(pid) {
+            None => return true,
+            Some(p) if p.start_time() != start_time => return true,
+            Some(p) if p.status() == ProcessStatus::Zombie => return true,
+            Some(_) => {}
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(EXIT_POLL);
+    }
+}
+
+// Public API
+/// Kill `p` and confirm the PID actually exited, escalating per `policy`.
+/// Refuses when the PID was recycled since `p` was sampled, or when its executable is rated
+/// "never touch" in the process notes. Reads the state, so never call it under `with_state`.
+pub fn terminate(p: &Process, policy: &EscalationPolicy) -> Result<(), KillErrorKind> {
+    let (pid, start_time) = (p.pid(), p.start_time());
+    if !is_same_process(&mut System::new(), pid, start_time) {
+        return Err(KillErrorKind::NotFound);
+    }
+    if crate::notes::check_process(p).is_err() {
+        return Err(KillErrorKind::NeverTouch);
+    }
+
+    let timeout = Duration::from_millis(policy.exit_timeout_ms);
+    if cfg!(not(windows))
+        && policy.graceful_first
+        && p.kill_with(sysinfo::Signal::Term) == Some(true)
+        && wait_for_exit(pid, start_time, timeout)
+    {
+        return Ok(());
+    }
+    // On Windows, Signal::Term is not supported - kill() terminates directly
+    if !p.kill() {
+        return Err(KillErrorKind::AccessDenied);
+    }
+    if wait_for_exit(pid, start_time, timeout) {
+        Ok(())
+    } else {
+        Err(KillErrorKind::StillRunning)
+    }
+}
+
+/// Post WM_CLOSE to every visible top-level window of `pid`; false when it has none
+#[cfg(windows)]
+fn close_windows(pid: u32) -> bool {
+    use windows::Win32::Foundation::{BOOL, HWND, LPARAM, WPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindowThreadProcessId, IsWindowVisible, PostMessageW, WM_CLOSE,
+    };
+
+    struct Search {
+        pid: u32,
+        closed: u32,
+    }
+
+    unsafe extern "system" fn visit(window: HWND, param: LPARAM) -> BOOL {
+        let search = &mut *(param.0 as *mut Search);
+        let mut owner = 0u32;
+        GetWindowThreadProcessId(window, Some(&mut owner as *mut u32));
+        if owner == search.pid
+            && IsWindowVisible(window).as_bool()
+            && PostMessageW(window, WM_CLOSE, WPARAM(0), LPARAM(0)).is_ok()
+        {
+            search.closed += 1;
+        }
+        true.into()
+    }
+
+    let mut search = Search { pid, closed: 0 };
+    let _ = unsafe { EnumWindows(Some(visit), LPARAM(&mut search as *mut Search as isize)) };
+    search.closed > 0
+}
+
+/// Ask `pid` to close the way "End task" does (WM_CLOSE on Windows, SIGTERM elsewhere),
+/// never escalating, and wait up to `timeout` for it to exit
+pub fn request_close(
+    pid: u32,
+    start_time: u64,
+    timeout: Duration,
+) -> Result<CloseOutcome, KillErrorKind> {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    if !is_same_process(&mut sys, pid, start_time) {
+        return Err(KillErrorKind::NotFound);
+    }
+    #[cfg(windows)]
+    {
+        if !close_windows(pid.as_u32()) {
+            return Ok(CloseOutcome::NoWindow);
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        let process = sys.process(pid).ok_or(KillErrorKind::NotFound)?;
+        if process.kill_with(sysinfo::Signal::Term) != Some(true) {
+            return Err(KillErrorKind::AccessDenied);
+        }
+    }
+    if wait_for_exit(pid, start_time, timeout) {
+        Ok(CloseOutcome::Exited)
+    } else {
+        Ok(CloseOutcome::StillRunning)
+    }
+}
+
+/// Whether we would be allowed to kill `pid`, without touching it
+pub fn can_terminate(pid: u32) -> bool {
+    #[cfg(windows)]
+    {
+        win::with_process(pid, win::PROCESS_TERMINATE, |_| true).is_ok()
+    }
+    #[cfg(not(windows))]
+    {
+        // kill(2): our real or effective UID must match the target's real or saved UID,
+        // unless we hold CAP_KILL
+        let status = |pid: &str| std::fs::read_to_string(format!("/proc/{}/status", pid));
+        let (Ok(own), Ok(target)) = (status("self"), status(&pid.to_string())) else {
+            return false;
+        };
+        let field = |status: &str, key: &str| -> Vec<String> {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix(key))
+                .map(|rest| rest.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+        const CAP_KILL: u32 = 5;
+        let has_cap_kill = field(&own, "CapEff:")
+            .first()
+            .and_then(|caps| u64::from_str_radix(caps, 16).ok())
+            .is_some_and(|caps| caps & (1 << CAP_KILL) != 0);
+        // Uid: real, effective, saved, filesystem
+        let own_uids = field(&own, "Uid:");
+        let target_uids = field(&target, "Uid:");
+        has_cap_kill
+            || own_uids
+                .iter()
+                .take(2)
+                .any(|uid| target_uids.first() == Some(uid) || target_uids.get(2) == Some(uid))
+    }
+}
+
+pub fn suspend_process(pid: u32) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        win::with_process(pid, win::PROCESS_SUSPEND_RESUME, |h| unsafe {
+            win::NtSuspendProcess(h) >= 0
+        })
+    }
+    #[cfg(not(windows))]
+    {
+        send_signal(pid, sysinfo::Signal::Stop)
+    }
+}
+
+/// Resume `pid` only if it is still the process started at `start_time`, so a recycled PID
+/// that was never ours is left alone
+pub fn resume_same(pid: u32, start_time: u64) -> Result<(), String> {
+    if !is_same_process(&mut System::new(), Pid::from_u32(pid), start_time) {
+        return Err(format!("PID {} is no longer the suspended process", pid));
+    }
+    resume_process(pid)
+}
+
+pub fn resume_process(pid: u32) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        win::with_process(pid, win::PROCESS_SUSPEND_RESUME, |h| unsafe {
+            win::NtResumeProcess(h) >= 0
+        })
+    }
+    #[cfg(not(windows))]
+    {
+        send_signal(pid, sysinfo::Signal::Continue)
+    }
+}
+
+pub fn set_priority(pid: u32, priority: Priority) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        let class = match priority {
+            Priority::Idle => win::IDLE_PRIORITY_CLASS,
+            Priority::BelowNormal => win::BELOW_NORMAL_PRIORITY_CLASS,
+            Priority::Normal => win::NORMAL_PRIORITY_CLASS,
+            Priority::AboveNormal => win::ABOVE_NORMAL_PRIORITY_CLASS,
+            Priority::High => win::HIGH_PRIORITY_CLASS,
+        };
+        win::with_process(pid, win::PROCESS_SET_INFORMATION, |h| unsafe {
+            win::SetPriorityClass(h, class) != 0
+        })
+    }
+    #[cfg(not(windows))]
+    {
+        let nice = match priority {
+            Priority::Idle => 19,
+            Priority::BelowNormal => 10,
+            Priority::Normal => 0,
+            Priority::AboveNormal => -5,
+            Priority::High => -10,
+        };
+        set_nice(pid, nice)
+    }
+}
+
+#[cfg(not(windows))]
+fn set_nice(pid: u32, nice: i32) -> Result<(), String> {
+    let ok = unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) } == 0;
+    if ok {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to change priority of PID {} (permission denied?)",
+            pid
+        ))
+    }
+}
+
+/// A process's priority exactly as the OS reports it (the priority class on Windows, the nice
+/// value elsewhere), so a temporary change can be put back as it was
+#[derive(Clone, Copy)]
+pub struct SavedPriority(i64);
+
+pub fn save_priority(pid: u32) -> Result<SavedPriority, String> {
+    #[cfg(windows)]
+    {
+        let mut class = 0;
+        win::with_process(pid, win::PROCESS_QUERY_LIMITED_INFORMATION, |h| unsafe {
+            class = win::GetPriorityClass(h);
+            class != 0
+        })?;
+        Ok(SavedPriority(class as i64))
+    }
+    #[cfg(not(windows))]
+    {
+        // Field 19 of /proc/<pid>/stat; the command name before it may contain spaces
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .ok()
+            .and_then(|stat| {
+                let (_, rest) = stat.rsplit_once(')')?;
+                rest.split_whitespace().nth(16)?.parse().ok()
+            })
+            .map(SavedPriority)
+            .ok_or_else(|| format!("Cannot read the priority of PID {}", pid))
+    }
+}
+
+/// Put back a priority from `save_priority`, unless the PID now belongs to another process
+pub fn restore_priority(pid: u32, start_time: u64, saved: SavedPriority) -> Result<(), String> {
+    if !is_same_process(&mut System::new(), Pid::from_u32(pid), start_time) {
+        return Err(format!("PID {} is no longer the same process", pid));
+    }
+    #[cfg(windows)]
+    {
+        win::with_process(pid, win::PROCESS_SET_INFORMATION, |h| unsafe {
+            win::SetPriorityClass(h, saved.0 as win::DWORD) != 0
+        })
+    }
+    #[cfg(not(windows))]
+    {
+        set_nice(pid, saved.0 as i32)
+    }
+}
+
+/// Efficiency mode the way Task Manager applies it: EcoQoS throttling plus idle priority.
+/// Turning it off hands throttling back to the system and puts back the priority the process
+/// had before (normal if we never turned it on). Linux has no EcoQoS, so there it only changes
+/// the nice value.
+pub fn set_efficiency_mode(pid: u32, enabled: bool) -> Result<(), String> {
+    let mut saved = EFFICIENCY_SAVED.lock().unwrap();
+    // Forget processes that exited or whose PID was recycled
+    let mut pids: Vec<Pid> = saved.iter().map(|(p, _, _)| Pid::from_u32(*p)).collect();
+    pids.push(Pid::from_u32(pid));
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::Some(&pids), true);
+    saved.retain(|(p, start_time, _)| {
+        sys.process(Pid::from_u32(*p))
+            .is_some_and(|p| p.start_time() == *start_time)
+    });
+    let start_time = sys
+        .process(Pid::from_u32(pid))
+        .map(|p| p.start_time())
+        .ok_or("Process not found")?;
+    let previous = saved.iter().position(|(p, _, _)| *p == pid);
+    if enabled && previous.is_none() {
+        saved.push((pid, start_time, save_priority(pid)?));
+    }
+
+    #[cfg(windows)]
+    {
+        let mask = if enabled {
+            win::PROCESS_POWER_THROTTLING_EXECUTION_SPEED
+        } else {
+            0
+        };
+        let state = win::PROCESS_POWER_THROTTLING_STATE {
+            version: win::PROCESS_POWER_THROTTLING_CURRENT_VERSION,
+            control_mask: mask,
+            state_mask: mask,
+        };
+        win::with_process(pid, win::PROCESS_SET_INFORMATION, |h| unsafe {
+            win::SetProcessInformation(
+                h,
+                win::PROCESS_POWER_THROTTLING,
+                &state as *const _ as *const std::ffi::c_void,
+                std::mem::size_of::<win::PROCESS_POWER_THROTTLING_STATE>() as u32,
+            ) != 0
+        })?;
+    }
+    match previous {
+        _ if enabled => set_priority(pid, Priority::Idle),
+        Some(i) => {
+            let (_, _, priority) = saved.remove(i);
+            restore_priority(pid, start_time, priority)
+        }
+        None => set_priority(pid, Priority::Normal),
+    }
+}
+
+/// Ask Windows to page out the process working set (memory comes back on demand)
+pub fn trim_memory(pid: u32) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        win::with_process(
+            pid,
+            win::PROCESS_SET_QUOTA | win::PROCESS_QUERY_LIMITED_INFORMATION,
+            |h| unsafe { win::K32EmptyWorkingSet(h) != 0 },
+        )
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = pid;
+        Err("Memory trimming is only supported on Windows".into())
+    }
+}
+
+#[cfg(not(windows))]
+fn send_signal(pid: u32, signal: sysinfo::Signal) -> Result<(), String> {
+    let mut sys = sysinfo::System::new();
+    let pid = sysinfo::Pid::from_u32(pid);
+    sys.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[pid]), true);
+
+    let process = sys.process(pid).ok_or("Process not found")?;
+    match process.kill_with(signal) {
+        Some(true) => Ok(()),
+        Some(false) => Err(format!("Failed to signal PID {} (permission denied?)", pid)),
+        None => Err("Signal not supported on this platform".into()),
+    }
+}
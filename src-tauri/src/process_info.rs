@@ -0,0 +1,117 @@
+// "What is this process?": an offline database of common Windows and Linux processes with a
+// plain description and how safe they are to end. Shipped inside the binary as a deflated zip
+// holding process_info.json, and unpacked on first use.
+
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read};
+use std::sync::OnceLock;
+use zip::ZipArchive;
+
+const DATABASE_ZIP: &[u8] = include_bytes!("../resources/process_info.zip");
+const DATABASE_ENTRY: &str = "process_info.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Safety {
+    Critical, // Part of the OS; ending it crashes, logs out or restarts
+    System,   // OS component that usually restarts by itself
+    Caution,  // Fine to end once you know what it is doing
+    Safe,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    names: Vec<String>, // Lower-case; Windows names keep ".exe"
+    platform: String,   // "windows", "linux" or "any"
+    title: String,
+    description: String,
+    safety: Safety,
+    #[serde(default)]
+    guidance: Option<String>,
+}
+
+// Used when an entry has no guidance of its own
+#[derive(Deserialize)]
+struct DefaultGuidance {
+    critical: String,
+    system: String,
+    caution: String,
+    safe: String,
+}
+
+impl DefaultGuidance {
+    fn get(&self, safety: Safety) -> &str {
+        match safety {
+            Safety::Critical => &self.critical,
+            Safety::System => &self.system,
+            Safety::Caution => &self.caution,
+            Safety::Safe => &self.safe,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Database {
+    guidance: DefaultGuidance,
+    processes: Vec<Entry>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ProcessInfo {
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub platform: String,
+    pub safety: Safety,
+    pub guidance: String,
+}
+
+static DATABASE: OnceLock<Option<Database>> = OnceLock::new();
+
+fn load() -> Result<Database, String> {
+    let mut zip = ZipArchive::new(Cursor::new(DATABASE_ZIP)).map_err(|e| e.to_string())?;
+    let mut entry = zip.by_name(DATABASE_ENTRY).map_err(|e| e.to_string())?;
+    let mut data = String::new();
+    entry
+        .read_to_string(&mut data)
+        .map_err(|e| format!("{}: {}", DATABASE_ENTRY, e))?;
+    serde_json::from_str(&data).map_err(|e| format!("{}: {}", DATABASE_ENTRY, e))
+}
+
+fn database() -> Option<&'static Database> {
+    DATABASE
+        .get_or_init(|| {
+            load()
+                .map_err(|e| tracing::warn!(error = %e, "process info database unreadable"))
+                .ok()
+        })
+        .as_ref()
+}
+
+// Public API
+/// Description and safety guidance for the process called `name`, if it is a known one
+pub fn lookup(name: &str) -> Option<ProcessInfo> {
+    let db = database()?;
+    let key = name.trim().to_lowercase();
+    // "foo" also finds "foo.exe"; kernel threads are listed without their "/0:1" suffix
+    let candidates = [
+        key.clone(),
+        format!("{}.exe", key),
+        key.trim_end_matches(".exe").to_string(),
+        key.split('/').next().unwrap_or_default().to_string(),
+    ];
+    let entry = candidates
+        .iter()
+        .find_map(|c| db.processes.iter().find(|e| e.names.contains(c)))?;
+    Some(ProcessInfo {
+        name: name.to_string(),
+        title: entry.title.clone(),
+        description: entry.description.clone(),
+        platform: entry.platform.clone(),
+        safety: entry.safety,
+        guidance: entry
+            .guidance
+            .clone()
+            .unwrap_or_else(|| db.guidance.get(entry.safety).to_string()),
+    })
+}
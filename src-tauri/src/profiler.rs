@@ -0,0 +1,375 @@
+// Stack sampling of a busy process, to see *why* it is burning CPU before killing it.
+// Windows suspends each thread that used CPU since the last tick and walks its stack with
+// DbgHelp; Linux records call graphs with perf. Frames are counted across all samples.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::{protection, sampler};
+
+pub const MAX_SECONDS: u32 = 30;
+const TOP_FRAMES: usize = 25;
+
+#[derive(Serialize, Clone)]
+pub struct FrameCount {
+    pub frame: String,      // "module!symbol", or "module+0x1a2b" without symbols
+    pub self_samples: u32,  // Samples with this frame on top of the stack
+    pub total_samples: u32, // Samples with this frame anywhere in the stack
+    pub percent: f32,       // self_samples as a share of all samples
+}
+
+#[derive(Serialize, Clone)]
+pub struct StackSample {
+    pub pid: u32,
+    pub seconds: u32,
+    pub samples: u32,
+    pub top_frames: Vec<FrameCount>, // Hottest first
+}
+
+#[derive(Default)]
+struct Tally {
+    samples: u32,
+    self_counts: HashMap<String, u32>,
+    total_counts: HashMap<String, u32>,
+}
+
+impl Tally {
+    /// `stack` is leaf first
+    fn add(&mut self, stack: &[String]) {
+        let Some(leaf) = stack.first() else {
+            return;
+        };
+        self.samples += 1;
+        *self.self_counts.entry(leaf.clone()).or_default() += 1;
+        // Recursion would count a frame more than once per sample
+        let mut seen = HashSet::new();
+        for frame in stack {
+            if seen.insert(frame) {
+                *self.total_counts.entry(frame.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    fn top(self) -> Vec<FrameCount> {
+        let samples = self.samples.max(1) as f32;
+        let mut frames: Vec<FrameCount> = self
+            .total_counts
+            .into_iter()
+            .map(|(frame, total_samples)| {
+                let self_samples = self.self_counts.get(&frame).copied().unwrap_or(0);
+                FrameCount {
+                    frame,
+                    self_samples,
+                    total_samples,
+                    percent: self_samples as f32 / samples * 100.0,
+                }
+            })
+            .collect();
+        frames.sort_by(|a, b| {
+            b.self_samples
+                .cmp(&a.self_samples)
+                .then(b.total_samples.cmp(&a.total_samples))
+        });
+        frames.truncate(TOP_FRAMES);
+        frames
+    }
+}
+
+#[cfg(all(windows, target_arch = "x86_64"))]
+mod dbghelp {
+    use std::collections::HashMap;
+    use std::ffi::c_void;
+    use std::time::{Duration, Instant};
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{CloseHandle, FILETIME, HANDLE};
+    use windows::Win32::System::Diagnostics::Debug::{
+        AddrModeFlat, GetThreadContext, StackWalk64, SymCleanup, SymFromAddrW,
+        SymFunctionTableAccess64, SymGetModuleBase64, SymGetModuleInfoW64, SymInitializeW,
+        SymSetOptions, CONTEXT, CONTEXT_FULL_AMD64, IMAGEHLP_MODULEW64, STACKFRAME64, SYMBOL_INFOW,
+        SYMOPT_DEFERRED_LOADS, SYMOPT_UNDNAME,
+    };
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
+    };
+    use windows::Win32::System::Threading::{
+        GetThreadTimes, OpenProcess, OpenThread, ResumeThread, SuspendThread,
+        PROCESS_QUERY_INFORMATION, PROCESS_VM_READ, THREAD_GET_CONTEXT,
+        THREAD_QUERY_LIMITED_INFORMATION, THREAD_SUSPEND_RESUME,
+    };
+
+    use super::Tally;
+
+    const TICK: Duration = Duration::from_millis(50); // 20 samples a second per busy thread
+    const MAX_DEPTH: usize = 64;
+    const IMAGE_FILE_MACHINE_AMD64: u32 = 0x8664;
+    const MAX_NAME: usize = 256;
+
+    unsafe extern "system" fn table_access(process: HANDLE, address: u64) -> *mut c_void {
+        SymFunctionTableAccess64(process, address)
+    }
+
+    unsafe extern "system" fn module_base(process: HANDLE, address: u64) -> u64 {
+        SymGetModuleBase64(process, address)
+    }
+
+    fn thread_ids(pid: u32) -> Vec<u32> {
+        let mut ids = Vec::new();
+        unsafe {
+            let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) else {
+                return ids;
+            };
+            let mut entry = THREADENTRY32 {
+                dwSize: std::mem::size_of::<THREADENTRY32>() as u32,
+                ..Default::default()
+            };
+            let mut more = Thread32First(snapshot, &mut entry).is_ok();
+            while more {
+                if entry.th32OwnerProcessID == pid {
+                    ids.push(entry.th32ThreadID);
+                }
+                more = Thread32Next(snapshot, &mut entry).is_ok();
+            }
+            CloseHandle(snapshot).ok();
+        }
+        ids
+    }
+
+    /// Kernel + user time in 100ns units
+    fn cpu_time(thread: HANDLE) -> Option<u64> {
+        let mut times = [FILETIME::default(); 4];
+        let [created, exited, kernel, user] = &mut times;
+        unsafe { GetThreadTimes(thread, created, exited, kernel, user) }.ok()?;
+        let value = |t: &FILETIME| ((t.dwHighDateTime as u64) << 32) | t.dwLowDateTime as u64;
+        Some(value(kernel) + value(user))
+    }
+
+    /// Return addresses of a suspended thread, leaf first
+    fn walk(process: HANDLE, thread: HANDLE) -> Vec<u64> {
+        let mut context = CONTEXT {
+            ContextFlags: CONTEXT_FULL_AMD64,
+            ..Default::default()
+        };
+        if unsafe { GetThreadContext(thread, &mut context) }.is_err() {
+            return Vec::new();
+        }
+        let mut frame = STACKFRAME64::default();
+        frame.AddrPC.Offset = context.Rip;
+        frame.AddrPC.Mode = AddrModeFlat;
+        frame.AddrFrame.Offset = context.Rbp;
+        frame.AddrFrame.Mode = AddrModeFlat;
+        frame.AddrStack.Offset = context.Rsp;
+        frame.AddrStack.Mode = AddrModeFlat;
+
+        let mut addresses = Vec::new();
+        while addresses.len() < MAX_DEPTH {
+            let more = unsafe {
+                StackWalk64(
+                    IMAGE_FILE_MACHINE_AMD64,
+                    process,
+                    thread,
+                    &mut frame,
+                    &mut context as *mut CONTEXT as *mut c_void,
+                    None,
+                    Some(table_access),
+                    Some(module_base),
+                    None,
+                )
+            };
+            if !more.as_bool() || frame.AddrPC.Offset == 0 {
+                break;
+            }
+            addresses.push(frame.AddrPC.Offset);
+        }
+        addresses
+    }
+
+    fn resolve(process: HANDLE, address: u64) -> String {
+        let mut module = IMAGEHLP_MODULEW64 {
+            SizeOfStruct: std::mem::size_of::<IMAGEHLP_MODULEW64>() as u32,
+            ..Default::default()
+        };
+        let module_name = unsafe { SymGetModuleInfoW64(process, address, &mut module) }
+            .ok()
+            .map(|_| {
+                let name = &module.ModuleName;
+                let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+                String::from_utf16_lossy(&name[..len])
+            });
+
+        // SYMBOL_INFOW ends in a variable-length name
+        let mut buffer = vec![0u64; (std::mem::size_of::<SYMBOL_INFOW>() + MAX_NAME * 2) / 8 + 1];
+        let symbol = buffer.as_mut_ptr() as *mut SYMBOL_INFOW;
+        let symbol_name = unsafe {
+            (*symbol).SizeOfStruct = std::mem::size_of::<SYMBOL_INFOW>() as u32;
+            (*symbol).MaxNameLen = MAX_NAME as u32;
+            SymFromAddrW(process, address, None, symbol).ok().map(|_| {
+                let len = ((*symbol).NameLen as usize).min(MAX_NAME);
+                let name = std::slice::from_raw_parts((*symbol).Name.as_ptr(), len);
+                String::from_utf16_lossy(name)
+            })
+        };
+
+        match (module_name, symbol_name) {
+            (Some(module_name), Some(symbol_name)) => format!("{}!{}", module_name, symbol_name),
+            (Some(module_name), None) => {
+                format!("{}+0x{:x}", module_name, address - module.BaseOfImage)
+            }
+            (None, _) => format!("0x{:x}", address),
+        }
+    }
+
+    pub fn sample(pid: u32, seconds: u32) -> Result<Tally, String> {
+        let process = unsafe {
+            OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid)
+                .map_err(|e| format!("Cannot open process {}: {}", pid, e))?
+        };
+        unsafe {
+            SymSetOptions(SYMOPT_UNDNAME | SYMOPT_DEFERRED_LOADS);
+            if let Err(e) = SymInitializeW(process, PCWSTR::null(), true) {
+                CloseHandle(process).ok();
+                return Err(format!("Cannot load symbols: {}", e));
+            }
+        }
+
+        // Only threads that ran since the last tick are sampled, so idle ones don't drown
+        // out the hot path
+        let mut last_cpu: HashMap<u32, u64> = HashMap::new();
+        let mut stacks: Vec<Vec<u64>> = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(seconds as u64);
+        while Instant::now() < deadline {
+            for tid in thread_ids(pid) {
+                let access =
+                    THREAD_SUSPEND_RESUME | THREAD_GET_CONTEXT | THREAD_QUERY_LIMITED_INFORMATION;
+                let Ok(thread) = (unsafe { OpenThread(access, false, tid) }) else {
+                    continue;
+                };
+                let busy = match cpu_time(thread) {
+                    Some(now) => last_cpu.insert(tid, now).is_some_and(|before| now > before),
+                    None => false,
+                };
+                if busy && unsafe { SuspendThread(thread) } != u32::MAX {
+                    let stack = walk(process, thread);
+                    unsafe { ResumeThread(thread) };
+                    if !stack.is_empty() {
+                        stacks.push(stack);
+                    }
+                }
+                unsafe {
+                    CloseHandle(thread).ok();
+                }
+            }
+            std::thread::sleep(TICK);
+        }
+
+        // Symbols are looked up after sampling so threads stay suspended as briefly as possible
+        let mut names: HashMap<u64, String> = HashMap::new();
+        let mut tally = Tally::default();
+        for stack in stacks {
+            let frames: Vec<String> = stack
+                .iter()
+                .map(|&address| {
+                    names
+                        .entry(address)
+                        .or_insert_with(|| resolve(process, address))
+                        .clone()
+                })
+                .collect();
+            tally.add(&frames);
+        }
+        unsafe {
+            SymCleanup(process).ok();
+            CloseHandle(process).ok();
+        }
+        Ok(tally)
+    }
+}
+
+#[cfg(all(windows, target_arch = "x86_64"))]
+fn sample(pid: u32, seconds: u32) -> Result<Tally, String> {
+    dbghelp::sample(pid, seconds)
+}
+
+/// "7f3a2b1c do_work+0x1c (/usr/lib/libfoo.so.1)" -> "libfoo.so.1!do_work"
+#[cfg(target_os = "linux")]
+fn perf_frame(line: &str) -> Option<String> {
+    let (_address, rest) = line.trim().split_once(char::is_whitespace)?;
+    let (symbol, dso) = match rest.rsplit_once(" (") {
+        Some((symbol, dso)) => (symbol.trim(), dso.trim_end_matches(')')),
+        None => (rest.trim(), ""),
+    };
+    let symbol = symbol.split("+0x").next().unwrap_or(symbol);
+    let module = std::path::Path::new(dso)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| dso.to_string());
+    Some(match (module.is_empty(), symbol) {
+        (true, symbol) => symbol.to_string(),
+        (false, "[unknown]") => module,
+        (false, symbol) => format!("{}!{}", module, symbol),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn sample(pid: u32, seconds: u32) -> Result<Tally, String> {
+    use crate::containers::hidden_command;
+
+    let data = std::env::temp_dir().join(format!("automatekilltask-perf-{}.data", pid));
+    let record = hidden_command("perf")
+        .args(["record", "-F", "99", "-g", "-q", "-p"])
+        .arg(pid.to_string())
+        .arg("-o")
+        .arg(&data)
+        .args(["--", "sleep"])
+        .arg(seconds.to_string())
+        .output()
+        .map_err(|_| "Stack sampling needs perf (linux-tools) on Linux".to_string())?;
+    if !record.status.success() {
+        std::fs::remove_file(&data).ok();
+        return Err(format!(
+            "perf record failed: {}",
+            String::from_utf8_lossy(&record.stderr).trim()
+        ));
+    }
+    let script = hidden_command("perf")
+        .args(["script", "-F", "ip,sym,dso", "-i"])
+        .arg(&data)
+        .output();
+    std::fs::remove_file(&data).ok();
+    let script = script.map_err(|e| format!("perf script failed: {}", e))?;
+
+    // One event per block: its call chain, leaf first, then a blank line
+    let mut tally = Tally::default();
+    let text = String::from_utf8_lossy(&script.stdout);
+    for block in text.split("\n\n") {
+        let stack: Vec<String> = block.lines().filter_map(perf_frame).collect();
+        tally.add(&stack);
+    }
+    Ok(tally)
+}
+
+#[cfg(not(any(all(windows, target_arch = "x86_64"), target_os = "linux")))]
+fn sample(_pid: u32, _seconds: u32) -> Result<Tally, String> {
+    Err("Stack sampling is not supported on this platform".into())
+}
+
+// Public API
+/// Sample `pid`'s thread stacks for `seconds` (capped at 30) and return the hottest frames
+pub fn sample_process(pid: u32, seconds: u32) -> Result<StackSample, String> {
+    let seconds = seconds.clamp(1, MAX_SECONDS);
+    // Suspending our own threads (or the WebView2 host drawing the UI) would freeze the app
+    let own = sampler::with_processes(|_| false, |sys, _| protection::own_pids(sys));
+    if own.contains(&pid) {
+        return Err("Refusing to sample AutomateKillTask's own process".into());
+    }
+    let tally = sample(pid, seconds)?;
+    tracing::info!(pid, seconds, samples = tally.samples, "stack sample");
+    if tally.samples == 0 {
+        return Err("No samples: the process was idle or has exited".into());
+    }
+    Ok(StackSample {
+        pid,
+        seconds,
+        samples: tally.samples,
+        top_frames: tally.top(),
+    })
+}
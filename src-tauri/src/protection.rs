@@ -0,0 +1,139 @@
+// Built-in list of system processes that no kill path may touch, plus our own process tree
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Mutex;
+use sysinfo::{Process, System};
+
+use crate::read_state;
+
+// Matched exactly (case-insensitive) against the process name
+const PROTECTED_NAMES: &[&str] = &[
+    // Windows
+    "system",
+    "registry",
+    "memory compression",
+    "secure system",
+    "smss.exe",
+    "csrss.exe",
+    "wininit.exe",
+    "winlogon.exe",
+    "services.exe",
+    "lsass.exe",
+    "lsaiso.exe",
+    // Linux / macOS
+    "init",
+    "systemd",
+    "kthreadd",
+    "launchd",
+    "kernel_task",
+];
+
+/// Error returned when a kill targets a protected process
+pub struct ProtectedProcess {
+    pub name: String,
+    pub pid: u32,
+}
+
+impl fmt::Display for ProtectedProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ProtectedProcess: {} (PID {}) is a protected system process",
+            self.name, self.pid
+        )
+    }
+}
+
+impl From<ProtectedProcess> for String {
+    fn from(err: ProtectedProcess) -> Self {
+        err.to_string()
+    }
+}
+
+// Own PIDs a rule matched that we already warned about, so the log isn't flooded
+static WARNED_SELF: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// WebView2 hosts are started by the runtime, tagged with the exe name of the app they serve
+fn is_own_webview(p: &Process, exe_name: &str) -> bool {
+    is_webview_host(&p.name().to_string_lossy())
+        && p.cmd().iter().any(|arg| {
+            arg.to_string_lossy()
+                .to_lowercase()
+                .strip_prefix("--webview-exe-name=")
+                .is_some_and(|v| v == exe_name)
+        })
+}
+
+fn is_protected(p: &Process) -> bool {
+    let pid = p.pid().as_u32();
+    let name = p.name().to_string_lossy().to_lowercase();
+    pid <= 1
+        || PROTECTED_NAMES.contains(&name.as_str())
+        || p.thread_kind() == Some(sysinfo::ThreadKind::Kernel)
+}
+
+// Public API
+pub fn is_webview_host(name: &str) -> bool {
+    name.to_lowercase().starts_with("msedgewebview2")
+}
+
+/// Names of the protected system processes, lower-case
+pub fn protected_names() -> &'static [&'static str] {
+    PROTECTED_NAMES
+}
+
+/// Expert override from settings; read it before taking the state lock
+pub fn override_enabled() -> bool {
+    read_state(|state| state.settings.allow_protected_kills)
+}
+
+pub fn check(p: &Process, allow_override: bool) -> Result<(), ProtectedProcess> {
+    if !allow_override && is_protected(p) {
+        Err(ProtectedProcess {
+            name: p.name().to_string_lossy().to_string(),
+            pid: p.pid().as_u32(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// PIDs of this app, every descendant of it and its WebView2 hosts
+pub fn own_pids(sys: &System) -> HashSet<u32> {
+    let mut own = HashSet::from([std::process::id()]);
+    let exe_name = std::env::current_exe()
+        .ok()
+        .and_then(|e| e.file_name().map(|n| n.to_string_lossy().to_lowercase()))
+        .unwrap_or_default();
+
+    for (pid, p) in sys.processes() {
+        if !exe_name.is_empty() && is_own_webview(p, &exe_name) {
+            own.insert(pid.as_u32());
+        }
+    }
+
+    // Pull in children until the tree stops growing
+    loop {
+        let before = own.len();
+        for (pid, p) in sys.processes() {
+            if p.parent().is_some_and(|ppid| own.contains(&ppid.as_u32())) {
+                own.insert(pid.as_u32());
+            }
+        }
+        if own.len() == before {
+            return own;
+        }
+    }
+}
+
+/// True the first time a rule matches one of our own PIDs; used to warn once per process
+pub fn should_warn_self(pid: u32) -> bool {
+    let mut warned = WARNED_SELF.lock().unwrap();
+    if warned.contains(&pid) {
+        false
+    } else {
+        warned.push(pid);
+        true
+    }
+}
@@ -0,0 +1,76 @@
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::read_state;
+
+/// One quiet window; `end` earlier than `start` spans midnight into the next day
+#[derive(Serialize, Deserialize, Clone)]
+pub struct QuietWindow {
+    pub weekday: u32,  // 1 = Monday ... 7 = Sunday
+    pub start: String, // "HH:MM"
+    pub end: String,   // "HH:MM"
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct QuietHours {
+    pub enabled: bool,
+    pub detect_only: bool, // Downgrade auto-kills to "Detected" while quiet
+    pub windows: Vec<QuietWindow>,
+}
+
+fn parse_time(text: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(text, "%H:%M").ok()
+}
+
+impl QuietWindow {
+    fn contains(&self, now: NaiveDateTime) -> bool {
+        let (Some(start), Some(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        let today = now.weekday().number_from_monday();
+        let yesterday = now
+            .date()
+            .pred_opt()
+            .map(|d| d.weekday().number_from_monday());
+        let time = now.time();
+
+        if start <= end {
+            self.weekday == today && time >= start && time < end
+        } else {
+            // Overnight: the tail after midnight belongs to the previous day's window
+            (self.weekday == today && time >= start)
+                || (Some(self.weekday) == yesterday && time < end)
+        }
+    }
+}
+
+impl QuietHours {
+    pub fn validate(&self) -> Result<(), String> {
+        for w in &self.windows {
+            if !(1..=7).contains(&w.weekday) {
+                return Err("Weekday must be 1 (Monday) to 7 (Sunday)".into());
+            }
+            if parse_time(&w.start).is_none() || parse_time(&w.end).is_none() {
+                return Err("Times must be in HH:MM format".into());
+            }
+        }
+        Ok(())
+    }
+
+    fn is_active_at(&self, now: NaiveDateTime) -> bool {
+        self.enabled && self.windows.iter().any(|w| w.contains(now))
+    }
+}
+
+// Public API
+pub fn is_active() -> bool {
+    let quiet = read_state(|state| state.settings.quiet_hours.clone());
+    quiet.is_active_at(Local::now().naive_local())
+}
+
+/// True when the watcher should only detect, not kill
+pub fn detect_only_now() -> bool {
+    let quiet = read_state(|state| state.settings.quiet_hours.clone());
+    quiet.detect_only && quiet.is_active_at(Local::now().naive_local())
+}
@@ -0,0 +1,148 @@
+// Background refresher: each data source is sampled on its own schedule and commands read
+// the latest sample, instead of every command call doing its own full refresh
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::metrics::{self, MetricKind, MetricSample};
+use crate::{power, sampler, DiskInfo};
+
+// How often the refresher checks which sources are due
+const TICK: Duration = Duration::from_millis(250);
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RefreshIntervals {
+    pub stats_ms: u64,     // CPU, memory, network and sensors
+    pub processes_ms: u64, // Process list and per-process stats
+    pub gpu_ms: u64,       // Per-process GPU usage
+    pub disks_ms: u64,
+}
+
+impl Default for RefreshIntervals {
+    fn default() -> Self {
+        Self {
+            stats_ms: 1000,
+            processes_ms: 2000,
+            gpu_ms: 1000,
+            disks_ms: 30_000,
+        }
+    }
+}
+
+impl RefreshIntervals {
+    pub fn validate(&self) -> Result<(), String> {
+        let all = [self.stats_ms, self.processes_ms, self.gpu_ms, self.disks_ms];
+        if all.iter().any(|ms| !(250..=600_000).contains(ms)) {
+            return Err("Refresh intervals must be between 250 ms and 10 minutes".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Source {
+    Stats,
+    Processes,
+    Gpu,
+    Disks,
+}
+
+// Indexed by `Source`; atomics so the sampler can read them under its own lock
+static INTERVALS_MS: [AtomicU64; 4] = [
+    AtomicU64::new(1000),
+    AtomicU64::new(2000),
+    AtomicU64::new(1000),
+    AtomicU64::new(30_000),
+];
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+static STATS: Mutex<Option<MetricSample>> = Mutex::new(None);
+static GPU: Mutex<Option<HashMap<u32, f32>>> = Mutex::new(None);
+static DISKS: Mutex<Option<Vec<DiskInfo>>> = Mutex::new(None);
+
+fn refresh(source: Source) {
+    match source {
+        Source::Stats => {
+            let sample =
+                metrics::collect(&[MetricKind::System, MetricKind::Network, MetricKind::Sensors]);
+            *STATS.lock().unwrap() = Some(sample);
+        }
+        // Only the listing; callers refresh the processes they look at
+        Source::Processes => sampler::with_processes(|_| false, |_, _| ()),
+        Source::Gpu => {
+            let usage = metrics::collect(&[MetricKind::ProcessGpu]).process_gpu;
+            *GPU.lock().unwrap() = Some(usage);
+        }
+        Source::Disks => {
+            let disks = metrics::collect(&[MetricKind::Disks]).disks;
+            *DISKS.lock().unwrap() = Some(disks);
+        }
+    }
+}
+
+// Public API
+/// Use `intervals` from now on
+pub fn apply(intervals: &RefreshIntervals) {
+    let all = [
+        intervals.stats_ms,
+        intervals.processes_ms,
+        intervals.gpu_ms,
+        intervals.disks_ms,
+    ];
+    for (slot, ms) in INTERVALS_MS.iter().zip(all) {
+        slot.store(ms, Ordering::Relaxed);
+    }
+}
+
+/// Current interval for `source`, stretched in low-power mode
+pub fn interval(source: Source) -> Duration {
+    power::interval(Duration::from_millis(
+        INTERVALS_MS[source as usize].load(Ordering::Relaxed),
+    ))
+}
+
+/// Latest CPU/memory/network/sensor sample, or None if the refresher isn't running
+pub fn cached_stats() -> Option<MetricSample> {
+    RUNNING
+        .load(Ordering::Relaxed)
+        .then(|| STATS.lock().unwrap().clone())
+        .flatten()
+}
+
+pub fn cached_gpu() -> Option<HashMap<u32, f32>> {
+    RUNNING
+        .load(Ordering::Relaxed)
+        .then(|| GPU.lock().unwrap().clone())
+        .flatten()
+}
+
+pub fn cached_disks() -> Option<Vec<DiskInfo>> {
+    RUNNING
+        .load(Ordering::Relaxed)
+        .then(|| DISKS.lock().unwrap().clone())
+        .flatten()
+}
+
+pub fn start(intervals: &RefreshIntervals) {
+    apply(intervals);
+    RUNNING.store(true, Ordering::Relaxed);
+    thread::spawn(|| {
+        let sources = [Source::Stats, Source::Processes, Source::Gpu, Source::Disks];
+        let mut last: [Option<Instant>; 4] = [None; 4];
+        loop {
+            for source in sources {
+                let due = last[source as usize].is_none_or(|at| at.elapsed() >= interval(source));
+                if due {
+                    refresh(source);
+                    last[source as usize] = Some(Instant::now());
+                }
+            }
+            thread::sleep(TICK);
+        }
+    });
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::read_state;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A machine running `akt-agent`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RemoteHost {
+    pub name: String,
+    pub address: String, // e.g. "http://192.168.1.20:7878"
+    pub token: String,
+    pub added_at: String,
+}
+
+/// What the UI gets back from `list_remote_hosts` (never includes the token)
+#[derive(Serialize)]
+pub struct RemoteHostInfo {
+    pub name: String,
+    pub address: String,
+    pub added_at: String,
+}
+
+impl From<&RemoteHost> for RemoteHostInfo {
+    fn from(host: &RemoteHost) -> Self {
+        Self {
+            name: host.name.clone(),
+            address: host.address.clone(),
+            added_at: host.added_at.clone(),
+        }
+    }
+}
+
+fn find_host(name: &str) -> Result<RemoteHost, String> {
+    read_state(|state| {
+        state
+            .remote_hosts
+            .iter()
+            .find(|h| h.name.to_lowercase() == name.to_lowercase())
+            .cloned()
+    })
+    .ok_or_else(|| format!("Unknown remote host: {}", name))
+}
+
+// Encode a value for use in a URL path segment or query string
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn send(host: &str, method: &str, path: &str, body: Option<Value>) -> Result<Value, String> {
+    let host = find_host(host)?;
+    let url = format!("{}{}", host.address.trim_end_matches('/'), path);
+
+    let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+    let request = agent
+        .request(method, &url)
+        .set("Authorization", &format!("Bearer {}", host.token));
+    let response = match body {
+        Some(body) => request.send_json(body),
+        None => request.call(),
+    };
+
+    match response {
+        Ok(resp) => resp.into_json::<Value>().map_err(|e| e.to_string()),
+        // The agent reports failures as {"error": "..."}
+        Err(ureq::Error::Status(code, resp)) => Err(resp
+            .into_json::<Value>()
+            .ok()
+            .and_then(|v| v["error"].as_str().map(|s| s.to_string()))
+            .unwrap_or_else(|| format!("{} returned HTTP {}", host.name, code))),
+        Err(e) => Err(format!("{} is unreachable: {}", host.name, e)),
+    }
+}
+
+// Public API
+pub fn get_stats(host: &str) -> Result<Value, String> {
+    send(host, "GET", "/api/stats", None)
+}
+
+pub fn grouped_processes(host: &str, names: &[String]) -> Result<Value, String> {
+    let path = format!("/api/processes?names={}", encode(&names.join(",")));
+    send(host, "GET", &path, None)
+}
+
+pub fn get_blacklist(host: &str) -> Result<Value, String> {
+    send(host, "GET", "/api/blacklist", None)
+}
+
+pub fn add_to_blacklist(
+    host: &str,
+    name: &str,
+    auto_kill: bool,
+    cpu_threshold: f32,
+) -> Result<Value, String> {
+    let body = serde_json::json!({
+        "name": name,
+        "auto_kill": auto_kill,
+        "cpu_threshold": cpu_threshold,
+    });
+    send(host, "POST", "/api/blacklist", Some(body))
+}
+
+pub fn remove_from_blacklist(host: &str, name: &str) -> Result<Value, String> {
+    send(
+        host,
+        "DELETE",
+        &format!("/api/blacklist/{}", encode(name)),
+        None,
+    )
+}
+
+pub fn kill_pid(host: &str, pid: u32) -> Result<Value, String> {
+    send(host, "POST", &format!("/api/kill/{}", pid), None)
+}
+
+pub fn kill_process_group(host: &str, name: &str) -> Result<Value, String> {
+    send(
+        host,
+        "POST",
+        &format!("/api/kill-group/{}", encode(name)),
+        None,
+    )
+}
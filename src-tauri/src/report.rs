@@ -0,0 +1,259 @@
+// Weekly resource report: a standalone HTML page built from the hourly history (CPU, memory,
+// disks and running apps, see digest.rs) and the activity log. No scripts or external assets,
+// so it opens anywhere and can be mailed or archived as is.
+
+use chrono::{Duration as ChronoDuration, Local};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::digest::{self, Sample};
+use crate::read_state;
+
+const DAYS: i64 = 7;
+const TOP_APPS: usize = 15;
+const TOP_KILLED: usize = 10;
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em auto;max-width:860px;\
+color:#222}h1{margin-bottom:0}.sub{color:#666;margin-top:4px}h2{margin-top:2em;\
+border-bottom:1px solid #ddd;padding-bottom:4px}table{border-collapse:collapse;width:100%}\
+th,td{text-align:left;padding:4px 8px;border-bottom:1px solid #eee}td.n,th.n{text-align:right}\
+.cards{display:flex;gap:12px}.card{flex:1;border:1px solid #ddd;border-radius:8px;padding:12px}\
+.card b{display:block;font-size:1.6em}svg{width:100%;height:90px;background:#fafafa}\
+.empty{color:#888}";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Inline line chart of 0-100% values
+fn sparkline(values: &[f32], color: &str) -> String {
+    if values.len() < 2 {
+        return "<p class=\"empty\">Not enough samples yet</p>".to_string();
+    }
+    let step = 600.0 / (values.len() - 1) as f32;
+    let points: Vec<String> = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            format!(
+                "{:.1},{:.1}",
+                i as f32 * step,
+                90.0 - v.clamp(0.0, 100.0) * 0.9
+            )
+        })
+        .collect();
+    format!(
+        "<svg viewBox=\"0 0 600 90\" preserveAspectRatio=\"none\"><polyline fill=\"none\" \
+        stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/></svg>",
+        color,
+        points.join(" ")
+    )
+}
+
+fn average(values: impl Iterator<Item = f32>) -> (f32, f32) {
+    let (mut sum, mut peak, mut count) = (0.0, 0.0f32, 0);
+    for v in values {
+        sum += v;
+        peak = peak.max(v);
+        count += 1;
+    }
+    (if count > 0 { sum / count as f32 } else { 0.0 }, peak)
+}
+
+fn trends(html: &mut String, samples: &[Sample]) {
+    html.push_str("<h2>CPU and memory</h2>");
+    let cpu: Vec<f32> = samples.iter().map(|s| s.cpu_percent).collect();
+    let memory: Vec<f32> = samples.iter().map(|s| s.memory_percent).collect();
+    let _ = write!(html, "<p>CPU</p>{}", sparkline(&cpu, "#d9534f"));
+    let _ = write!(html, "<p>Memory</p>{}", sparkline(&memory, "#337ab7"));
+
+    let mut days: BTreeMap<&str, Vec<&Sample>> = BTreeMap::new();
+    for s in samples {
+        days.entry(s.at.get(..10).unwrap_or(&s.at))
+            .or_default()
+            .push(s);
+    }
+    html.push_str(
+        "<table><tr><th>Day</th><th class=\"n\">CPU avg</th><th class=\"n\">CPU peak</th>\
+        <th class=\"n\">Memory avg</th><th class=\"n\">Memory peak</th></tr>",
+    );
+    for (day, day_samples) in &days {
+        let (cpu_avg, cpu_peak) = average(day_samples.iter().map(|s| s.cpu_percent));
+        let (mem_avg, mem_peak) = average(day_samples.iter().map(|s| s.memory_percent));
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td class=\"n\">{:.0}%</td><td class=\"n\">{:.0}%</td>\
+            <td class=\"n\">{:.0}%</td><td class=\"n\">{:.0}%</td></tr>",
+            day, cpu_avg, cpu_peak, mem_avg, mem_peak
+        );
+    }
+    html.push_str("</table>");
+}
+
+fn top_apps(html: &mut String, samples: &[Sample]) {
+    html.push_str("<h2>Top apps by usage time</h2>");
+    let mut seen: HashMap<&str, i64> = HashMap::new();
+    for name in samples.iter().flat_map(|s| &s.apps) {
+        *seen.entry(name.as_str()).or_default() += 1;
+    }
+    let mut apps: Vec<(&str, i64)> = seen.into_iter().collect();
+    apps.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    apps.truncate(TOP_APPS);
+    if apps.is_empty() {
+        html.push_str("<p class=\"empty\">No app usage recorded yet</p>");
+        return;
+    }
+    html.push_str("<table><tr><th>App</th><th class=\"n\">Running</th></tr>");
+    for (name, count) in apps {
+        let minutes = count * digest::sample_minutes();
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td class=\"n\">{}h {:02}m</td></tr>",
+            escape(name),
+            minutes / 60,
+            minutes % 60
+        );
+    }
+    html.push_str("</table>");
+}
+
+/// Total kills, memory freed, and the table of most-killed apps
+fn kills(html: &mut String, since: &str) -> (usize, u64) {
+    let (per_day, per_app) = read_state(|state| {
+        let mut per_day: BTreeMap<String, usize> = BTreeMap::new();
+        let mut per_app: HashMap<String, (usize, u64)> = HashMap::new();
+        for log in state
+            .activity_logs
+            .iter()
+            .filter(|log| log.was_killed && log.detected_at.as_str() >= since)
+        {
+            *per_day
+                .entry(log.detected_at.get(..10).unwrap_or_default().to_string())
+                .or_default() += 1;
+            let app = per_app.entry(log.name.clone()).or_default();
+            app.0 += 1;
+            app.1 += log.freed_memory_kb;
+        }
+        (per_day, per_app)
+    });
+    let total: usize = per_day.values().sum();
+    let freed_kb: u64 = per_app.values().map(|(_, kb)| kb).sum();
+
+    html.push_str("<h2>Kills performed</h2>");
+    if total == 0 {
+        html.push_str("<p class=\"empty\">Nothing was killed this week</p>");
+        return (0, 0);
+    }
+    html.push_str("<table><tr><th>Day</th><th class=\"n\">Kills</th></tr>");
+    for (day, count) in &per_day {
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td class=\"n\">{}</td></tr>",
+            day, count
+        );
+    }
+    html.push_str("</table><p></p>");
+
+    let mut apps: Vec<(String, (usize, u64))> = per_app.into_iter().collect();
+    apps.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.0.cmp(&b.0)));
+    apps.truncate(TOP_KILLED);
+    html.push_str(
+        "<table><tr><th>App</th><th class=\"n\">Kills</th><th class=\"n\">Memory freed</th></tr>",
+    );
+    for (name, (count, kb)) in apps {
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{:.0} MB</td></tr>",
+            escape(&name),
+            count,
+            kb as f64 / 1024.0
+        );
+    }
+    html.push_str("</table>");
+    (total, freed_kb)
+}
+
+fn disk_growth(html: &mut String, samples: &[Sample]) {
+    html.push_str("<h2>Disk growth</h2>");
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        html.push_str("<p class=\"empty\">No disk samples yet</p>");
+        return;
+    };
+    html.push_str(
+        "<table><tr><th>Disk</th><th class=\"n\">Used</th><th class=\"n\">Change</th></tr>",
+    );
+    for (mount, now) in &last.disks {
+        let change = first
+            .disks
+            .iter()
+            .find(|(m, _)| m == mount)
+            .map_or("new".to_string(), |(_, then)| {
+                format!("{:+.1} points", now - then)
+            });
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td class=\"n\">{:.0}%</td><td class=\"n\">{}</td></tr>",
+            escape(mount),
+            now,
+            change
+        );
+    }
+    html.push_str("</table>");
+}
+
+fn render() -> String {
+    let now = Local::now().naive_local();
+    let since = (now - ChronoDuration::days(DAYS))
+        .format(TIME_FORMAT)
+        .to_string();
+    let samples = digest::samples_since(&since);
+    let host = sysinfo::System::host_name().unwrap_or_else(|| "this computer".to_string());
+
+    let mut body = String::new();
+    trends(&mut body, &samples);
+    top_apps(&mut body, &samples);
+    let (total_kills, freed_kb) = kills(&mut body, &since);
+    disk_growth(&mut body, &samples);
+
+    let (cpu_avg, _) = average(samples.iter().map(|s| s.cpu_percent));
+    let (mem_avg, _) = average(samples.iter().map(|s| s.memory_percent));
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Weekly report - {host}</title>\
+        <style>{style}</style></head><body><h1>Weekly resource report</h1>\
+        <p class=\"sub\">{host}, {since} to {until}</p><div class=\"cards\">\
+        <div class=\"card\"><b>{cpu:.0}%</b>average CPU</div>\
+        <div class=\"card\"><b>{mem:.0}%</b>average memory</div>\
+        <div class=\"card\"><b>{kills}</b>kills</div>\
+        <div class=\"card\"><b>{freed:.0} MB</b>memory freed</div></div>{body}</body></html>",
+        host = escape(&host),
+        style = STYLE,
+        since = &since[..10],
+        until = now.format("%Y-%m-%d"),
+        cpu = cpu_avg,
+        mem = mem_avg,
+        kills = total_kills,
+        freed = freed_kb as f64 / 1024.0,
+        body = body,
+    )
+}
+
+// Public API
+/// Write the report for the last 7 days into `folder` (default: reports/ in the data folder)
+pub fn generate_weekly_report(folder: Option<&Path>) -> Result<PathBuf, String> {
+    let folder = folder.map_or_else(|| crate::data_dir().join("reports"), Path::to_path_buf);
+    fs::create_dir_all(&folder)
+        .map_err(|e| format!("Cannot create {}: {}", folder.display(), e))?;
+    let path = folder.join(format!(
+        "weekly-report-{}.html",
+        Local::now().format("%Y-%m-%d")
+    ));
+    fs::write(&path, render()).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    tracing::info!(path = %path.display(), "weekly report written");
+    Ok(path)
+}
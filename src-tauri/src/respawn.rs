@@ -0,0 +1,199 @@
+// Watch killed apps for a quick comeback, which usually means a service, scheduled task
+// or updater restarts them and killing alone will never stick. An app that comes back too
+// often in an hour is a respawn loop: killing it is paused and its launcher looked up.
+
+use chrono::{Duration as ChronoDuration, Local};
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use sysinfo::System;
+
+use crate::launcher::{self, Launcher};
+
+const LOOP_WINDOW: Duration = Duration::from_secs(60 * 60);
+const LOOP_PAUSE: Duration = Duration::from_secs(60 * 60);
+
+struct Watch {
+    rule: String,
+    name: String,
+    exe_path: Option<String>,
+    pid: u32,
+    start_time: u64,
+    killed_at: String, // detected_at of the kill's log entry
+    until: Instant,
+}
+
+/// A killed app that came back within the watch window
+#[derive(Serialize, Clone)]
+pub struct Respawn {
+    pub rule: String,
+    pub name: String,
+    pub exe_path: Option<String>,
+    pub pid: u32,     // The killed instance
+    pub new_pid: u32, // The instance that replaced it
+    pub killed_at: String,
+    pub suggestion: String,
+}
+
+/// Sent as "respawn-loop": an app that keeps coming back, no longer killed for now
+#[derive(Serialize, Clone)]
+pub struct RespawnLoop {
+    pub rule: String,
+    pub name: String,
+    pub exe_path: Option<String>,
+    pub count: usize, // Comebacks in the last hour
+    pub launcher: Launcher,
+    pub suggestion: String,
+    pub paused_until: String,
+}
+
+static WATCHES: Mutex<Vec<Watch>> = Mutex::new(Vec::new());
+// (rule, lower-case name, when) of each comeback in the last hour
+static HISTORY: Mutex<Vec<(String, String, Instant)>> = Mutex::new(Vec::new());
+static LOOPS: Mutex<Vec<(Instant, RespawnLoop)>> = Mutex::new(Vec::new());
+
+fn loop_suggestion(name: &str, launcher: &Launcher) -> String {
+    let name = ("name", name.to_string());
+    match launcher {
+        Launcher::Service { name: service } => crate::i18n::t(
+            "suggest.loop_service",
+            &[name, ("service", service.clone())],
+        ),
+        Launcher::ScheduledTask { host } => {
+            crate::i18n::t("suggest.loop_task", &[name, ("host", host.clone())])
+        }
+        Launcher::Process {
+            name: parent, pid, ..
+        } => crate::i18n::t(
+            "suggest.loop_process",
+            &[name, ("launcher", parent.clone()), ("pid", pid.to_string())],
+        ),
+        Launcher::Unknown => crate::i18n::t("suggest.loop_unknown", &[name]),
+    }
+}
+
+// Public API
+/// Start watching for `p` to come back within `window_secs` (0 = don't watch)
+pub fn watch(rule: &str, p: &sysinfo::Process, killed_at: &str, window_secs: u64) {
+    if window_secs == 0 {
+        return;
+    }
+    WATCHES.lock().unwrap().push(Watch {
+        rule: rule.to_string(),
+        name: p.name().to_string_lossy().to_string(),
+        exe_path: p.exe().map(|e| e.to_string_lossy().to_string()),
+        pid: p.pid().as_u32(),
+        start_time: p.start_time(),
+        killed_at: killed_at.to_string(),
+        until: Instant::now() + Duration::from_secs(window_secs),
+    });
+}
+
+/// Watched apps that are running again; those and expired watches are dropped
+pub fn check(sys: &System, window_secs: u64) -> Vec<Respawn> {
+    let now = Instant::now();
+    let mut found = Vec::new();
+    WATCHES.lock().unwrap().retain(|w| {
+        if now >= w.until {
+            return false;
+        }
+        let comeback = sys.processes().values().find(|p| {
+            let same_instance = p.pid().as_u32() == w.pid && p.start_time() == w.start_time;
+            let exe = p.exe().map(|e| e.to_string_lossy());
+            // Exe paths are only known for sampled processes; fall back to the name alone
+            let same_exe = match (&w.exe_path, &exe) {
+                (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                _ => true,
+            };
+            !same_instance && same_exe && p.name().to_string_lossy().eq_ignore_ascii_case(&w.name)
+        });
+        match comeback {
+            Some(p) => {
+                found.push(Respawn {
+                    rule: w.rule.clone(),
+                    name: w.name.clone(),
+                    exe_path: w.exe_path.clone(),
+                    pid: w.pid,
+                    new_pid: p.pid().as_u32(),
+                    killed_at: w.killed_at.clone(),
+                    suggestion: crate::i18n::t(
+                        "suggest.auto_restarts",
+                        &[
+                            ("name", w.name.clone()),
+                            ("seconds", window_secs.to_string()),
+                        ],
+                    ),
+                });
+                false
+            }
+            None => true,
+        }
+    });
+    found
+}
+
+/// Comebacks that push an app over `limit` an hour (0 = off); killing it pauses for an hour
+pub fn detect_loops(sys: &System, respawns: &[Respawn], limit: u32) -> Vec<RespawnLoop> {
+    if limit == 0 {
+        return vec![];
+    }
+    let now = Instant::now();
+    let mut history = HISTORY.lock().unwrap();
+    history.retain(|(_, _, at)| now.duration_since(*at) < LOOP_WINDOW);
+    let mut found = vec![];
+    for r in respawns {
+        let name = r.name.to_lowercase();
+        history.push((r.rule.clone(), name.clone(), now));
+        let count = history
+            .iter()
+            .filter(|(rule, n, _)| *rule == r.rule && *n == name)
+            .count();
+        if count <= limit as usize || is_looping(&r.rule, &r.name) {
+            continue;
+        }
+        let launcher = launcher::identify(sys, r.new_pid);
+        let paused_until = Local::now() + ChronoDuration::seconds(LOOP_PAUSE.as_secs() as i64);
+        let found_loop = RespawnLoop {
+            rule: r.rule.clone(),
+            name: r.name.clone(),
+            exe_path: r.exe_path.clone(),
+            count,
+            suggestion: loop_suggestion(&r.name, &launcher),
+            launcher,
+            paused_until: paused_until.format("%Y-%m-%d %H:%M:%S").to_string(),
+        };
+        tracing::warn!(rule = %r.rule, name = %r.name, count, "respawn loop, killing paused");
+        LOOPS
+            .lock()
+            .unwrap()
+            .push((now + LOOP_PAUSE, found_loop.clone()));
+        found.push(found_loop);
+    }
+    found
+}
+
+/// Whether killing `name` under `rule` is paused by a respawn loop
+pub fn is_looping(rule: &str, name: &str) -> bool {
+    let now = Instant::now();
+    let mut loops = LOOPS.lock().unwrap();
+    loops.retain(|(until, _)| now < *until);
+    loops
+        .iter()
+        .any(|(_, l)| l.rule == rule && l.name.eq_ignore_ascii_case(name))
+}
+
+pub fn loops() -> Vec<RespawnLoop> {
+    let now = Instant::now();
+    let mut loops = LOOPS.lock().unwrap();
+    loops.retain(|(until, _)| now < *until);
+    loops.iter().map(|(_, l)| l.clone()).collect()
+}
+
+/// Kill the rule's apps again, e.g. once their launcher has been disabled
+pub fn resume(rule: &str) -> bool {
+    HISTORY.lock().unwrap().retain(|(r, _, _)| r != rule);
+    let mut loops = LOOPS.lock().unwrap();
+    let before = loops.len();
+    loops.retain(|(_, l)| l.rule != rule);
+    loops.len() != before
+}
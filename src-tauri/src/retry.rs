@@ -0,0 +1,139 @@
+// Backoff for failed auto-kills, so a process we can't kill isn't retried every poll forever
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Attempts per PID before the watcher gives up on it
+pub const MAX_ATTEMPTS: u32 = 5;
+// Delay after the first failure; doubles after each further one
+const BASE_DELAY: Duration = Duration::from_secs(2);
+
+struct Retry {
+    attempts: u32,
+    next_at: Option<Instant>, // None = gave up
+    history: Vec<String>,
+}
+
+/// A failing PID as saved across restarts
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PendingRetry {
+    pub pid: u32,
+    pub start_time: u64,
+    pub attempts: u32,
+    pub gave_up: bool,
+    pub history: Vec<String>,
+}
+
+fn delay_after(attempts: u32) -> Option<Duration> {
+    (attempts < MAX_ATTEMPTS).then(|| BASE_DELAY * 2u32.pow(attempts - 1))
+}
+
+/// What the log entry should say about this attempt
+pub struct Attempt {
+    pub number: u32,
+    pub next_retry_secs: Option<u64>, // None after success or when giving up
+    pub history: Vec<String>,         // Empty when the first attempt succeeded
+}
+
+// Keyed by (PID, start time) so a recycled PID starts fresh; kept in memory only
+static RETRIES: Mutex<Option<HashMap<(u32, u64), Retry>>> = Mutex::new(None);
+
+// Public API
+/// False while a failed PID is backing off, or once it has used up its attempts
+pub fn is_due(pid: u32, start_time: u64) -> bool {
+    let retries = RETRIES.lock().unwrap();
+    match retries.as_ref().and_then(|r| r.get(&(pid, start_time))) {
+        Some(retry) => retry.next_at.is_some_and(|at| Instant::now() >= at),
+        None => true,
+    }
+}
+
+/// Record the outcome of a kill attempt made at `at`
+pub fn record(pid: u32, start_time: u64, success: bool, at: &str) -> Attempt {
+    let mut guard = RETRIES.lock().unwrap();
+    let retries = guard.get_or_insert_with(HashMap::new);
+
+    if success {
+        return match retries.remove(&(pid, start_time)) {
+            Some(mut retry) => {
+                retry
+                    .history
+                    .push(format!("{} attempt {} succeeded", at, retry.attempts + 1));
+                Attempt {
+                    number: retry.attempts + 1,
+                    next_retry_secs: None,
+                    history: retry.history,
+                }
+            }
+            None => Attempt {
+                number: 1,
+                next_retry_secs: None,
+                history: Vec::new(),
+            },
+        };
+    }
+
+    let retry = retries.entry((pid, start_time)).or_insert(Retry {
+        attempts: 0,
+        next_at: None,
+        history: Vec::new(),
+    });
+    retry.attempts += 1;
+    retry
+        .history
+        .push(format!("{} attempt {} failed", at, retry.attempts));
+    let delay = delay_after(retry.attempts);
+    retry.next_at = delay.map(|d| Instant::now() + d);
+
+    Attempt {
+        number: retry.attempts,
+        next_retry_secs: delay.map(|d| d.as_secs()),
+        history: retry.history.clone(),
+    }
+}
+
+/// PIDs still failing or given up on, for the runtime state file
+pub fn pending() -> Vec<PendingRetry> {
+    let retries = RETRIES.lock().unwrap();
+    retries
+        .iter()
+        .flatten()
+        .map(|((pid, start_time), retry)| PendingRetry {
+            pid: *pid,
+            start_time: *start_time,
+            attempts: retry.attempts,
+            gave_up: retry.next_at.is_none(),
+            history: retry.history.clone(),
+        })
+        .collect()
+}
+
+/// Pick up retries saved by the previous session; each waits its full backoff again
+pub fn restore(pending: Vec<PendingRetry>) {
+    let mut guard = RETRIES.lock().unwrap();
+    let retries = guard.get_or_insert_with(HashMap::new);
+    for p in pending.into_iter().filter(|p| p.attempts > 0) {
+        let next_at = if p.gave_up {
+            None
+        } else {
+            delay_after(p.attempts).map(|d| Instant::now() + d)
+        };
+        retries.insert(
+            (p.pid, p.start_time),
+            Retry {
+                attempts: p.attempts,
+                next_at,
+                history: p.history,
+            },
+        );
+    }
+}
+
+/// Drop entries for processes that have exited
+pub fn forget_exited(is_running: impl Fn(u32, u64) -> bool) {
+    if let Some(retries) = RETRIES.lock().unwrap().as_mut() {
+        retries.retain(|(pid, start_time), _| is_running(*pid, *start_time));
+    }
+}
@@ -0,0 +1,178 @@
+// Watcher runtime state (kill retries, processes we suspended, pending confirmations, snoozes),
+// autosaved apart from the config so a restart can pick it up and a crash can be told apart
+// from a clean exit
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use crate::{app_limits, boost, confirm, focus, pressure, process_ctl, read_state, retry};
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A process one of our features suspended, which stays frozen if we die without resuming it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SuspendedProcess {
+    pub pid: u32,
+    #[serde(default)]
+    pub start_time: Option<u64>, // Resumed only if the PID still has it; None when not tracked
+    pub by: String, // "app limit", "boost", "focus" or "memory pressure"
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+struct RuntimeState {
+    session_started: String,
+    last_saved: String,
+    clean_exit: bool,
+    retries: Vec<retry::PendingRetry>,
+    suspended: Vec<SuspendedProcess>,
+    pending_confirmations: Vec<confirm::PendingKill>,
+    snoozed_rules: Vec<String>, // The snoozes themselves live in the config
+}
+
+/// How the previous session ended and what it was in the middle of
+#[derive(Serialize, Clone)]
+pub struct ShutdownInfo {
+    pub first_run: bool,
+    pub crashed: bool,
+    pub session_started: Option<String>,
+    pub last_seen: Option<String>, // Last autosave; a crash happened after this
+    pub retries: Vec<retry::PendingRetry>, // Restored into this session
+    pub suspended: Vec<SuspendedProcess>, // Resumed on startup so nothing stays frozen
+    pub pending_confirmations: Vec<confirm::PendingKill>, // Dropped; the watcher asks again
+    pub snoozed_rules: Vec<String>,
+}
+
+static LAST_SHUTDOWN: OnceLock<ShutdownInfo> = OnceLock::new();
+static SESSION_STARTED: OnceLock<String> = OnceLock::new();
+// Stops the autosave from overwriting the clean-exit marker
+static EXITING: AtomicBool = AtomicBool::new(false);
+
+fn path() -> PathBuf {
+    crate::data_dir().join("runtime_state.json")
+}
+
+fn now() -> String {
+    Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn snapshot(clean_exit: bool) -> RuntimeState {
+    let mut suspended: Vec<SuspendedProcess> = vec![];
+    for (by, processes) in [
+        ("app limit", app_limits::suspended()),
+        ("focus", focus::suspended()),
+        ("memory pressure", pressure::suspended()),
+    ] {
+        suspended.extend(
+            processes
+                .into_iter()
+                .map(|(pid, start_time)| SuspendedProcess {
+                    pid,
+                    start_time: Some(start_time),
+                    by: by.to_string(),
+                }),
+        );
+    }
+    suspended.extend(
+        boost::suspended_pids()
+            .into_iter()
+            .map(|pid| SuspendedProcess {
+                pid,
+                start_time: None,
+                by: "boost".to_string(),
+            }),
+    );
+    let now = now();
+    let snoozed_rules = read_state(|state| {
+        state
+            .blacklist
+            .iter()
+            .filter(|r| r.snoozed_until.as_ref().is_some_and(|until| *until > now))
+            .map(|r| r.name.clone())
+            .collect()
+    });
+
+    RuntimeState {
+        session_started: SESSION_STARTED.get().cloned().unwrap_or_default(),
+        last_saved: now,
+        clean_exit,
+        retries: retry::pending(),
+        suspended,
+        pending_confirmations: confirm::list(),
+        snoozed_rules,
+    }
+}
+
+fn save(state: &RuntimeState) {
+    if let Ok(data) = serde_json::to_string_pretty(state) {
+        fs::write(path(), data).ok();
+    }
+}
+
+// Public API
+/// Read what the previous session left behind, restore its retries, resume anything it
+/// suspended, then keep this session's state autosaved
+pub fn start() {
+    let previous: Option<RuntimeState> = fs::read_to_string(path())
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok());
+    SESSION_STARTED.get_or_init(now);
+
+    let info = match previous {
+        Some(prev) => {
+            retry::restore(prev.retries.clone());
+            for p in &prev.suspended {
+                match p.start_time {
+                    Some(start_time) => process_ctl::resume_same(p.pid, start_time).ok(),
+                    None => process_ctl::resume_process(p.pid).ok(),
+                };
+            }
+            ShutdownInfo {
+                first_run: false,
+                crashed: !prev.clean_exit,
+                session_started: Some(prev.session_started),
+                last_seen: Some(prev.last_saved),
+                retries: prev.retries,
+                suspended: prev.suspended,
+                pending_confirmations: prev.pending_confirmations,
+                snoozed_rules: prev.snoozed_rules,
+            }
+        }
+        None => ShutdownInfo {
+            first_run: true,
+            crashed: false,
+            session_started: None,
+            last_seen: None,
+            retries: vec![],
+            suspended: vec![],
+            pending_confirmations: vec![],
+            snoozed_rules: vec![],
+        },
+    };
+    LAST_SHUTDOWN.get_or_init(|| info);
+
+    save(&snapshot(false));
+    thread::spawn(|| loop {
+        thread::sleep(SAVE_INTERVAL);
+        if EXITING.load(Ordering::Relaxed) {
+            break;
+        }
+        save(&snapshot(false));
+    });
+}
+
+/// Record that this session is ending normally
+pub fn mark_clean_exit() {
+    EXITING.store(true, Ordering::Relaxed);
+    save(&snapshot(true));
+}
+
+pub fn last_shutdown() -> Option<ShutdownInfo> {
+    LAST_SHUTDOWN.get().cloned()
+}
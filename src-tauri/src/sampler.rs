@@ -0,0 +1,111 @@
+// Shared process table, refreshed incrementally: every poll lists all PIDs cheaply,
+// but only the processes a caller asks about get their CPU, memory and paths refreshed
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use sysinfo::{
+    CpuRefreshKind, Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind,
+};
+
+use crate::{protection, refresher};
+
+struct Shared {
+    sys: System,
+    listed_at: Option<Instant>,
+    refreshed_at: HashMap<Pid, Instant>, // Last full-stats refresh of each process
+}
+
+// Kept across polls so CPU usage is measured over the real interval between them
+static SYSTEM: Mutex<Option<Shared>> = Mutex::new(None);
+
+fn details() -> ProcessRefreshKind {
+    ProcessRefreshKind::nothing()
+        .with_cpu()
+        .with_memory()
+        .with_exe(UpdateKind::OnlyIfNotSet)
+        .with_cmd(UpdateKind::OnlyIfNotSet)
+        .with_user(UpdateKind::OnlyIfNotSet)
+}
+
+// Public API
+/// List every process, then refresh full stats only for the ones `wanted` selects
+pub fn refresh(sys: &mut System, wanted: impl Fn(&Process) -> bool) {
+    // Listing only: new and dead processes, names and parents
+    sys.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing());
+    let pids: Vec<Pid> = sys
+        .processes()
+        .values()
+        // own_pids() needs the command line of WebView2 hosts
+        .filter(|p| wanted(p) || protection::is_webview_host(&p.name().to_string_lossy()))
+        .map(|p| p.pid())
+        .collect();
+    sys.refresh_processes_specifics(ProcessesToUpdate::Some(&pids), false, details());
+}
+
+/// Refresh the process list, then full stats for the processes `wanted` selects, and run `f`.
+/// Unselected processes only have up-to-date names and parents. Nothing younger than the
+/// process refresh interval is refreshed again, however many callers ask.
+/// Don't call this from inside `with_state` - it holds its own lock while `f` runs.
+pub fn with_processes<R>(
+    wanted: impl Fn(&Process) -> bool,
+    f: impl FnOnce(&System, f32) -> R,
+) -> R {
+    let mut guard = SYSTEM.lock().unwrap();
+    let shared = guard.get_or_insert_with(|| {
+        let mut sys = System::new();
+        sys.refresh_cpu_list(CpuRefreshKind::nothing());
+        Shared {
+            sys,
+            listed_at: None,
+            refreshed_at: HashMap::new(),
+        }
+    });
+    let max_age = refresher::interval(refresher::Source::Processes);
+    let now = Instant::now();
+    let is_stale = |at: Option<&Instant>| at.is_none_or(|at| now.duration_since(*at) >= max_age);
+
+    if is_stale(shared.listed_at.as_ref()) {
+        shared.sys.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing(),
+        );
+        shared.listed_at = Some(now);
+        let sys = &shared.sys;
+        shared
+            .refreshed_at
+            .retain(|pid, _| sys.process(*pid).is_some());
+    }
+    let pids: Vec<Pid> = shared
+        .sys
+        .processes()
+        .values()
+        .filter(|p| wanted(p) || protection::is_webview_host(&p.name().to_string_lossy()))
+        .map(|p| p.pid())
+        .filter(|pid| is_stale(shared.refreshed_at.get(pid)))
+        .collect();
+    if !pids.is_empty() {
+        shared
+            .sys
+            .refresh_processes_specifics(ProcessesToUpdate::Some(&pids), false, details());
+        for pid in pids {
+            shared.refreshed_at.insert(pid, now);
+        }
+    }
+
+    let cpu_count = (shared.sys.cpus().len() as f32).max(1.0);
+    f(&shared.sys, cpu_count)
+}
+
+/// Every process with full stats (browse views and snapshots of the whole system)
+pub fn with_all_processes<R>(f: impl FnOnce(&System, f32) -> R) -> R {
+    with_processes(|_| true, f)
+}
+
+/// Make the next caller list processes again, e.g. right after one was seen starting
+pub fn invalidate() {
+    if let Some(shared) = SYSTEM.lock().unwrap().as_mut() {
+        shared.listed_at = None;
+    }
+}
@@ -0,0 +1,162 @@
+use chrono::{Local, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::Duration;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::matcher::Matcher;
+use crate::{read_state, sampler, with_state};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleAction {
+    KillProcess { name: String },
+    BlacklistCheck,
+    RestartApp { name: String },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleTrigger {
+    Daily { time: String }, // "HH:MM", local time
+    Interval { seconds: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Schedule {
+    pub id: u32,
+    pub label: String,
+    pub action: ScheduleAction,
+    pub trigger: ScheduleTrigger,
+    pub created_at: String,
+    #[serde(default)]
+    pub last_run: Option<String>,
+    #[serde(default)]
+    pub last_result: Option<String>,
+}
+
+impl Schedule {
+    fn is_due(&self, now: NaiveDateTime) -> bool {
+        let last_run = self
+            .last_run
+            .as_deref()
+            .and_then(|s| NaiveDateTime::parse_from_str(s, TIME_FORMAT).ok());
+
+        match &self.trigger {
+            ScheduleTrigger::Daily { time } => {
+                let Ok(at) = NaiveTime::parse_from_str(time, "%H:%M") else {
+                    return false;
+                };
+                let since = now.time().signed_duration_since(at).num_seconds();
+                // Fire within the first minute after the target time, once per day
+                (0..60).contains(&since) && last_run.is_none_or(|l| l.date() != now.date())
+            }
+            ScheduleTrigger::Interval { seconds } => {
+                last_run.is_none_or(|l| (now - l).num_seconds() >= *seconds as i64)
+            }
+        }
+    }
+}
+
+pub fn validate_trigger(trigger: &ScheduleTrigger) -> Result<(), String> {
+    match trigger {
+        ScheduleTrigger::Daily { time } => NaiveTime::parse_from_str(time, "%H:%M")
+            .map(|_| ())
+            .map_err(|_| "Time must be in HH:MM format".to_string()),
+        ScheduleTrigger::Interval { seconds } if *seconds < 5 => {
+            Err("Interval must be at least 5 seconds".into())
+        }
+        ScheduleTrigger::Interval { .. } => Ok(()),
+    }
+}
+
+fn run_action(action: &ScheduleAction) -> Result<String, String> {
+    match action {
+        ScheduleAction::KillProcess { name } => crate::kill_processes_by_name(name),
+        ScheduleAction::BlacklistCheck => {
+            let logs = crate::run_blacklist_check();
+            let killed = logs.iter().filter(|l| l.was_killed).count();
+            Ok(format!("Blacklist checked, {} killed", killed))
+        }
+        ScheduleAction::RestartApp { name } => restart_app(name),
+    }
+}
+
+/// Kill every instance of an app and start it again the way one of them was started
+fn restart_app(name: &str) -> Result<String, String> {
+    let matcher = Matcher::for_name(name);
+    let is_match = |p: &sysinfo::Process| matcher.matches(&p.name().to_string_lossy());
+    let pid = sampler::with_processes(is_match, |sys, _| {
+        sys.processes()
+            .values()
+            .find(|p| is_match(p) && p.exe().is_some())
+            .map(|p| p.pid())
+    })
+    .ok_or("No running instance to restart")?;
+
+    // The sampler doesn't keep working folders
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::everything(),
+    );
+    let p = sys.process(pid).ok_or("No running instance to restart")?;
+    let exe = p
+        .exe()
+        .ok_or("No running instance to restart")?
+        .to_string_lossy()
+        .to_string();
+    // The first element is the program itself
+    let args: Vec<String> = p
+        .cmd()
+        .iter()
+        .skip(1)
+        .map(|a| a.to_string_lossy().to_string())
+        .collect();
+    let cwd = p.cwd().map(|d| d.to_path_buf());
+
+    crate::kill_processes_by_name(name)?;
+    // Give the old instances a moment to release their files
+    thread::sleep(Duration::from_secs(2));
+    // Some instances survived (or couldn't be killed); a second copy would fight them
+    sampler::invalidate();
+    if sampler::with_processes(is_match, |sys, _| sys.processes().values().any(is_match)) {
+        return Err(format!("{} is still running, not restarted", name));
+    }
+
+    crate::launch::start(&exe, &args, cwd.as_deref()).map(|_| format!("{} restarted", name))
+}
+
+// Public API
+pub fn start_executor() {
+    thread::spawn(|| loop {
+        thread::sleep(crate::power::interval(Duration::from_secs(1)));
+
+        let now = Local::now().naive_local();
+        let due: Vec<Schedule> = read_state(|state| {
+            state
+                .schedules
+                .iter()
+                .filter(|s| s.is_due(now))
+                .cloned()
+                .collect()
+        });
+
+        for schedule in due {
+            let result = match run_action(&schedule.action) {
+                Ok(msg) => msg,
+                Err(err) => format!("Error: {}", err),
+            };
+
+            with_state(|state| {
+                if let Some(s) = state.schedules.iter_mut().find(|s| s.id == schedule.id) {
+                    s.last_run = Some(now.format(TIME_FORMAT).to_string());
+                    s.last_result = Some(result);
+                }
+            });
+        }
+    });
+}
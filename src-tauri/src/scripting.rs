@@ -0,0 +1,85 @@
+use chrono::{Datelike, Local, Timelike};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+// Scripts run inside the watcher loop, so keep runaway scripts from stalling it
+const MAX_OPERATIONS: u64 = 100_000;
+
+static ENGINE: OnceLock<Engine> = OnceLock::new();
+// Compiled scripts keyed by their source text
+static AST_CACHE: Mutex<Option<HashMap<String, AST>>> = Mutex::new(None);
+
+/// Process facts exposed to `should_kill(process)`
+pub struct ProcessContext {
+    pub name: String,
+    pub pid: u32,
+    pub cpu: f32,
+    pub gpu: f32,
+    pub memory_kb: u64,
+    pub cmdline: String,
+}
+
+impl ProcessContext {
+    fn to_map(&self) -> Map {
+        let now = Local::now();
+        let mut map = Map::new();
+        map.insert("name".into(), Dynamic::from(self.name.clone()));
+        map.insert("pid".into(), Dynamic::from_int(self.pid as i64));
+        map.insert("cpu".into(), Dynamic::from_float(self.cpu as f64));
+        map.insert("gpu".into(), Dynamic::from_float(self.gpu as f64));
+        map.insert("memory_kb".into(), Dynamic::from_int(self.memory_kb as i64));
+        map.insert("cmdline".into(), Dynamic::from(self.cmdline.clone()));
+        map.insert("hour".into(), Dynamic::from_int(now.hour() as i64));
+        map.insert("minute".into(), Dynamic::from_int(now.minute() as i64));
+        // 1 = Monday ... 7 = Sunday
+        map.insert(
+            "weekday".into(),
+            Dynamic::from_int(now.weekday().number_from_monday() as i64),
+        );
+        map
+    }
+}
+
+fn engine() -> &'static Engine {
+    ENGINE.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine
+    })
+}
+
+fn compile_cached(script: &str) -> Result<AST, String> {
+    let mut cache = AST_CACHE.lock().unwrap();
+    let cache = cache.get_or_insert_with(HashMap::new);
+    if let Some(ast) = cache.get(script) {
+        return Ok(ast.clone());
+    }
+
+    let ast = compile(script)?;
+    cache.insert(script.to_string(), ast.clone());
+    Ok(ast)
+}
+
+// Public API
+/// Compile a script and make sure it defines `should_kill(process)`
+pub fn compile(script: &str) -> Result<AST, String> {
+    let ast = engine()
+        .compile(script)
+        .map_err(|e| format!("Script error: {}", e))?;
+
+    if !ast
+        .iter_functions()
+        .any(|f| f.name == "should_kill" && f.params.len() == 1)
+    {
+        return Err("Script must define fn should_kill(process)".into());
+    }
+    Ok(ast)
+}
+
+pub fn should_kill(script: &str, ctx: &ProcessContext) -> Result<bool, String> {
+    let ast = compile_cached(script)?;
+    engine()
+        .call_fn::<bool>(&mut Scope::new(), &ast, "should_kill", (ctx.to_map(),))
+        .map_err(|e| format!("Script error: {}", e))
+}
@@ -0,0 +1,150 @@
+// The app's own footprint, and a watchdog that slows polling down when it goes over budget
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{notify_event, power, protection, read_state, sampler};
+
+// Own processes are sampled this often
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+// Throttling is lifted once usage drops below this share of the budget
+const RELEASE_RATIO: f32 = 0.8;
+
+/// Limits the monitor should stay within
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SelfBudget {
+    pub enabled: bool,
+    pub cpu_percent: f32, // Share of total CPU across all cores
+    pub memory_mb: u64,
+}
+
+impl Default for SelfBudget {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cpu_percent: 5.0,
+            memory_mb: 400,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct SelfUsage {
+    pub cpu_percent: f32,
+    pub memory_kb: u64,
+    pub process_count: usize, // Main process, WebView2 hosts and children
+    pub ipc_calls_total: u64,
+    pub ipc_calls_per_sec: f32,
+    pub over_budget: bool,
+    pub throttled: bool,
+    pub last_warning: Option<String>,
+    pub sampled_at: String,
+}
+
+static IPC_CALLS: AtomicU64 = AtomicU64::new(0);
+static LATEST: Mutex<Option<SelfUsage>> = Mutex::new(None);
+
+fn check_budget(usage: &mut SelfUsage, budget: &SelfBudget) {
+    let memory_mb = (usage.memory_kb / 1024) as f32;
+    let over_cpu = usage.cpu_percent > budget.cpu_percent;
+    let over_memory = memory_mb > budget.memory_mb as f32;
+    usage.over_budget = budget.enabled && (over_cpu || over_memory);
+
+    let was_throttled = power::is_throttled();
+    if usage.over_budget {
+        let warning = format!(
+            "Monitor over budget: {:.1}% CPU (limit {:.1}%), {:.0} MB (limit {} MB); refresh slowed down",
+            usage.cpu_percent, budget.cpu_percent, memory_mb, budget.memory_mb
+        );
+        if !was_throttled {
+            notify_event("self-budget-warning", warning.clone());
+        }
+        usage.last_warning = Some(warning);
+        power::set_throttled(true);
+    } else if was_throttled {
+        let calm = usage.cpu_percent <= budget.cpu_percent * RELEASE_RATIO
+            && memory_mb <= budget.memory_mb as f32 * RELEASE_RATIO;
+        if calm || !budget.enabled {
+            power::set_throttled(false);
+        }
+    }
+    usage.throttled = power::is_throttled();
+}
+
+// Public API
+/// Counted by the invoke handler for every frontend command
+pub fn record_ipc_call() {
+    IPC_CALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn latest() -> SelfUsage {
+    LATEST.lock().unwrap().clone().unwrap_or_else(|| SelfUsage {
+        ipc_calls_total: IPC_CALLS.load(Ordering::Relaxed),
+        throttled: power::is_throttled(),
+        ..Default::default()
+    })
+}
+
+pub fn start_watchdog() {
+    thread::spawn(|| {
+        // Our process tree as of the last sample; WebView2 hosts come and go
+        let mut pids: HashSet<u32> = HashSet::new();
+        let mut last_calls = IPC_CALLS.load(Ordering::Relaxed);
+        let mut last_sample = Instant::now();
+
+        loop {
+            thread::sleep(SAMPLE_INTERVAL);
+            // Stats only for our own processes; the listing finds new ones, which are measured
+            // from the next sample on. The sampler always reads WebView2 hosts' command lines,
+            // so own_pids() sees them even before they are measured.
+            let main = std::process::id();
+            let (own, cpu_percent, memory_kb) = sampler::with_processes(
+                |p| p.pid().as_u32() == main || pids.contains(&p.pid().as_u32()),
+                |sys, cpu_count| {
+                    let own = protection::own_pids(sys);
+                    let processes: Vec<_> = sys
+                        .processes()
+                        .values()
+                        .filter(|p| own.contains(&p.pid().as_u32()))
+                        .collect();
+                    let cpu = processes.iter().map(|p| p.cpu_usage()).sum::<f32>() / cpu_count;
+                    let memory_kb = processes.iter().map(|p| p.memory() / 1024).sum::<u64>();
+                    (own, cpu, memory_kb)
+                },
+            );
+            pids = own;
+
+            let calls = IPC_CALLS.load(Ordering::Relaxed);
+            let elapsed = last_sample.elapsed().as_secs_f32().max(1.0);
+            let mut usage = SelfUsage {
+                cpu_percent,
+                memory_kb,
+                process_count: pids.len(),
+                ipc_calls_total: calls,
+                ipc_calls_per_sec: (calls - last_calls) as f32 / elapsed,
+                sampled_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                ..Default::default()
+            };
+            last_calls = calls;
+            last_sample = Instant::now();
+
+            let budget = read_state(|state| state.settings.self_budget.clone());
+            check_budget(&mut usage, &budget);
+            if usage.last_warning.is_none() {
+                // Keep the most recent warning visible after recovering
+                usage.last_warning = LATEST
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .and_then(|u| u.last_warning.clone());
+            }
+            *LATEST.lock().unwrap() = Some(usage);
+        }
+    });
+}
@@ -0,0 +1,70 @@
+// Terminal Services / RDP session awareness, so enforcement on a multi-user server
+// can stay inside the user's own session
+
+use serde::{Deserialize, Serialize};
+
+/// Which sessions the watcher and app limits act on
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SessionScope {
+    #[default]
+    All,
+    Interactive, // Only the session this app runs in
+    Sessions {
+        ids: Vec<u32>,
+    },
+}
+
+#[derive(Serialize)]
+pub struct SessionInfo {
+    pub current: Option<u32>,
+    pub console: Option<u32>, // Physical console; differs from `current` over RDP
+    pub scope: SessionScope,
+}
+
+impl SessionScope {
+    /// Processes whose session can't be determined are only allowed in `All` mode
+    pub fn allows(&self, session: Option<u32>) -> bool {
+        match self {
+            SessionScope::All => true,
+            SessionScope::Interactive => session.is_some() && session == current_session(),
+            SessionScope::Sessions { ids } => session.is_some_and(|s| ids.contains(&s)),
+        }
+    }
+}
+
+// Public API
+/// Terminal Services session of `pid` (Windows only)
+pub fn session_of(pid: u32) -> Option<u32> {
+    #[cfg(windows)]
+    {
+        use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+
+        let mut session = 0;
+        unsafe { ProcessIdToSessionId(pid, &mut session) }
+            .ok()
+            .map(|_| session)
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+pub fn current_session() -> Option<u32> {
+    session_of(std::process::id())
+}
+
+pub fn console_session() -> Option<u32> {
+    #[cfg(windows)]
+    {
+        // 0xFFFFFFFF while no one is attached to the console
+        let id = unsafe { windows::Win32::System::RemoteDesktop::WTSGetActiveConsoleSessionId() };
+        (id != u32::MAX).then_some(id)
+    }
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
@@ -0,0 +1,186 @@
+use chrono::Local;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::sampler;
+
+// Oldest snapshots are dropped beyond this
+const MAX_SNAPSHOTS: usize = 20;
+// A process counts as "changed" when CPU moves this many points...
+const CPU_CHANGE_POINTS: f32 = 5.0;
+// ...or memory moves this many KB (50 MB)
+const MEMORY_CHANGE_KB: u64 = 50 * 1024;
+
+/// All instances of one process name at snapshot time
+#[derive(Serialize, Clone)]
+pub struct SnapshotEntry {
+    pub name: String,
+    pub count: u32,
+    pub cpu: f32,
+    pub memory_kb: u64,
+}
+
+#[derive(Clone)]
+struct Snapshot {
+    id: u32,
+    label: String,
+    taken_at: String,
+    processes: HashMap<String, SnapshotEntry>,
+}
+
+#[derive(Serialize)]
+pub struct SnapshotInfo {
+    pub id: u32,
+    pub label: String,
+    pub taken_at: String,
+    pub process_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct SnapshotChange {
+    pub name: String,
+    pub cpu_before: f32,
+    pub cpu_after: f32,
+    pub memory_before_kb: u64,
+    pub memory_after_kb: u64,
+}
+
+#[derive(Serialize)]
+pub struct SnapshotDiff {
+    pub from: SnapshotInfo,
+    pub to: SnapshotInfo,
+    pub appeared: Vec<SnapshotEntry>,
+    pub disappeared: Vec<SnapshotEntry>,
+    pub changed: Vec<SnapshotChange>,
+}
+
+// (next id, snapshots) - kept in memory for the session only
+static SNAPSHOTS: Mutex<(u32, Vec<Snapshot>)> = Mutex::new((1, Vec::new()));
+
+impl Snapshot {
+    fn info(&self) -> SnapshotInfo {
+        SnapshotInfo {
+            id: self.id,
+            label: self.label.clone(),
+            taken_at: self.taken_at.clone(),
+            process_count: self.processes.len(),
+        }
+    }
+}
+
+fn capture() -> HashMap<String, SnapshotEntry> {
+    // Every process, from the shared sampler: CPU usage is measured since its last refresh
+    sampler::with_all_processes(|sys, cpu_count| {
+        let mut processes: HashMap<String, SnapshotEntry> = HashMap::new();
+        for p in sys.processes().values() {
+            let name = p.name().to_string_lossy().to_string();
+            let entry = processes
+                .entry(name.clone())
+                .or_insert_with(|| SnapshotEntry {
+                    name,
+                    count: 0,
+                    cpu: 0.0,
+                    memory_kb: 0,
+                });
+            entry.count += 1;
+            entry.cpu += p.cpu_usage() / cpu_count;
+            entry.memory_kb += p.memory() / 1024;
+        }
+        processes
+    })
+}
+
+fn sorted_by_memory(mut entries: Vec<SnapshotEntry>) -> Vec<SnapshotEntry> {
+    entries.sort_by_key(|e| Reverse(e.memory_kb));
+    entries
+}
+
+// Public API
+pub fn take(label: Option<String>) -> SnapshotInfo {
+    let processes = capture();
+    let mut snapshots = SNAPSHOTS.lock().unwrap();
+
+    let id = snapshots.0;
+    snapshots.0 += 1;
+    let snapshot = Snapshot {
+        id,
+        label: label
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .unwrap_or_else(|| format!("Snapshot {}", id)),
+        taken_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        processes,
+    };
+    let info = snapshot.info();
+
+    snapshots.1.push(snapshot);
+    if snapshots.1.len() > MAX_SNAPSHOTS {
+        snapshots.1.remove(0);
+    }
+    info
+}
+
+pub fn list() -> Vec<SnapshotInfo> {
+    SNAPSHOTS
+        .lock()
+        .unwrap()
+        .1
+        .iter()
+        .map(|s| s.info())
+        .collect()
+}
+
+/// What appeared, disappeared and changed significantly going from snapshot `a` to `b`
+pub fn diff(a: u32, b: u32) -> Result<SnapshotDiff, String> {
+    let snapshots = SNAPSHOTS.lock().unwrap();
+    let find = |id: u32| {
+        snapshots
+            .1
+            .iter()
+            .find(|s| s.id == id)
+            .ok_or(format!("Snapshot {} not found", id))
+    };
+    let (from, to) = (find(a)?, find(b)?);
+
+    let appeared = to
+        .processes
+        .values()
+        .filter(|e| !from.processes.contains_key(&e.name))
+        .cloned()
+        .collect();
+    let disappeared = from
+        .processes
+        .values()
+        .filter(|e| !to.processes.contains_key(&e.name))
+        .cloned()
+        .collect();
+
+    let mut changed: Vec<SnapshotChange> = from
+        .processes
+        .values()
+        .filter_map(|before| {
+            let after = to.processes.get(&before.name)?;
+            let significant = (after.cpu - before.cpu).abs() >= CPU_CHANGE_POINTS
+                || after.memory_kb.abs_diff(before.memory_kb) >= MEMORY_CHANGE_KB;
+            significant.then(|| SnapshotChange {
+                name: before.name.clone(),
+                cpu_before: before.cpu,
+                cpu_after: after.cpu,
+                memory_before_kb: before.memory_kb,
+                memory_after_kb: after.memory_kb,
+            })
+        })
+        .collect();
+    // Biggest memory movers first
+    changed.sort_by_key(|c| Reverse(c.memory_after_kb.abs_diff(c.memory_before_kb)));
+
+    Ok(SnapshotDiff {
+        from: from.info(),
+        to: to.info(),
+        appeared: sorted_by_memory(appeared),
+        disappeared: sorted_by_memory(disappeared),
+        changed,
+    })
+}
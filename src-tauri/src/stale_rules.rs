@@ -0,0 +1,86 @@
+// Blacklist entries that haven't matched anything in a long time, so configs can be kept clean
+
+use chrono::{Duration as ChronoDuration, Local};
+use serde::Serialize;
+use std::thread;
+use std::time::Duration;
+
+use crate::{emit_event, read_state, AppState, BlacklistEntry};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Payload of the "stale-rules" suggestion
+#[derive(Serialize, Clone)]
+pub struct StaleRules {
+    pub days: u32,
+    pub names: Vec<String>,
+}
+
+fn cutoff(days: u32) -> String {
+    (Local::now() - ChronoDuration::days(days as i64))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// Armed rules whose last match is older than `cutoff`. Rules with no recorded match are left
+/// out: they may predate match tracking, or guard against something that rarely runs.
+fn is_stale(entry: &BlacklistEntry, cutoff: &str) -> bool {
+    entry.auto_kill
+        && entry
+            .last_matched
+            .as_deref()
+            .is_some_and(|matched| matched < cutoff)
+}
+
+// Public API
+/// Names of rules that haven't matched in `days` days
+pub fn stale(state: &AppState, days: u32) -> Vec<String> {
+    let cutoff = cutoff(days);
+    state
+        .blacklist
+        .iter()
+        .filter(|e| is_stale(e, &cutoff))
+        .map(|e| e.name.clone())
+        .collect()
+}
+
+/// Turn off auto-kill for rules that haven't matched in `days` days, returning their names.
+/// The rules stay in the blacklist and can be re-armed.
+pub fn disable(state: &mut AppState, days: u32) -> Vec<String> {
+    let cutoff = cutoff(days);
+    state
+        .blacklist
+        .iter_mut()
+        .filter(|e| is_stale(e, &cutoff))
+        .map(|e| {
+            e.auto_kill = false;
+            e.name.clone()
+        })
+        .collect()
+}
+
+/// Hourly, emit "stale-rules" with any rules past `Settings.stale_rule_days` (0 = off).
+/// The same set is only suggested once.
+pub fn start_checker() {
+    thread::spawn(|| {
+        let mut last_suggested: Vec<String> = Vec::new();
+        loop {
+            let days = read_state(|state| state.settings.stale_rule_days);
+            let names = match days {
+                0 => Vec::new(),
+                days => read_state(|state| stale(state, days)),
+            };
+            if !names.is_empty() && names != last_suggested {
+                emit_event(
+                    "stale-rules",
+                    StaleRules {
+                        days,
+                        names: names.clone(),
+                    },
+                );
+            }
+            last_suggested = names;
+            thread::sleep(crate::power::interval(CHECK_INTERVAL));
+        }
+    });
+}
@@ -0,0 +1,209 @@
+// Where the app keeps its files. Normally the per-user data folder, or a folder the user picked
+// (e.g. a synced one), recorded in a small pointer file in the per-user folder. In portable mode
+// it's a `data` folder next to the executable, so the tool can run from a USB stick without
+// leaving its state on the machine. Portable mode is on when a `portable` (or `portable.txt`)
+// marker file sits beside the executable, or with --portable / AKT_PORTABLE=1.
+
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+const MARKERS: [&str; 2] = ["portable", "portable.txt"];
+const PORTABLE_FOLDER: &str = "data";
+// Lives in the default folder and holds the path of the chosen one
+const POINTER_FILE: &str = "data_location.txt";
+// Still held open by the running logger, so copied but not removed after a move
+const LOGS_FOLDER: &str = "logs";
+
+#[derive(Serialize)]
+pub struct DataLocation {
+    pub path: String,
+    pub portable: bool,
+    pub custom: bool, // Moved away from the default folder with set_data_directory
+    pub fixed: bool,  // Set by portable mode or --data-dir, so it can't be moved from the app
+}
+
+// Resolved once; the executable doesn't move while running
+static PORTABLE_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+// The pointer file's target, read on first use and updated by `migrate`
+static CUSTOM_DIR: OnceLock<RwLock<Option<PathBuf>>> = OnceLock::new();
+
+fn exe_dir() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from))
+}
+
+fn detect_portable() -> Option<PathBuf> {
+    let dir = exe_dir()?;
+    let marked = MARKERS.iter().any(|m| dir.join(m).is_file());
+    (marked || crate::overrides::portable()).then(|| dir.join(PORTABLE_FOLDER))
+}
+
+fn default_dir() -> PathBuf {
+    let mut path = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("tauri-app");
+    path
+}
+
+fn custom_dir() -> &'static RwLock<Option<PathBuf>> {
+    CUSTOM_DIR.get_or_init(|| {
+        let saved = fs::read_to_string(default_dir().join(POINTER_FILE)).ok();
+        RwLock::new(
+            saved
+                .map(|text| PathBuf::from(text.trim()))
+                .filter(|path| path.is_absolute()),
+        )
+    })
+}
+
+/// Point the default folder at `target`, or back at itself for None; replaced in one rename
+fn write_pointer(target: Option<&Path>) -> io::Result<()> {
+    let pointer = default_dir().join(POINTER_FILE);
+    match target {
+        Some(target) => {
+            fs::create_dir_all(default_dir())?;
+            let staged = pointer.with_extension("tmp");
+            fs::write(&staged, target.to_string_lossy().as_bytes())?;
+            fs::rename(&staged, &pointer)
+        }
+        None if pointer.exists() => fs::remove_file(&pointer),
+        None => Ok(()),
+    }
+}
+
+/// Copy the contents of `from` into `to`, leaving out the pointer file
+fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name() == POINTER_FILE {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_tree(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove everything but the pointer file and logs. Best effort: whatever is left behind only
+/// takes up space.
+fn clear(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        if name == POINTER_FILE || name == LOGS_FOLDER {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            fs::remove_dir_all(&path).ok();
+        } else {
+            fs::remove_file(&path).ok();
+        }
+    }
+}
+
+fn is_fixed() -> bool {
+    crate::overrides::data_dir().is_some() || portable_dir().is_some()
+}
+
+// Public API
+/// The data folder beside the executable when running in portable mode
+pub fn portable_dir() -> Option<PathBuf> {
+    PORTABLE_DIR.get_or_init(detect_portable).clone()
+}
+
+pub fn is_portable() -> bool {
+    crate::overrides::data_dir().is_none() && portable_dir().is_some()
+}
+
+/// Where WebView2 keeps its profile in portable mode, so that stays on the stick too;
+/// None leaves it in the per-user default
+pub fn webview_data_dir() -> Option<PathBuf> {
+    is_portable().then(|| resolve().join("webview"))
+}
+
+/// Portable folder, else the folder picked with `migrate`, else the per-user default
+pub fn resolve() -> PathBuf {
+    portable_dir()
+        .or_else(|| custom_dir().read().unwrap().clone())
+        .unwrap_or_else(default_dir)
+}
+
+pub fn location() -> DataLocation {
+    DataLocation {
+        path: crate::data_dir().to_string_lossy().to_string(),
+        portable: is_portable(),
+        custom: !is_fixed() && custom_dir().read().unwrap().is_some(),
+        fixed: is_fixed(),
+    }
+}
+
+/// Move everything in the data folder to `target` and use it from now on. The pointer file is
+/// only switched once the copy is complete, and the old folder only cleared after that, so a
+/// failure at any step leaves the current folder in use and untouched. Callers hold the state
+/// lock so nothing is written mid-move.
+pub fn migrate(target: &Path) -> Result<PathBuf, String> {
+    if is_fixed() {
+        return Err("The data folder is set by portable mode or --data-dir".into());
+    }
+    if !target.is_absolute() {
+        return Err("Pick an absolute folder path".into());
+    }
+    let current = crate::data_dir();
+    let target = target.to_path_buf();
+    if target == current {
+        return Err("That is already the data folder".into());
+    }
+    if target.starts_with(&current) || current.starts_with(&target) {
+        return Err(
+            "The new folder can't be inside the current one, or the other way round".into(),
+        );
+    }
+    // Moving back to the default folder finds the pointer file and old logs still there
+    let is_default = target == default_dir();
+    let in_use = fs::read_dir(&target).is_ok_and(|entries| {
+        entries.flatten().any(|e| {
+            let name = e.file_name();
+            !(is_default && (name == POINTER_FILE || name == LOGS_FOLDER))
+        })
+    });
+    if in_use {
+        return Err(format!("{} is not empty", target.display()));
+    }
+
+    let rollback = || {
+        if is_default {
+            clear(&target);
+        } else {
+            fs::remove_dir_all(&target).ok();
+        }
+    };
+    if let Err(e) = copy_tree(&current, &target) {
+        rollback();
+        return Err(format!(
+            "Failed to copy data to {}: {}",
+            target.display(),
+            e
+        ));
+    }
+    let pointer = (!is_default).then_some(target.as_path());
+    if let Err(e) = write_pointer(pointer) {
+        rollback();
+        return Err(format!("Failed to record the new data folder: {}", e));
+    }
+
+    *custom_dir().write().unwrap() = pointer.map(Path::to_path_buf);
+    clear(&current);
+    tracing::info!(from = %current.display(), to = %target.display(), "data folder moved");
+    Ok(target)
+}
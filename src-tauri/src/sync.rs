@@ -0,0 +1,666 @@
+// Optional sync of rules and settings across a user's machines, through storage they provide:
+// a file on a WebDAV server, an S3 (or S3-compatible) object or a GitHub Gist. Every machine
+// pushes the same document config.toml is made of. When both sides changed since the last sync
+// the newer change wins; `merge` folds the other side's rules back in by hand. Secrets and
+// machine-specific settings never leave the machine, and scripts that arrive from another
+// machine wait for the user to accept them here before they can run.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{config_file, read_state, with_state};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const DOCUMENT_VERSION: u32 = 1;
+const GIST_FILE: &str = "automatekilltask-sync.json";
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ"; // UTC, so machines in other zones compare
+
+// Settings kept out of the synced document in both directions
+const PRIVATE_SETTINGS: [&str; 5] = [
+    "/mqtt/client_id",
+    "/mqtt/password",
+    "/mqtt/command_secret",
+    "/bot/token",
+    "/email_digest/password",
+];
+// Rule fields that run code; remote changes to them are held for confirmation
+const SCRIPT_FIELDS: [&str; 2] = ["on_kill_script", "rule_script"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncBackend {
+    #[default]
+    Webdav,
+    S3,
+    Gist,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SyncSettings {
+    pub enabled: bool,
+    pub backend: SyncBackend,
+    pub url: String,      // WebDAV file or S3 object URL
+    pub username: String, // WebDAV user or S3 access key ID
+    pub password: String, // WebDAV password, S3 secret key or GitHub token
+    pub region: String,   // S3 only, e.g. "eu-central-1"
+    pub gist_id: String,  // Gist only; create a secret gist and paste its ID
+    pub interval_minutes: u32,
+    pub last_synced: Option<String>,
+    pub last_hash: Option<String>, // Document both sides had after the last sync
+    pub local_changed_at: Option<String>, // When this machine's rules or settings last changed
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: SyncBackend::Webdav,
+            url: String::new(),
+            username: String::new(),
+            password: String::new(),
+            region: "us-east-1".to_string(),
+            gist_id: String::new(),
+            interval_minutes: 15,
+            last_synced: None,
+            last_hash: None,
+            local_changed_at: None,
+        }
+    }
+}
+
+impl SyncSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=1440).contains(&self.interval_minutes) {
+            return Err("Sync interval must be between 1 minute and 24 hours".into());
+        }
+        if !self.enabled {
+            return Ok(());
+        }
+        match self.backend {
+            SyncBackend::Webdav | SyncBackend::S3 => {
+                let url = tauri::Url::parse(self.url.trim())
+                    .map_err(|_| "Enter the full URL of the sync file".to_string())?;
+                if !matches!(url.scheme(), "http" | "https") || url.query().is_some() {
+                    return Err("The sync URL must be http(s) without a query string".into());
+                }
+            }
+            SyncBackend::Gist => {
+                if self.gist_id.trim().is_empty() {
+                    return Err("A gist ID is required".into());
+                }
+            }
+        }
+        if self.backend == SyncBackend::S3
+            && (self.region.trim().is_empty() || self.username.trim().is_empty())
+        {
+            return Err("S3 needs a region and an access key".into());
+        }
+        if self.backend != SyncBackend::Webdav && self.password.trim().is_empty() {
+            return Err("A secret key or token is required".into());
+        }
+        Ok(())
+    }
+
+    /// Whether `other` points at the same storage; if not, the last sync says nothing about it
+    pub fn same_target(&self, other: &SyncSettings) -> bool {
+        self.backend == other.backend
+            && self.url.trim() == other.url.trim()
+            && self.gist_id.trim() == other.gist_id.trim()
+    }
+}
+
+/// What is stored remotely
+#[derive(Serialize, Deserialize)]
+struct SyncDocument {
+    version: u32,
+    updated_at: String,
+    machine: String,
+    config: Value, // { settings, rules }, see config_file::document
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncAction {
+    UpToDate,
+    Pushed,
+    Pulled,
+}
+
+/// Result of a sync, also sent as "sync-completed" when anything changed
+#[derive(Serialize, Clone)]
+pub struct SyncOutcome {
+    pub action: SyncAction,
+    pub conflict: bool, // Both sides had changed; the older change was dropped
+    pub remote_machine: Option<String>,
+}
+
+/// A script another machine set on a rule, not applied until accepted on this one
+#[derive(Serialize, Clone, PartialEq)]
+pub struct PendingScript {
+    pub rule: String,
+    pub field: String, // "on_kill_script" or "rule_script"
+    pub script: String,
+    pub machine: String,
+}
+
+#[derive(Serialize)]
+pub struct SyncStatus {
+    pub last_synced: Option<String>,
+    pub last_error: Option<String>,
+}
+
+// One sync at a time, whether from the worker or the UI
+static SYNCING: Mutex<()> = Mutex::new(());
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+static PENDING_SCRIPTS: Mutex<Vec<PendingScript>> = Mutex::new(Vec::new());
+
+fn now() -> String {
+    Utc::now().format(TIME_FORMAT).to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hash(config: &Value) -> String {
+    // serde_json keeps object keys sorted, so equal documents hash the same everywhere
+    hex(&Sha256::digest(config.to_string().as_bytes()))
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    const BLOCK: usize = 64;
+    let mut key = if key.len() > BLOCK {
+        Sha256::digest(key).to_vec()
+    } else {
+        key.to_vec()
+    };
+    key.resize(BLOCK, 0);
+    let pad = |byte: u8| key.iter().map(|k| k ^ byte).collect::<Vec<u8>>();
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(data)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .to_vec()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().fold(0u32, |n, b| (n << 8) | *b as u32) << (8 * (3 - chunk.len()));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build()
+}
+
+fn describe(err: ureq::Error) -> String {
+    match err {
+        ureq::Error::Status(code, _) => format!("HTTP {}", code),
+        e => e.to_string(),
+    }
+}
+
+/// A request for the S3 object at `settings.url`, signed with AWS Signature Version 4
+fn s3_request(settings: &SyncSettings, method: &str, body: &[u8]) -> Result<ureq::Request, String> {
+    let url = tauri::Url::parse(settings.url.trim()).map_err(|e| e.to_string())?;
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err("The S3 URL has no host".into()),
+    };
+    let time = Utc::now();
+    let amz_date = time.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = time.format("%Y%m%d").to_string();
+    let region = settings.region.trim();
+    let payload_hash = hex(&Sha256::digest(body));
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+
+    let canonical = format!(
+        "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        method,
+        url.path(),
+        host,
+        payload_hash,
+        amz_date,
+        signed_headers,
+        payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical.as_bytes()))
+    );
+    let key = [region, "s3", "aws4_request"].iter().fold(
+        hmac(
+            format!("AWS4{}", settings.password.trim()).as_bytes(),
+            date.as_bytes(),
+        ),
+        |key, part| hmac(&key, part.as_bytes()),
+    );
+    let signature = hex(&hmac(&key, to_sign.as_bytes()));
+
+    Ok(agent()
+        .request(method, url.as_str())
+        .set("x-amz-date", &amz_date)
+        .set("x-amz-content-sha256", &payload_hash)
+        .set(
+            "Authorization",
+            &format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                settings.username.trim(),
+                scope,
+                signed_headers,
+                signature
+            ),
+        ))
+}
+
+fn webdav_request(settings: &SyncSettings, method: &str) -> ureq::Request {
+    let request = agent().request(method, settings.url.trim());
+    if settings.username.trim().is_empty() {
+        return request;
+    }
+    let credentials = format!("{}:{}", settings.username.trim(), settings.password);
+    request.set(
+        "Authorization",
+        &format!("Basic {}", base64(credentials.as_bytes())),
+    )
+}
+
+fn gist_request(settings: &SyncSettings, method: &str) -> ureq::Request {
+    agent()
+        .request(
+            method,
+            &format!("https://api.github.com/gists/{}", settings.gist_id.trim()),
+        )
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "AutomateKillTask")
+        .set(
+            "Authorization",
+            &format!("Bearer {}", settings.password.trim()),
+        )
+}
+
+/// The stored document, or None when nothing has been pushed yet
+fn fetch(settings: &SyncSettings) -> Result<Option<SyncDocument>, String> {
+    let response = match settings.backend {
+        SyncBackend::Webdav => webdav_request(settings, "GET").call(),
+        SyncBackend::S3 => s3_request(settings, "GET", b"")?.call(),
+        SyncBackend::Gist => gist_request(settings, "GET").call(),
+    };
+    let text = match response {
+        Ok(response) => response.into_string().map_err(|e| e.to_string())?,
+        Err(ureq::Error::Status(404, _)) if settings.backend != SyncBackend::Gist => {
+            return Ok(None)
+        }
+        Err(e) => return Err(describe(e)),
+    };
+
+    let text = match settings.backend {
+        SyncBackend::Gist => {
+            let gist: Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+            match gist["files"][GIST_FILE]["content"].as_str() {
+                Some(content) => content.to_string(),
+                None => return Ok(None),
+            }
+        }
+        _ => text,
+    };
+    let document: SyncDocument =
+        serde_json::from_str(&text).map_err(|e| format!("Unreadable sync file: {}", e))?;
+    if document.version > DOCUMENT_VERSION {
+        return Err("The sync file was written by a newer version of the app".into());
+    }
+    Ok(Some(document))
+}
+
+fn upload(settings: &SyncSettings, document: &SyncDocument) -> Result<(), String> {
+    let body = serde_json::to_string_pretty(document).map_err(|e| e.to_string())?;
+    let result = match settings.backend {
+        SyncBackend::Webdav => webdav_request(settings, "PUT")
+            .set("Content-Type", "application/json")
+            .send_string(&body),
+        SyncBackend::S3 => s3_request(settings, "PUT", body.as_bytes())?
+            .set("Content-Type", "application/json")
+            .send_bytes(body.as_bytes()),
+        SyncBackend::Gist => gist_request(settings, "PATCH")
+            .send_json(json!({ "files": { GIST_FILE: { "content": body } } })),
+    };
+    result.map(|_| ()).map_err(describe)
+}
+
+fn strip_private(config: &mut Value) {
+    for pointer in PRIVATE_SETTINGS {
+        let (parent, key) = pointer.rsplit_once('/').unwrap_or(("", pointer));
+        if let Some(Value::Object(map)) = config.pointer_mut(&format!("/settings{}", parent)) {
+            map.remove(key);
+        }
+    }
+}
+
+/// This machine's rules and settings as they are synced
+fn local_config() -> Result<Value, String> {
+    let mut config = config_file::document()?;
+    strip_private(&mut config);
+    Ok(config)
+}
+
+/// Take the scripts this machine doesn't already have out of `rules`, so applying them keeps
+/// the local script (none for a new rule), and return them for confirmation
+fn hold_scripts(rules: &mut Value, machine: &str) -> Vec<PendingScript> {
+    let local = read_state(|state| state.blacklist.clone());
+    let mut held = Vec::new();
+    for rule in rules
+        .as_array_mut()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+    {
+        let Some(name) = rule.get("name").and_then(Value::as_str).map(str::to_string) else {
+            continue;
+        };
+        let existing = local
+            .iter()
+            .find(|e| e.name.to_lowercase() == name.to_lowercase());
+        for field in SCRIPT_FIELDS {
+            let Some(script) = rule.get(field).and_then(Value::as_str).map(str::to_string) else {
+                continue;
+            };
+            let current = existing.and_then(|e| match field {
+                "on_kill_script" => e.on_kill_script.as_deref(),
+                _ => e.rule_script.as_deref(),
+            });
+            if current != Some(script.as_str()) {
+                rule.remove(field);
+                held.push(PendingScript {
+                    rule: name.clone(),
+                    field: field.to_string(),
+                    script,
+                    machine: machine.to_string(),
+                });
+            }
+        }
+    }
+    held
+}
+
+/// The remote document as this machine would apply it: no private settings, no unconfirmed
+/// scripts. Replaces the pending scripts with the ones it holds back.
+fn remote_config(document: &SyncDocument) -> Value {
+    let mut config = document.config.clone();
+    strip_private(&mut config);
+    let held = match config.get_mut("rules") {
+        Some(rules) => hold_scripts(rules, &document.machine),
+        None => Vec::new(),
+    };
+    set_pending(held);
+    config
+}
+
+fn set_pending(scripts: Vec<PendingScript>) {
+    let mut pending = PENDING_SCRIPTS.lock().unwrap();
+    if *pending != scripts {
+        if !scripts.is_empty() {
+            tracing::info!(
+                count = scripts.len(),
+                "synced scripts waiting for confirmation"
+            );
+        }
+        crate::emit_event("sync-scripts-pending", scripts.clone());
+        *pending = scripts;
+    }
+}
+
+fn push(settings: &SyncSettings, config: Value) -> Result<String, String> {
+    let config_hash = hash(&config);
+    let document = SyncDocument {
+        version: DOCUMENT_VERSION,
+        updated_at: settings.local_changed_at.clone().unwrap_or_else(now),
+        machine: sysinfo::System::host_name().unwrap_or_default(),
+        config,
+    };
+    upload(settings, &document)?;
+    Ok(config_hash)
+}
+
+fn pull(config: &Value) -> Result<String, String> {
+    config_file::apply_document(config)?;
+    // Hash what we ended up with, which is what the next sync compares against
+    local_config().map(|config| hash(&config))
+}
+
+fn record(synced_hash: String) {
+    with_state(|state| {
+        state.settings.sync.last_hash = Some(synced_hash);
+        state.settings.sync.last_synced = Some(now());
+    });
+}
+
+fn sync_once(settings: &SyncSettings) -> Result<SyncOutcome, String> {
+    let _guard = SYNCING.lock().unwrap();
+    let local = local_config()?;
+    let local_hash = hash(&local);
+    let base = settings.last_hash.as_deref();
+
+    let Some(remote) = fetch(settings)? else {
+        set_pending(Vec::new());
+        record(push(settings, local)?);
+        return Ok(SyncOutcome {
+            action: SyncAction::Pushed,
+            conflict: false,
+            remote_machine: None,
+        });
+    };
+    let remote_config = remote_config(&remote);
+    let remote_hash = hash(&remote_config);
+    let remote_machine = Some(remote.machine.clone());
+    if remote_hash == local_hash {
+        record(local_hash);
+        return Ok(SyncOutcome {
+            action: SyncAction::UpToDate,
+            conflict: false,
+            remote_machine,
+        });
+    }
+
+    let local_changed = base != Some(local_hash.as_str());
+    let remote_changed = base != Some(remote_hash.as_str());
+    let conflict = local_changed && remote_changed;
+    // Last writer wins; a machine that never noted a change of its own defers to the remote
+    let local_wins = match (local_changed, remote_changed) {
+        (true, true) => settings
+            .local_changed_at
+            .as_ref()
+            .is_some_and(|at| *at > remote.updated_at),
+        (_, remote_changed) => !remote_changed,
+    };
+    let (action, synced_hash) = if local_wins {
+        set_pending(Vec::new());
+        (SyncAction::Pushed, push(settings, local)?)
+    } else {
+        (SyncAction::Pulled, pull(&remote_config)?)
+    };
+    record(synced_hash);
+    if conflict {
+        tracing::warn!(
+            local_won = local_wins,
+            "sync conflict resolved by last writer"
+        );
+    }
+    Ok(SyncOutcome {
+        action,
+        conflict,
+        remote_machine,
+    })
+}
+
+/// Note when this machine's rules or settings change, for last-writer-wins
+fn note_local_change(settings: &SyncSettings, observed: &mut Option<String>) {
+    let Ok(local) = local_config() else {
+        return;
+    };
+    let local_hash = hash(&local);
+    if observed.as_deref() == Some(local_hash.as_str()) {
+        return;
+    }
+    let changed = settings.last_hash.as_deref() != Some(local_hash.as_str());
+    *observed = Some(local_hash);
+    if changed {
+        with_state(|state| {
+            state.settings.sync.local_changed_at = Some(now());
+        });
+    }
+}
+
+fn report(result: Result<SyncOutcome, String>) -> Result<SyncOutcome, String> {
+    match &result {
+        Ok(outcome) => {
+            *LAST_ERROR.lock().unwrap() = None;
+            if outcome.action != SyncAction::UpToDate {
+                tracing::info!(action = ?outcome.action, "rules synced");
+                crate::emit_event("sync-completed", outcome.clone());
+            }
+        }
+        Err(err) => {
+            tracing::warn!("Sync failed: {}", err);
+            *LAST_ERROR.lock().unwrap() = Some(err.clone());
+        }
+    }
+    result
+}
+
+// Public API
+pub fn start() {
+    thread::spawn(|| {
+        let mut observed = None;
+        let mut last_run: Option<Instant> = None;
+        loop {
+            let settings = read_state(|state| state.settings.sync.clone());
+            if settings.enabled {
+                note_local_change(&settings, &mut observed);
+                let every = Duration::from_secs(settings.interval_minutes as u64 * 60);
+                if last_run.is_none_or(|at| at.elapsed() >= every) {
+                    last_run = Some(Instant::now());
+                    // Re-read: note_local_change may have just stamped a change
+                    let settings = read_state(|state| state.settings.sync.clone());
+                    report(sync_once(&settings)).ok();
+                }
+            }
+            thread::sleep(crate::power::interval(CHECK_INTERVAL));
+        }
+    });
+}
+
+pub fn status() -> SyncStatus {
+    SyncStatus {
+        last_synced: read_state(|state| state.settings.sync.last_synced.clone()),
+        last_error: LAST_ERROR.lock().unwrap().clone(),
+    }
+}
+
+/// Sync right away with the saved settings
+pub fn sync_now() -> Result<SyncOutcome, String> {
+    let settings = read_state(|state| state.settings.sync.clone());
+    if !settings.enabled {
+        return Err("Sync is not enabled".into());
+    }
+    report(sync_once(&settings))
+}
+
+/// Resolve a conflict by hand: add the remote rules this machine doesn't have, keep local
+/// settings and rules otherwise, and push the result. Returns how many rules were added.
+pub fn merge() -> Result<usize, String> {
+    let settings = read_state(|state| state.settings.sync.clone());
+    if !settings.enabled {
+        return Err("Sync is not enabled".into());
+    }
+    let _guard = SYNCING.lock().unwrap();
+    let remote = fetch(&settings)?.ok_or("Nothing has been synced yet")?;
+    let remote_config = remote_config(&remote);
+    let local = local_config()?;
+
+    let mut rules = local["rules"].as_array().cloned().unwrap_or_default();
+    let known: Vec<String> = rules
+        .iter()
+        .filter_map(|r| r["name"].as_str().map(str::to_lowercase))
+        .collect();
+    let added: Vec<Value> = remote_config["rules"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|r| {
+            r["name"]
+                .as_str()
+                .is_some_and(|name| !known.contains(&name.to_lowercase()))
+        })
+        .cloned()
+        .collect();
+    let count = added.len();
+    rules.extend(added);
+    config_file::apply_document(&json!({ "rules": rules }))?;
+
+    let settings = SyncSettings {
+        local_changed_at: Some(now()),
+        ..settings
+    };
+    record(push(&settings, local_config()?)?);
+    Ok(count)
+}
+
+pub fn pending_scripts() -> Vec<PendingScript> {
+    PENDING_SCRIPTS.lock().unwrap().clone()
+}
+
+/// Apply a held script to its rule; the next sync then sees both sides agree
+pub fn accept_script(rule: &str, field: &str) -> Result<(), String> {
+    let script = PENDING_SCRIPTS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|p| p.rule == rule && p.field == field)
+        .map(|p| p.script.clone())
+        .ok_or("That script is no longer pending")?;
+    if field == "rule_script" {
+        crate::scripting::compile(&script)?;
+    }
+    let applied = with_state(|state| {
+        let entry = state
+            .blacklist
+            .iter_mut()
+            .find(|e| e.name.to_lowercase() == rule.to_lowercase())?;
+        match field {
+            "on_kill_script" => entry.on_kill_script = Some(script),
+            _ => entry.rule_script = Some(script),
+        }
+        Some(())
+    });
+    applied.ok_or_else(|| format!("No rule named {}", rule))?;
+    PENDING_SCRIPTS
+        .lock()
+        .unwrap()
+        .retain(|p| !(p.rule == rule && p.field == field));
+    Ok(())
+}
@@ -0,0 +1,104 @@
+// Activity log entries mirrored to journald (native protocol, structured AKT_* fields) or,
+// without systemd, to syslog via /dev/log. Linux only; lets servers running the headless
+// agent feed enforcement events to their usual log tooling.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::ActivityLog;
+
+#[cfg(unix)]
+const IDENTIFIER: &str = "automatekilltask";
+#[cfg(unix)]
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+#[cfg(unix)]
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+// Set by the agent's --syslog flag, on top of the saved setting
+static FORCED: AtomicBool = AtomicBool::new(false);
+
+/// syslog severities: notice for kills, info for everything else
+#[cfg(unix)]
+fn priority(log: &ActivityLog) -> u8 {
+    if log.was_killed {
+        5
+    } else {
+        6
+    }
+}
+
+#[cfg(unix)]
+fn message(log: &ActivityLog) -> String {
+    format!("{} (PID {}): {}", log.name, log.pid, log.reason)
+}
+
+/// Journal export format; values are kept to one line so the simple KEY=value form works
+#[cfg(unix)]
+fn journal_entry(log: &ActivityLog) -> String {
+    let mut fields = vec![
+        ("MESSAGE", message(log)),
+        ("PRIORITY", priority(log).to_string()),
+        ("SYSLOG_IDENTIFIER", IDENTIFIER.to_string()),
+        ("AKT_PROCESS", log.name.clone()),
+        ("AKT_PID", log.pid.to_string()),
+        ("AKT_KILLED", log.was_killed.to_string()),
+        ("AKT_REASON", log.reason.clone()),
+        ("AKT_CPU", format!("{:.1}", log.cpu_usage)),
+        ("AKT_GPU", format!("{:.1}", log.gpu_usage)),
+        ("AKT_MEMORY_KB", log.memory_kb.to_string()),
+        ("AKT_DETECTED_AT", log.detected_at.clone()),
+    ];
+    if log.was_killed {
+        fields.push(("AKT_FREED_MEMORY_KB", log.freed_memory_kb.to_string()));
+    }
+    if let Some(exe) = &log.exe_path {
+        fields.push(("AKT_EXE", exe.clone()));
+    }
+    if let Some(user) = &log.user {
+        fields.push(("AKT_USER", user.clone()));
+    }
+    fields
+        .into_iter()
+        .map(|(key, value)| format!("{}={}\n", key, value.replace('\n', " ")))
+        .collect()
+}
+
+#[cfg(unix)]
+fn send(log: &ActivityLog) -> std::io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let socket = UnixDatagram::unbound()?;
+    if socket
+        .send_to(journal_entry(log).as_bytes(), JOURNAL_SOCKET)
+        .is_ok()
+    {
+        return Ok(());
+    }
+    // RFC 3164 with facility "user" (1); the syslog daemon adds the timestamp and host
+    let line = format!(
+        "<{}>{}[{}]: {}",
+        8 + priority(log),
+        IDENTIFIER,
+        std::process::id(),
+        message(log)
+    );
+    socket.send_to(line.as_bytes(), SYSLOG_SOCKET).map(|_| ())
+}
+
+// Public API
+/// Mirror regardless of the saved setting, for this process only
+pub fn force_enable() {
+    FORCED.store(true, Ordering::Relaxed);
+}
+
+/// Send one activity log entry if mirroring is on (`enabled` is the saved setting)
+pub fn mirror(log: &ActivityLog, enabled: bool) {
+    if !enabled && !FORCED.load(Ordering::Relaxed) {
+        return;
+    }
+    #[cfg(unix)]
+    if let Err(err) = send(log) {
+        tracing::debug!("syslog mirror failed: {}", err);
+    }
+    #[cfg(not(unix))]
+    let _ = log;
+}
@@ -0,0 +1,131 @@
+// Alternatives to killing: lock the workstation or put a full-screen warning over the
+// offending app (parental-control style rules), or throttle it with efficiency mode
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+use crate::APP_HANDLE;
+
+/// What a blacklist entry does once its thresholds are hit
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    #[default]
+    Kill,
+    LockWorkstation,
+    FullScreenWarning,
+    Efficiency,  // EcoQoS + idle priority; the process keeps running, just slower
+    BlockLaunch, // Kill on sight, and on Windows stop it from starting at all, see launch_block.rs
+    // Kill what started it, up to the rule's launcher_depth levels up, then the process itself
+    KillLauncher,
+}
+
+#[derive(Serialize, Clone)]
+pub struct FullScreenWarning {
+    pub rule: String,
+    pub process_name: String,
+}
+
+// The process keeps running after a lock or warning, so each rule waits this long
+// before acting again instead of re-locking on every poll
+const COOLDOWN: Duration = Duration::from_secs(60);
+
+static LAST_ACTION: Mutex<Option<HashMap<String, Instant>>> = Mutex::new(None);
+// (PID, start time) of processes already put in efficiency mode
+static THROTTLED: Mutex<Option<HashSet<(u32, u64)>>> = Mutex::new(None);
+
+// Public API
+/// True (and starts the cooldown) if `rule` hasn't acted within the last minute
+pub fn claim(rule: &str) -> bool {
+    let mut guard = LAST_ACTION.lock().unwrap();
+    let last = guard.get_or_insert_with(HashMap::new);
+    let now = Instant::now();
+    if last.get(rule).is_some_and(|at| now < *at + COOLDOWN) {
+        return false;
+    }
+    last.insert(rule.to_string(), now);
+    true
+}
+
+/// True (and remembers it) the first time a process is throttled; efficiency mode
+/// is applied once per process instead of once per cooldown
+pub fn claim_process(pid: u32, start_time: u64) -> bool {
+    THROTTLED
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashSet::new)
+        .insert((pid, start_time))
+}
+
+/// Drop throttled processes that have exited
+pub fn forget_exited(is_running: impl Fn(u32, u64) -> bool) {
+    if let Some(throttled) = THROTTLED.lock().unwrap().as_mut() {
+        throttled.retain(|(pid, start_time)| is_running(*pid, *start_time));
+    }
+}
+
+/// Run a lock or warning action. Must not be called while holding the app state lock.
+/// Efficiency mode needs the PID, see `process_ctl::set_efficiency_mode`.
+pub fn run(action: RuleAction, rule: &str, process_name: &str) -> Result<(), String> {
+    match action {
+        RuleAction::Kill
+        | RuleAction::Efficiency
+        | RuleAction::BlockLaunch
+        | RuleAction::KillLauncher => Ok(()),
+        RuleAction::LockWorkstation => lock_workstation(),
+        RuleAction::FullScreenWarning => show_warning(FullScreenWarning {
+            rule: rule.to_string(),
+            process_name: process_name.to_string(),
+        }),
+    }
+}
+
+pub fn lock_workstation() -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        unsafe { windows::Win32::System::Shutdown::LockWorkStation() }
+            .map_err(|e| format!("Failed to lock workstation: {}", e))
+    }
+    #[cfg(not(windows))]
+    {
+        let status = std::process::Command::new("loginctl")
+            .arg("lock-session")
+            .status()
+            .map_err(|e| format!("Failed to lock workstation: {}", e))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("loginctl lock-session exited with {}", status))
+        }
+    }
+}
+
+/// Bring the main window up full-screen and on top; the frontend draws the warning
+pub fn show_warning(warning: FullScreenWarning) -> Result<(), String> {
+    let handle = APP_HANDLE.get().ok_or("App not initialized")?;
+    let window = handle
+        .get_webview_window("main")
+        .ok_or("Main window not found")?;
+    window.unminimize().ok();
+    window.show().map_err(|e| e.to_string())?;
+    window.set_always_on_top(true).ok();
+    window.set_fullscreen(true).map_err(|e| e.to_string())?;
+    window.set_focus().ok();
+    handle
+        .emit("fullscreen-warning", warning)
+        .map_err(|e| e.to_string())
+}
+
+/// Put the main window back to normal after the user acknowledges the warning
+pub fn dismiss_warning() -> Result<(), String> {
+    let handle = APP_HANDLE.get().ok_or("App not initialized")?;
+    let window = handle
+        .get_webview_window("main")
+        .ok_or("Main window not found")?;
+    window.set_fullscreen(false).map_err(|e| e.to_string())?;
+    window.set_always_on_top(false).ok();
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+// Curated rule templates shipped inside the binary
+const TEMPLATES_JSON: &str = include_str!("../resources/rule_templates.json");
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TemplateRule {
+    pub name: String,
+    pub auto_kill: bool,
+    pub cpu_threshold: f32,
+    #[serde(default = "default_hundred")]
+    pub gpu_threshold: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RuleTemplate {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub rules: Vec<TemplateRule>,
+}
+
+fn default_hundred() -> f32 {
+    101.0
+}
+
+// Public API
+pub fn list_templates() -> Vec<RuleTemplate> {
+    serde_json::from_str(TEMPLATES_JSON).unwrap_or_default()
+}
+
+pub fn find_template(id: &str) -> Option<RuleTemplate> {
+    list_templates().into_iter().find(|t| t.id == id)
+}
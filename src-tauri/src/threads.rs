@@ -0,0 +1,378 @@
+// Per-thread view of one process, like Process Explorer's Threads tab: state, CPU time, recent
+// CPU share and where each thread started, to find the one thread burning a core.
+// Windows reads the kernel's process list (NtQuerySystemInformation) for state and times;
+// Linux reads /proc/<pid>/task.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Long enough to tell a busy thread from an idle one
+const CPU_WINDOW: Duration = Duration::from_millis(500);
+
+#[derive(Serialize, Clone)]
+pub struct ThreadInfo {
+    pub tid: u32,
+    pub name: Option<String>,
+    pub state: String, // e.g. "running", "waiting (UserRequest)"
+    pub priority: Option<i32>,
+    pub cpu_time_ms: u64,      // Kernel + user since the thread started
+    pub cpu_percent: f32,      // Of one core, over the last half second
+    pub start: Option<String>, // Start address as "module+0x1a2b" (Windows only)
+}
+
+/// One reading of a thread; CPU time in milliseconds
+struct Reading {
+    name: Option<String>,
+    state: String,
+    priority: Option<i32>,
+    cpu_time_ms: u64,
+}
+
+#[cfg(windows)]
+mod nt {
+    use std::collections::HashMap;
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Module32FirstW, Module32NextW, MODULEENTRY32W, TH32CS_SNAPMODULE,
+        TH32CS_SNAPMODULE32,
+    };
+    use windows::Win32::System::Threading::{OpenThread, THREAD_QUERY_INFORMATION};
+
+    use super::Reading;
+
+    const SYSTEM_PROCESS_INFORMATION_CLASS: i32 = 5;
+    const THREAD_QUERY_SET_WIN32_START_ADDRESS: i32 = 9;
+    const STATUS_INFO_LENGTH_MISMATCH: i32 = 0xC000_0004_u32 as i32;
+
+    // Layouts from winternl.h; only a few fields are read
+    #[allow(dead_code)]
+    #[repr(C)]
+    struct SystemProcessInformation {
+        next_entry_offset: u32,
+        number_of_threads: u32,
+        reserved1: [u8; 48],
+        image_name: [usize; 2], // UNICODE_STRING
+        base_priority: i32,
+        unique_process_id: usize,
+        reserved2: usize,
+        handle_count: u32,
+        session_id: u32,
+        reserved3: usize,
+        peak_virtual_size: usize,
+        virtual_size: usize,
+        reserved4: u32,
+        peak_working_set_size: usize,
+        working_set_size: usize,
+        reserved5: usize,
+        quota_paged_pool_usage: usize,
+        reserved6: usize,
+        quota_non_paged_pool_usage: usize,
+        pagefile_usage: usize,
+        peak_pagefile_usage: usize,
+        private_page_count: usize,
+        reserved7: [i64; 6],
+    }
+
+    #[allow(dead_code)]
+    #[repr(C)]
+    struct SystemThreadInformation {
+        kernel_time: i64, // 100ns units
+        user_time: i64,
+        create_time: i64,
+        wait_time: u32,
+        start_address: usize,
+        client_id: [usize; 2], // Process and thread ID
+        priority: i32,
+        base_priority: i32,
+        context_switches: u32,
+        thread_state: u32,
+        wait_reason: u32,
+    }
+
+    #[link(name = "ntdll")]
+    extern "system" {
+        fn NtQuerySystemInformation(
+            class: i32,
+            information: *mut c_void,
+            length: u32,
+            return_length: *mut u32,
+        ) -> i32;
+        fn NtQueryInformationThread(
+            thread: HANDLE,
+            class: i32,
+            information: *mut c_void,
+            length: u32,
+            return_length: *mut u32,
+        ) -> i32;
+    }
+
+    fn state_name(state: u32, wait_reason: u32) -> String {
+        let state = match state {
+            0 => "initialized",
+            1 | 7 => "ready",
+            2 => "running",
+            3 => "standby",
+            4 => "terminated",
+            5 => "waiting",
+            6 | 9 => "transition",
+            _ => return format!("state {}", state),
+        };
+        if state != "waiting" {
+            return state.to_string();
+        }
+        let reason = match wait_reason {
+            0 | 7 => "Executive",
+            4 | 11 => "DelayExecution",
+            5 | 12 => "Suspended",
+            6 | 13 => "UserRequest",
+            15 => "Queue",
+            16 | 17 => "Lpc",
+            21 => "KeyedEvent",
+            28 => "PushLock",
+            29 => "Mutex",
+            37 => "AlertByThreadId",
+            other => return format!("waiting (reason {})", other),
+        };
+        format!("waiting ({})", reason)
+    }
+
+    /// The whole process list in one buffer; grows until the kernel says it fits
+    fn system_processes() -> Result<Vec<u64>, String> {
+        let mut buffer: Vec<u64> = vec![0; 64 * 1024];
+        loop {
+            let mut needed = 0u32;
+            let status = unsafe {
+                NtQuerySystemInformation(
+                    SYSTEM_PROCESS_INFORMATION_CLASS,
+                    buffer.as_mut_ptr() as *mut c_void,
+                    (buffer.len() * 8) as u32,
+                    &mut needed,
+                )
+            };
+            match status {
+                STATUS_INFO_LENGTH_MISMATCH => {
+                    // Processes can start between calls, so leave some room
+                    buffer = vec![0; (needed as usize / 8 + 1) * 2];
+                }
+                status if status < 0 => {
+                    return Err(format!("NtQuerySystemInformation failed: {:#x}", status))
+                }
+                _ => return Ok(buffer),
+            }
+        }
+    }
+
+    pub fn threads(pid: u32) -> Result<HashMap<u32, Reading>, String> {
+        let buffer = system_processes()?;
+        let base = buffer.as_ptr() as *const u8;
+        let mut offset = 0usize;
+        loop {
+            let process = unsafe { &*(base.add(offset) as *const SystemProcessInformation) };
+            if process.unique_process_id == pid as usize {
+                let first = unsafe {
+                    (process as *const SystemProcessInformation).add(1)
+                        as *const SystemThreadInformation
+                };
+                let readings = (0..process.number_of_threads as usize)
+                    .map(|i| unsafe { &*first.add(i) })
+                    .map(|t| {
+                        let reading = Reading {
+                            name: None,
+                            state: state_name(t.thread_state, t.wait_reason),
+                            priority: Some(t.priority),
+                            cpu_time_ms: ((t.kernel_time + t.user_time) / 10_000) as u64,
+                        };
+                        (t.client_id[1] as u32, reading)
+                    })
+                    .collect();
+                return Ok(readings);
+            }
+            if process.next_entry_offset == 0 {
+                return Err("Process not found".into());
+            }
+            offset += process.next_entry_offset as usize;
+        }
+    }
+
+    /// Loaded modules as (base, size, name)
+    fn modules(pid: u32) -> Vec<(usize, usize, String)> {
+        let mut modules = Vec::new();
+        unsafe {
+            let Ok(snapshot) =
+                CreateToolhelp32Snapshot(TH32CS_SNAPMODULE | TH32CS_SNAPMODULE32, pid)
+            else {
+                return modules;
+            };
+            let mut entry = MODULEENTRY32W {
+                dwSize: std::mem::size_of::<MODULEENTRY32W>() as u32,
+                ..Default::default()
+            };
+            let mut more = Module32FirstW(snapshot, &mut entry).is_ok();
+            while more {
+                let name = &entry.szModule;
+                let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+                modules.push((
+                    entry.modBaseAddr as usize,
+                    entry.modBaseSize as usize,
+                    String::from_utf16_lossy(&name[..len]),
+                ));
+                more = Module32NextW(snapshot, &mut entry).is_ok();
+            }
+            CloseHandle(snapshot).ok();
+        }
+        modules
+    }
+
+    /// "module+0x1a2b" for each thread whose Win32 start address could be read
+    pub fn start_addresses(pid: u32, tids: &[u32]) -> HashMap<u32, String> {
+        let modules = modules(pid);
+        let mut starts = HashMap::new();
+        for &tid in tids {
+            let Ok(thread) = (unsafe { OpenThread(THREAD_QUERY_INFORMATION, false, tid) }) else {
+                continue;
+            };
+            let mut address = 0usize;
+            let status = unsafe {
+                NtQueryInformationThread(
+                    thread,
+                    THREAD_QUERY_SET_WIN32_START_ADDRESS,
+                    &mut address as *mut usize as *mut c_void,
+                    std::mem::size_of::<usize>() as u32,
+                    std::ptr::null_mut(),
+                )
+            };
+            unsafe {
+                CloseHandle(thread).ok();
+            }
+            if status < 0 || address == 0 {
+                continue;
+            }
+            let start = modules
+                .iter()
+                .find(|(base, size, _)| (*base..base + size).contains(&address))
+                .map(|(base, _, name)| format!("{}+0x{:x}", name, address - base))
+                .unwrap_or_else(|| format!("0x{:x}", address));
+            starts.insert(tid, start);
+        }
+        starts
+    }
+}
+
+#[cfg(windows)]
+fn read_threads(pid: u32) -> Result<HashMap<u32, Reading>, String> {
+    nt::threads(pid)
+}
+
+#[cfg(target_os = "linux")]
+fn read_threads(pid: u32) -> Result<HashMap<u32, Reading>, String> {
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as u64;
+    let tasks = std::fs::read_dir(format!("/proc/{}/task", pid))
+        .map_err(|_| "Process not found".to_string())?;
+    let mut readings = HashMap::new();
+    for task in tasks.flatten() {
+        let Some(tid) = task
+            .file_name()
+            .to_str()
+            .and_then(|t| t.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        // Threads can exit between listing and reading
+        let Ok(stat) = std::fs::read_to_string(task.path().join("stat")) else {
+            continue;
+        };
+        // The name is in parentheses and may contain spaces, so split after the last ')'
+        let (Some(open), Some(close)) = (stat.find('('), stat.rfind(')')) else {
+            continue;
+        };
+        let name = stat[open + 1..close].to_string();
+        let fields: Vec<&str> = stat[close + 1..].split_whitespace().collect();
+        let field = |i: usize| {
+            fields
+                .get(i)
+                .and_then(|f| f.parse::<u64>().ok())
+                .unwrap_or(0)
+        };
+        // fields[0] is the state; utime and stime are fields 14 and 15 of the whole line
+        let cpu_ticks = field(11) + field(12);
+        let wchan = std::fs::read_to_string(task.path().join("wchan"))
+            .ok()
+            .filter(|w| !w.is_empty() && w != "0");
+        let state = match (fields.first().copied(), wchan) {
+            (Some("R"), _) => "running".to_string(),
+            (Some("S"), Some(wchan)) => format!("sleeping ({})", wchan),
+            (Some("S"), None) => "sleeping".to_string(),
+            (Some("D"), Some(wchan)) => format!("disk wait ({})", wchan),
+            (Some("D"), None) => "disk wait".to_string(),
+            (Some("T" | "t"), _) => "stopped".to_string(),
+            (Some("Z"), _) => "zombie".to_string(),
+            (Some(other), _) => other.to_string(),
+            (None, _) => "unknown".to_string(),
+        };
+        readings.insert(
+            tid,
+            Reading {
+                name: Some(name),
+                state,
+                priority: fields.get(15).and_then(|p| p.parse().ok()),
+                cpu_time_ms: cpu_ticks * 1000 / ticks,
+            },
+        );
+    }
+    Ok(readings)
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn read_threads(_pid: u32) -> Result<HashMap<u32, Reading>, String> {
+    Err("Thread details are not supported on this platform".into())
+}
+
+// Public API
+/// Threads of `pid`, busiest first. Takes about half a second to measure recent CPU.
+pub fn process_threads(pid: u32) -> Result<Vec<ThreadInfo>, String> {
+    let before = read_threads(pid)?;
+    let started = Instant::now();
+    thread::sleep(CPU_WINDOW);
+    let after = read_threads(pid)?;
+    let elapsed_ms = started.elapsed().as_millis().max(1) as f32;
+
+    #[cfg(windows)]
+    let mut starts = nt::start_addresses(pid, &after.keys().copied().collect::<Vec<_>>());
+    let mut threads: Vec<ThreadInfo> = after
+        .into_iter()
+        .map(|(tid, reading)| {
+            let used = before
+                .get(&tid)
+                .map_or(0, |b| reading.cpu_time_ms.saturating_sub(b.cpu_time_ms));
+            #[cfg(windows)]
+            let start = starts.remove(&tid);
+            #[cfg(not(windows))]
+            let start = None;
+            ThreadInfo {
+                tid,
+                name: reading.name,
+                state: reading.state,
+                priority: reading.priority,
+                cpu_time_ms: reading.cpu_time_ms,
+                cpu_percent: (used as f32 / elapsed_ms * 100.0).min(100.0),
+                start,
+            }
+        })
+        .collect();
+    threads.sort_by(|a, b| {
+        b.cpu_percent
+            .total_cmp(&a.cpu_percent)
+            .then(b.cpu_time_ms.cmp(&a.cpu_time_ms))
+    });
+    Ok(threads)
+}
+
+/// Thread IDs of `pid`, without measuring anything
+pub fn thread_ids(pid: u32) -> Result<Vec<u32>, String> {
+    let mut ids: Vec<u32> = read_threads(pid)?.into_keys().collect();
+    ids.sort_unstable();
+    Ok(ids)
+}
@@ -0,0 +1,163 @@
+// Tray icon drawn from live load, so the machine's state shows at a glance with the window
+// closed: a dot colored by CPU usage, or a tiny chart of the last few CPU samples. The tooltip
+// carries the CPU and RAM numbers.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::thread;
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::{TrayIcon, TrayIconBuilder};
+use tauri::{AppHandle, Manager};
+
+use crate::i18n::t;
+use crate::read_state;
+
+const TRAY_ID: &str = "load";
+const SIZE: u32 = 32;
+const UPDATE_INTERVAL: Duration = Duration::from_secs(3);
+const CHART_SAMPLES: usize = 8; // Bars of SIZE / CHART_SAMPLES pixels each
+
+// Same thresholds and palette as the usage bars in the UI
+const GREEN: [u8; 4] = [0x3f, 0xb9, 0x50, 0xff];
+const YELLOW: [u8; 4] = [0xd2, 0x99, 0x22, 0xff];
+const RED: [u8; 4] = [0xf8, 0x51, 0x49, 0xff];
+const TRACK: [u8; 4] = [0x30, 0x36, 0x3d, 0xff];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayIconStyle {
+    #[default]
+    Dot, // Green/yellow/red by current CPU usage
+    Chart, // Recent CPU usage as bars
+}
+
+fn level_color(percent: f32) -> [u8; 4] {
+    if percent >= 90.0 {
+        RED
+    } else if percent >= 70.0 {
+        YELLOW
+    } else {
+        GREEN
+    }
+}
+
+fn put(rgba: &mut [u8], x: u32, y: u32, color: [u8; 4]) {
+    let at = ((y * SIZE + x) * 4) as usize;
+    rgba[at..at + 4].copy_from_slice(&color);
+}
+
+fn render_dot(cpu: f32) -> Vec<u8> {
+    let mut rgba = vec![0; (SIZE * SIZE * 4) as usize];
+    let center = (SIZE as f32 - 1.0) / 2.0;
+    let radius = SIZE as f32 / 2.0 - 2.0;
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let (dx, dy) = (x as f32 - center, y as f32 - center);
+            if dx * dx + dy * dy <= radius * radius {
+                put(&mut rgba, x, y, level_color(cpu));
+            }
+        }
+    }
+    rgba
+}
+
+/// Oldest sample on the left; each bar sits on a dim track so an idle machine still shows
+fn render_chart(history: &VecDeque<f32>) -> Vec<u8> {
+    let mut rgba = vec![0; (SIZE * SIZE * 4) as usize];
+    let bar_width = SIZE / CHART_SAMPLES as u32;
+    let offset = CHART_SAMPLES - history.len();
+    for (i, cpu) in history.iter().enumerate() {
+        let height = ((cpu.clamp(0.0, 100.0) / 100.0 * SIZE as f32).round() as u32).max(1);
+        let left = (offset + i) as u32 * bar_width;
+        // One pixel gap between bars
+        for x in left..left + bar_width - 1 {
+            for y in 0..SIZE {
+                let color = if y >= SIZE - height {
+                    level_color(*cpu)
+                } else {
+                    TRACK
+                };
+                put(&mut rgba, x, y, color);
+            }
+        }
+    }
+    rgba
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        window.show().ok();
+        window.unminimize().ok();
+        window.set_focus().ok();
+    }
+}
+
+/// The tray menu in the current language
+fn menu(app: &AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let item =
+        |id: &str| MenuItem::with_id(app, id, t(&format!("tray.{}", id), &[]), true, None::<&str>);
+    Menu::with_items(app, &[&item("show")?, &item("mini")?, &item("quit")?])
+}
+
+fn build(app: &AppHandle) -> tauri::Result<TrayIcon> {
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(Image::new_owned(render_dot(0.0), SIZE, SIZE))
+        .tooltip("AutomateKillTask")
+        .menu(&menu(app)?)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "show" => show_main_window(app),
+            "mini" => {
+                crate::mini_monitor::toggle(app).ok();
+            }
+            "quit" => app.exit(0),
+            _ => {}
+        })
+        .build(app)
+}
+
+// Public API
+/// Add the tray icon and keep it updated. Best effort: some Linux desktops have no tray.
+pub fn start(app: &AppHandle) {
+    let tray = match build(app) {
+        Ok(tray) => tray,
+        Err(e) => {
+            tracing::warn!("Tray icon unavailable: {}", e);
+            return;
+        }
+    };
+    thread::spawn(move || {
+        let mut history = VecDeque::with_capacity(CHART_SAMPLES);
+        loop {
+            let stats = crate::system_stats();
+            if history.len() == CHART_SAMPLES {
+                history.pop_front();
+            }
+            history.push_back(stats.cpu_usage);
+
+            let rgba = match read_state(|state| state.settings.tray_icon) {
+                TrayIconStyle::Dot => render_dot(stats.cpu_usage),
+                TrayIconStyle::Chart => render_chart(&history),
+            };
+            tray.set_icon(Some(Image::new_owned(rgba, SIZE, SIZE))).ok();
+            tray.set_tooltip(Some(t(
+                "tray.tooltip",
+                &[
+                    ("cpu", format!("{:.0}", stats.cpu_usage)),
+                    ("memory", format!("{:.0}", stats.memory_percent)),
+                    ("used", format!("{:.1}", stats.memory_used_gb)),
+                ],
+            )))
+            .ok();
+            thread::sleep(crate::power::interval(UPDATE_INTERVAL));
+        }
+    });
+}
+
+/// Rebuild the menu after the language changed; the tooltip follows on its next update
+pub fn relabel(app: &AppHandle) {
+    if let (Some(tray), Ok(menu)) = (app.tray_by_id(TRAY_ID), menu(app)) {
+        tray.set_menu(Some(menu)).ok();
+    }
+}
@@ -1,4 +1,7 @@
 import { createApp } from "vue";
+import { getCurrentWindow } from "@tauri-apps/api/window";
 import App from "./App.vue";
+import MiniMonitor from "./MiniMonitor.vue";
 
-createApp(App).mount("#app");
+// Both windows load this page; the mini monitor gets its own small view
+createApp(getCurrentWindow().label === "mini" ? MiniMonitor : App).mount("#app");